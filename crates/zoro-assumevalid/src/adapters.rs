@@ -9,10 +9,13 @@ use stwo_cairo_serialize::CairoSerialize;
 
 use zebra_chain::block::Header;
 use zebra_chain::serialization::ZcashSerialize;
+use zoro_chain_params::{EQUIHASH_INDEX_BITS, EQUIHASH_INDICES};
 use zoro_spv_verify::ChainState;
+use zoro_zcash_client::{DisplayOrderHash, InternalOrderHash};
 
 use num_bigint::BigUint;
 use num_traits::Num;
+use rayon::prelude::*;
 use starknet_ff::FieldElement;
 
 // ============================================================================
@@ -24,6 +27,7 @@ use starknet_ff::FieldElement;
 pub struct U256String(pub String);
 #[derive(Clone)]
 pub struct ByteArrayString(pub String);
+/// 32-byte digest as display-order hex, serialized as 8 u32 words of the internal byte order
 #[derive(Clone)]
 pub struct DigestString(pub String);
 pub struct U256StringLittleEndian(pub String);
@@ -129,6 +133,13 @@ impl CairoSerialize for ByteArrayString {
     }
 }
 
+impl DigestString {
+    /// Digest given in internal byte order
+    pub fn internal(bytes: [u8; 32]) -> Self {
+        Self(InternalOrderHash(bytes).to_display().to_string())
+    }
+}
+
 impl CairoSerialize for DigestString {
     // Reversed hex string into 4-byte words then into BE u32
     fn serialize(&self, output: &mut Vec<FieldElement>) {
@@ -138,12 +149,12 @@ impl CairoSerialize for DigestString {
             .or_else(|| s.strip_prefix("0X"))
             .unwrap_or(s);
 
-        // Convert 64-char hex to 8 u32 words (reversed for little-endian)
-        let bytes = hex::decode(hex_str).expect("Invalid hex string");
-        assert!(bytes.len() == 32, "expected 32-byte digest");
-        let mut rev = bytes;
-        rev.reverse();
-        for chunk in rev.chunks(4) {
+        // Convert the display-order hex to 8 u32 words of the internal byte order
+        let digest = hex_str
+            .parse::<DisplayOrderHash>()
+            .expect("expected 32-byte hex digest")
+            .to_internal();
+        for chunk in digest.0.chunks(4) {
             let mut word_bytes = [0u8; 4];
             word_bytes[..chunk.len()].copy_from_slice(chunk);
             let word = u32::from_be_bytes(word_bytes) as u128;
@@ -156,10 +167,10 @@ impl CairoSerialize for DigestString {
 // Cairo-compatible view structures matching packages/consensus/src/types/
 // ============================================================================
 
-/// View for test.cairo Args struct that matches Cairo's structure
+/// View for test.cairo Args struct after the chain state, which is serialized separately (see
+/// [`chain_state_args_hex`]) so that it can be carried over between batches
 #[derive(CairoSerialize)]
-struct ArgsView {
-    chain_state: ChainStateView,
+struct BatchView {
     blocks: Vec<BlockView>,
     /// Sorted indices hints for each block (for O(n) Equihash uniqueness verification).
     /// Each hint contains the same 512 indices as the block's solution, but sorted ascending.
@@ -219,95 +230,87 @@ struct HeaderView {
     pub indices: Vec<u32>,
 }
 
-/// Main adapter function for test.cairo Args
+/// Cairo arguments serializing `chain_state`, the prefix of the test.cairo Args
+pub fn chain_state_args_hex(chain_state: &ChainState) -> Vec<String> {
+    let mut felts = Vec::new();
+    chain_state_to_view(chain_state).serialize(&mut felts);
+    felts_to_hex(felts)
+}
+
+/// Main adapter function for test.cairo Args, following the `chain_state_args` prefix
+///
+/// Blocks are converted in parallel, and each solution is decoded once for both the block and its
+/// sorted indices hint.
 pub fn to_runner_args_hex(
-    chain_state: ChainState,
+    chain_state_args: &[String],
     headers: &[Header],
     chain_state_proof: Option<CairoProof<Blake2sMerkleHasher>>,
-    sorted_indices_hints: Vec<Vec<u32>>,
 ) -> Vec<String> {
-    let blocks: Vec<BlockView> = headers
-        .iter()
+    let (blocks, sorted_indices_hints): (Vec<BlockView>, Vec<Vec<u32>>) = headers
+        .par_iter()
         .map(|header| {
             // Extract bits from difficulty_threshold
             let bits = u32::from_be_bytes(header.difficulty_threshold.bytes_in_display_order());
 
-            // Serialize solution to get bytes, then extract 21-bit indices
-            let mut solution_bytes = Vec::new();
-            header
-                .solution
-                .zcash_serialize(&mut solution_bytes)
-                .expect("solution serialization failed");
-            // Skip the compact size prefix (1-3 bytes depending on size)
-            let solution_data = if solution_bytes.len() > 1344 {
-                // Has compact size prefix
-                &solution_bytes[solution_bytes.len() - 1344..]
-            } else {
-                &solution_bytes[..]
-            };
-            let indices = extract_equihash_indices(solution_data);
-
-            // Nonce needs byte reversal: internal order -> display order
-            let nonce_reversed: Vec<u8> = header.nonce.0.iter().rev().cloned().collect();
+            let indices = solution_indices(header);
+            let mut sorted_indices = indices.clone();
+            sorted_indices.sort_unstable();
 
-            // Merkle root needs byte reversal: internal order -> display order
-            let merkle_root_reversed: Vec<u8> =
-                header.merkle_root.0.iter().rev().cloned().collect();
-
-            BlockView {
+            let block = BlockView {
                 header: HeaderView {
                     version: header.version,
-                    final_sapling_root: DigestString(hex::encode(*header.commitment_bytes)),
+                    // Unlike the other digests, the commitment words are taken in reversed
+                    // byte order: the header bytes are passed as if in display order
+                    final_sapling_root: DigestString(
+                        DisplayOrderHash(*header.commitment_bytes).to_string(),
+                    ),
                     time: header.time.timestamp() as u32,
                     bits,
-                    nonce: DigestString(hex::encode(&nonce_reversed)),
+                    nonce: DigestString::internal(header.nonce.0),
                     indices,
                 },
                 data: TransactionDataView {
-                    merkle_root: DigestString(hex::encode(&merkle_root_reversed)),
+                    merkle_root: DigestString::internal(header.merkle_root.0),
                 },
-            }
+            };
+            (block, sorted_indices)
         })
-        .collect();
-
-    let chain_state_view = chain_state_to_view(chain_state);
+        .unzip();
 
-    let args_view = ArgsView {
-        chain_state: chain_state_view,
+    let batch_view = BatchView {
         blocks,
         sorted_indices_hints,
         chain_state_proof,
     };
 
     let mut felts = Vec::new();
-    args_view.serialize(&mut felts);
+    batch_view.serialize(&mut felts);
+
+    let mut args = Vec::with_capacity(chain_state_args.len() + felts.len());
+    args.extend_from_slice(chain_state_args);
+    args.extend(felts_to_hex(felts));
+    args
+}
 
+/// Format felts as runner arguments, in parallel since a chain state proof has many of them
+fn felts_to_hex(felts: Vec<FieldElement>) -> Vec<String> {
     felts
-        .into_iter()
+        .into_par_iter()
         .map(|felt| format!("0x{felt:x}"))
         .collect()
 }
 
-fn chain_state_to_view(chain_state: ChainState) -> ChainStateView {
+fn chain_state_to_view(chain_state: &ChainState) -> ChainStateView {
     let pow_target_history: Vec<U256String> = chain_state
         .pow_target_history
         .iter()
         .map(|target| U256String(bytes_to_decimal_string(target.as_bytes())))
         .collect();
 
-    // best_block_hash needs byte reversal: internal order -> display order
-    let hash_reversed: Vec<u8> = chain_state
-        .best_block_hash
-        .0
-        .iter()
-        .rev()
-        .cloned()
-        .collect();
-
     ChainStateView {
         block_height: chain_state.block_height,
         total_work: U256String(chain_state.total_work.to_string()),
-        best_block_hash: DigestString(hex::encode(&hash_reversed)),
+        best_block_hash: DigestString::internal(chain_state.best_block_hash.0),
         current_target: U256String(bytes_to_decimal_string(
             chain_state.current_target.as_bytes(),
         )),
@@ -322,8 +325,8 @@ fn chain_state_to_view(chain_state: ChainState) -> ChainStateView {
 /// For Zcash mainnet (n=200, k=9), this extracts 512 indices of 21 bits each
 /// from the 1344-byte minimal-encoded solution (big-endian bitstream).
 pub fn extract_equihash_indices(solution_bytes: &[u8]) -> Vec<u32> {
-    const BITS_PER_INDEX: usize = 21;
-    const NUM_INDICES: usize = 512;
+    const BITS_PER_INDEX: usize = EQUIHASH_INDEX_BITS;
+    const NUM_INDICES: usize = EQUIHASH_INDICES;
 
     let mut indices = Vec::with_capacity(NUM_INDICES);
 
@@ -348,31 +351,34 @@ fn bytes_to_decimal_string(bytes: &[u8; 32]) -> String {
     big_uint.to_str_radix(10)
 }
 
+/// Equihash solution indices of a header
+fn solution_indices(header: &Header) -> Vec<u32> {
+    // Serialize solution to get bytes
+    let mut solution_bytes = Vec::new();
+    header
+        .solution
+        .zcash_serialize(&mut solution_bytes)
+        .expect("solution serialization failed");
+
+    // Skip the compact size prefix (1-3 bytes depending on size)
+    let solution_data = if solution_bytes.len() > 1344 {
+        &solution_bytes[solution_bytes.len() - 1344..]
+    } else {
+        &solution_bytes[..]
+    };
+    extract_equihash_indices(solution_data)
+}
+
 /// Generate sorted indices hints from block headers.
 ///
 /// For each header, extracts the Equihash solution indices and returns them sorted.
 /// This is used as a hint for O(n) uniqueness verification in Cairo.
 pub fn generate_sorted_indices_hints(headers: &[Header]) -> Vec<Vec<u32>> {
     headers
-        .iter()
+        .par_iter()
         .map(|header| {
-            // Serialize solution to get bytes
-            let mut solution_bytes = Vec::new();
-            header
-                .solution
-                .zcash_serialize(&mut solution_bytes)
-                .expect("solution serialization failed");
-
-            // Skip the compact size prefix (1-3 bytes depending on size)
-            let solution_data = if solution_bytes.len() > 1344 {
-                &solution_bytes[solution_bytes.len() - 1344..]
-            } else {
-                &solution_bytes[..]
-            };
-
-            // Extract indices and sort them
-            let mut indices = extract_equihash_indices(solution_data);
-            indices.sort();
+            let mut indices = solution_indices(header);
+            indices.sort_unstable();
             indices
         })
         .collect()
@@ -0,0 +1,437 @@
+//! Chain state proofs stored in a Google Cloud Storage bucket
+//!
+//! Each proof is uploaded under the height of the chain state it proves, zero-padded so that
+//! the lexicographic order of the object names is the height order:
+//! `proofs/0000001000.json` proves the chain state at height 1000, and with it every block up
+//! to that height.
+//!
+//! Proofs are uploaded in chunks through a resumable upload session and downloaded with range
+//! requests, so that a failed transfer resumes where it stopped. Both directions are checked
+//! against the CRC32C checksum of the object.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::{DateTime, Utc};
+use gcp_auth::TokenProvider;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use reqwest::{header, StatusCode};
+use serde::Deserialize;
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, info, warn};
+
+/// Prefix of the proof object names
+pub const PROOF_OBJECT_PREFIX: &str = "proofs/";
+
+const STORAGE_API_URL: &str = "https://storage.googleapis.com/storage/v1";
+const UPLOAD_API_URL: &str = "https://storage.googleapis.com/upload/storage/v1";
+const STORAGE_SCOPE: &str = "https://www.googleapis.com/auth/devstorage.read_write";
+
+/// Size of the uploaded chunks (GCS requires a multiple of 256 KiB)
+const UPLOAD_CHUNK_SIZE: usize = 32 * 256 * 1024;
+/// Number of consecutive failed attempts after which a transfer is abandoned
+const MAX_TRANSFER_ATTEMPTS: u32 = 5;
+/// Delay before the first retry of a transfer, doubled after each failure
+const TRANSFER_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Name of the object holding the proof of the chain state at `height`
+pub fn proof_object_name(height: u32) -> String {
+    format!("{PROOF_OBJECT_PREFIX}{height:010}.json")
+}
+
+/// Height of the chain state proven by the object `name`, if it is a proof object
+pub fn parse_proof_object_name(name: &str) -> Option<u32> {
+    name.strip_prefix(PROOF_OBJECT_PREFIX)?
+        .strip_suffix(".json")?
+        .parse()
+        .ok()
+}
+
+/// A proof object of the bucket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofObject {
+    /// Height of the proven chain state
+    pub height: u32,
+    /// Object name
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ObjectList {
+    #[serde(default)]
+    items: Vec<ObjectItem>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ObjectItem {
+    name: String,
+    time_created: DateTime<Utc>,
+}
+
+/// A proof object listed from the bucket, with its creation time
+#[derive(Debug, Clone)]
+pub struct ListedProof {
+    pub object: ProofObject,
+    pub created: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+struct ObjectMetadata {
+    /// Size in bytes, as a decimal string
+    size: String,
+    /// Base64-encoded big-endian CRC32C checksum
+    crc32c: Option<String>,
+}
+
+/// State of a resumable upload session
+enum UploadStatus {
+    /// Number of bytes persisted so far
+    Incomplete(usize),
+    Complete(ObjectMetadata),
+}
+
+fn encode_crc32c(crc: u32) -> String {
+    STANDARD.encode(crc.to_be_bytes())
+}
+
+/// Delay before the retry following `failures` consecutive failures
+fn retry_delay(failures: u32) -> Duration {
+    TRANSFER_RETRY_DELAY * 2u32.pow(failures.saturating_sub(1))
+}
+
+/// Proofs stored in a GCS bucket
+pub struct GcsProofStore {
+    bucket: String,
+    client: reqwest::Client,
+    /// Credentials from the environment (service account, metadata server or gcloud); public
+    /// buckets can be read without
+    auth: Option<Arc<dyn TokenProvider>>,
+}
+
+impl GcsProofStore {
+    /// Open the proofs of `bucket`, with the default credentials if available
+    pub async fn new(bucket: &str) -> Result<Self> {
+        let auth = match gcp_auth::provider().await {
+            Ok(auth) => Some(auth),
+            Err(e) => {
+                debug!("No GCS credentials, using anonymous access: {e}");
+                None
+            }
+        };
+        Ok(Self {
+            bucket: bucket.to_string(),
+            // Resumable uploads answer 308 to report progress, which is not a redirection
+            client: reqwest::Client::builder()
+                .redirect(reqwest::redirect::Policy::none())
+                .build()?,
+            auth,
+        })
+    }
+
+    /// Public URL of an object, for buckets readable by all users
+    pub fn public_url(&self, name: &str) -> String {
+        format!("https://storage.googleapis.com/{}/{}", self.bucket, name)
+    }
+
+    /// Upload the proof of the chain state at `height`, in chunks that are retried on failure
+    pub async fn upload_proof(&self, height: u32, proof_file: &Path) -> Result<ProofObject> {
+        let name = proof_object_name(height);
+        let body = tokio::fs::read(proof_file)
+            .await
+            .with_context(|| format!("Failed to read proof {}", proof_file.display()))?;
+        if body.is_empty() {
+            bail!("Proof {} is empty", proof_file.display());
+        }
+        let crc32c = encode_crc32c(crc32c::crc32c(&body));
+
+        // GCS rejects the final chunk if the content does not match the declared checksum
+        let url = format!(
+            "{UPLOAD_API_URL}/b/{}/o?uploadType=resumable&name={}",
+            self.bucket,
+            utf8_percent_encode(&name, NON_ALPHANUMERIC)
+        );
+        let request = self
+            .client
+            .post(url)
+            .header("X-Upload-Content-Type", "application/json")
+            .header("X-Upload-Content-Length", body.len())
+            .json(&serde_json::json!({ "crc32c": crc32c }));
+        let response = self.send(request).await?;
+        let session = response
+            .headers()
+            .get(header::LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| anyhow!("GCS did not return an upload session"))?
+            .to_string();
+
+        let mut offset = 0;
+        let mut failures = 0;
+        let object = loop {
+            let end = (offset + UPLOAD_CHUNK_SIZE).min(body.len());
+            let request = self
+                .client
+                .put(&session)
+                .header(
+                    header::CONTENT_RANGE,
+                    format!("bytes {}-{}/{}", offset, end - 1, body.len()),
+                )
+                .body(body[offset..end].to_vec());
+            match self.upload_status(request).await {
+                Ok(UploadStatus::Complete(object)) => break object,
+                Ok(UploadStatus::Incomplete(persisted)) => {
+                    debug!("Uploaded {}/{} bytes of {}", persisted, body.len(), name);
+                    offset = persisted;
+                    failures = 0;
+                }
+                Err(e) => {
+                    failures += 1;
+                    if failures >= MAX_TRANSFER_ATTEMPTS {
+                        return Err(e.context(format!("Failed to upload {name}")));
+                    }
+                    let delay = retry_delay(failures);
+                    warn!("Upload of {} failed, resuming in {:?}: {}", name, delay, e);
+                    tokio::time::sleep(delay).await;
+                    // Ask the session how much was persisted, the chunk may have partly landed
+                    let request = self
+                        .client
+                        .put(&session)
+                        .header(header::CONTENT_RANGE, format!("bytes */{}", body.len()));
+                    match self.upload_status(request).await {
+                        Ok(UploadStatus::Complete(object)) => break object,
+                        Ok(UploadStatus::Incomplete(persisted)) => offset = persisted,
+                        Err(e) => debug!("Could not query the upload session: {e}"),
+                    }
+                }
+            }
+        };
+        if object.crc32c.as_deref() != Some(crc32c.as_str()) {
+            bail!("Checksum mismatch after uploading {name}");
+        }
+        info!(
+            "Uploaded proof of height {} to gs://{}/{}",
+            height, self.bucket, name
+        );
+        Ok(ProofObject { height, name })
+    }
+
+    /// Send a request of a resumable upload session and decode its progress
+    async fn upload_status(&self, request: reqwest::RequestBuilder) -> Result<UploadStatus> {
+        let response = self.send_unchecked(request).await?;
+        match response.status() {
+            StatusCode::PERMANENT_REDIRECT => {
+                // `Range: bytes=0-<last>`, absent if nothing was persisted yet
+                let persisted = response
+                    .headers()
+                    .get(header::RANGE)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|range| range.rsplit_once('-'))
+                    .and_then(|(_, last)| last.parse::<usize>().ok())
+                    .map_or(0, |last| last + 1);
+                Ok(UploadStatus::Incomplete(persisted))
+            }
+            status if status.is_success() => Ok(UploadStatus::Complete(response.json().await?)),
+            _ => Err(error_response(response).await),
+        }
+    }
+
+    /// Download a proof object to `dest`, resuming the partial download left by an earlier
+    /// attempt
+    pub async fn download_proof(&self, object: &ProofObject, dest: &Path) -> Result<()> {
+        let name = utf8_percent_encode(&object.name, NON_ALPHANUMERIC).to_string();
+        let metadata: ObjectMetadata = self
+            .send(
+                self.client
+                    .get(format!("{STORAGE_API_URL}/b/{}/o/{}", self.bucket, name)),
+            )
+            .await?
+            .json()
+            .await?;
+        let size: u64 = metadata.size.parse()?;
+
+        if let Some(parent) = dest.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let partial = dest.with_extension("part");
+        let url = format!("{STORAGE_API_URL}/b/{}/o/{}?alt=media", self.bucket, name);
+        let mut failures = 0;
+        loop {
+            let downloaded = match tokio::fs::metadata(&partial).await {
+                Ok(metadata) => metadata.len(),
+                Err(_) => 0,
+            };
+            if downloaded >= size {
+                break;
+            }
+            let request = self
+                .client
+                .get(&url)
+                .header(header::RANGE, format!("bytes={downloaded}-"));
+            if let Err(e) = self
+                .download_range(request, &partial, downloaded, &mut failures)
+                .await
+            {
+                failures += 1;
+                if failures >= MAX_TRANSFER_ATTEMPTS {
+                    return Err(e.context(format!("Failed to download {}", object.name)));
+                }
+                let delay = retry_delay(failures);
+                warn!(
+                    "Download of {} failed, resuming in {:?}: {}",
+                    object.name, delay, e
+                );
+                tokio::time::sleep(delay).await;
+            }
+        }
+
+        let bytes = tokio::fs::read(&partial).await?;
+        if let Some(expected) = &metadata.crc32c {
+            if encode_crc32c(crc32c::crc32c(&bytes)) != *expected {
+                tokio::fs::remove_file(&partial).await?;
+                bail!("Checksum mismatch after downloading {}", object.name);
+            }
+        }
+        tokio::fs::rename(&partial, dest).await?;
+        debug!(
+            "Downloaded gs://{}/{} to {}",
+            self.bucket,
+            object.name,
+            dest.display()
+        );
+        Ok(())
+    }
+
+    /// Append the bytes of a range request to the partial download holding `downloaded` bytes.
+    ///
+    /// `failures` is reset after every chunk written, so that only failures without progress in
+    /// between abandon the download.
+    async fn download_range(
+        &self,
+        request: reqwest::RequestBuilder,
+        partial: &Path,
+        downloaded: u64,
+        failures: &mut u32,
+    ) -> Result<()> {
+        let mut response = self.send(request).await?;
+        // A server ignoring the range sends the whole object again
+        let append = downloaded > 0 && response.status() == StatusCode::PARTIAL_CONTENT;
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(partial)
+            .await?;
+        while let Some(chunk) = response.chunk().await? {
+            file.write_all(&chunk).await?;
+            *failures = 0;
+        }
+        file.flush().await?;
+        Ok(())
+    }
+
+    /// Find the smallest proof whose chain state covers the block at `height`, that is the proof
+    /// of the lowest chain state at or above `height`
+    pub async fn find_proof_covering(&self, height: u32) -> Result<Option<ProofObject>> {
+        let list = self
+            .list(&[
+                ("startOffset", proof_object_name(height)),
+                ("maxResults", "1".to_string()),
+            ])
+            .await?;
+        Ok(list.items.into_iter().find_map(|item| {
+            parse_proof_object_name(&item.name).map(|height| ProofObject {
+                height,
+                name: item.name,
+            })
+        }))
+    }
+
+    /// Find the proof of the highest chain state
+    pub async fn latest_proof(&self) -> Result<Option<ProofObject>> {
+        Ok(self.list_proofs().await?.pop().map(|proof| proof.object))
+    }
+
+    /// List all the proofs of the bucket, by increasing height
+    pub async fn list_proofs(&self) -> Result<Vec<ListedProof>> {
+        let mut proofs = Vec::new();
+        let mut page_token = None;
+        loop {
+            let mut query = Vec::new();
+            if let Some(token) = page_token.take() {
+                query.push(("pageToken", token));
+            }
+            let list = self.list(&query).await?;
+            // Objects are listed in name order, hence in height order
+            proofs.extend(list.items.into_iter().filter_map(|item| {
+                Some(ListedProof {
+                    object: ProofObject {
+                        height: parse_proof_object_name(&item.name)?,
+                        name: item.name,
+                    },
+                    created: item.time_created,
+                })
+            }));
+            match list.next_page_token {
+                Some(token) => page_token = Some(token),
+                None => return Ok(proofs),
+            }
+        }
+    }
+
+    /// Delete a proof object
+    pub async fn delete_proof(&self, object: &ProofObject) -> Result<()> {
+        let url = format!(
+            "{STORAGE_API_URL}/b/{}/o/{}",
+            self.bucket,
+            utf8_percent_encode(&object.name, NON_ALPHANUMERIC)
+        );
+        self.send(self.client.delete(url)).await?;
+        Ok(())
+    }
+
+    /// List the proof objects, with extra query parameters
+    async fn list(&self, query: &[(&str, String)]) -> Result<ObjectList> {
+        let request = self
+            .client
+            .get(format!("{STORAGE_API_URL}/b/{}/o", self.bucket))
+            .query(&[("prefix", PROOF_OBJECT_PREFIX)])
+            .query(query);
+        Ok(self.send(request).await?.json().await?)
+    }
+
+    /// Send a request with the credentials, failing on HTTP errors
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let response = self.send_unchecked(request).await?;
+        if !response.status().is_success() {
+            return Err(error_response(response).await);
+        }
+        Ok(response)
+    }
+
+    /// Send a request with the credentials
+    async fn send_unchecked(
+        &self,
+        mut request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response> {
+        if let Some(auth) = &self.auth {
+            let token = auth
+                .token(&[STORAGE_SCOPE])
+                .await
+                .map_err(|e| anyhow!("Failed to get a GCS access token: {e}"))?;
+            request = request.bearer_auth(token.as_str());
+        }
+        Ok(request.send().await?)
+    }
+}
+
+async fn error_response(response: reqwest::Response) -> anyhow::Error {
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    anyhow!("GCS request failed with {status}: {}", body.trim())
+}
@@ -1,24 +1,39 @@
 use std::path::PathBuf;
 
-use crate::adapters::{generate_sorted_indices_hints, to_runner_args_hex};
+use crate::adapters::{chain_state_args_hex, to_runner_args_hex};
 use anyhow::{anyhow, Result};
 use cairo_air::utils::{deserialize_proof_from_file, ProofFormat};
 use stwo::core::vcs::blake2_merkle::Blake2sMerkleHasher;
 use tracing::debug;
 use zebra_chain::block::Header as BlockHeader;
-use zoro_spv_verify::ChainState;
+use zoro_spv_verify::{fold_headers, BlockHeaderRecord, ChainState, ProverHeartbeat};
+
+/// Header carrying the prover key of a heartbeat
+const PROVER_KEY_HEADER: &str = "x-prover-key";
+
+/// Default maximum number of headers requested from `/headers` at once
+pub const DEFAULT_HEADERS_BATCH_SIZE: u32 = 500;
 
 /// Configuration for the zoro-assumevalid client
 #[derive(Debug, Clone)]
 pub struct ProveConfig {
     /// Bridge node RPC URL
     pub bridge_node_url: String,
+    /// Proxy for the bridge node requests, e.g. `socks5h://127.0.0.1:9050` for Tor (optional)
+    pub proxy: Option<String>,
+    /// Key of the bridge node's `/proving-status` heartbeats, none are sent if unset (optional)
+    pub prover_key: Option<String>,
+    /// Maximum number of headers requested from `/headers` at once
+    pub headers_batch_size: u32,
 }
 
 impl Default for ProveConfig {
     fn default() -> Self {
         Self {
             bridge_node_url: "http://127.0.0.1:5000".to_string(),
+            proxy: None,
+            prover_key: None,
+            headers_batch_size: DEFAULT_HEADERS_BATCH_SIZE,
         }
     }
 }
@@ -31,9 +46,13 @@ pub struct ProveClient {
 
 impl ProveClient {
     /// Create a new ProveClient with the given configuration
-    pub fn new(config: ProveConfig) -> Self {
-        let client = reqwest::Client::new();
-        Self { config, client }
+    pub fn new(config: ProveConfig) -> Result<Self> {
+        let mut builder = reqwest::Client::builder();
+        if let Some(proxy) = &config.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+        }
+        let client = builder.build()?;
+        Ok(Self { config, client })
     }
 
     /// Fetch chain state for a given block height
@@ -46,14 +65,46 @@ impl ProveClient {
         Ok(response.json().await?)
     }
 
-    /// Fetch block headers for a given range
+    /// Fetch the `size` block headers from height `offset`
+    ///
+    /// Headers are requested `headers_batch_size` at a time. The bridge node may return fewer
+    /// headers than requested when it caps the size of its responses, the rest is requested from
+    /// the next height. Each header must be listed at its height, under its own hash.
     pub async fn get_block_headers(&self, offset: u32, size: u32) -> Result<Vec<BlockHeader>> {
-        let url = format!(
-            "{}/headers?offset={}&size={}",
-            self.config.bridge_node_url, offset, size
-        );
-        let response = self.make_request(&url).await?;
-        Ok(response.json().await?)
+        let mut headers = Vec::with_capacity(size as usize);
+        while (headers.len() as u32) < size {
+            let height = offset + headers.len() as u32;
+            let requested =
+                (size - headers.len() as u32).min(self.config.headers_batch_size.max(1));
+            let url = format!(
+                "{}/headers?offset={}&size={}",
+                self.config.bridge_node_url, height, requested
+            );
+            let response = self.make_request(&url).await?;
+            let batch: Vec<BlockHeaderRecord> = response.json().await?;
+            if batch.is_empty() {
+                return Err(anyhow!(
+                    "Bridge node returned no header at height {height}, is it indexed yet?"
+                ));
+            }
+            if batch.len() as u32 > requested {
+                return Err(anyhow!(
+                    "Bridge node returned {} headers at height {height}, requested {requested}",
+                    batch.len()
+                ));
+            }
+            for (record, expected) in batch.into_iter().zip(height..) {
+                if record.height != expected {
+                    return Err(anyhow!(
+                        "Bridge node returned the header of height {} where {expected} was expected",
+                        record.height
+                    ));
+                }
+                record.check_hash()?;
+                headers.push(record.header);
+            }
+        }
+        Ok(headers)
     }
 
     /// Get the current head (latest block height)
@@ -63,6 +114,25 @@ impl ProveClient {
         Ok(response.json().await?)
     }
 
+    /// Push a heartbeat to the bridge node's `/proving-status`, if a prover key is configured
+    pub async fn send_heartbeat(&self, heartbeat: &ProverHeartbeat) -> Result<()> {
+        let Some(prover_key) = &self.config.prover_key else {
+            return Ok(());
+        };
+        let url = format!("{}/proving-status", self.config.bridge_node_url);
+        let response = self
+            .client
+            .post(&url)
+            .header(PROVER_KEY_HEADER, prover_key)
+            .json(heartbeat)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("HTTP error: {}", response.status()));
+        }
+        Ok(())
+    }
+
     /// Make an HTTP request
     async fn make_request(&self, url: &str) -> Result<reqwest::Response> {
         debug!("Making request to {}", url);
@@ -81,6 +151,24 @@ impl ProveClient {
     }
 }
 
+/// Chain state at the start of a batch, along with its serialization
+///
+/// The chain state a batch ends with is the one the next batch starts from, so it is computed from
+/// the batch headers and serialized once instead of being fetched from the bridge node again.
+#[derive(Debug, Clone)]
+pub struct ChainStatePrefix {
+    pub chain_state: ChainState,
+    /// Cairo arguments serializing the chain state, which the batch arguments start with
+    pub args: Vec<String>,
+}
+
+impl ChainStatePrefix {
+    pub fn new(chain_state: ChainState) -> Self {
+        let args = chain_state_args_hex(&chain_state);
+        Self { chain_state, args }
+    }
+}
+
 /// Parameters for generating assumevalid args
 #[derive(Debug, Clone)]
 pub struct AssumeValidParams {
@@ -90,21 +178,41 @@ pub struct AssumeValidParams {
     pub block_count: u32,
     /// Optional chain state proof path
     pub chain_state_proof_path: Option<PathBuf>,
+    /// Chain state carried over from the previous batch, fetched from the bridge node when
+    /// missing or at another height than `start_height`
+    pub chain_state: Option<ChainStatePrefix>,
+}
+
+/// Assumevalid args of a batch
+#[derive(Debug, Clone)]
+pub struct BatchArgs {
+    pub args: Vec<String>,
+    /// Chain state after the batch headers, for the next batch
+    pub next_chain_state: ChainStatePrefix,
 }
 
 /// Generate assumevalid args for the given parameters
 pub async fn generate_assumevalid_args(
     client: &ProveClient,
     params: AssumeValidParams,
-) -> Result<Vec<String>> {
+) -> Result<BatchArgs> {
     debug!(
         "Generating assumevalid args for height {} with {} blocks",
         params.start_height, params.block_count
     );
 
-    // Fetch chain state for the starting height
-    let chain_state = client.get_chain_state(params.start_height).await?;
-    debug!("Fetched chain state for height {}", params.start_height);
+    // Reuse the chain state of the previous batch, or fetch it for the starting height
+    let chain_state = match params.chain_state {
+        Some(prefix) if prefix.chain_state.block_height == params.start_height => {
+            debug!("Reusing chain state for height {}", params.start_height);
+            prefix
+        }
+        _ => {
+            let chain_state = client.get_chain_state(params.start_height).await?;
+            debug!("Fetched chain state for height {}", params.start_height);
+            ChainStatePrefix::new(chain_state)
+        }
+    };
 
     // Fetch block headers for the range: starting AFTER the current chain_state height
     let block_headers = client
@@ -112,6 +220,12 @@ pub async fn generate_assumevalid_args(
         .await?;
     debug!("Fetched {} block headers", block_headers.len());
 
+    // Check that the headers chain on the chain state before serializing them
+    let next_chain_state = ChainStatePrefix::new(fold_headers(
+        chain_state.chain_state.clone(),
+        &block_headers,
+    )?);
+
     let chain_state_proof = if let Some(path) = &params.chain_state_proof_path {
         println!("Deserializing proof from {}", path.display());
         Some(deserialize_proof_from_file::<Blake2sMerkleHasher>(
@@ -123,31 +237,26 @@ pub async fn generate_assumevalid_args(
         None
     };
 
-    // Generate sorted indices hints from headers
-    let sorted_indices_hints = generate_sorted_indices_hints(&block_headers);
-
     // Generate Cairo-compatible arguments
-    let cairo_args = to_runner_args_hex(
-        chain_state,
-        &block_headers,
-        chain_state_proof,
-        sorted_indices_hints,
-    );
+    let cairo_args = to_runner_args_hex(&chain_state.args, &block_headers, chain_state_proof);
 
     debug!("Generated {} Cairo arguments", cairo_args.len());
 
-    Ok(cairo_args)
+    Ok(BatchArgs {
+        args: cairo_args,
+        next_chain_state,
+    })
 }
 
-/// Generate and save test args to a file
+/// Generate and save test args to a file, returning the chain state of the next batch
 pub async fn generate_and_save_args(
     client: &ProveClient,
     params: AssumeValidParams,
     file_path: &str,
-) -> Result<()> {
-    let cairo_args = generate_assumevalid_args(client, params).await?;
-    save_cairo_args_to_file(&cairo_args, file_path).await?;
-    Ok(())
+) -> Result<ChainStatePrefix> {
+    let batch_args = generate_assumevalid_args(client, params).await?;
+    save_cairo_args_to_file(&batch_args.args, file_path).await?;
+    Ok(batch_args.next_chain_state)
 }
 
 /// Save Cairo arguments to a file
@@ -6,8 +6,12 @@
 
 pub mod adapters;
 
+pub mod gcs;
 pub mod generate_args;
 pub mod prove;
+pub mod quarantine;
+pub mod retention;
+pub mod telemetry;
 
 pub use prove::{prove, ProveParams};
 
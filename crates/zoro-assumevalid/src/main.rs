@@ -1,8 +1,17 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
+use std::process::ExitCode;
 use tracing_subscriber::{self, EnvFilter};
+use zoro_assumevalid::gcs::GcsProofStore;
+use zoro_assumevalid::generate_args::DEFAULT_HEADERS_BATCH_SIZE;
 use zoro_assumevalid::prove::{prove, ProveParams};
+use zoro_assumevalid::quarantine::{Quarantine, QuarantineCommand};
+use zoro_assumevalid::retention::{gc, RetentionPolicy};
+use zoro_assumevalid::telemetry::StepBudget;
+use zoro_spv_verify::build_info::{self, BuildInfo};
+use zoro_spv_verify::i18n::Locale;
+use zoro_spv_verify::report::report;
 
 /// Zoro AssumeValid - Generate assumevalid arguments and prove Cairo programs
 #[derive(Parser)]
@@ -14,6 +23,14 @@ struct Cli {
     #[arg(long, default_value = "https://staging.zoro.wtf")]
     bridge_url: String,
 
+    /// Proxy for the bridge node requests (e.g., socks5h://127.0.0.1:9050 for Tor)
+    #[arg(long, env = "BRIDGE_PROXY")]
+    proxy: Option<String>,
+
+    /// Maximum number of block headers requested from the bridge node at once
+    #[arg(long, default_value_t = DEFAULT_HEADERS_BATCH_SIZE)]
+    headers_batch_size: u32,
+
     /// Log level
     #[arg(long, default_value = "info")]
     log_level: String,
@@ -26,13 +43,15 @@ struct Cli {
 enum Commands {
     /// Prove multiple batches iteratively (similar to prove_pow in Python)
     Prove {
-        /// Use cloud storage to detect latest proof height instead of local directory scanning
+        /// Resume from the latest proof of the GCS bucket when it is ahead of the output directory
         #[arg(long)]
         load_from_gcs: bool,
 
+        /// Upload each proof to the GCS bucket, under the height of its chain state
         #[arg(long)]
         save_to_gcs: bool,
 
+        /// GCS bucket name for loading/saving proofs
         #[arg(long, default_value = "zoro-proofs")]
         gcs_bucket: String,
 
@@ -40,10 +59,27 @@ enum Commands {
         #[arg(long, default_value = "1")]
         total_blocks: u32,
 
-        /// Step size for each batch
+        /// Step size for each batch, or for the first one with --auto-step-size
         #[arg(long, default_value = "1")]
         step_size: u32,
 
+        /// Choose the largest step size fitting in the budgets from the telemetry of the
+        /// previous batches
+        #[arg(long)]
+        auto_step_size: bool,
+
+        /// Peak memory budget of a batch in MB, with --auto-step-size
+        #[arg(long)]
+        memory_budget_mb: Option<f64>,
+
+        /// Run and prove time budget of a batch in seconds, with --auto-step-size
+        #[arg(long)]
+        time_budget_secs: Option<f64>,
+
+        /// Largest step size chosen with --auto-step-size
+        #[arg(long, default_value = "100")]
+        max_step_size: u32,
+
         /// Output directory for all proofs
         #[arg(long, default_value = ".proofs")]
         output_dir: PathBuf,
@@ -59,14 +95,75 @@ enum Commands {
         #[arg(long)]
         prover_params_file: Option<PathBuf>,
 
+        /// Key of the bridge node's /proving-status, to report progress with heartbeats
+        #[arg(long, env = "PROVER_KEY")]
+        prover_key: Option<String>,
+
         /// Don't delete temporary files after completion
         #[arg(long, default_value = "false")]
         keep_temp_files: bool,
     },
+    /// Delete the proofs of the GCS bucket not kept by the retention policy
+    Gc {
+        /// GCS bucket name
+        #[arg(long, default_value = "zoro-proofs")]
+        gcs_bucket: String,
+
+        /// Number of proofs of the highest chain states kept
+        #[arg(long, default_value = "1")]
+        keep_latest: usize,
+
+        /// Keep the proofs whose height is a multiple of this
+        #[arg(long)]
+        keep_every: Option<u32>,
+
+        /// Keep the proofs created less than this many days ago (development buckets)
+        #[arg(long)]
+        expire_after_days: Option<i64>,
+
+        /// List the proofs that would be deleted without deleting them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Inspect and edit the batches quarantined after failing to prove
+    Quarantine {
+        /// Output directory of the proofs
+        #[arg(long, default_value = ".proofs")]
+        output_dir: PathBuf,
+
+        #[command(subcommand)]
+        command: QuarantineCommand,
+    },
+    /// Find the smallest proof of the GCS bucket whose chain state covers a block
+    FindProof {
+        /// Block height
+        #[arg(long)]
+        height: u32,
+
+        /// GCS bucket name
+        #[arg(long, default_value = "zoro-proofs")]
+        gcs_bucket: String,
+    },
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
+    match run().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{}", report(&error, Locale::from_env()));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run() -> Result<()> {
+    if build_info::verbose_version_requested(std::env::args()) {
+        let info = BuildInfo::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"), &[]);
+        println!("{}", info.verbose_version());
+        return Ok(());
+    }
+
     let cli = Cli::parse();
 
     // Initialize logging - validate and normalize the log level
@@ -108,18 +205,37 @@ async fn main() -> Result<()> {
             gcs_bucket,
             total_blocks,
             step_size,
+            auto_step_size,
+            memory_budget_mb,
+            time_budget_secs,
+            max_step_size,
             output_dir,
             prover_params_file,
+            prover_key,
             keep_temp_files,
         } => {
+            if auto_step_size && memory_budget_mb.is_none() && time_budget_secs.is_none() {
+                return Err(anyhow!(
+                    "--auto-step-size requires --memory-budget-mb or --time-budget-secs"
+                ));
+            }
+            let step_budget = auto_step_size.then_some(StepBudget {
+                memory_mb: memory_budget_mb,
+                time_secs: time_budget_secs,
+                max_step_size,
+            });
             let params = ProveParams {
                 executable,
                 load_from_gcs,
                 save_to_gcs,
                 gcs_bucket,
                 bridge_url: cli.bridge_url,
+                proxy: cli.proxy,
+                prover_key,
+                headers_batch_size: cli.headers_batch_size,
                 total_blocks,
                 step_size,
+                step_budget,
                 output_dir,
                 prover_params_file,
                 keep_temp_files,
@@ -127,6 +243,53 @@ async fn main() -> Result<()> {
 
             prove(params).await?;
         }
+        Commands::Gc {
+            gcs_bucket,
+            keep_latest,
+            keep_every,
+            expire_after_days,
+            dry_run,
+        } => {
+            let policy = RetentionPolicy {
+                keep_latest,
+                keep_every,
+                expire_after: expire_after_days.map(chrono::Duration::days),
+            };
+            let gcs = GcsProofStore::new(&gcs_bucket).await?;
+            gc(&gcs, &policy, dry_run).await?;
+        }
+        Commands::Quarantine {
+            output_dir,
+            command,
+        } => {
+            let mut quarantine = Quarantine::load(&output_dir)?;
+            match command {
+                QuarantineCommand::List => {
+                    println!("{}", serde_json::to_string_pretty(quarantine.batches())?);
+                }
+                QuarantineCommand::SetStep { height, step_size } => {
+                    quarantine.set_step_size(height, step_size)?;
+                }
+                QuarantineCommand::Clear { height } => {
+                    if !quarantine.clear(height)? {
+                        return Err(anyhow!("No quarantined batch starts at height {height}"));
+                    }
+                }
+            }
+        }
+        Commands::FindProof { height, gcs_bucket } => {
+            let gcs = GcsProofStore::new(&gcs_bucket).await?;
+            let object = gcs
+                .find_proof_covering(height)
+                .await?
+                .ok_or_else(|| anyhow!("No proof in gs://{gcs_bucket} covers block {height}"))?;
+            let found = serde_json::json!({
+                "height": object.height,
+                "object": object.name,
+                "url": gcs.public_url(&object.name),
+            });
+            println!("{}", serde_json::to_string_pretty(&found)?);
+        }
     }
 
     Ok(())
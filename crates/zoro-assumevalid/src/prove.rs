@@ -19,6 +19,7 @@ use std::time::Instant;
 use stwo_cairo_adapter::adapter::adapt;
 use stwo_cairo_prover::prover::create_and_serialize_proof;
 use tracing::{debug, error, info, warn};
+use zoro_spv_verify::{ProverHeartbeat, ProvingBatch};
 
 /// Get current memory usage in MB
 fn get_memory_mb() -> f64 {
@@ -27,38 +28,74 @@ fn get_memory_mb() -> f64 {
         .unwrap_or(0.0)
 }
 
+use crate::gcs::GcsProofStore;
 use crate::generate_args::{generate_and_save_args, AssumeValidParams, ProveClient, ProveConfig};
+use crate::quarantine::Quarantine;
+use crate::telemetry::{BatchTelemetry, StepBudget, TelemetryLog};
 use crate::BOOTLOADER_STR;
 
+/// Programs the bootloader runs for every batch, loaded once per proving session
+pub struct ProgramCache {
+    /// Bootloader program, parsed from the embedded resource
+    bootloader: Program,
+    /// Absolute path of the Cairo1 executable JSON file
+    executable: PathBuf,
+}
+
+impl ProgramCache {
+    pub fn load(executable: &Path) -> Result<Self> {
+        // Resolve paths to absolute with helpful error messages
+        let executable = executable.canonicalize().map_err(|e| {
+            anyhow!(
+                "Executable file not found at '{}': {}",
+                executable.display(),
+                e
+            )
+        })?;
+
+        // Load bootloader program from embedded resource
+        let bootloader = Program::from_bytes(BOOTLOADER_STR.as_bytes(), Some("main"))
+            .map_err(|e| anyhow!("Failed to load bootloader program: {e}"))?;
+
+        Ok(Self {
+            bootloader,
+            executable,
+        })
+    }
+}
+
+/// Proof of a batch and the resources used to produce it
+#[derive(Debug, Clone)]
+pub struct ProvedBatch {
+    pub proof_file: PathBuf,
+    /// Cairo VM steps of the bootloader run
+    pub steps: usize,
+    /// Peak resident memory, in MB
+    pub peak_memory_mb: f64,
+    /// Time to run and prove, in seconds
+    pub elapsed_secs: f64,
+}
+
 /// Runs the Cairo program through the bootloader and generates a STARK proof.
 ///
 /// This function:
-/// 1. Loads the bootloader program from embedded resources
-/// 2. Generates the program input JSON for the bootloader
-/// 3. Runs the Cairo VM
-/// 4. Adapts the VM output for the prover
-/// 5. Generates and serializes the STARK proof
+/// 1. Generates the program input JSON for the bootloader
+/// 2. Runs the Cairo VM
+/// 3. Adapts the VM output for the prover
+/// 4. Generates and serializes the STARK proof
 pub fn run_and_prove_with_library(
-    executable: &Path,
+    programs: &ProgramCache,
     arguments_file: &Path,
     output_dir: &Path,
     prover_params: Option<&Path>,
     verify: bool,
-) -> Result<PathBuf> {
+) -> Result<ProvedBatch> {
     let start_time = Instant::now();
     let start_mem = get_memory_mb();
 
     // Create output directory
     fs::create_dir_all(output_dir)?;
 
-    // Resolve paths to absolute with helpful error messages
-    let executable_abs = executable.canonicalize().map_err(|e| {
-        anyhow!(
-            "Executable file not found at '{}': {}",
-            executable.display(),
-            e
-        )
-    })?;
     let args_file = arguments_file.canonicalize().map_err(|e| {
         anyhow!(
             "Arguments file not found at '{}': {}",
@@ -73,7 +110,7 @@ pub fn run_and_prove_with_library(
         "tasks": [
             {
                 "type": "Cairo1Executable",
-                "path": executable_abs.to_string_lossy(),
+                "path": programs.executable.to_string_lossy(),
                 "program_hash_function": "blake",
                 "user_args_file": args_file.to_string_lossy(),
             }
@@ -81,10 +118,6 @@ pub fn run_and_prove_with_library(
     });
     let program_input_str = serde_json::to_string(&program_input)?;
 
-    // Load bootloader program from embedded resource
-    let bootloader_program = Program::from_bytes(BOOTLOADER_STR.as_bytes(), Some("main"))
-        .map_err(|e| anyhow!("Failed to load bootloader program: {e}"))?;
-
     // Configure Cairo VM for proof mode
     let cairo_run_config = get_cairo_run_config(
         &None,                      // no dynamic layout params
@@ -99,16 +132,21 @@ pub fn run_and_prove_with_library(
     debug!("Running Cairo VM...");
     let vm_start = Instant::now();
     let runner = cairo_run_program(
-        &bootloader_program,
+        &programs.bootloader,
         Some(program_input_str),
         cairo_run_config,
     )
     .map_err(|e| anyhow!("Cairo VM execution failed: {e}"))?;
     let vm_elapsed = vm_start.elapsed();
     let vm_mem = get_memory_mb();
+    let steps = runner
+        .get_execution_resources()
+        .map_err(|e| anyhow!("Failed to get execution resources: {e}"))?
+        .n_steps;
     info!(
-        "Cairo VM: {:.2}s, memory: {:.1} MB",
+        "Cairo VM: {:.2}s, {} steps, memory: {:.1} MB",
         vm_elapsed.as_secs_f64(),
+        steps,
         vm_mem
     );
 
@@ -153,7 +191,12 @@ pub fn run_and_prove_with_library(
         start_mem
     );
 
-    Ok(proof_file)
+    Ok(ProvedBatch {
+        proof_file,
+        steps,
+        peak_memory_mb: peak_mem,
+        elapsed_secs: total_elapsed.as_secs_f64(),
+    })
 }
 
 /// Parameters for proving multiple batches iteratively
@@ -161,15 +204,25 @@ pub fn run_and_prove_with_library(
 pub struct ProveParams {
     /// Path to the Cairo1 executable JSON file
     pub executable: PathBuf,
+    /// Resume from the latest proof of the GCS bucket when it is ahead of the output directory
     pub load_from_gcs: bool,
+    /// Upload each proof to the GCS bucket
     pub save_to_gcs: bool,
     /// GCS bucket name for loading/saving proofs
     pub gcs_bucket: String,
     pub bridge_url: String,
+    /// Proxy for the bridge node requests (optional)
+    pub proxy: Option<String>,
+    /// Key of the bridge node's `/proving-status` heartbeats (optional)
+    pub prover_key: Option<String>,
+    /// Maximum number of headers requested from the bridge node at once
+    pub headers_batch_size: u32,
     /// Total number of blocks to process
     pub total_blocks: u32,
-    /// Step size for each batch
+    /// Step size for each batch, or for the first one when tuned by `step_budget`
     pub step_size: u32,
+    /// Choose the step size of each batch from the telemetry of the previous ones (optional)
+    pub step_budget: Option<StepBudget>,
     /// Output directory for all proofs
     pub output_dir: PathBuf,
     /// Path to the prover parameters JSON file (optional)
@@ -244,7 +297,27 @@ pub async fn create_batch_dir(
 
 /// Main function to prove multiple batches iteratively
 pub async fn prove(params: ProveParams) -> Result<()> {
-    let start_height = auto_detect_start_height(&params.output_dir);
+    let gcs = if params.load_from_gcs || params.save_to_gcs {
+        Some(GcsProofStore::new(&params.gcs_bucket).await?)
+    } else {
+        None
+    };
+
+    let mut start_height = auto_detect_start_height(&params.output_dir);
+    if let (true, Some(gcs)) = (params.load_from_gcs, &gcs) {
+        if let Some(latest) = gcs.latest_proof().await? {
+            if latest.height > start_height {
+                // Picked up by `find_proof_file` as the previous proof of the first batch
+                let proof_file = params
+                    .output_dir
+                    .join(format!("gcs_to_{}", latest.height))
+                    .join("proof.json");
+                gcs.download_proof(&latest, &proof_file).await?;
+                info!("Resuming from the GCS proof of height {}", latest.height);
+                start_height = latest.height;
+            }
+        }
+    }
 
     info!(
         "Starting iterative proving process: start_height={}, total_blocks={}, step_size={}",
@@ -258,15 +331,52 @@ pub async fn prove(params: ProveParams) -> Result<()> {
     let end_height = start_height + params.total_blocks;
     let mut current_height = start_height;
 
+    let programs = ProgramCache::load(&params.executable)?;
+    let client = ProveClient::new(ProveConfig {
+        bridge_node_url: params.bridge_url.clone(),
+        proxy: params.proxy.clone(),
+        prover_key: params.prover_key.clone(),
+        headers_batch_size: params.headers_batch_size,
+    })?;
+    // Chain state the next batch starts from, once a batch has been proven
+    let mut chain_state = None;
+    let mut telemetry = TelemetryLog::load(&params.output_dir).await?;
+    let mut quarantine = Quarantine::load(&params.output_dir)?;
+    // End height of the range of a failed batch, and the reduced step size used until it
+    let mut narrowed: Option<(u32, u32)> = None;
+
     // Process batches sequentially
     while current_height < end_height {
-        let current_step = std::cmp::min(params.step_size, end_height - current_height);
+        if narrowed.is_some_and(|(until, _)| current_height >= until) {
+            narrowed = None;
+        }
+        let forced_step = quarantine.step_size(current_height);
+        let step_size = match (forced_step, narrowed) {
+            (Some(step_size), _) => step_size,
+            (None, Some((_, step_size))) => step_size,
+            (None, None) => match &params.step_budget {
+                Some(budget) => budget
+                    .step_size(telemetry.records())
+                    .unwrap_or(params.step_size),
+                None => params.step_size,
+            },
+        };
+        let current_step = std::cmp::min(step_size, end_height - current_height);
         if current_step == 0 {
             break;
         }
 
         let job_info = format!("Job(height='{current_height}', blocks={current_step})");
         info!("{} proving...", job_info);
+        send_heartbeat(
+            &client,
+            current_height,
+            Some(ProvingBatch {
+                start_height: current_height,
+                block_count: current_step,
+            }),
+        )
+        .await;
 
         let batch_dir = create_batch_dir(current_height, current_step, &params.output_dir).await?;
 
@@ -277,20 +387,18 @@ pub async fn prove(params: ProveParams) -> Result<()> {
         debug!("{} generating args...", job_info);
         let args_start_time = Instant::now();
 
-        let config = ProveConfig {
-            bridge_node_url: params.bridge_url.clone(),
-        };
-        let client = ProveClient::new(config);
-
         let assumevalid_params = AssumeValidParams {
             start_height: current_height,
             block_count: current_step,
             chain_state_proof_path,
+            chain_state: chain_state.clone(),
         };
 
         let args_file = batch_dir.join("arguments.json");
         println!("args_file: {}", args_file.to_string_lossy());
-        generate_and_save_args(&client, assumevalid_params, &args_file.to_string_lossy()).await?;
+        let next_chain_state =
+            generate_and_save_args(&client, assumevalid_params, &args_file.to_string_lossy())
+                .await?;
         let args_elapsed = args_start_time.elapsed();
         debug!(
             "{} args generated in {:.2}s",
@@ -300,7 +408,7 @@ pub async fn prove(params: ProveParams) -> Result<()> {
 
         // Prove the batch using the library directly
         let batch_result = run_and_prove_with_library(
-            &params.executable,
+            &programs,
             &args_file,
             &batch_dir,
             params.prover_params_file.as_deref(),
@@ -308,10 +416,27 @@ pub async fn prove(params: ProveParams) -> Result<()> {
         );
 
         match batch_result {
-            Ok(_proof_path) => {
+            Ok(batch) => {
                 info!("{} done", job_info);
 
+                telemetry
+                    .append(BatchTelemetry {
+                        start_height: current_height,
+                        block_count: current_step,
+                        steps: batch.steps,
+                        peak_memory_mb: batch.peak_memory_mb,
+                        elapsed_secs: batch.elapsed_secs,
+                    })
+                    .await?;
+
+                if let (true, Some(gcs)) = (params.save_to_gcs, &gcs) {
+                    gcs.upload_proof(current_height + current_step, &batch.proof_file)
+                        .await?;
+                }
+
                 current_height += current_step;
+                chain_state = Some(next_chain_state);
+                send_heartbeat(&client, current_height, None).await;
 
                 // Clean up temporary files if requested
                 if !params.keep_temp_files {
@@ -321,12 +446,47 @@ pub async fn prove(params: ProveParams) -> Result<()> {
                 }
             }
             Err(e) => {
-                error!("Batch at height {} failed: {}", current_height, e);
-                info!("Stopping further processing due to batch failure");
-                return Err(e);
+                let failures = quarantine
+                    .record_failure(current_height, current_step, &e)?
+                    .failures;
+                error!(
+                    "Batch at height {} failed ({} failures, quarantined in {}): {:#}",
+                    current_height,
+                    failures,
+                    quarantine.path().display(),
+                    e
+                );
+                if forced_step.is_some() || current_step == 1 {
+                    info!("Stopping further processing due to batch failure");
+                    return Err(e);
+                }
+                // Prove the blocks before the failing one in smaller batches
+                let step_size = current_step / 2;
+                narrowed = Some((current_height + current_step, step_size));
+                warn!(
+                    "Retrying heights {} to {} with {} blocks per batch",
+                    current_height,
+                    current_height + current_step,
+                    step_size
+                );
             }
         }
     }
 
     Ok(())
 }
+
+/// Report the proving progress to the bridge node, which does not stop proving if it fails
+async fn send_heartbeat(
+    client: &ProveClient,
+    proven_height: u32,
+    in_progress: Option<ProvingBatch>,
+) {
+    let heartbeat = ProverHeartbeat {
+        proven_height,
+        in_progress,
+    };
+    if let Err(e) = client.send_heartbeat(&heartbeat).await {
+        warn!("Failed to send proving heartbeat: {}", e);
+    }
+}
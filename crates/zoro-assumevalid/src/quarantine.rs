@@ -0,0 +1,172 @@
+//! Quarantine of the batches that failed to prove
+//!
+//! A failed batch is recorded in `quarantine.json` in the output directory, with the error that
+//! made it fail. Since every batch builds on the proof of the previous one, a failed batch cannot
+//! be skipped: the prove loop halves the step size over its range instead, which isolates the
+//! failing block while proving everything before it. The operator can also force the step size of
+//! the batch starting at a quarantined height (`quarantine set-step`), e.g. to prove a
+//! pathological block on its own.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use clap::Subcommand;
+use serde::{Deserialize, Serialize};
+
+/// Name of the quarantine file in the output directory
+pub const QUARANTINE_FILE: &str = "quarantine.json";
+
+#[derive(Subcommand)]
+pub enum QuarantineCommand {
+    /// Print the quarantined batches
+    List,
+    /// Force the step size of the batch starting at a height
+    SetStep {
+        /// Start height of the batch
+        #[arg(long)]
+        height: u32,
+        /// Number of blocks of the batch
+        #[arg(long)]
+        step_size: u32,
+    },
+    /// Remove the batch starting at a height from the quarantine
+    Clear {
+        /// Start height of the batch
+        #[arg(long)]
+        height: u32,
+    },
+}
+
+/// A batch that failed to prove
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantinedBatch {
+    pub start_height: u32,
+    /// Number of blocks of the last failed attempt
+    pub block_count: u32,
+    /// Number of failed attempts
+    pub failures: u32,
+    /// Error of the last failed attempt, with its causes
+    pub last_error: String,
+    pub last_failure: DateTime<Utc>,
+    /// Step size set by the operator for the batch starting at `start_height`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step_size: Option<u32>,
+}
+
+/// Quarantined batches of an output directory, sorted by start height
+pub struct Quarantine {
+    path: PathBuf,
+    batches: Vec<QuarantinedBatch>,
+}
+
+impl Quarantine {
+    pub fn load(output_dir: &Path) -> Result<Self> {
+        let path = output_dir.join(QUARANTINE_FILE);
+        let batches = match std::fs::read(&path) {
+            Ok(contents) => serde_json::from_slice(&contents)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self { path, batches })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn batches(&self) -> &[QuarantinedBatch] {
+        &self.batches
+    }
+
+    /// Step size set by the operator for the batch starting at `start_height`
+    pub fn step_size(&self, start_height: u32) -> Option<u32> {
+        self.get(start_height).and_then(|batch| batch.step_size)
+    }
+
+    /// Record a failed attempt to prove `block_count` blocks after `start_height`
+    pub fn record_failure(
+        &mut self,
+        start_height: u32,
+        block_count: u32,
+        error: &anyhow::Error,
+    ) -> Result<&QuarantinedBatch> {
+        let index = match self.index(start_height) {
+            Ok(index) => index,
+            Err(index) => {
+                self.batches.insert(
+                    index,
+                    QuarantinedBatch {
+                        start_height,
+                        block_count,
+                        failures: 0,
+                        last_error: String::new(),
+                        last_failure: Utc::now(),
+                        step_size: None,
+                    },
+                );
+                index
+            }
+        };
+        let batch = &mut self.batches[index];
+        batch.block_count = block_count;
+        batch.failures += 1;
+        batch.last_error = format!("{error:#}");
+        batch.last_failure = Utc::now();
+        self.save()?;
+        Ok(&self.batches[index])
+    }
+
+    /// Force the step size of the batch starting at `start_height`, quarantining it if needed
+    pub fn set_step_size(&mut self, start_height: u32, step_size: u32) -> Result<()> {
+        if step_size == 0 {
+            bail!("Step size must be positive");
+        }
+        match self.index(start_height) {
+            Ok(index) => self.batches[index].step_size = Some(step_size),
+            Err(index) => self.batches.insert(
+                index,
+                QuarantinedBatch {
+                    start_height,
+                    block_count: step_size,
+                    failures: 0,
+                    last_error: String::new(),
+                    last_failure: Utc::now(),
+                    step_size: Some(step_size),
+                },
+            ),
+        }
+        self.save()
+    }
+
+    /// Remove the batch starting at `start_height`, returning whether it was quarantined
+    pub fn clear(&mut self, start_height: u32) -> Result<bool> {
+        match self.index(start_height) {
+            Ok(index) => {
+                self.batches.remove(index);
+                self.save()?;
+                Ok(true)
+            }
+            Err(_) => Ok(false),
+        }
+    }
+
+    fn get(&self, start_height: u32) -> Option<&QuarantinedBatch> {
+        self.index(start_height)
+            .ok()
+            .map(|index| &self.batches[index])
+    }
+
+    fn index(&self, start_height: u32) -> Result<usize, usize> {
+        self.batches
+            .binary_search_by_key(&start_height, |batch| batch.start_height)
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(&self.batches)?)?;
+        Ok(())
+    }
+}
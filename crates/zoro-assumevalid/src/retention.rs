@@ -0,0 +1,83 @@
+//! Retention of the proofs stored in GCS, applied by the `gc` command
+//!
+//! A proof is kept if any rule of the policy keeps it, and deleted otherwise. The latest proof
+//! is always kept, since it is the one proving resumes from.
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Duration, Utc};
+use tracing::info;
+
+use crate::gcs::{GcsProofStore, ListedProof};
+
+/// Which proofs of the bucket to keep
+#[derive(Debug, Clone)]
+pub struct RetentionPolicy {
+    /// Number of proofs of the highest chain states kept (at least 1)
+    pub keep_latest: usize,
+    /// Keep the proofs whose height is a multiple of this, as long-term checkpoints
+    pub keep_every: Option<u32>,
+    /// Keep the proofs created less than this long ago; older ones expire unless another rule
+    /// keeps them (meant for development buckets)
+    pub expire_after: Option<Duration>,
+}
+
+impl RetentionPolicy {
+    /// Whether the proof at `index` of `proofs` (sorted by increasing height) is kept at `now`
+    fn keeps(&self, proofs: &[ListedProof], index: usize, now: DateTime<Utc>) -> bool {
+        let proof = &proofs[index];
+        index + self.keep_latest >= proofs.len()
+            || self
+                .keep_every
+                .is_some_and(|every| proof.object.height % every == 0)
+            || self
+                .expire_after
+                .is_some_and(|expire_after| now - proof.created < expire_after)
+    }
+
+    /// Select the proofs to delete among `proofs`, sorted by increasing height
+    pub fn expired<'a>(
+        &self,
+        proofs: &'a [ListedProof],
+        now: DateTime<Utc>,
+    ) -> Vec<&'a ListedProof> {
+        (0..proofs.len())
+            .filter(|&index| !self.keeps(proofs, index, now))
+            .map(|index| &proofs[index])
+            .collect()
+    }
+}
+
+/// Delete the proofs of the bucket not kept by `policy`, returning how many were deleted (or
+/// would be, on a dry run)
+pub async fn gc(store: &GcsProofStore, policy: &RetentionPolicy, dry_run: bool) -> Result<usize> {
+    if policy.keep_latest == 0 {
+        bail!("The retention policy must keep at least the latest proof");
+    }
+    if policy.keep_every == Some(0) {
+        bail!("Checkpoint interval must be positive");
+    }
+    let proofs = store.list_proofs().await?;
+    let expired = policy.expired(&proofs, Utc::now());
+    for proof in &expired {
+        if dry_run {
+            info!(
+                "Would delete {} (created {})",
+                proof.object.name, proof.created
+            );
+        } else {
+            store.delete_proof(&proof.object).await?;
+            info!("Deleted {} (created {})", proof.object.name, proof.created);
+        }
+    }
+    info!(
+        "{} of {} proofs {}",
+        expired.len(),
+        proofs.len(),
+        if dry_run {
+            "would be deleted"
+        } else {
+            "deleted"
+        }
+    );
+    Ok(expired.len())
+}
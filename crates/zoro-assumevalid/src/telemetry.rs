@@ -0,0 +1,116 @@
+//! Per-batch proving telemetry, and the step size it suggests
+//!
+//! Every proven batch appends a record to `telemetry.jsonl` in the output directory. With a
+//! [`StepBudget`], the step size of the next batch is the largest one whose memory and time,
+//! extrapolated from the per-block cost of the recent batches, fit in the budget.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tracing::warn;
+
+/// Name of the telemetry file in the output directory
+pub const TELEMETRY_FILE: &str = "telemetry.jsonl";
+
+/// Number of the most recent batches the step size is derived from
+const RECENT_BATCHES: usize = 20;
+
+/// Resources used to prove a batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchTelemetry {
+    pub start_height: u32,
+    pub block_count: u32,
+    /// Cairo VM steps of the bootloader run
+    pub steps: usize,
+    /// Peak resident memory while running and proving, in MB
+    pub peak_memory_mb: f64,
+    /// Time to run and prove the batch, in seconds
+    pub elapsed_secs: f64,
+}
+
+/// Limits on the resources of a single batch
+#[derive(Debug, Clone)]
+pub struct StepBudget {
+    /// Peak memory limit, in MB
+    pub memory_mb: Option<f64>,
+    /// Run and prove time limit, in seconds
+    pub time_secs: Option<f64>,
+    /// Largest step size chosen
+    pub max_step_size: u32,
+}
+
+impl StepBudget {
+    /// Largest step size fitting in the budget given the `records` of previous batches, or
+    /// `None` without any record
+    pub fn step_size(&self, records: &[BatchTelemetry]) -> Option<u32> {
+        let recent = &records[records.len().saturating_sub(RECENT_BATCHES)..];
+        if recent.is_empty() {
+            return None;
+        }
+        // The worst per-block cost of the recent batches, so that base costs which do not grow
+        // with the batch size are accounted for pessimistically.
+        let per_block = |cost: fn(&BatchTelemetry) -> f64| {
+            recent
+                .iter()
+                .filter(|record| record.block_count > 0)
+                .map(|record| cost(record) / record.block_count as f64)
+                .fold(0.0, f64::max)
+        };
+        let fit = |budget: Option<f64>, per_block: f64| match budget {
+            Some(budget) if per_block > 0.0 => (budget / per_block).floor(),
+            _ => f64::INFINITY,
+        };
+        let blocks = fit(self.memory_mb, per_block(|record| record.peak_memory_mb))
+            .min(fit(self.time_secs, per_block(|record| record.elapsed_secs)));
+        Some((blocks.min(self.max_step_size as f64) as u32).max(1))
+    }
+}
+
+/// Telemetry records of the batches proven into an output directory
+pub struct TelemetryLog {
+    path: PathBuf,
+    records: Vec<BatchTelemetry>,
+}
+
+impl TelemetryLog {
+    /// Load the records of `output_dir`, skipping lines that don't parse
+    pub async fn load(output_dir: &Path) -> Result<Self> {
+        let path = output_dir.join(TELEMETRY_FILE);
+        let records = match tokio::fs::read_to_string(&path).await {
+            Ok(contents) => contents
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .filter_map(|line| match serde_json::from_str(line) {
+                    Ok(record) => Some(record),
+                    Err(e) => {
+                        warn!("Skipping telemetry record of {}: {}", path.display(), e);
+                        None
+                    }
+                })
+                .collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self { path, records })
+    }
+
+    pub fn records(&self) -> &[BatchTelemetry] {
+        &self.records
+    }
+
+    /// Append a record to the log and its file
+    pub async fn append(&mut self, record: BatchTelemetry) -> Result<()> {
+        let mut line = serde_json::to_string(&record)?;
+        line.push('\n');
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(line.as_bytes()).await?;
+        self.records.push(record);
+        Ok(())
+    }
+}
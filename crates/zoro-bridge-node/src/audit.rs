@@ -0,0 +1,139 @@
+//! Append-only audit log of the proofs served by the RPC server
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use blake2::{Blake2s256, Digest};
+use serde::Serialize;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+/// Configuration of the audit log
+#[derive(Debug, Clone)]
+pub struct AuditLogConfig {
+    /// JSONL file the records are appended to
+    pub path: PathBuf,
+    /// The file is rotated once it would grow beyond this size
+    pub max_bytes: u64,
+    /// Number of rotated files kept (`<path>.1` is the most recent)
+    pub keep: usize,
+}
+
+/// Record of a served proof
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditRecord {
+    /// Unix timestamp in seconds
+    pub timestamp: u64,
+    /// Endpoint that served the proof
+    pub endpoint: &'static str,
+    /// Identifier requested by the client (block hash or transaction identifier)
+    pub identifier: String,
+    /// Canonical txid of the proven transaction, in display order
+    pub txid: Option<String>,
+    /// Height of the proven block
+    pub block_height: u32,
+    /// Chain height the block inclusion proof was generated against
+    pub chain_height: Option<u32>,
+    /// FlyClient MMR root recorded at the chain height
+    pub mmr_root: Option<String>,
+    /// Hex-encoded BLAKE2s-256 digest of the JSON response body
+    pub response_hash: String,
+}
+
+impl AuditRecord {
+    /// Create a record for a response, hashing its JSON body
+    pub fn new<T: Serialize>(
+        endpoint: &'static str,
+        identifier: &str,
+        block_height: u32,
+        response: &T,
+    ) -> Result<Self, serde_json::Error> {
+        // Same serialization as the `Json` response body
+        let body = serde_json::to_vec(response)?;
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        Ok(Self {
+            timestamp,
+            endpoint,
+            identifier: identifier.to_string(),
+            txid: None,
+            block_height,
+            chain_height: None,
+            mmr_root: None,
+            response_hash: hex::encode(Blake2s256::digest(&body)),
+        })
+    }
+}
+
+/// Audit log writer, rotating the file by size
+#[derive(Debug)]
+pub struct AuditLog {
+    config: AuditLogConfig,
+    /// Size of the current file, serializing appends and rotations
+    size: Mutex<u64>,
+}
+
+impl AuditLog {
+    pub async fn new(config: AuditLogConfig) -> Result<Self, std::io::Error> {
+        if let Some(parent) = config.path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let size = match fs::metadata(&config.path).await {
+            Ok(metadata) => metadata.len(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => 0,
+            Err(e) => return Err(e),
+        };
+        Ok(Self {
+            config,
+            size: Mutex::new(size),
+        })
+    }
+
+    /// Append a record, rotating the file first if it would grow beyond the maximum size
+    pub async fn append(&self, record: &AuditRecord) -> Result<(), anyhow::Error> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+
+        let mut size = self.size.lock().await;
+        if *size > 0 && *size + line.len() as u64 > self.config.max_bytes {
+            self.rotate().await?;
+            *size = 0;
+        }
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.config.path)
+            .await?;
+        file.write_all(&line).await?;
+        file.flush().await?;
+        *size += line.len() as u64;
+        Ok(())
+    }
+
+    /// Shift `<path>.N` to `<path>.N+1`, dropping the oldest, and move the current file to
+    /// `<path>.1`
+    async fn rotate(&self) -> Result<(), std::io::Error> {
+        if self.config.keep == 0 {
+            return fs::remove_file(&self.config.path).await;
+        }
+        let oldest = rotated_path(&self.config.path, self.config.keep);
+        if fs::try_exists(&oldest).await? {
+            fs::remove_file(&oldest).await?;
+        }
+        for n in (1..self.config.keep).rev() {
+            let from = rotated_path(&self.config.path, n);
+            if fs::try_exists(&from).await? {
+                fs::rename(&from, rotated_path(&self.config.path, n + 1)).await?;
+            }
+        }
+        fs::rename(&self.config.path, rotated_path(&self.config.path, 1)).await
+    }
+}
+
+fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{n}"));
+    PathBuf::from(name)
+}
@@ -12,9 +12,11 @@ use accumulators::{
 use clap::{command, Parser};
 use primitive_types::U256;
 use zcash_history::{NodeData, Version, V1};
-use zoro_zcash_client::ZcashClient;
+use zoro_chain_params::{Network, NetworkUpgrade};
+use zoro_spv_verify::build_info::{self, BuildInfo};
+use zoro_zcash_client::{InternalOrderHash, ZcashClient};
 
-const HEARTWOOD_ACTIVATION: u32 = 903_000;
+const HEARTWOOD_ACTIVATION: u32 = Network::Mainnet.activation_height(NetworkUpgrade::Heartwood);
 
 /// Compute root directly using zcash_history for debugging
 async fn compute_zcash_root_directly(mmr: &MMR) -> String {
@@ -49,25 +51,16 @@ async fn compute_zcash_root_directly(mmr: &MMR) -> String {
     }
 
     // Return the hash of the bagged root
-    let mut hash = V1::hash(&bagged);
-    hash.reverse(); // big-endian for display
-    hex::encode(hash)
-}
-
-mod branch_id {
-    pub const HEARTWOOD: u32 = 0xf5b9230b;
-    pub const CANOPY: u32 = 0xe9ff75a6;
-    pub const NU5: u32 = 0xc2d6d0b4;
+    InternalOrderHash(V1::hash(&bagged))
+        .to_display()
+        .to_string()
 }
 
 fn branch_id_for_height(height: u32) -> u32 {
-    if height >= 1_687_104 {
-        branch_id::NU5
-    } else if height >= 1_046_400 {
-        branch_id::CANOPY
-    } else {
-        branch_id::HEARTWOOD
-    }
+    Network::Mainnet
+        .flyclient_epoch(height.max(HEARTWOOD_ACTIVATION))
+        .map_or(NetworkUpgrade::Heartwood, |epoch| epoch.upgrade)
+        .branch_id()
 }
 
 fn work_from_bits(bits: u32) -> U256 {
@@ -131,6 +124,16 @@ struct Cli {
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    if build_info::verbose_version_requested(std::env::args()) {
+        let info = BuildInfo::new(
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION"),
+            &[("sqlcipher", cfg!(feature = "sqlcipher"))],
+        );
+        println!("{}", info.verbose_version());
+        return Ok(());
+    }
+
     let cli = Cli::parse();
 
     println!("FlyClient Root Verification");
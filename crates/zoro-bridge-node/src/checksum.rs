@@ -0,0 +1,266 @@
+//! Checksums of the accumulator store rows (FlyClient MMR nodes and counters)
+//!
+//! Every key-value row written through this module gets a checksum of its key and value in the
+//! `accumulator_checksums` table, within the same transaction. Reads verify the rows against their
+//! checksum, so that a corrupted node fails the request with an explicit error instead of ending
+//! up in a proof that clients reject. Rows written before checksums were introduced have none and
+//! are read unverified.
+
+use std::collections::HashMap;
+use std::ops::DerefMut;
+
+use accumulators::store::{sqlite::SQLiteStore, Store as AccumulatorsStore, StoreError};
+use async_trait::async_trait;
+use blake2::{Blake2s256, Digest};
+use serde::Serialize;
+use sqlx::Row;
+
+use crate::store::{begin_transaction, commit_transaction, rollback_transaction};
+
+/// Checksum of every accumulator row, by key
+pub const CREATE_CHECKSUMS_TABLE: &str = r#"CREATE TABLE IF NOT EXISTS accumulator_checksums (
+    key TEXT PRIMARY KEY,
+    checksum INTEGER NOT NULL
+);"#;
+
+/// Number of rows verified per query by [`verify_all`]
+const VERIFY_BATCH: u32 = 1000;
+
+/// Checksum of a row: the first 8 bytes of the BLAKE2s-256 digest of its key and value. The key
+/// is covered so that a value moved to another key is detected as well.
+pub fn row_checksum(key: &str, value: &str) -> i64 {
+    let digest = Blake2s256::new()
+        .chain_update(key.as_bytes())
+        .chain_update([0])
+        .chain_update(value.as_bytes())
+        .finalize();
+    i64::from_le_bytes(digest[..8].try_into().expect("digest is 32 bytes"))
+}
+
+fn corrupted(key: &str, reason: &str) -> StoreError {
+    StoreError::Custom(
+        format!(
+            "Corrupted accumulator row {key}: {reason} (`zoro-bridge-node db verify-checksums` \
+             lists the corrupted rows)"
+        )
+        .into(),
+    )
+}
+
+/// Get the checksums recorded for `keys`
+async fn get_checksums(
+    store: &SQLiteStore,
+    keys: &[&str],
+) -> Result<HashMap<String, i64>, StoreError> {
+    if keys.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let sql = format!(
+        "SELECT key, checksum FROM accumulator_checksums WHERE key IN ({})",
+        vec!["?"; keys.len()].join(", ")
+    );
+    let mut query = sqlx::query(&sql);
+    for key in keys {
+        query = query.bind(*key);
+    }
+    let mut conn = store.acquire_connection().await?;
+    let rows = query.fetch_all(conn.deref_mut()).await?;
+    Ok(rows
+        .iter()
+        .map(|row| (row.get("key"), row.get("checksum")))
+        .collect())
+}
+
+/// Check the values read for `keys` against their checksums
+fn verify(
+    keys: &[&str],
+    values: &HashMap<String, String>,
+    checksums: &HashMap<String, i64>,
+) -> Result<(), StoreError> {
+    for key in keys {
+        match (values.get(*key), checksums.get(*key)) {
+            (Some(value), Some(checksum)) if row_checksum(key, value) != *checksum => {
+                return Err(corrupted(key, "checksum mismatch"));
+            }
+            (None, Some(_)) => return Err(corrupted(key, "row is missing")),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Run `writes` in a transaction, or a savepoint if one is already in progress on the
+/// single-writer connection
+async fn atomically<T>(
+    store: &SQLiteStore,
+    writes: impl std::future::Future<Output = Result<T, StoreError>>,
+) -> Result<T, StoreError> {
+    begin_transaction(store).await?;
+    match writes.await {
+        Ok(res) => {
+            commit_transaction(store).await?;
+            Ok(res)
+        }
+        Err(e) => {
+            rollback_transaction(store).await?;
+            Err(e)
+        }
+    }
+}
+
+/// Read rows and their checksums, verifying them
+async fn read_verified(
+    store: &SQLiteStore,
+    keys: &[&str],
+) -> Result<HashMap<String, String>, StoreError> {
+    let values = store.get_many(keys.to_vec()).await?;
+    verify(keys, &values, &get_checksums(store, keys).await?)?;
+    Ok(values)
+}
+
+/// Get a row, verifying its checksum
+pub async fn get(store: &SQLiteStore, key: &str) -> Result<Option<String>, StoreError> {
+    Ok(get_many(store, vec![key]).await?.remove(key))
+}
+
+/// Get rows, verifying their checksums.
+///
+/// Rows failing verification are read once more, since the indexer may rewrite a row (the MMR
+/// counters) between the reads of its value and of its checksum.
+pub async fn get_many(
+    store: &SQLiteStore,
+    keys: Vec<&str>,
+) -> Result<HashMap<String, String>, StoreError> {
+    match read_verified(store, &keys).await {
+        Ok(values) => Ok(values),
+        Err(_) => read_verified(store, &keys).await,
+    }
+}
+
+/// Write rows along with their checksums
+pub async fn set_many(
+    store: &SQLiteStore,
+    entries: HashMap<String, String>,
+) -> Result<(), StoreError> {
+    let checksums: Vec<_> = entries
+        .iter()
+        .map(|(key, value)| (key.clone(), row_checksum(key, value)))
+        .collect();
+    atomically(store, async {
+        store.set_many(entries).await?;
+        let mut conn = store.acquire_connection().await?;
+        for (key, checksum) in checksums {
+            sqlx::query(
+                "INSERT OR REPLACE INTO accumulator_checksums (key, checksum) VALUES (?, ?)",
+            )
+            .bind(key)
+            .bind(checksum)
+            .execute(conn.deref_mut())
+            .await?;
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// Delete rows along with their checksums
+pub async fn delete_many(store: &SQLiteStore, keys: Vec<&str>) -> Result<(), StoreError> {
+    atomically(store, async {
+        store.delete_many(keys.clone()).await?;
+        let mut conn = store.acquire_connection().await?;
+        for key in keys {
+            sqlx::query("DELETE FROM accumulator_checksums WHERE key = ?")
+                .bind(key)
+                .execute(conn.deref_mut())
+                .await?;
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// Result of [`verify_all`]
+#[derive(Debug, Default, Serialize)]
+pub struct ChecksumReport {
+    /// Number of rows with a checksum
+    pub checked: u64,
+    /// Keys of the rows whose value does not match their checksum, or that are missing
+    pub corrupted: Vec<String>,
+}
+
+/// Verify every row that has a checksum
+pub async fn verify_all(store: &SQLiteStore) -> Result<ChecksumReport, StoreError> {
+    let mut report = ChecksumReport::default();
+    let mut last_key = String::new();
+    loop {
+        let rows = {
+            let mut conn = store.acquire_connection().await?;
+            sqlx::query(
+                "SELECT key, checksum FROM accumulator_checksums WHERE key > ? ORDER BY key LIMIT ?",
+            )
+            .bind(&last_key)
+            .bind(VERIFY_BATCH)
+            .fetch_all(conn.deref_mut())
+            .await?
+        };
+        let Some(last) = rows.last() else {
+            return Ok(report);
+        };
+        last_key = last.get("key");
+        let checksums: HashMap<String, i64> = rows
+            .iter()
+            .map(|row| (row.get("key"), row.get("checksum")))
+            .collect();
+        let keys: Vec<&str> = checksums.keys().map(String::as_str).collect();
+        let values = store.get_many(keys.clone()).await?;
+        for key in keys {
+            if verify(&[key], &values, &checksums).is_err() {
+                report.corrupted.push(key.to_string());
+            }
+        }
+        report.checked += rows.len() as u64;
+    }
+}
+
+/// Accumulator store writing and verifying row checksums, backing the FlyClient MMRs of the
+/// indexer
+#[derive(Debug)]
+pub struct ChecksummedStore {
+    inner: SQLiteStore,
+}
+
+impl ChecksummedStore {
+    pub fn new(inner: SQLiteStore) -> Self {
+        Self { inner }
+    }
+
+    /// Underlying store, e.g. to group writes into a transaction
+    pub fn inner(&self) -> &SQLiteStore {
+        &self.inner
+    }
+}
+
+#[async_trait]
+impl AccumulatorsStore for ChecksummedStore {
+    fn id(&self) -> String {
+        self.inner.id()
+    }
+    async fn get(&self, key: &str) -> Result<Option<String>, StoreError> {
+        get(&self.inner, key).await
+    }
+    async fn get_many(&self, keys: Vec<&str>) -> Result<HashMap<String, String>, StoreError> {
+        get_many(&self.inner, keys).await
+    }
+    async fn set(&self, key: &str, value: &str) -> Result<(), StoreError> {
+        set_many(&self.inner, HashMap::from([(key.into(), value.into())])).await
+    }
+    async fn set_many(&self, entries: HashMap<String, String>) -> Result<(), StoreError> {
+        set_many(&self.inner, entries).await
+    }
+    async fn delete(&self, key: &str) -> Result<(), StoreError> {
+        delete_many(&self.inner, vec![key]).await
+    }
+    async fn delete_many(&self, keys: Vec<&str>) -> Result<(), StoreError> {
+        delete_many(&self.inner, keys).await
+    }
+}
@@ -0,0 +1,90 @@
+//! Coalescing of concurrent upstream requests
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::OnceCell;
+
+/// Runs a single upstream request for concurrent callers asking for the same key.
+///
+/// Callers arriving while a request is in flight wait for its result instead of issuing their
+/// own. Results are not cached: once the request completes, the next caller starts a new one.
+#[derive(Debug)]
+pub struct Coalescer<K, V> {
+    in_flight: Mutex<HashMap<K, Arc<OnceCell<V>>>>,
+}
+
+impl<K, V> Default for Coalescer<K, V> {
+    fn default() -> Self {
+        Self {
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> Coalescer<K, V> {
+    /// Get the value for `key`, running `request` unless a request for it is already in flight.
+    ///
+    /// If the caller running the request is cancelled, one of the waiting callers runs its own.
+    pub async fn run<F, Fut>(&self, key: K, request: F) -> V
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = V>,
+    {
+        let cell = self
+            .in_flight
+            .lock()
+            .unwrap()
+            .entry(key.clone())
+            .or_default()
+            .clone();
+        let value = cell.get_or_init(request).await.clone();
+
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if in_flight
+            .get(&key)
+            .is_some_and(|current| Arc::ptr_eq(current, &cell))
+        {
+            in_flight.remove(&key);
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_concurrent_calls_share_one_request() {
+        let coalescer = Coalescer::default();
+        let calls = AtomicUsize::new(0);
+        let calls = &calls;
+        let request = move || async move {
+            calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            calls.load(Ordering::SeqCst)
+        };
+
+        let (first, second, other) = tokio::join!(
+            coalescer.run("block", request),
+            coalescer.run("block", request),
+            coalescer.run("other block", request),
+        );
+        // One request per key, the second caller got the result of the first
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(first, second);
+        assert_ne!(first, 0);
+        assert_ne!(other, 0);
+
+        // Results are not cached once the request completed
+        coalescer.run("block", request).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert!(coalescer.in_flight.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,184 @@
+//! Periodic cross-verification of historical FlyClient MMR roots against the Zcash node
+//!
+//! The indexer only compares the root of the block it just indexed with `blockcommitments`. A
+//! root corrupted afterwards in the store (bad disk, manual edit, faulty migration) would go
+//! unnoticed until a client fails to verify a proof. The cross-checker picks a random indexed
+//! height on every tick and compares its recorded root with the commitment of the next block.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::broadcast;
+use tracing::{debug, error, info};
+use zoro_zcash_client::{RpcAuth, RpcTimeouts, ZcashClient};
+
+use crate::{
+    chain_state::ChainStateStore, db_key::DbKey, health::AppHealth, indexer::HEARTWOOD_ACTIVATION,
+    metrics::QueryMetrics, rpc::epoch_start_height, store::AppStore,
+};
+
+/// Configuration of the cross-checker
+#[derive(Debug, Clone)]
+pub struct CrossCheckConfig {
+    /// Zcash RPC URL
+    pub rpc_url: String,
+    /// Zcash RPC credentials
+    pub rpc_auth: RpcAuth,
+    /// Zcash RPC request timeouts
+    pub rpc_timeouts: RpcTimeouts,
+    /// Path to the database storing the app state
+    pub db_path: PathBuf,
+    /// Accumulator store ID
+    pub id: String,
+    /// Database encryption key (optional)
+    pub db_key: Option<DbKey>,
+    /// Query metrics of the cross-checker store
+    pub query_metrics: Arc<QueryMetrics>,
+    /// Delay between two checks
+    pub interval: Duration,
+}
+
+/// Outcome of a check of the root recorded at a height
+#[derive(Debug, PartialEq, Eq)]
+pub enum CheckOutcome {
+    /// The recorded root matches the commitment of the next block
+    Match,
+    /// The recorded root differs from the commitment of the next block
+    Mismatch { recorded: String, expected: String },
+}
+
+/// Background task comparing the FlyClient MMR roots recorded at random heights with the
+/// commitments of the Zcash node
+pub struct CrossChecker {
+    config: CrossCheckConfig,
+    health: Arc<AppHealth>,
+    rx_shutdown: broadcast::Receiver<()>,
+}
+
+impl CrossChecker {
+    pub fn new(
+        config: CrossCheckConfig,
+        health: Arc<AppHealth>,
+        rx_shutdown: broadcast::Receiver<()>,
+    ) -> Self {
+        health.crosscheck_enabled();
+        Self {
+            config,
+            health,
+            rx_shutdown,
+        }
+    }
+
+    async fn run_inner(&mut self) -> Result<(), anyhow::Error> {
+        info!(
+            "Starting FlyClient root cross-checks every {:?}",
+            self.config.interval
+        );
+        let store = AppStore::multiple_concurrent_readers(
+            &self.config.db_path,
+            Some(self.config.id.clone()),
+            self.config.db_key.as_ref(),
+            self.config.query_metrics.clone(),
+        )?;
+        let zcash_client =
+            ZcashClient::with_auth(self.config.rpc_url.clone(), self.config.rpc_auth.clone())
+                .await?
+                .with_timeouts(self.config.rpc_timeouts.clone())?;
+
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(self.config.interval) => {},
+                _ = self.rx_shutdown.recv() => return Ok(()),
+            }
+            let head = match store.get_latest_chain_state_height().await {
+                Ok(head) => head,
+                Err(_) => {
+                    debug!("Skipping FlyClient root cross-check: no block indexed yet");
+                    continue;
+                }
+            };
+            let Some(height) = pick_height(head, random_u64()) else {
+                debug!("Skipping FlyClient root cross-check: no root committed below {head}");
+                continue;
+            };
+            match check_height(&store, &zcash_client, height).await {
+                Ok(CheckOutcome::Match) => {
+                    debug!("FlyClient root ✓ at height {} (cross-check)", height);
+                    self.health.crosscheck_passed();
+                }
+                Ok(CheckOutcome::Mismatch { recorded, expected }) => {
+                    error!(
+                        "FlyClient root MISMATCH at height {} (cross-check), the store may be corrupted!",
+                        height
+                    );
+                    error!("  Recorded: {}", recorded);
+                    error!("  Expected: {}", expected);
+                    self.health.crosscheck_failed(height);
+                }
+                Err(e) => debug!("Could not cross-check FlyClient root at {}: {e}", height),
+            }
+        }
+    }
+
+    pub async fn run(&mut self) -> Result<(), ()> {
+        match self.run_inner().await {
+            Err(err) => {
+                error!("FlyClient root cross-checker exited: {}", err);
+                Err(())
+            }
+            Ok(()) => {
+                info!("FlyClient root cross-checker terminated");
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Compare the root recorded at `height` with the commitment of the block at `height + 1`
+async fn check_height(
+    store: &AppStore,
+    zcash_client: &ZcashClient,
+    height: u32,
+) -> Result<CheckOutcome, anyhow::Error> {
+    let record = store
+        .get_mmr_root(height)
+        .await
+        .map_err(|e| anyhow::anyhow!("No MMR root recorded at {height}: {e}"))?;
+    // The commitment of the next block in the epoch covers the blocks up to `height`
+    let verify_height = epoch_start_height(height) + record.leaf_count as u32;
+    let expected = zcash_client.get_block_commitment(verify_height).await?;
+    if expected == record.root {
+        Ok(CheckOutcome::Match)
+    } else {
+        Ok(CheckOutcome::Mismatch {
+            recorded: record.root,
+            expected,
+        })
+    }
+}
+
+/// Height to check below the indexed `head`, drawn from `random`.
+///
+/// Only heights whose root is committed by an indexed block are candidates: the last block of an
+/// epoch is excluded, since the first block of the next epoch commits to the root of a new MMR.
+fn pick_height(head: u32, random: u64) -> Option<u32> {
+    if head <= HEARTWOOD_ACTIVATION {
+        return None;
+    }
+    let candidates = head - HEARTWOOD_ACTIVATION;
+    let mut height = HEARTWOOD_ACTIVATION + (random % candidates as u64) as u32;
+    if epoch_start_height(height + 1) == height + 1 {
+        // Fall back to the previous height, always in the same epoch since epochs are longer
+        // than one block
+        height -= 1;
+    }
+    Some(height)
+}
+
+/// Random number from fresh random hasher keys, enough to spread the checks over heights
+fn random_u64() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
@@ -0,0 +1,145 @@
+//! Store inspection commands (`zoro-bridge-node db`), run against a read-only store
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::Context;
+use clap::Subcommand;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    chain_state::ChainStateStore, db_key::DbKey, metrics::QueryMetrics, mmr_audit, store::AppStore,
+};
+
+#[derive(Subcommand)]
+pub enum DbCommand {
+    /// Print the latest indexed height and its block hash
+    Head,
+    /// Print the block header at a height
+    GetHeader { height: u32 },
+    /// Print the chain state at a height
+    GetChainState { height: u32 },
+    /// Print the FlyClient MMR root recorded at a height (defaults to the latest indexed height)
+    MmrRoot { height: Option<u32> },
+    /// Print the schema version, the latest indexed height, the file size and the number of
+    /// rows of each table
+    Stats,
+    /// Verify the checksums of the FlyClient MMR rows, listing the corrupted ones
+    VerifyChecksums,
+    /// Recompute the block header MMR from the stored block headers and compare its roots with
+    /// the sparse roots shard files, reporting the first divergent shard
+    AuditMmr {
+        /// Directory of the sparse roots shard files
+        #[arg(long)]
+        sparse_roots_dir: PathBuf,
+        /// First height to audit, starting from the roots stored at the previous height
+        #[arg(long, default_value = "0")]
+        from: u32,
+        /// Last height to audit (defaults to the last height of the shard index)
+        #[arg(long)]
+        to: Option<u32>,
+    },
+}
+
+/// Run a store inspection command, printing its result as JSON on stdout
+pub async fn run(
+    command: &DbCommand,
+    db_path: &Path,
+    id: &str,
+    key: Option<&DbKey>,
+) -> Result<(), anyhow::Error> {
+    if !db_path.exists() {
+        anyhow::bail!("Database {} does not exist", db_path.display());
+    }
+    let store = AppStore::multiple_concurrent_readers(
+        db_path,
+        Some(id.to_string()),
+        key,
+        Arc::new(QueryMetrics::new("db", None)),
+    )?;
+
+    match command {
+        DbCommand::Head => {
+            let height = get_head(&store).await?;
+            let header = get_header(&store, height).await?;
+            print(&json!({ "height": height, "hash": header.hash().to_string() }))
+        }
+        DbCommand::GetHeader { height } => print(&get_header(&store, *height).await?),
+        DbCommand::GetChainState { height } => print(
+            &store
+                .get_chain_state(*height)
+                .await
+                .with_context(|| format!("No chain state at height {height}"))?,
+        ),
+        DbCommand::MmrRoot { height } => {
+            let height = match height {
+                Some(height) => *height,
+                None => get_head(&store).await?,
+            };
+            print(
+                &store
+                    .get_mmr_root(height)
+                    .await
+                    .with_context(|| format!("No FlyClient MMR root at height {height}"))?,
+            )
+        }
+        DbCommand::Stats => {
+            let head = store.get_latest_chain_state_height().await.ok();
+            let file_size = std::fs::metadata(db_path)?.len();
+            let tables = store.table_row_counts().await?;
+            print(&json!({
+                "schema_version": store.schema_version().await?,
+                "head": head,
+                "file_size": file_size,
+                "tables": tables
+                    .into_iter()
+                    .map(|(table, rows)| (table, rows.into()))
+                    .collect::<serde_json::Map<_, _>>(),
+            }))
+        }
+        DbCommand::VerifyChecksums => {
+            let report = store.verify_checksums().await?;
+            print(&report)?;
+            if !report.corrupted.is_empty() {
+                anyhow::bail!("{} corrupted rows", report.corrupted.len());
+            }
+            Ok(())
+        }
+        DbCommand::AuditMmr {
+            sparse_roots_dir,
+            from,
+            to,
+        } => {
+            let report = mmr_audit::audit(&store, sparse_roots_dir, *from, *to).await?;
+            print(&report)?;
+            if let Some(file) = &report.first_divergent_shard {
+                anyhow::bail!("Sparse roots diverge from the block headers in {file}");
+            }
+            Ok(())
+        }
+    }
+}
+
+async fn get_head(store: &AppStore) -> Result<u32, anyhow::Error> {
+    store
+        .get_latest_chain_state_height()
+        .await
+        .context("No block indexed yet")
+}
+
+async fn get_header(
+    store: &AppStore,
+    height: u32,
+) -> Result<zebra_chain::block::Header, anyhow::Error> {
+    store
+        .get_block_headers(height, 1)
+        .await?
+        .pop()
+        .with_context(|| format!("No block header at height {height}"))
+}
+
+fn print<T: Serialize>(value: &T) -> Result<(), anyhow::Error> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
@@ -0,0 +1,67 @@
+//! Database encryption key loading (SQLCipher)
+
+use std::path::PathBuf;
+
+use tokio::{fs, process::Command};
+
+/// Where to load the database encryption key from
+#[derive(Debug, Clone)]
+pub enum DbKeySource {
+    /// Environment variable holding the key
+    Env(String),
+    /// File holding the key (surrounding whitespace is ignored)
+    File(PathBuf),
+    /// Shell command printing the key on stdout, e.g. a KMS or Vault CLI call
+    Command(String),
+}
+
+/// SQLCipher database key.
+///
+/// A 64-digit hex string is used as a raw 256-bit key, anything else as a passphrase.
+#[derive(Clone)]
+pub struct DbKey(String);
+
+impl std::fmt::Debug for DbKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("DbKey(<redacted>)")
+    }
+}
+
+impl DbKey {
+    /// Load the key from the given source
+    pub async fn load(source: &DbKeySource) -> Result<Self, anyhow::Error> {
+        let key = match source {
+            DbKeySource::Env(var) => std::env::var(var)
+                .map_err(|e| anyhow::anyhow!("Failed to read database key from ${var}: {e}"))?,
+            DbKeySource::File(path) => fs::read_to_string(path).await.map_err(|e| {
+                anyhow::anyhow!("Failed to read database key from {}: {e}", path.display())
+            })?,
+            DbKeySource::Command(command) => {
+                let output = Command::new("sh").arg("-c").arg(command).output().await?;
+                if !output.status.success() {
+                    anyhow::bail!(
+                        "Database key command exited with {}: {}",
+                        output.status,
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    );
+                }
+                String::from_utf8(output.stdout)
+                    .map_err(|_| anyhow::anyhow!("Database key command printed invalid UTF-8"))?
+            }
+        };
+        let key = key.trim();
+        if key.is_empty() {
+            anyhow::bail!("Database key is empty");
+        }
+        Ok(Self(key.to_string()))
+    }
+
+    /// Value of the SQLCipher `key` pragma
+    pub(crate) fn pragma_value(&self) -> String {
+        if self.0.len() == 64 && self.0.chars().all(|c| c.is_ascii_hexdigit()) {
+            format!("\"x'{}'\"", self.0)
+        } else {
+            format!("'{}'", self.0.replace('\'', "''"))
+        }
+    }
+}
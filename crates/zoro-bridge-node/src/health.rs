@@ -0,0 +1,280 @@
+//! Node health state shared between the indexer and the RPC server
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+use crate::metrics::{self, QueryMetrics};
+
+/// Health of the sparse roots file sink
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SinkHealth {
+    /// Whether the last write succeeded
+    pub healthy: bool,
+    /// Height indexing is paused at while the sink is failing
+    pub paused_at_height: Option<u32>,
+    /// Error of the last failed write
+    pub last_error: Option<String>,
+    /// Total number of failed writes since startup
+    pub write_failures_total: u64,
+}
+
+/// Outcome of the FlyClient root cross-checks
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CrossCheckHealth {
+    /// Total number of historical roots checked since startup
+    pub checks_total: u64,
+    /// Total number of checked roots that differ from the Zcash node commitment
+    pub mismatches_total: u64,
+    /// Height of the last mismatching root
+    pub last_mismatch_height: Option<u32>,
+}
+
+/// Outcome of the FlyClient root checks run by the indexer on newly appended leaves
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RootCheckHealth {
+    /// Total number of roots matching the Zcash node commitment since startup
+    pub matches_total: u64,
+    /// Total number of roots differing from the Zcash node commitment since startup
+    pub mismatches_total: u64,
+    /// Height of the last block whose root matched
+    pub last_verified_height: Option<u32>,
+    /// Height of the last block whose root differed
+    pub last_mismatch_height: Option<u32>,
+}
+
+/// Health report returned by `/health`
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    /// `ok`, `degraded`, or `draining` before a shutdown
+    pub status: &'static str,
+    /// Whether the node is draining before a shutdown requested on `/admin/shutdown`
+    pub draining: bool,
+    /// Sparse roots file sink, if enabled
+    pub sparse_roots_sink: Option<SinkHealth>,
+    /// FlyClient root cross-checks, if enabled
+    pub flyclient_crosscheck: Option<CrossCheckHealth>,
+    /// FlyClient root checks of the indexer, if enabled
+    pub flyclient_root_checks: Option<RootCheckHealth>,
+}
+
+impl HealthReport {
+    pub fn is_healthy(&self) -> bool {
+        !self.draining
+            && self
+                .sparse_roots_sink
+                .as_ref()
+                .is_none_or(|sink| sink.healthy)
+            && self
+                .flyclient_crosscheck
+                .as_ref()
+                .is_none_or(|crosscheck| crosscheck.mismatches_total == 0)
+            && self
+                .flyclient_root_checks
+                .as_ref()
+                .is_none_or(|checks| checks.mismatches_total == 0)
+    }
+}
+
+/// Health state of the node components
+#[derive(Debug, Default)]
+pub struct AppHealth {
+    sparse_roots_sink: Mutex<Option<SinkHealth>>,
+    flyclient_crosscheck: Mutex<Option<CrossCheckHealth>>,
+    flyclient_root_checks: Mutex<Option<RootCheckHealth>>,
+    /// Store query metrics exported along with the health metrics
+    query_metrics: Mutex<Vec<Arc<QueryMetrics>>>,
+    draining: AtomicBool,
+}
+
+impl AppHealth {
+    /// Export the query metrics of a store on `/metrics`
+    pub fn register_query_metrics(&self, query_metrics: Arc<QueryMetrics>) {
+        self.query_metrics.lock().unwrap().push(query_metrics);
+    }
+
+    /// Mark the node as draining before a shutdown, so that load balancers stop routing to it
+    pub fn start_draining(&self) {
+        self.draining.store(true, Ordering::Relaxed);
+    }
+
+    /// Mark the sparse roots sink as enabled and healthy
+    pub fn sparse_roots_sink_enabled(&self) {
+        let mut sink = self.sparse_roots_sink.lock().unwrap();
+        *sink = Some(SinkHealth {
+            healthy: true,
+            ..Default::default()
+        });
+    }
+
+    /// Record a failed sparse roots write that paused indexing at `height`
+    pub fn sparse_roots_sink_failed(&self, height: u32, error: &anyhow::Error) {
+        let mut sink = self.sparse_roots_sink.lock().unwrap();
+        let sink = sink.get_or_insert_with(SinkHealth::default);
+        sink.healthy = false;
+        sink.paused_at_height = Some(height);
+        sink.last_error = Some(error.to_string());
+        sink.write_failures_total += 1;
+    }
+
+    /// Record a successful sparse roots write, returning whether the sink was failing before
+    pub fn sparse_roots_sink_succeeded(&self) -> bool {
+        let mut sink = self.sparse_roots_sink.lock().unwrap();
+        let Some(sink) = sink.as_mut() else {
+            return false;
+        };
+        let recovered = !sink.healthy;
+        sink.healthy = true;
+        sink.paused_at_height = None;
+        recovered
+    }
+
+    /// Mark the FlyClient root cross-checks as enabled
+    pub fn crosscheck_enabled(&self) {
+        *self.flyclient_crosscheck.lock().unwrap() = Some(CrossCheckHealth::default());
+    }
+
+    /// Record a historical root matching the Zcash node commitment
+    pub fn crosscheck_passed(&self) {
+        let mut crosscheck = self.flyclient_crosscheck.lock().unwrap();
+        crosscheck
+            .get_or_insert_with(CrossCheckHealth::default)
+            .checks_total += 1;
+    }
+
+    /// Record a historical root at `height` differing from the Zcash node commitment
+    pub fn crosscheck_failed(&self, height: u32) {
+        let mut crosscheck = self.flyclient_crosscheck.lock().unwrap();
+        let crosscheck = crosscheck.get_or_insert_with(CrossCheckHealth::default);
+        crosscheck.checks_total += 1;
+        crosscheck.mismatches_total += 1;
+        crosscheck.last_mismatch_height = Some(height);
+    }
+
+    /// Mark the FlyClient root checks of the indexer as enabled
+    pub fn root_checks_enabled(&self) {
+        *self.flyclient_root_checks.lock().unwrap() = Some(RootCheckHealth::default());
+    }
+
+    /// Record a root of the block at `height` matching the Zcash node commitment
+    pub fn root_check_passed(&self, height: u32) {
+        let mut checks = self.flyclient_root_checks.lock().unwrap();
+        let checks = checks.get_or_insert_with(RootCheckHealth::default);
+        checks.matches_total += 1;
+        checks.last_verified_height = Some(height);
+    }
+
+    /// Record a root of the block at `height` differing from the Zcash node commitment
+    pub fn root_check_failed(&self, height: u32) {
+        let mut checks = self.flyclient_root_checks.lock().unwrap();
+        let checks = checks.get_or_insert_with(RootCheckHealth::default);
+        checks.mismatches_total += 1;
+        checks.last_mismatch_height = Some(height);
+    }
+
+    pub fn report(&self) -> HealthReport {
+        let sparse_roots_sink = self.sparse_roots_sink.lock().unwrap().clone();
+        let flyclient_crosscheck = self.flyclient_crosscheck.lock().unwrap().clone();
+        let flyclient_root_checks = self.flyclient_root_checks.lock().unwrap().clone();
+        let mut report = HealthReport {
+            status: "ok",
+            draining: self.draining.load(Ordering::Relaxed),
+            sparse_roots_sink,
+            flyclient_crosscheck,
+            flyclient_root_checks,
+        };
+        if report.draining {
+            report.status = "draining";
+        } else if !report.is_healthy() {
+            report.status = "degraded";
+        }
+        report
+    }
+
+    /// Render the health state in the Prometheus text exposition format
+    pub fn metrics(&self) -> String {
+        let mut out = String::new();
+        let report = self.report();
+        if let Some(sink) = report.sparse_roots_sink {
+            let _ = writeln!(
+                out,
+                "# HELP zoro_sparse_roots_sink_up Whether the sparse roots file sink is writing successfully"
+            );
+            let _ = writeln!(out, "# TYPE zoro_sparse_roots_sink_up gauge");
+            let _ = writeln!(out, "zoro_sparse_roots_sink_up {}", sink.healthy as u8);
+            let _ = writeln!(
+                out,
+                "# HELP zoro_sparse_roots_sink_write_failures_total Failed sparse roots file writes"
+            );
+            let _ = writeln!(
+                out,
+                "# TYPE zoro_sparse_roots_sink_write_failures_total counter"
+            );
+            let _ = writeln!(
+                out,
+                "zoro_sparse_roots_sink_write_failures_total {}",
+                sink.write_failures_total
+            );
+        }
+        if let Some(crosscheck) = report.flyclient_crosscheck {
+            let _ = writeln!(
+                out,
+                "# HELP zoro_flyclient_crosscheck_total Historical FlyClient MMR roots checked against the Zcash node"
+            );
+            let _ = writeln!(out, "# TYPE zoro_flyclient_crosscheck_total counter");
+            let _ = writeln!(
+                out,
+                "zoro_flyclient_crosscheck_total {}",
+                crosscheck.checks_total
+            );
+            let _ = writeln!(
+                out,
+                "# HELP zoro_flyclient_crosscheck_mismatches_total Historical FlyClient MMR roots differing from the Zcash node commitment"
+            );
+            let _ = writeln!(
+                out,
+                "# TYPE zoro_flyclient_crosscheck_mismatches_total counter"
+            );
+            let _ = writeln!(
+                out,
+                "zoro_flyclient_crosscheck_mismatches_total {}",
+                crosscheck.mismatches_total
+            );
+        }
+        if let Some(checks) = report.flyclient_root_checks {
+            let _ = writeln!(
+                out,
+                "# HELP zoro_flyclient_root_checks_total FlyClient MMR roots of newly indexed blocks checked against the Zcash node"
+            );
+            let _ = writeln!(out, "# TYPE zoro_flyclient_root_checks_total counter");
+            let _ = writeln!(
+                out,
+                "zoro_flyclient_root_checks_total{{result=\"match\"}} {}",
+                checks.matches_total
+            );
+            let _ = writeln!(
+                out,
+                "zoro_flyclient_root_checks_total{{result=\"mismatch\"}} {}",
+                checks.mismatches_total
+            );
+            if let Some(height) = checks.last_verified_height {
+                let _ = writeln!(
+                    out,
+                    "# HELP zoro_flyclient_root_last_verified_height Height of the last indexed block whose FlyClient MMR root matched the Zcash node"
+                );
+                let _ = writeln!(out, "# TYPE zoro_flyclient_root_last_verified_height gauge");
+                let _ = writeln!(out, "zoro_flyclient_root_last_verified_height {height}");
+            }
+        }
+        let query_metrics = self.query_metrics.lock().unwrap().clone();
+        if !query_metrics.is_empty() {
+            metrics::render_header(&mut out);
+            for store_metrics in query_metrics {
+                store_metrics.render(&mut out);
+            }
+        }
+        out
+    }
+}
@@ -1,47 +1,60 @@
 //! Zcash blockchain indexer that builds header state
 
-use std::{path::PathBuf, sync::Arc};
+use std::{fmt, path::PathBuf, str::FromStr, sync::Arc, time::Duration};
 
 use accumulators::{
     hasher::flyclient::{encode_node_data, ZcashFlyclientHasher},
     mmr::MMR,
-    store::{sqlite::SQLiteStore, SubKey},
+    store::{StoreError, SubKey},
 };
 use primitive_types::U256;
 use tokio::sync::broadcast;
 use tracing::{debug, error, info};
 use zcash_history::NodeData;
-use zebra_chain::block::Hash as BlockHash;
-use zoro_zcash_client::ZcashClient;
+use zebra_chain::{
+    block::{Hash as BlockHash, Header},
+    transaction::Transaction,
+};
+use zoro_chain_params::{FlyClientEpoch, Network, NetworkUpgrade};
+use zoro_spv_verify::{block_mmr::header_leaf, MmrRootRecord, SparseRoots, SparseRootsRecord};
+use zoro_zcash_client::{RpcAuth, RpcTimeouts, ZcashClient};
 
 use crate::{
     chain_state::{ChainStateManager, ChainStateStore},
-    store::AppStore,
+    checksum::ChecksummedStore,
+    db_key::DbKey,
+    health::AppHealth,
+    metrics::QueryMetrics,
+    node_version::check_node_version,
+    sparse_roots::{SparseRootsSink, SparseRootsSinkConfig},
+    store::{begin_transaction, commit_transaction, rollback_transaction, AppStore},
 };
 
 /// Heartwood activation height (mainnet) - FlyClient starts here
-const HEARTWOOD_ACTIVATION: u32 = 903_000;
-/// Canopy activation height (mainnet) - new epoch, reset MMR
-const CANOPY_ACTIVATION: u32 = 1_046_400;
-/// NU5 activation height (mainnet) - new epoch, reset MMR  
-const NU5_ACTIVATION: u32 = 1_687_104;
-
-/// Branch IDs for different network upgrades
-mod branch_id {
-    pub const HEARTWOOD: u32 = 0xf5b9230b;
-    pub const CANOPY: u32 = 0xe9ff75a6;
-    pub const NU5: u32 = 0xc2d6d0b4;
+pub(crate) const HEARTWOOD_ACTIVATION: u32 =
+    Network::Mainnet.activation_height(NetworkUpgrade::Heartwood);
+
+/// Number of stored headers replayed per transaction when rebuilding the block header MMR
+const SPARSE_ROOTS_REPLAY_BATCH: u32 = 1000;
+
+/// Number of blocks fetched and indexed per transaction while backfilling
+const BACKFILL_BATCH_SIZE: u32 = 1000;
+
+/// Initial delay before retrying a failed sparse roots write
+const SPARSE_ROOTS_RETRY_MIN_DELAY: Duration = Duration::from_secs(1);
+/// Maximum delay between sparse roots write retries
+const SPARSE_ROOTS_RETRY_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// FlyClient epoch (mainnet) containing `height`, heights before Heartwood counting as Heartwood
+pub(crate) fn flyclient_epoch(height: u32) -> FlyClientEpoch {
+    Network::Mainnet
+        .flyclient_epoch(height.max(HEARTWOOD_ACTIVATION))
+        .expect("Heartwood starts a FlyClient epoch")
 }
 
 /// Get the epoch name for a height
 fn epoch_name_for_height(height: u32) -> &'static str {
-    if height >= NU5_ACTIVATION {
-        "nu5"
-    } else if height >= CANOPY_ACTIVATION {
-        "canopy"
-    } else {
-        "heartwood"
-    }
+    flyclient_epoch(height).name()
 }
 
 /// Convert zebra BlockHash to [u8; 32]
@@ -51,13 +64,7 @@ fn block_hash_to_bytes(hash: &BlockHash) -> [u8; 32] {
 
 /// Get branch ID for a given block height
 fn branch_id_for_height(height: u32) -> u32 {
-    if height >= 1_687_104 {
-        branch_id::NU5
-    } else if height >= 1_046_400 {
-        branch_id::CANOPY
-    } else {
-        branch_id::HEARTWOOD
-    }
+    flyclient_epoch(height).upgrade.branch_id()
 }
 
 /// Compute work from compact bits (nBits)
@@ -107,10 +114,346 @@ fn node_data_from_parts(
     }
 }
 
+/// Load the block header MMR roots at `next_block_height - 1`.
+///
+/// Heights indexed before the block header MMR was maintained are replayed from the stored
+/// block headers.
+async fn restore_sparse_roots(
+    store: &AppStore,
+    next_block_height: u32,
+) -> Result<SparseRoots, anyhow::Error> {
+    let (mut sparse_roots, mut height) = match store.get_latest_sparse_roots_height().await {
+        Ok(height) => (store.get_sparse_roots(height).await?, height + 1),
+        // No roots recorded yet, replay from genesis
+        Err(StoreError::GetError) => (SparseRoots::default(), 0),
+        Err(e) => return Err(e.into()),
+    };
+    if height < next_block_height {
+        info!(
+            "Replaying block headers {}..{} into the block header MMR",
+            height, next_block_height
+        );
+    }
+    while height < next_block_height {
+        let num_blocks = (next_block_height - height).min(SPARSE_ROOTS_REPLAY_BATCH);
+        let headers = store.get_block_headers(height, num_blocks).await?;
+        if headers.len() != num_blocks as usize {
+            anyhow::bail!(
+                "Missing block headers in store for heights {}..{}",
+                height,
+                height + num_blocks
+            );
+        }
+        store.begin().await?;
+        for header in &headers {
+            sparse_roots.add(header_leaf(header)?);
+            store.add_sparse_roots(height, &sparse_roots).await?;
+            height += 1;
+        }
+        store.commit().await?;
+    }
+    Ok(sparse_roots)
+}
+
+/// Write the sparse roots at `record.height` to the sink, pausing indexing until it succeeds.
+///
+/// Failures are reported to the node health state. Since a failed append may leave a partial
+/// line behind, retries rewrite the shard from the store. Returns `false` if a shutdown was
+/// requested while waiting for the sink to recover.
+async fn write_sparse_roots(
+    sink: &SparseRootsSink,
+    store: &AppStore,
+    health: &AppHealth,
+    record: &SparseRootsRecord,
+    rx_shutdown: &mut broadcast::Receiver<()>,
+) -> Result<bool, anyhow::Error> {
+    let mut res = sink.write(store, record).await;
+    let mut delay = SPARSE_ROOTS_RETRY_MIN_DELAY;
+    while let Err(e) = res {
+        error!(
+            "Failed to write sparse roots for block #{}, indexing paused: {e}",
+            record.height
+        );
+        health.sparse_roots_sink_failed(record.height, &e);
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {},
+            _ = rx_shutdown.recv() => return Ok(false),
+        }
+        delay = (delay * 2).min(SPARSE_ROOTS_RETRY_MAX_DELAY);
+        res = sink.resume(store, record.height + 1).await;
+    }
+    if health.sparse_roots_sink_succeeded() {
+        info!(
+            "Sparse roots sink recovered at block #{}, indexing resumed",
+            record.height
+        );
+    }
+    Ok(true)
+}
+
+/// First network upgrade activation height after `height`
+fn next_activation_height(height: u32) -> Option<u32> {
+    Network::Mainnet.next_flyclient_epoch_start(height)
+}
+
+/// Number of roots checked at the start of each epoch by [`RootCheckCadence::EpochBoundary`],
+/// also checked by [`RootCheckCadence::Every`]
+const EPOCH_BOUNDARY_ROOT_CHECKS: usize = 5;
+
+/// Which newly appended FlyClient MMR roots the indexer compares with the Zcash node
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RootCheckCadence {
+    /// Roots whose leaf count is a multiple of `interval`, and the first roots of each epoch
+    Every { interval: u32 },
+    /// The first roots of each epoch only
+    EpochBoundary,
+    /// No root is checked
+    Off,
+}
+
+impl RootCheckCadence {
+    /// Whether the root of an epoch MMR with `leaves` leaves is checked
+    fn should_check(&self, leaves: usize) -> bool {
+        match self {
+            RootCheckCadence::Every { interval } => {
+                leaves <= EPOCH_BOUNDARY_ROOT_CHECKS || leaves.is_multiple_of(*interval as usize)
+            }
+            RootCheckCadence::EpochBoundary => leaves <= EPOCH_BOUNDARY_ROOT_CHECKS,
+            RootCheckCadence::Off => false,
+        }
+    }
+}
+
+impl Default for RootCheckCadence {
+    fn default() -> Self {
+        RootCheckCadence::Every { interval: 10 }
+    }
+}
+
+impl fmt::Display for RootCheckCadence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RootCheckCadence::Every { interval } => write!(f, "every:{interval}"),
+            RootCheckCadence::EpochBoundary => f.write_str("epoch_boundary"),
+            RootCheckCadence::Off => f.write_str("off"),
+        }
+    }
+}
+
+impl FromStr for RootCheckCadence {
+    type Err = anyhow::Error;
+
+    /// Parse `every:<N>`, `epoch_boundary` or `off`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some(("every", interval)) => match interval.parse() {
+                Ok(interval) if interval > 0 => Ok(RootCheckCadence::Every { interval }),
+                _ => anyhow::bail!("Invalid root check interval {interval:?}"),
+            },
+            None if s == "epoch_boundary" => Ok(RootCheckCadence::EpochBoundary),
+            None if s == "off" => Ok(RootCheckCadence::Off),
+            _ => anyhow::bail!(
+                "Unsupported root check cadence {s:?} (expected every:<N>, epoch_boundary or off)"
+            ),
+        }
+    }
+}
+
+/// Compare a FlyClient MMR root with the root committed by the Zcash node, logging the outcome
+/// and recording it in the node health
+async fn check_flyclient_root(
+    zcash_client: &ZcashClient,
+    health: &AppHealth,
+    height: u32,
+    epoch: &str,
+    leaves: usize,
+    our_root: &str,
+) {
+    // Get expected root from RPC (blockcommitments at next block)
+    // Leaf count gives us offset within current epoch
+    let epoch_start = flyclient_epoch(height).start_height;
+    let verify_height = epoch_start + leaves as u32;
+    match zcash_client.get_block_commitment(verify_height).await {
+        Ok(expected) => {
+            if expected == our_root {
+                health.root_check_passed(height);
+                info!(
+                    "FlyClient root ✓ at height {} ({} epoch {} leaves)",
+                    verify_height, epoch, leaves
+                );
+            } else {
+                health.root_check_failed(height);
+                error!("FlyClient root MISMATCH at height {}!", verify_height);
+                error!("  Our root: {}", our_root);
+                error!("  Expected: {}", expected);
+            }
+        }
+        Err(e) => {
+            debug!("Could not verify FlyClient root: {e}");
+        }
+    }
+}
+
+/// FlyClient MMR of a network upgrade epoch
+struct EpochMmr {
+    /// Epoch name
+    epoch: &'static str,
+    mmr: MMR,
+    /// Store backing the MMR, used to group appends into a single transaction
+    store: Arc<ChecksummedStore>,
+}
+
+impl EpochMmr {
+    /// Open the MMR of `epoch` on the connection of the indexer store, so that appends are
+    /// committed with the transaction of the indexed blocks
+    async fn open(store: &AppStore, epoch: &'static str) -> Result<Self, anyhow::Error> {
+        let mmr_id = format!("flyclient_{}", epoch);
+        let store = store
+            .accumulator_store(&mmr_id)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to open FlyClient store for {}: {e}", epoch))?;
+        let store = Arc::new(store);
+        let hasher = Arc::new(ZcashFlyclientHasher);
+        let mmr = MMR::new(store.clone(), hasher, Some(mmr_id));
+        Ok(Self { epoch, mmr, store })
+    }
+
+    /// Switch to the MMR of the epoch of `height` when crossing an activation height
+    async fn enter_epoch(&mut self, height: u32, store: &AppStore) -> Result<(), anyhow::Error> {
+        let epoch = epoch_name_for_height(height);
+        if epoch != self.epoch {
+            info!(
+                "Epoch transition at height {}: {} -> {}",
+                height, self.epoch, epoch
+            );
+            *self = Self::open(store, epoch).await?;
+            info!("Started new FlyClient MMR for epoch: {}", epoch);
+        }
+        Ok(())
+    }
+
+    /// Append a leaf, returning the leaf count and the root of the MMR
+    async fn append(&mut self, node: &NodeData) -> Result<(usize, String), anyhow::Error> {
+        self.mmr
+            .append(encode_node_data(node))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to append to FlyClient MMR: {e}"))?;
+        let leaves = self.mmr.leaves_count.get().await.unwrap_or(0);
+        let root = self
+            .mmr
+            .root_hash
+            .get(SubKey::None)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to get FlyClient MMR root: {e}"))?
+            .ok_or_else(|| anyhow::anyhow!("FlyClient MMR root is empty"))?;
+        Ok((leaves, root))
+    }
+
+    /// Append leaves in a single store transaction, returning the leaf count and the root of
+    /// the MMR after each append.
+    ///
+    /// The new parents are computed from the peaks read within the transaction and all store
+    /// updates are committed at once, instead of one commit per written node. Within a
+    /// transaction of the indexer store, the appends are a savepoint of it.
+    async fn append_batch(
+        &mut self,
+        nodes: &[NodeData],
+    ) -> Result<Vec<(usize, String)>, anyhow::Error> {
+        begin_transaction(self.store.inner()).await?;
+        let mut appended = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            match self.append(node).await {
+                Ok(res) => appended.push(res),
+                Err(e) => {
+                    // Release the savepoint, so that the enclosing transaction can be rolled back
+                    rollback_transaction(self.store.inner()).await?;
+                    return Err(e);
+                }
+            }
+        }
+        commit_transaction(self.store.inner()).await?;
+        Ok(appended)
+    }
+}
+
+/// Block data fetched from the Zcash node for indexing
+struct BlockData {
+    height: u32,
+    header: Header,
+    hash: BlockHash,
+    /// Sapling root and Sapling transaction count, for Heartwood+ blocks
+    flyclient_data: Option<([u8; 32], u64)>,
+    /// Block transactions, if they are stored for degraded-mode proof serving
+    transactions: Option<Vec<Arc<Transaction>>>,
+}
+
+impl BlockData {
+    async fn fetch(
+        zcash_client: &ZcashClient,
+        height: u32,
+        header: Header,
+        hash: BlockHash,
+        store_transactions: bool,
+    ) -> Result<Self, anyhow::Error> {
+        let flyclient_data = if height >= HEARTWOOD_ACTIVATION {
+            let data = zcash_client
+                .get_block_flyclient_data(height)
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to get FlyClient data: {e}"))?;
+            Some(data)
+        } else {
+            None
+        };
+        let transactions = if store_transactions {
+            let block = zcash_client
+                .get_block(&hash)
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to get block: {e}"))?;
+            Some(block.transactions)
+        } else {
+            None
+        };
+        Ok(Self {
+            height,
+            header,
+            hash,
+            flyclient_data,
+            transactions,
+        })
+    }
+
+    /// FlyClient MMR leaf of the block, for Heartwood+ blocks
+    fn flyclient_node(&self) -> Option<NodeData> {
+        let (sapling_root, sapling_tx) = self.flyclient_data?;
+        let bits = u32::from_be_bytes(self.header.difficulty_threshold.bytes_in_display_order());
+        Some(node_data_from_parts(
+            block_hash_to_bytes(&self.hash),
+            self.height,
+            self.header.time.timestamp() as u32,
+            bits,
+            sapling_root,
+            sapling_tx,
+        ))
+    }
+}
+
+/// State updated by every indexed block
+struct IndexerState {
+    store: Arc<AppStore>,
+    chain_state_mgr: ChainStateManager,
+    /// Block header MMR in the sparse representation consumed by the Cairo programs
+    sparse_roots: SparseRoots,
+    /// FlyClient MMR of the current epoch
+    flyclient_mmr: EpochMmr,
+    next_block_height: u32,
+}
+
 /// Zcash block indexer that builds FlyClient MMR accumulator
 pub struct Indexer {
     /// Indexer configuration
     config: IndexerConfig,
+    /// Node health state
+    health: Arc<AppHealth>,
     /// Shutdown signal receiver
     rx_shutdown: broadcast::Receiver<()>,
 }
@@ -119,163 +462,308 @@ pub struct Indexer {
 pub struct IndexerConfig {
     /// Zcash RPC URL
     pub rpc_url: String,
-    /// Zcash RPC user:password (optional)
-    pub rpc_userpwd: Option<String>,
+    /// Zcash RPC credentials
+    pub rpc_auth: RpcAuth,
+    /// Zcash RPC request timeouts
+    pub rpc_timeouts: RpcTimeouts,
     /// ID
     pub id: String,
     /// Path to the database storing the header state
     pub db_path: PathBuf,
     /// Indexing lag in blocks
     pub indexing_lag: u32,
+    /// Sparse roots shard files (optional)
+    pub sparse_roots_sink: Option<SparseRootsSinkConfig>,
+    /// Database encryption key (optional)
+    pub db_key: Option<DbKey>,
+    /// Query metrics of the indexer store
+    pub query_metrics: Arc<QueryMetrics>,
+    /// Store the transactions of indexed blocks, so that the RPC server can build transaction
+    /// proofs while the Zcash node is unavailable
+    pub store_transactions: bool,
+    /// Only warn when the Zcash node is older than the oldest supported release
+    pub allow_unsupported_node: bool,
+    /// Which newly appended FlyClient MMR roots are compared with the Zcash node
+    pub root_check: RootCheckCadence,
 }
 
 impl Indexer {
-    pub fn new(config: IndexerConfig, rx_shutdown: broadcast::Receiver<()>) -> Self {
+    pub fn new(
+        config: IndexerConfig,
+        health: Arc<AppHealth>,
+        rx_shutdown: broadcast::Receiver<()>,
+    ) -> Self {
         Self {
             config,
+            health,
             rx_shutdown,
         }
     }
 
+    /// Index consecutive blocks of the same epoch, starting at `state.next_block_height`.
+    ///
+    /// The block headers are stored with a single bulk insert and the FlyClient leaves appended
+    /// in a single transaction. Returns `false` if a shutdown was requested while waiting for the
+    /// sparse roots sink to recover.
+    async fn index_blocks(
+        &mut self,
+        zcash_client: &ZcashClient,
+        state: &mut IndexerState,
+        sparse_roots_sink: Option<&SparseRootsSink>,
+        blocks: Vec<BlockData>,
+    ) -> Result<bool, anyhow::Error> {
+        let store = state.store.clone();
+
+        // FlyClient data of Heartwood+ blocks
+        let (heights, nodes): (Vec<u32>, Vec<NodeData>) = blocks
+            .iter()
+            .filter_map(|block| Some((block.height, block.flyclient_node()?)))
+            .unzip();
+        if let Some(first_height) = heights.first() {
+            state
+                .flyclient_mmr
+                .enter_epoch(*first_height, &store)
+                .await?;
+        }
+
+        // Headers, chain states, block header MMR roots, FlyClient MMR nodes and roots are
+        // committed in a single transaction, so that a crash leaves them at the same height.
+        // A failed write rolls the transaction back, so that it does not stay open on the writer
+        let mut records = Vec::new();
+        store.begin().await?;
+        let written = async {
+            for block in &blocks {
+                state.sparse_roots.add(header_leaf(&block.header)?);
+                store
+                    .add_sparse_roots(block.height, &state.sparse_roots)
+                    .await?;
+                if let Some(transactions) = &block.transactions {
+                    store
+                        .add_block_transactions(block.height, transactions)
+                        .await?;
+                }
+                if sparse_roots_sink.is_some() {
+                    records.push(SparseRootsRecord {
+                        height: block.height,
+                        roots: state.sparse_roots.clone(),
+                    });
+                }
+            }
+            let block_headers = blocks
+                .iter()
+                .map(|block| (block.height, block.header.clone()))
+                .collect();
+            state
+                .chain_state_mgr
+                .update_batch(block_headers)
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to update chain state: {e}"))?;
+
+            let appended = state.flyclient_mmr.append_batch(&nodes).await?;
+            let epoch = state.flyclient_mmr.epoch;
+            // Record the roots so that proofs can later be pinned to these heights
+            for (height, (leaf_count, root)) in heights.iter().zip(&appended) {
+                store
+                    .add_mmr_root(&MmrRootRecord {
+                        height: *height,
+                        epoch: epoch.to_string(),
+                        leaf_count: *leaf_count,
+                        root: root.clone(),
+                    })
+                    .await?;
+            }
+            Ok::<_, anyhow::Error>((appended, epoch))
+        }
+        .await;
+        let (appended, epoch) = match written {
+            Ok(written) => {
+                store.commit().await?;
+                written
+            }
+            Err(e) => {
+                store.rollback().await?;
+                return Err(e);
+            }
+        };
+
+        if let Some(sink) = sparse_roots_sink {
+            for record in &records {
+                if !write_sparse_roots(sink, &store, &self.health, record, &mut self.rx_shutdown)
+                    .await?
+                {
+                    return Ok(false);
+                }
+            }
+        }
+
+        for (height, (leaves, root)) in heights.iter().zip(&appended) {
+            if self.config.root_check.should_check(*leaves) {
+                check_flyclient_root(zcash_client, &self.health, *height, epoch, *leaves, root)
+                    .await;
+            }
+            debug!("FlyClient MMR ({}) updated for block #{}", epoch, height);
+        }
+
+        match blocks.as_slice() {
+            [block] => info!("Block #{} {} processed", block.height, block.hash),
+            [first, .., last] => info!("Blocks #{}..#{} processed", first.height, last.height),
+            [] => {}
+        }
+        state.next_block_height += blocks.len() as u32;
+        Ok(true)
+    }
+
+    /// Index the blocks buried deeper than the indexing lag in batches.
+    ///
+    /// The data of a batch is fetched up front, then indexed with [`Self::index_blocks`].
+    /// Batches stop at network upgrade activation heights, so that each one belongs to a single
+    /// FlyClient epoch. Returns `false` if a shutdown was requested.
+    async fn backfill(
+        &mut self,
+        zcash_client: &ZcashClient,
+        state: &mut IndexerState,
+        sparse_roots_sink: Option<&SparseRootsSink>,
+    ) -> Result<bool, anyhow::Error> {
+        loop {
+            let chain_height = zcash_client
+                .get_chain_height()
+                .await?
+                .saturating_sub(self.config.indexing_lag);
+            let end_height = chain_height.saturating_add(1);
+            if end_height.saturating_sub(state.next_block_height) < BACKFILL_BATCH_SIZE {
+                return Ok(true);
+            }
+            info!(
+                "Backfilling blocks {}..{}",
+                state.next_block_height, end_height
+            );
+
+            while state.next_block_height < end_height {
+                let start_height = state.next_block_height;
+                let mut num_blocks = (end_height - start_height).min(BACKFILL_BATCH_SIZE);
+                if let Some(activation) = next_activation_height(start_height) {
+                    num_blocks = num_blocks.min(activation - start_height);
+                }
+                let store_transactions = self.config.store_transactions;
+                let fetch = async {
+                    let mut blocks = Vec::with_capacity(num_blocks as usize);
+                    for height in start_height..start_height + num_blocks {
+                        let (header, hash) =
+                            zcash_client.get_block_header_by_height(height).await?;
+                        blocks.push(
+                            BlockData::fetch(
+                                zcash_client,
+                                height,
+                                header,
+                                hash,
+                                store_transactions,
+                            )
+                            .await?,
+                        );
+                    }
+                    Ok::<_, anyhow::Error>(blocks)
+                };
+                let blocks = tokio::select! {
+                    res = fetch => res?,
+                    _ = self.rx_shutdown.recv() => return Ok(false),
+                };
+                if !self
+                    .index_blocks(zcash_client, state, sparse_roots_sink, blocks)
+                    .await?
+                {
+                    return Ok(false);
+                }
+            }
+        }
+    }
+
     async fn run_inner(&mut self) -> Result<(), anyhow::Error> {
         info!("Block indexer started");
 
         let mut zcash_client =
-            ZcashClient::new(self.config.rpc_url.clone(), self.config.rpc_userpwd.clone()).await?;
+            ZcashClient::with_auth(self.config.rpc_url.clone(), self.config.rpc_auth.clone())
+                .await?
+                .with_timeouts(self.config.rpc_timeouts.clone())?;
         info!("Zcash RPC client initialized");
+        check_node_version(&zcash_client, self.config.allow_unsupported_node).await?;
 
         // We need to specify id to have deterministic keys in the database
         let id = Some(self.config.id.clone());
-        let store =
-            Arc::new(AppStore::single_atomic_writer(&self.config.db_path, id.clone()).await?);
+        let store = Arc::new(
+            AppStore::single_atomic_writer(
+                &self.config.db_path,
+                id.clone(),
+                self.config.db_key.as_ref(),
+                self.config.query_metrics.clone(),
+            )
+            .await?,
+        );
 
-        let mut next_block_height = match store.get_latest_chain_state_height().await {
+        let next_block_height = match store.get_latest_chain_state_height().await {
             Ok(height) => height + 1,
             _ => 0,
         };
 
-        let mut chain_state_mgr =
-            ChainStateManager::restore(store.clone(), next_block_height).await?;
+        let chain_state_mgr = ChainStateManager::restore(store.clone(), next_block_height).await?;
         info!("Chain state manager initialized");
 
-        // Helper to create MMR for a specific epoch
-        async fn create_epoch_mmr(db_path: &str, epoch: &str) -> Result<MMR, anyhow::Error> {
-            let mmr_id = format!("flyclient_{}", epoch);
-            let fc_store = SQLiteStore::new(db_path, Some(true), Some(&mmr_id))
-                .await
-                .map_err(|e| {
-                    anyhow::anyhow!("Failed to open FlyClient store for {}: {e}", epoch)
-                })?;
-            let hasher = Arc::new(ZcashFlyclientHasher);
-            Ok(MMR::new(Arc::new(fc_store), hasher, Some(mmr_id)))
+        let sparse_roots = restore_sparse_roots(&store, next_block_height).await?;
+        info!(
+            "Block header MMR initialized ({} leaves)",
+            sparse_roots.leaf_count()?
+        );
+        let sparse_roots_sink = match &self.config.sparse_roots_sink {
+            Some(config) => {
+                let sink = SparseRootsSink::new(config.clone()).await?;
+                sink.resume(&store, next_block_height).await?;
+                self.health.sparse_roots_sink_enabled();
+                Some(sink)
+            }
+            None => None,
+        };
+        if self.config.root_check != RootCheckCadence::Off {
+            self.health.root_checks_enabled();
         }
 
-        // Determine current epoch based on next block height
-        let current_epoch = epoch_name_for_height(next_block_height);
-        let db_path = self.config.db_path.to_str().unwrap().to_string();
-
-        // Initialize FlyClient MMR for current epoch
-        let flyclient_mmr = create_epoch_mmr(&db_path, current_epoch).await?;
-        let leaves = flyclient_mmr.leaves_count.get().await.unwrap_or(0);
+        // Initialize FlyClient MMR for the epoch of the next block
+        let flyclient_mmr =
+            EpochMmr::open(&store, epoch_name_for_height(next_block_height)).await?;
+        let leaves = flyclient_mmr.mmr.leaves_count.get().await.unwrap_or(0);
         info!(
             "FlyClient MMR ({}) initialized at {:?} ({} leaves)",
-            current_epoch, self.config.db_path, leaves
+            flyclient_mmr.epoch, self.config.db_path, leaves
         );
 
-        // Track current epoch for detecting transitions
-        let mut current_epoch_name = current_epoch.to_string();
+        let mut state = IndexerState {
+            store,
+            chain_state_mgr,
+            sparse_roots,
+            flyclient_mmr,
+            next_block_height,
+        };
+        let sparse_roots_sink = sparse_roots_sink.as_ref();
 
-        // Wrap for mutable access
-        let flyclient_mmr = Arc::new(tokio::sync::Mutex::new(flyclient_mmr));
+        if !self
+            .backfill(&zcash_client, &mut state, sparse_roots_sink)
+            .await?
+        {
+            return Ok(());
+        }
 
         loop {
             tokio::select! {
-                res = zcash_client.wait_block_header(next_block_height, self.config.indexing_lag) => {
-                    match res {
-                        Ok((block_header, block_hash)) => {
-                            store.begin().await?;
-                            chain_state_mgr.update(next_block_height, &block_header).await.map_err(|e| anyhow::anyhow!("Failed to update chain state: {e}"))?;
-                            store.commit().await?;
-
-                            // Process FlyClient MMR for Heartwood+ blocks
-                            if next_block_height >= HEARTWOOD_ACTIVATION {
-                                // Check for epoch transition - need to create new MMR
-                                let new_epoch = epoch_name_for_height(next_block_height);
-                                if new_epoch != current_epoch_name {
-                                    info!(
-                                        "Epoch transition at height {}: {} -> {}",
-                                        next_block_height, current_epoch_name, new_epoch
-                                    );
-                                    // Create new MMR for the new epoch
-                                    let new_mmr = create_epoch_mmr(&db_path, new_epoch).await?;
-                                    *flyclient_mmr.lock().await = new_mmr;
-                                    current_epoch_name = new_epoch.to_string();
-                                    info!("Started new FlyClient MMR for epoch: {}", new_epoch);
-                                }
-
-                                let (sapling_root, sapling_tx) = zcash_client
-                                    .get_block_flyclient_data(next_block_height)
-                                    .await
-                                    .map_err(|e| anyhow::anyhow!("Failed to get FlyClient data: {e}"))?;
-
-                                let bits = u32::from_be_bytes(
-                                    block_header.difficulty_threshold.bytes_in_display_order(),
-                                );
-                                let node = node_data_from_parts(
-                                    block_hash_to_bytes(&block_hash),
-                                    next_block_height,
-                                    block_header.time.timestamp() as u32,
-                                    bits,
-                                    sapling_root,
-                                    sapling_tx,
-                                );
-
-                                // Append to FlyClient MMR
-                                let mut mmr = flyclient_mmr.lock().await;
-                                mmr.append(encode_node_data(&node)).await
-                                    .map_err(|e| anyhow::anyhow!("Failed to append to FlyClient MMR: {e}"))?;
-
-                                // Verify root every 10 blocks
-                                let leaves = mmr.leaves_count.get().await.unwrap_or(0);
-                                if leaves % 10 == 0 || leaves <= 5 {
-                                    if let Some(our_root) = mmr.root_hash.get(SubKey::None).await.ok().flatten() {
-                                        // Get expected root from RPC (blockcommitments at next block)
-                                        // Leaf count gives us offset within current epoch
-                                        let epoch_start = if next_block_height >= NU5_ACTIVATION {
-                                            NU5_ACTIVATION
-                                        } else if next_block_height >= CANOPY_ACTIVATION {
-                                            CANOPY_ACTIVATION
-                                        } else {
-                                            HEARTWOOD_ACTIVATION
-                                        };
-                                        let verify_height = epoch_start + leaves as u32;
-                                        match zcash_client.get_block_commitment(verify_height).await {
-                                            Ok(expected) => {
-                                                if our_root == expected {
-                                                    info!("FlyClient root ✓ at height {} ({} epoch {} leaves)", verify_height, current_epoch_name, leaves);
-                                                } else {
-                                                    error!("FlyClient root MISMATCH at height {}!", verify_height);
-                                                    error!("  Our root: {}", our_root);
-                                                    error!("  Expected: {}", expected);
-                                                }
-                                            }
-                                            Err(e) => {
-                                                debug!("Could not verify FlyClient root: {e}");
-                                            }
-                                        }
-                                    }
-                                }
-
-                                debug!("FlyClient MMR ({}) updated for block #{}", current_epoch_name, next_block_height);
-                            }
-
-
-                            info!("Block #{} {} processed", next_block_height, block_hash);
-                            next_block_height += 1;
-                        },
-                        Err(e) => {
-                            return Err(e.into())
-                        }
+                res = zcash_client.wait_block_header(state.next_block_height, self.config.indexing_lag) => {
+                    let (block_header, block_hash) = res?;
+                    let block = BlockData::fetch(
+                        &zcash_client,
+                        state.next_block_height,
+                        block_header,
+                        block_hash,
+                        self.config.store_transactions,
+                    )
+                    .await?;
+                    if !self.index_blocks(&zcash_client, &mut state, sparse_roots_sink, vec![block]).await? {
+                        return Ok(());
                     }
                 },
                 _ = self.rx_shutdown.recv() => {
@@ -0,0 +1,151 @@
+//! Limit on concurrent proof generations
+//!
+//! Proofs build the Merkle tree of a block and compute MMR proofs over the store, which competes
+//! with the indexer for CPU and database connections. A burst of proof requests could otherwise
+//! starve the indexer, so only a few proofs are generated at a time, a bounded number of requests
+//! wait for their turn, and the others are rejected with `429 Too Many Requests`.
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Default number of proofs generated concurrently
+pub const DEFAULT_MAX_CONCURRENT_PROOFS: usize = 8;
+/// Default number of proof requests waiting for a generation slot
+pub const DEFAULT_MAX_QUEUED_PROOFS: usize = 64;
+
+/// Limits of the proof generation queue
+#[derive(Debug, Clone)]
+pub struct ProofLimiterConfig {
+    /// Number of proofs generated concurrently
+    pub max_concurrent: usize,
+    /// Number of requests waiting for a slot, beyond which requests are rejected
+    pub max_queued: usize,
+}
+
+impl Default for ProofLimiterConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent: DEFAULT_MAX_CONCURRENT_PROOFS,
+            max_queued: DEFAULT_MAX_QUEUED_PROOFS,
+        }
+    }
+}
+
+/// Generation slots shared by the proof endpoints
+#[derive(Debug)]
+pub struct ProofLimiter {
+    slots: Arc<Semaphore>,
+    config: ProofLimiterConfig,
+    queued: AtomicUsize,
+    rejected_total: AtomicU64,
+    /// Set once the node drains before shutting down, new proofs are then refused
+    draining: AtomicBool,
+}
+
+/// Why a proof request was not given a generation slot
+#[derive(Debug)]
+pub enum Unavailable {
+    /// Every slot is taken and the queue is full
+    Saturated,
+    /// The node is draining before a shutdown
+    Draining,
+}
+
+/// Waiting request, removed from the queue when dropped, including when the request is cancelled
+struct Queued<'a>(&'a AtomicUsize);
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl ProofLimiter {
+    pub fn new(mut config: ProofLimiterConfig) -> Self {
+        // At least one proof at a time, or every request would wait forever
+        config.max_concurrent = config.max_concurrent.max(1);
+        Self {
+            slots: Arc::new(Semaphore::new(config.max_concurrent)),
+            config,
+            queued: AtomicUsize::new(0),
+            rejected_total: AtomicU64::new(0),
+            draining: AtomicBool::new(false),
+        }
+    }
+
+    /// Take a generation slot, held until the returned permit is dropped, waiting in the queue
+    /// if every slot is taken
+    pub async fn acquire(&self) -> Result<OwnedSemaphorePermit, Unavailable> {
+        if self.draining.load(Ordering::Relaxed) {
+            return Err(Unavailable::Draining);
+        }
+        if let Ok(permit) = self.slots.clone().try_acquire_owned() {
+            return Ok(permit);
+        }
+        let queued = self.queued.fetch_add(1, Ordering::Relaxed);
+        let _queued = Queued(&self.queued);
+        if queued >= self.config.max_queued {
+            self.rejected_total.fetch_add(1, Ordering::Relaxed);
+            return Err(Unavailable::Saturated);
+        }
+        // The semaphore is never closed
+        Ok(self
+            .slots
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("Proof semaphore closed"))
+    }
+
+    /// Refuse new proofs from now on. Returns `false` if the node was already draining.
+    pub fn start_draining(&self) -> bool {
+        !self.draining.swap(true, Ordering::Relaxed)
+    }
+
+    /// Wait for the proofs being generated or queued to complete, at most `timeout`. Returns
+    /// whether every proof completed in time. Call once, after [`Self::start_draining`].
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        // The semaphore is fair, so all the slots are granted after the queued requests are served
+        let slots = self.config.max_concurrent as u32;
+        match tokio::time::timeout(timeout, self.slots.acquire_many(slots)).await {
+            Ok(permits) => {
+                // Held for good, no proof is generated past the drain
+                permits.expect("Proof semaphore closed").forget();
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Render the queue state in the Prometheus text exposition format
+    pub fn render(&self, out: &mut String) {
+        let in_flight = self.config.max_concurrent - self.slots.available_permits();
+        let _ = writeln!(out, "# HELP zoro_proofs_in_flight Proofs being generated");
+        let _ = writeln!(out, "# TYPE zoro_proofs_in_flight gauge");
+        let _ = writeln!(out, "zoro_proofs_in_flight {in_flight}");
+        let _ = writeln!(
+            out,
+            "# HELP zoro_proofs_queued Proof requests waiting for a generation slot"
+        );
+        let _ = writeln!(out, "# TYPE zoro_proofs_queued gauge");
+        let _ = writeln!(
+            out,
+            "zoro_proofs_queued {}",
+            self.queued.load(Ordering::Relaxed)
+        );
+        let _ = writeln!(
+            out,
+            "# HELP zoro_proofs_rejected_total Proof requests rejected with the queue full"
+        );
+        let _ = writeln!(out, "# TYPE zoro_proofs_rejected_total counter");
+        let _ = writeln!(
+            out,
+            "zoro_proofs_rejected_total {}",
+            self.rejected_total.load(Ordering::Relaxed)
+        );
+    }
+}
@@ -1,51 +1,238 @@
 #![doc = include_str!("../README.md")]
 
-use std::path::PathBuf;
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
-use clap::{command, Parser};
+use clap::{command, Parser, Subcommand};
 use tokio::task::JoinHandle;
 use tracing::{error, info, subscriber::set_global_default};
 use tracing_subscriber::filter::EnvFilter;
+use zoro_chain_params::Network;
+use zoro_spv_verify::build_info::{self, BuildInfo};
+use zoro_spv_verify::FinalityPolicy;
+use zoro_zcash_client::{RpcAuth, RpcTimeouts};
 
 use crate::{
-    indexer::{Indexer, IndexerConfig},
-    rpc::{RpcConfig, RpcServer},
+    audit::AuditLogConfig,
+    crosscheck::{CrossCheckConfig, CrossChecker},
+    db::DbCommand,
+    db_key::{DbKey, DbKeySource},
+    health::AppHealth,
+    indexer::{Indexer, IndexerConfig, RootCheckCadence},
+    limiter::{ProofLimiterConfig, DEFAULT_MAX_CONCURRENT_PROOFS, DEFAULT_MAX_QUEUED_PROOFS},
+    metrics::QueryMetrics,
+    naming::FieldNaming,
+    quota::{load_api_keys, QuotaConfig},
+    rpc::{RpcConfig, RpcServer, DEFAULT_MAX_HEADERS},
+    selftest::SelfTestConfig,
     shutdown::Shutdown,
+    sparse_roots::{ShardCompression, ShardLayout, SparseRootsSinkConfig},
 };
 
+mod audit;
 mod chain_state;
+mod checksum;
+mod coalesce;
+mod crosscheck;
+mod db;
+mod db_key;
+mod health;
 mod indexer;
+mod limiter;
+mod metrics;
+mod mmr_audit;
+mod naming;
+mod node_version;
+mod proving;
+mod quota;
 mod rpc;
+mod selftest;
 mod shutdown;
+mod snapshot;
+mod source;
+mod sparse_roots;
 mod store;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 #[command(propagate_version = true)]
+#[command(subcommand_negates_reqs = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
     /// RPC server host
     #[arg(long, default_value = "127.0.0.1:5000")]
     rpc_host: String,
     /// Zcash RPC URL
-    #[arg(long, env = "ZCASH_RPC")]
-    zcash_rpc_url: String,
+    #[arg(long, env = "ZCASH_RPC", required = true)]
+    zcash_rpc_url: Option<String>,
     /// Zcash RPC user:password (optional)
     #[arg(long, env = "USERPWD")]
     zcash_rpc_userpwd: Option<String>,
+    /// Zcash RPC cookie file (`.cookie` in the node data directory), read again when the node
+    /// rotates it
+    #[arg(
+        long,
+        env = "ZCASH_RPC_COOKIE_FILE",
+        conflicts_with = "zcash_rpc_userpwd"
+    )]
+    zcash_rpc_cookie_file: Option<PathBuf>,
+    /// Zcash RPC request timeout in seconds, for the methods without an override
+    #[arg(long, default_value = "5")]
+    zcash_rpc_timeout_secs: u64,
+    /// Zcash RPC request timeout override for a method, as <method>=<seconds> (repeatable,
+    /// `getblock` defaults to 60 seconds)
+    #[arg(long, value_parser = parse_method_timeout)]
+    zcash_rpc_method_timeout: Vec<(String, Duration)>,
     /// Path to the database storing the app state
-    #[arg(long, default_value = "./.data/app.db")]
+    #[arg(long, default_value = "./.data/app.db", global = true)]
     db_path: PathBuf,
+    /// Environment variable holding the database encryption key (requires the `sqlcipher` feature)
+    #[arg(long, conflicts_with_all = ["db_key_file", "db_key_command"], global = true)]
+    db_key_env: Option<String>,
+    /// File holding the database encryption key (requires the `sqlcipher` feature)
+    #[arg(long, conflicts_with = "db_key_command", global = true)]
+    db_key_file: Option<PathBuf>,
+    /// Shell command printing the database encryption key, e.g. a KMS decrypt call (requires the
+    /// `sqlcipher` feature)
+    #[arg(long, global = true)]
+    db_key_command: Option<String>,
     /// ID
-    #[arg(long, default_value = "blocks")]
+    #[arg(long, default_value = "blocks", global = true)]
     id: String,
     /// Indexing lag in blocks, to address potential reorgs
     #[arg(long, default_value = "1")]
     block_lag: u32,
+    /// Directory to write the block header MMR sparse roots shard files to (optional)
+    #[arg(long)]
+    sparse_roots_dir: Option<PathBuf>,
+    /// Number of heights per sparse roots shard file
+    #[arg(long, default_value = "10000")]
+    sparse_roots_shard_size: u32,
+    /// Write one sparse roots shard file per network upgrade epoch instead of fixed-size shards
+    #[arg(long)]
+    sparse_roots_per_epoch: bool,
+    /// Zcash network of the node (mainnet or testnet), whose upgrade activation heights bound
+    /// the sparse roots epochs
+    #[arg(long, default_value = "mainnet", value_parser = parse_network)]
+    network: Network,
+    /// Sparse roots shard file path template, relative to the output directory and without
+    /// extension ({start}, {end}, {shard} and {epoch} placeholders)
+    #[arg(long, default_value = "sparse_roots_{start}_{end}")]
+    sparse_roots_name_template: String,
+    /// Compression applied to completed sparse roots shard files
+    #[arg(long, value_enum, default_value = "none")]
+    sparse_roots_compression: ShardCompression,
+    /// URL of the chain state STARK proof for a height, advertised by `/full-proof`. The
+    /// `{height}` placeholder is replaced with the chain height.
+    #[arg(long)]
+    stark_proof_url_template: Option<String>,
+    /// Finality policy advertised by `/full-proof`: confirmations:<N>, work:<decimal> or
+    /// proof_covered
+    #[arg(long, default_value_t = FinalityPolicy::default())]
+    finality: FinalityPolicy,
+    /// Store the transactions of indexed blocks, so that transaction proofs can still be served
+    /// while the Zcash node is unavailable
+    #[arg(long)]
+    store_transactions: bool,
+    /// JSONL file recording every served proof (disabled if unset)
+    #[arg(long)]
+    audit_log: Option<PathBuf>,
+    /// Rotate the audit log once it reaches this many megabytes
+    #[arg(long, default_value = "100")]
+    audit_log_max_mb: u64,
+    /// Number of rotated audit log files kept
+    #[arg(long, default_value = "10")]
+    audit_log_keep: usize,
+    /// JSON file of the API keys granted access to the RPC server, with their daily quotas
+    /// (all requests are accepted if unset)
+    #[arg(long)]
+    api_keys: Option<PathBuf>,
+    /// Key required by the admin endpoints in the X-Admin-Key header (disabled if unset)
+    #[arg(long, env = "ADMIN_KEY", requires = "api_keys")]
+    admin_key: Option<String>,
+    /// Key required by /admin/shutdown in the X-Admin-Key header, independently of the API key
+    /// quotas (disabled if unset)
+    #[arg(long, env = "SHUTDOWN_KEY")]
+    shutdown_key: Option<String>,
+    /// Key required by prover heartbeats in the X-Prover-Key header, `/proving-status` is disabled
+    /// if unset
+    #[arg(long, env = "PROVER_KEY")]
+    prover_key: Option<String>,
+    /// Maximum number of headers returned by `/headers`, larger requests are truncated
+    #[arg(long, default_value_t = DEFAULT_MAX_HEADERS)]
+    max_headers_per_request: u32,
+    /// Maximum number of proofs generated concurrently by the RPC server
+    #[arg(long, default_value_t = DEFAULT_MAX_CONCURRENT_PROOFS)]
+    max_concurrent_proofs: usize,
+    /// Maximum number of proof requests waiting for a generation slot, further requests are
+    /// rejected with 429 Too Many Requests
+    #[arg(long, default_value_t = DEFAULT_MAX_QUEUED_PROOFS)]
+    max_queued_proofs: usize,
+    /// Field naming of the JSON responses: snake-case, or camel-case for clients not migrated
+    /// yet. Requests are accepted with either naming.
+    #[arg(long, value_enum, default_value = "snake-case")]
+    json_field_naming: FieldNaming,
+    /// Compare the FlyClient MMR root recorded at a random indexed height with the Zcash node
+    /// every this many seconds, to detect store corruption (0 disables)
+    #[arg(long, default_value = "600")]
+    crosscheck_interval_secs: u64,
+    /// FlyClient MMR roots of newly indexed blocks compared with the Zcash node: every:<N> (leaf
+    /// counts multiple of N and the first roots of each epoch), epoch_boundary or off
+    #[arg(long, default_value_t = RootCheckCadence::default())]
+    root_check: RootCheckCadence,
+    /// Log store queries slower than this many milliseconds, with their query plan (0 disables)
+    #[arg(long, default_value = "500")]
+    slow_query_ms: u64,
+    /// Start even if the Zcash node is older than the oldest supported release, only logging a
+    /// warning
+    #[arg(long)]
+    allow_unsupported_node: bool,
+    /// Check the Zcash node, the store and the disk space, print a readiness report and exit
+    /// (non-zero if a check fails)
+    #[arg(long)]
+    self_test: bool,
+    /// Free space required on the database volume by `--self-test`, in megabytes
+    #[arg(long, default_value = "1024")]
+    self_test_min_free_mb: u64,
     /// Logging level (off, error, warn, info, debug, trace)
     #[arg(long, default_value = "info")]
     log_level: String,
 }
 
+#[derive(Subcommand)]
+enum Command {
+    /// Inspect the store read-only, printing JSON
+    #[command(subcommand)]
+    Db(DbCommand),
+}
+
+fn parse_method_timeout(s: &str) -> Result<(String, Duration), String> {
+    let (method, secs) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected <method>=<seconds>, got {s:?}"))?;
+    let secs = secs
+        .parse()
+        .map_err(|_| format!("invalid timeout {secs:?} for {method}"))?;
+    Ok((method.to_string(), Duration::from_secs(secs)))
+}
+
+fn parse_network(s: &str) -> Result<Network, String> {
+    match s {
+        "mainnet" => Ok(Network::Mainnet),
+        "testnet" => Ok(Network::Testnet),
+        _ => Err(format!("expected mainnet or testnet, got {s:?}")),
+    }
+}
+
+/// Build metadata of the node, reported by `--version --verbose` and `/version`
+pub(crate) fn node_build_info() -> BuildInfo {
+    BuildInfo::new(
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+        &[("sqlcipher", cfg!(feature = "sqlcipher"))],
+    )
+}
+
 fn init_tracing(log_level: &str) {
     let env_filter =
         EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(log_level));
@@ -62,41 +249,189 @@ async fn main() {
     // Load environment variables from .env file if it exists
     dotenv::dotenv().ok();
 
+    if build_info::verbose_version_requested(std::env::args()) {
+        println!("{}", node_build_info().verbose_version());
+        return;
+    }
+
     let cli = Cli::parse();
     init_tracing(&cli.log_level);
 
     info!("Zoro bridge node is launching...");
 
+    let key_source = if let Some(var) = cli.db_key_env.clone() {
+        Some(DbKeySource::Env(var))
+    } else if let Some(path) = cli.db_key_file.clone() {
+        Some(DbKeySource::File(path))
+    } else {
+        cli.db_key_command.clone().map(DbKeySource::Command)
+    };
+    let db_key = match &key_source {
+        Some(source) => match DbKey::load(source).await {
+            Ok(key) => Some(key),
+            Err(e) => {
+                error!("Failed to load the database encryption key: {e}");
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    if let Some(Command::Db(command)) = &cli.command {
+        if let Err(e) = db::run(command, &cli.db_path, &cli.id, db_key.as_ref()).await {
+            error!("{e:#}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // Required by clap unless a subcommand is given
+    let zcash_rpc_url = cli.zcash_rpc_url.clone().expect("missing --zcash-rpc-url");
+    let zcash_rpc_auth = if let Some(path) = cli.zcash_rpc_cookie_file.clone() {
+        RpcAuth::CookieFile(path)
+    } else {
+        cli.zcash_rpc_userpwd
+            .clone()
+            .map_or(RpcAuth::None, RpcAuth::UserPassword)
+    };
+
+    let mut zcash_rpc_timeouts = RpcTimeouts {
+        default: Duration::from_secs(cli.zcash_rpc_timeout_secs),
+        ..Default::default()
+    };
+    zcash_rpc_timeouts
+        .methods
+        .extend(cli.zcash_rpc_method_timeout.clone());
+
+    if cli.self_test {
+        let config = SelfTestConfig {
+            rpc_url: zcash_rpc_url,
+            rpc_auth: zcash_rpc_auth,
+            rpc_timeouts: zcash_rpc_timeouts,
+            db_path: cli.db_path,
+            id: cli.id,
+            db_key,
+            min_free_mb: cli.self_test_min_free_mb,
+        };
+        let ready = selftest::run(&config).await;
+        std::process::exit(if ready { 0 } else { 1 });
+    }
+
+    let quota = match &cli.api_keys {
+        Some(path) => match load_api_keys(path).await {
+            Ok(api_keys) => Some(QuotaConfig {
+                api_keys,
+                admin_key: cli.admin_key.clone(),
+            }),
+            Err(e) => {
+                error!("Failed to load the API keys: {e}");
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
     // Instantiating components and wiring them together
-    let shutdown = Shutdown::default();
+    let mut shutdown = Shutdown::default();
+    let health = Arc::new(AppHealth::default());
+    let slow_query_threshold =
+        (cli.slow_query_ms > 0).then(|| Duration::from_millis(cli.slow_query_ms));
+    let indexer_query_metrics = Arc::new(QueryMetrics::new("indexer", slow_query_threshold));
+    let rpc_query_metrics = Arc::new(QueryMetrics::new("rpc", slow_query_threshold));
+    let crosscheck_query_metrics = Arc::new(QueryMetrics::new("crosscheck", slow_query_threshold));
+    health.register_query_metrics(indexer_query_metrics.clone());
+    health.register_query_metrics(rpc_query_metrics.clone());
+    health.register_query_metrics(crosscheck_query_metrics.clone());
 
     let indexer_config = IndexerConfig {
-        rpc_url: cli.zcash_rpc_url.clone(),
-        rpc_userpwd: cli.zcash_rpc_userpwd.clone(),
+        rpc_url: zcash_rpc_url.clone(),
+        rpc_auth: zcash_rpc_auth.clone(),
+        rpc_timeouts: zcash_rpc_timeouts.clone(),
         id: cli.id.clone(),
         db_path: cli.db_path.clone(),
         indexing_lag: cli.block_lag,
+        sparse_roots_sink: cli
+            .sparse_roots_dir
+            .clone()
+            .map(|output_dir| SparseRootsSinkConfig {
+                network: cli.network,
+                output_dir,
+                layout: if cli.sparse_roots_per_epoch {
+                    ShardLayout::Epoch
+                } else {
+                    ShardLayout::Fixed {
+                        size: cli.sparse_roots_shard_size,
+                    }
+                },
+                name_template: cli.sparse_roots_name_template.clone(),
+                compression: cli.sparse_roots_compression,
+            }),
+        db_key: db_key.clone(),
+        query_metrics: indexer_query_metrics,
+        store_transactions: cli.store_transactions,
+        allow_unsupported_node: cli.allow_unsupported_node,
+        root_check: cli.root_check,
     };
-    let mut indexer = Indexer::new(indexer_config, shutdown.subscribe());
+    let mut indexer = Indexer::new(indexer_config, health.clone(), shutdown.subscribe());
+
+    let crosscheck_config = (cli.crosscheck_interval_secs > 0).then(|| CrossCheckConfig {
+        rpc_url: zcash_rpc_url.clone(),
+        rpc_auth: zcash_rpc_auth.clone(),
+        rpc_timeouts: zcash_rpc_timeouts.clone(),
+        db_path: cli.db_path.clone(),
+        id: cli.id.clone(),
+        db_key: db_key.clone(),
+        query_metrics: crosscheck_query_metrics,
+        interval: Duration::from_secs(cli.crosscheck_interval_secs),
+    });
+    let mut crosschecker = crosscheck_config
+        .map(|config| CrossChecker::new(config, health.clone(), shutdown.subscribe()));
 
     let rpc_config = RpcConfig {
         rpc_host: cli.rpc_host,
         id: cli.id,
         db_path: cli.db_path.clone(),
-        rpc_url: cli.zcash_rpc_url.clone(),
-        rpc_userpwd: cli.zcash_rpc_userpwd.clone(),
+        rpc_url: zcash_rpc_url,
+        rpc_auth: zcash_rpc_auth,
+        rpc_timeouts: zcash_rpc_timeouts,
+        db_key,
+        query_metrics: rpc_query_metrics,
+        stark_proof_url_template: cli.stark_proof_url_template,
+        finality: cli.finality,
+        audit_log: cli.audit_log.map(|path| AuditLogConfig {
+            path,
+            max_bytes: cli.audit_log_max_mb * 1024 * 1024,
+            keep: cli.audit_log_keep,
+        }),
+        quota,
+        prover_key: cli.prover_key,
+        max_headers: cli.max_headers_per_request,
+        proof_limits: ProofLimiterConfig {
+            max_concurrent: cli.max_concurrent_proofs,
+            max_queued: cli.max_queued_proofs,
+        },
+        field_naming: cli.json_field_naming,
+        shutdown: shutdown.trigger(),
+        shutdown_key: cli.shutdown_key,
     };
-    let rpc_server = RpcServer::new(rpc_config, shutdown.subscribe());
+    let rpc_server = RpcServer::new(rpc_config, health, shutdown.subscribe());
 
     // Launching threads for each component
     let indexer_handle = tokio::spawn(async move { indexer.run().await });
     let rpc_handle = tokio::spawn(async move { rpc_server.run().await });
+    let crosscheck_handle = tokio::spawn(async move {
+        match crosschecker.as_mut() {
+            Some(crosschecker) => crosschecker.run().await,
+            None => Ok(()),
+        }
+    });
     let shutdown_handle = tokio::spawn(async move { shutdown.run().await });
 
     // If at least one component exits with an error, the node will exit with an error
     match tokio::try_join!(
         flatten(indexer_handle),
         flatten(rpc_handle),
+        flatten(crosscheck_handle),
         flatten(shutdown_handle)
     ) {
         Ok(_) => {
@@ -0,0 +1,102 @@
+//! Store query metrics: per-query duration histograms and slow query detection
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bounds (seconds) of the query duration histogram buckets
+const DURATION_BUCKETS: [f64; 10] = [
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.5, 1.0,
+];
+
+#[derive(Debug, Default, Clone)]
+struct Histogram {
+    /// Cumulative counts per bucket
+    buckets: [u64; DURATION_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(DURATION_BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+/// Duration metrics for the queries issued by a store
+#[derive(Debug)]
+pub struct QueryMetrics {
+    /// Value of the `store` label
+    store: &'static str,
+    /// Queries slower than this are logged along with their query plan
+    slow_query_threshold: Option<Duration>,
+    histograms: Mutex<BTreeMap<&'static str, Histogram>>,
+}
+
+impl QueryMetrics {
+    pub fn new(store: &'static str, slow_query_threshold: Option<Duration>) -> Self {
+        Self {
+            store,
+            slow_query_threshold,
+            histograms: Default::default(),
+        }
+    }
+
+    /// Record the duration of a query, returning whether it was slow
+    pub fn observe(&self, query: &'static str, elapsed: Duration) -> bool {
+        self.histograms
+            .lock()
+            .unwrap()
+            .entry(query)
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+        self.slow_query_threshold
+            .is_some_and(|threshold| elapsed >= threshold)
+    }
+
+    /// Render the histograms in the Prometheus text exposition format, without the `HELP` and
+    /// `TYPE` header lines
+    pub fn render(&self, out: &mut String) {
+        let store = self.store;
+        let histograms = self.histograms.lock().unwrap().clone();
+        for (query, histogram) in histograms {
+            for (bound, count) in DURATION_BUCKETS.iter().zip(histogram.buckets) {
+                let _ = writeln!(
+                    out,
+                    "zoro_store_query_duration_seconds_bucket{{store=\"{store}\",query=\"{query}\",le=\"{bound}\"}} {count}"
+                );
+            }
+            let _ = writeln!(
+                out,
+                "zoro_store_query_duration_seconds_bucket{{store=\"{store}\",query=\"{query}\",le=\"+Inf\"}} {}",
+                histogram.count
+            );
+            let _ = writeln!(
+                out,
+                "zoro_store_query_duration_seconds_sum{{store=\"{store}\",query=\"{query}\"}} {}",
+                histogram.sum
+            );
+            let _ = writeln!(
+                out,
+                "zoro_store_query_duration_seconds_count{{store=\"{store}\",query=\"{query}\"}} {}",
+                histogram.count
+            );
+        }
+    }
+}
+
+/// Write the `HELP` and `TYPE` lines of the query duration histogram
+pub fn render_header(out: &mut String) {
+    let _ = writeln!(
+        out,
+        "# HELP zoro_store_query_duration_seconds Duration of store queries"
+    );
+    let _ = writeln!(out, "# TYPE zoro_store_query_duration_seconds histogram");
+}
@@ -0,0 +1,143 @@
+//! Block header MMR audit (`zoro-bridge-node db audit-mmr`): the sparse roots shard files are
+//! compared with the roots recomputed from the stored block headers
+
+use std::path::Path;
+
+use serde::Serialize;
+use tracing::info;
+use zoro_spv_verify::{block_mmr::header_leaf, SparseRoots};
+
+use crate::chain_state::ChainStateStore;
+use crate::sparse_roots::{read_index, read_shard};
+use crate::store::AppStore;
+
+/// Number of block headers read from the store at once
+const AUDIT_BATCH_SIZE: u32 = 10_000;
+
+/// Outcome of the audit of one shard
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ShardStatus {
+    /// Every audited height has the recomputed roots
+    Match,
+    /// The record at `height` is missing or has other roots
+    Diverged { height: u32 },
+    /// The shard file cannot be read or decoded
+    Unreadable { error: String },
+}
+
+/// Audit of one shard file
+#[derive(Debug, Serialize)]
+pub struct ShardAudit {
+    /// Shard file, relative to the shard directory
+    pub file: String,
+    /// First audited height of the shard
+    pub start: u32,
+    /// Last audited height of the shard
+    pub end: u32,
+    #[serde(flatten)]
+    pub status: ShardStatus,
+}
+
+/// Result of [`audit`]
+#[derive(Debug, Serialize)]
+pub struct MmrAuditReport {
+    /// First audited height
+    pub from: u32,
+    /// Last audited height
+    pub to: u32,
+    /// Audited shards, in height order
+    pub shards: Vec<ShardAudit>,
+    /// First shard that is diverged or unreadable
+    pub first_divergent_shard: Option<String>,
+}
+
+/// Recompute the block header MMR over `from..=to` from the stored block headers, and compare
+/// the roots at every height with the shard files of `output_dir`.
+///
+/// The MMR starts from the roots stored at `from - 1`, or empty at genesis. `to` defaults to the
+/// last height of the shard index.
+pub async fn audit(
+    store: &AppStore,
+    output_dir: &Path,
+    from: u32,
+    to: Option<u32>,
+) -> Result<MmrAuditReport, anyhow::Error> {
+    let index = read_index(output_dir).await?;
+    let to = to.unwrap_or(index.last_height);
+    if from > to {
+        anyhow::bail!("Empty height range {from}..={to}");
+    }
+    let mut roots = match from.checked_sub(1) {
+        Some(height) => store.get_sparse_roots(height).await.map_err(|e| {
+            anyhow::anyhow!("No block header MMR roots in store at height {height}: {e}")
+        })?,
+        None => SparseRoots::default(),
+    };
+
+    let mut shards = Vec::new();
+    let mut height = from;
+    for entry in &index.shards {
+        let end = entry.end.map_or(to, |end| end.min(to));
+        if entry.start > to || end < from {
+            continue;
+        }
+        if entry.start > height {
+            anyhow::bail!("No shard listed for heights {height}..{}", entry.start);
+        }
+        let start = height;
+        info!("Auditing {} (heights {start}..={end})", entry.file);
+        let records = read_shard(output_dir, entry).await;
+        let mut status = match &records {
+            Ok(_) => ShardStatus::Match,
+            Err(e) => ShardStatus::Unreadable {
+                error: format!("{e:#}"),
+            },
+        };
+
+        while height <= end {
+            let num_blocks = (end - height + 1).min(AUDIT_BATCH_SIZE);
+            let headers = store.get_block_headers(height, num_blocks).await?;
+            if headers.len() != num_blocks as usize {
+                anyhow::bail!(
+                    "Missing block headers in store for heights {}..{}",
+                    height,
+                    height + num_blocks
+                );
+            }
+            for header in &headers {
+                roots.add(header_leaf(header)?);
+                let diverged = match (&status, &records) {
+                    (ShardStatus::Match, Ok(records)) => !records
+                        .get((height - entry.start) as usize)
+                        .is_some_and(|record| record.height == height && record.roots == roots),
+                    _ => false,
+                };
+                if diverged {
+                    status = ShardStatus::Diverged { height };
+                }
+                height += 1;
+            }
+        }
+        shards.push(ShardAudit {
+            file: entry.file.clone(),
+            start,
+            end,
+            status,
+        });
+    }
+    if height <= to {
+        anyhow::bail!("No shard listed for heights {height}..={to}");
+    }
+
+    let first_divergent_shard = shards
+        .iter()
+        .find(|shard| !matches!(shard.status, ShardStatus::Match))
+        .map(|shard| shard.file.clone());
+    Ok(MmrAuditReport {
+        from,
+        to,
+        shards,
+        first_divergent_shard,
+    })
+}
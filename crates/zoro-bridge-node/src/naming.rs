@@ -0,0 +1,96 @@
+//! Field naming of the JSON responses.
+//!
+//! Responses are built from the `zoro-spv-verify` types, with snake_case fields. While clients
+//! still expect camelCase fields, the bridge can rename the object keys of its JSON responses.
+//! The types accept both namings as input, through serde aliases, so clients can migrate either
+//! way during the deprecation window.
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::Request,
+    http::header::{CONTENT_LENGTH, CONTENT_TYPE},
+    middleware::Next,
+    response::Response,
+};
+use serde_json::Value;
+use tracing::error;
+
+/// Field naming of the JSON responses
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum FieldNaming {
+    /// Fields as named in the Rust types (`block_height`)
+    #[default]
+    SnakeCase,
+    /// Fields renamed for JavaScript clients (`blockHeight`)
+    CamelCase,
+}
+
+/// camelCase form of a snake_case key, other keys unchanged
+fn camel_case(key: &str) -> String {
+    let mut renamed = String::with_capacity(key.len());
+    let mut upper = false;
+    for c in key.chars() {
+        match c {
+            '_' if !renamed.is_empty() => upper = true,
+            _ if upper => {
+                renamed.extend(c.to_uppercase());
+                upper = false;
+            }
+            _ => renamed.push(c),
+        }
+    }
+    renamed
+}
+
+/// Rename the object keys of a JSON value to camelCase, recursively
+fn camel_case_keys(value: Value) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| (camel_case(&key), camel_case_keys(value)))
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(values.into_iter().map(camel_case_keys).collect()),
+        value => value,
+    }
+}
+
+/// Middleware renaming the fields of the JSON responses to camelCase. Health, metrics, version
+/// and admin responses, whose keys are not type fields, are left as is.
+pub async fn camel_case_responses(request: Request, next: Next) -> Response {
+    let path = request.uri().path();
+    if ["/health", "/metrics", "/version"].contains(&path) || path.starts_with("/admin/") {
+        return next.run(request).await;
+    }
+    let response = next.run(request).await;
+    let is_json = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"application/json"));
+    if !is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let renamed = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => serde_json::from_slice(&bytes)
+            .map(camel_case_keys)
+            .and_then(|value| serde_json::to_vec(&value))
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    match renamed {
+        Ok(body) => {
+            parts.headers.remove(CONTENT_LENGTH);
+            Response::from_parts(parts, Body::from(body))
+        }
+        Err(e) => {
+            error!("Failed to rename the fields of a JSON response: {e}");
+            Response::builder()
+                .status(axum::http::StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::from("Failed to encode the response"))
+                .unwrap_or_default()
+        }
+    }
+}
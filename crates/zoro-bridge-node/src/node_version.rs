@@ -0,0 +1,123 @@
+//! Compatibility of the Zcash node software with the indexer
+//!
+//! The indexer parses `getblock` responses, whose fields and encodings changed across node
+//! releases. A node older than the releases the indexer was validated against may still answer
+//! every call, while returning data the indexer misreads (e.g. a byte-reversed Sapling root),
+//! so its version is checked when the indexer starts.
+
+use tracing::{info, warn};
+use zoro_zcash_client::ZcashClient;
+
+/// Oldest supported release of a node implementation
+struct MinimumVersion {
+    /// Implementation name, as in the user agent (`/MagicBean:6.2.0/`)
+    implementation: &'static str,
+    /// Display name of the implementation
+    name: &'static str,
+    version: [u32; 3],
+    /// What older releases lack
+    reason: &'static str,
+}
+
+const MINIMUM_VERSIONS: &[MinimumVersion] = &[
+    MinimumVersion {
+        implementation: "MagicBean",
+        name: "zcashd",
+        version: [5, 0, 0],
+        reason: "`getblock` has no `blockcommitments` before NU5 support",
+    },
+    MinimumVersion {
+        implementation: "Zebra",
+        name: "zebrad",
+        version: [2, 0, 0],
+        reason: "`getblock` fields used by the indexer (`finalsaplingroot`, `blockcommitments`, \
+                 verbose transactions) differ from zcashd in older releases",
+    },
+];
+
+/// Implementation and version of a node, parsed from its user agent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeVersion {
+    pub implementation: String,
+    pub version: [u32; 3],
+}
+
+impl NodeVersion {
+    /// Parse a user agent such as `/Zebra:2.3.0/` or `/MagicBean:6.2.0(bitcore)/`
+    pub fn parse(subversion: &str) -> Option<Self> {
+        let agent = subversion.trim_matches('/').split('/').next()?;
+        let (implementation, version) = agent.split_once(':')?;
+        let version = version
+            .split(|c: char| !c.is_ascii_digit() && c != '.')
+            .next()?;
+        let mut parts = version.split('.').map(|part| part.parse::<u32>().ok());
+        let version = [
+            parts.next()??,
+            parts.next().flatten().unwrap_or(0),
+            parts.next().flatten().unwrap_or(0),
+        ];
+        Some(Self {
+            implementation: implementation.to_string(),
+            version,
+        })
+    }
+}
+
+/// Compatibility of a node with the indexer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Compatibility {
+    /// A supported release of a known implementation
+    Supported,
+    /// A release older than the oldest supported one
+    Unsupported(String),
+    /// An unknown implementation, or a user agent without a version
+    Unknown,
+}
+
+/// Compatibility of the node reporting the user agent `subversion`
+pub fn compatibility(subversion: &str) -> Compatibility {
+    let Some(node) = NodeVersion::parse(subversion) else {
+        return Compatibility::Unknown;
+    };
+    let Some(minimum) = MINIMUM_VERSIONS
+        .iter()
+        .find(|minimum| minimum.implementation == node.implementation)
+    else {
+        return Compatibility::Unknown;
+    };
+    if node.version >= minimum.version {
+        Compatibility::Supported
+    } else {
+        let [major, minor, patch] = minimum.version;
+        Compatibility::Unsupported(format!(
+            "{} older than {major}.{minor}.{patch} is not supported: {}",
+            minimum.name, minimum.reason
+        ))
+    }
+}
+
+/// Log the version of the Zcash node, and fail if it is not supported unless `allow_unsupported`
+pub async fn check_node_version(
+    zcash_client: &ZcashClient,
+    allow_unsupported: bool,
+) -> Result<(), anyhow::Error> {
+    let subversion = zcash_client.get_node_version().await?;
+    info!("Zcash node: {}", subversion);
+    match compatibility(&subversion) {
+        Compatibility::Supported => Ok(()),
+        Compatibility::Unknown => {
+            warn!(
+                "Unknown Zcash node implementation {}, `getblock` responses may be misread",
+                subversion
+            );
+            Ok(())
+        }
+        Compatibility::Unsupported(reason) if allow_unsupported => {
+            warn!("{reason} (allowed by --allow-unsupported-node)");
+            Ok(())
+        }
+        Compatibility::Unsupported(reason) => Err(anyhow::anyhow!(
+            "{reason}. Upgrade the node, or pass --allow-unsupported-node at your own risk"
+        )),
+    }
+}
@@ -0,0 +1,55 @@
+//! Proving status pushed by the prover and served on `/proving-status`
+
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use zoro_spv_verify::{ProverHeartbeat, ProvingStatus};
+
+use crate::quota::key_matches;
+
+/// Header carrying the prover key of a heartbeat
+pub const PROVER_KEY_HEADER: &str = "x-prover-key";
+
+/// Latest heartbeat of the prover
+#[derive(Debug)]
+pub struct ProverTracker {
+    /// Key required to push heartbeats
+    key: String,
+    /// Latest heartbeat and its time (UNIX seconds)
+    last: Mutex<Option<(ProverHeartbeat, u64)>>,
+}
+
+impl ProverTracker {
+    pub fn new(key: String) -> Self {
+        Self {
+            key,
+            last: Mutex::new(None),
+        }
+    }
+
+    /// Whether `key` grants pushing heartbeats
+    pub fn is_prover(&self, key: Option<&str>) -> bool {
+        key_matches(&self.key, key)
+    }
+
+    pub fn record(&self, heartbeat: ProverHeartbeat) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        *self.last.lock().unwrap() = Some((heartbeat, now));
+    }
+
+    /// Status of the prover against the latest indexed height, `None` before the first heartbeat
+    pub fn status(&self, indexed_head: u32) -> Option<ProvingStatus> {
+        let last = self.last.lock().unwrap();
+        let (heartbeat, last_heartbeat) = last.as_ref()?;
+        Some(ProvingStatus {
+            proven_height: heartbeat.proven_height,
+            in_progress: heartbeat.in_progress.clone(),
+            last_heartbeat: *last_heartbeat,
+            indexed_head,
+            lag: indexed_head.saturating_sub(heartbeat.proven_height),
+        })
+    }
+}
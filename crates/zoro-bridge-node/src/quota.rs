@@ -0,0 +1,343 @@
+//! Per-API-key quotas and usage accounting of the RPC server
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use axum::{
+    body::HttpBody,
+    extract::{Request, State},
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use subtle::ConstantTimeEq;
+use tokio::fs;
+use tracing::warn;
+
+use crate::store::AppStore;
+
+/// Header carrying the API key of a request
+pub const API_KEY_HEADER: &str = "x-api-key";
+/// Header carrying the admin key of an admin request
+pub const ADMIN_KEY_HEADER: &str = "x-admin-key";
+
+/// Whether `key` is `expected`, compared in constant time so that response times do not reveal
+/// how much of the key was guessed
+pub fn key_matches(expected: &str, key: Option<&str>) -> bool {
+    key.is_some_and(|key| expected.as_bytes().ct_eq(key.as_bytes()).into())
+}
+
+/// Interval between the writes of the usage to the store
+pub const USAGE_FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Endpoints whose successful responses count as proof computations
+const PROOF_ENDPOINTS: [&str; 6] = [
+    "/block-inclusion-proof/",
+    "/block/",
+    "/transaction-proof/",
+    "/transaction-proofs/",
+    "/transaction-absence/",
+    "/full-proof/",
+];
+
+/// API key granted access to the RPC server. Quotas are per UTC day; unset quotas are unlimited.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiKey {
+    /// Secret sent in the `X-Api-Key` header
+    pub key: String,
+    /// Name the usage is accounted under
+    pub name: String,
+    /// Maximum number of requests
+    #[serde(default)]
+    pub max_requests: Option<u64>,
+    /// Maximum number of response body bytes served
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+    /// Maximum number of proofs generated
+    #[serde(default)]
+    pub max_proofs: Option<u64>,
+}
+
+impl ApiKey {
+    /// Name of the first quota `usage` has reached
+    fn exceeded_quota(&self, usage: &ApiUsage) -> Option<&'static str> {
+        let reached = |max: Option<u64>, used: u64| max.is_some_and(|max| used >= max);
+        if reached(self.max_requests, usage.requests) {
+            Some("request")
+        } else if reached(self.max_bytes, usage.bytes) {
+            Some("bytes")
+        } else if reached(self.max_proofs, usage.proofs) {
+            Some("proof")
+        } else {
+            None
+        }
+    }
+}
+
+/// Load the API keys from a JSON file holding an array of [`ApiKey`]
+pub async fn load_api_keys(path: &Path) -> Result<Vec<ApiKey>, anyhow::Error> {
+    let data = fs::read(path)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to read API keys from {}: {e}", path.display()))?;
+    let keys: Vec<ApiKey> = serde_json::from_slice(&data)
+        .map_err(|e| anyhow::anyhow!("Failed to parse API keys from {}: {e}", path.display()))?;
+    if keys.is_empty() {
+        anyhow::bail!("No API keys in {}", path.display());
+    }
+    Ok(keys)
+}
+
+/// Configuration of the API key quotas
+#[derive(Debug, Clone)]
+pub struct QuotaConfig {
+    /// Keys granted access; requests without one of them are rejected
+    pub api_keys: Vec<ApiKey>,
+    /// Key required by the admin endpoints, which are disabled if unset
+    pub admin_key: Option<String>,
+}
+
+/// Usage of an API key over a day
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ApiUsage {
+    /// Number of requests
+    pub requests: u64,
+    /// Number of response body bytes served (before compression)
+    pub bytes: u64,
+    /// Number of proofs generated
+    pub proofs: u64,
+}
+
+impl ApiUsage {
+    fn add(&mut self, other: &ApiUsage) {
+        self.requests += other.requests;
+        self.bytes += other.bytes;
+        self.proofs += other.proofs;
+    }
+}
+
+/// Usage of an API key along with its quotas, returned by `/admin/usage`
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiKeyUsage {
+    pub name: String,
+    /// Days since the Unix epoch
+    pub day: u64,
+    #[serde(flatten)]
+    pub usage: ApiUsage,
+    pub max_requests: Option<u64>,
+    pub max_bytes: Option<u64>,
+    pub max_proofs: Option<u64>,
+}
+
+/// Current UTC day, in days since the Unix epoch
+pub fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() / 86_400)
+}
+
+/// Enforces the quotas of the API keys and accounts for their usage in the store.
+///
+/// Usage is counted in memory and written to the store by [`Meter::flush`], off the response
+/// path: the store has a single writer, shared with the indexer's long block transactions.
+#[derive(Debug)]
+pub struct Meter {
+    /// API keys, looked up with [`key_matches`]
+    keys: Vec<ApiKey>,
+    admin_key: Option<String>,
+    /// Writable store the usage is persisted to
+    store: AppStore,
+    /// Day and usage per key name on that day
+    usage: Mutex<(u64, HashMap<String, ApiUsage>)>,
+    /// Usage not written to the store yet, by key name and day
+    pending: Mutex<HashMap<(String, u64), ApiUsage>>,
+}
+
+impl Meter {
+    /// Create the meter, restoring today's usage from the store
+    pub async fn new(config: QuotaConfig, store: AppStore) -> Result<Self, anyhow::Error> {
+        let day = today();
+        let usage = store.get_api_usage(day).await?.into_iter().collect();
+        Ok(Self {
+            keys: config.api_keys,
+            admin_key: config.admin_key,
+            store,
+            usage: Mutex::new((day, usage)),
+            pending: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Whether `key` grants access to the admin endpoints
+    pub fn is_admin(&self, key: Option<&str>) -> bool {
+        self.admin_key
+            .as_deref()
+            .is_some_and(|admin_key| key_matches(admin_key, key))
+    }
+
+    /// Configured API key whose secret is `key`. Every key is compared in constant time, so that
+    /// response times do not reveal which one matched or how much of it was guessed.
+    fn api_key(&self, key: Option<&str>) -> Option<&ApiKey> {
+        self.keys.iter().fold(None, |found, candidate| {
+            if key_matches(&candidate.key, key) {
+                Some(candidate)
+            } else {
+                found
+            }
+        })
+    }
+
+    /// Admit a request made with `key`, counting it against the key's quotas. Returns the key
+    /// name and the day the request is accounted on.
+    fn admit(&self, key: Option<&str>) -> Result<(String, u64), (StatusCode, String)> {
+        let key = self.api_key(key).ok_or_else(|| {
+            (
+                StatusCode::UNAUTHORIZED,
+                format!("Missing or unknown API key (set the {API_KEY_HEADER} header)"),
+            )
+        })?;
+        let day = today();
+        let mut usage = self.usage.lock().unwrap();
+        if usage.0 != day {
+            *usage = (day, HashMap::new());
+        }
+        let current = usage.1.entry(key.name.clone()).or_default();
+        if let Some(quota) = key.exceeded_quota(current) {
+            return Err((
+                StatusCode::TOO_MANY_REQUESTS,
+                format!("Daily {quota} quota of API key {} exceeded", key.name),
+            ));
+        }
+        current.requests += 1;
+        Ok((key.name.clone(), day))
+    }
+
+    /// Account for a served response, persisted by the next [`Meter::flush`]
+    fn record(&self, name: &str, day: u64, bytes: u64, proof: bool) {
+        let served = ApiUsage {
+            requests: 0,
+            bytes,
+            proofs: proof as u64,
+        };
+        {
+            let mut usage = self.usage.lock().unwrap();
+            if usage.0 == day {
+                usage.1.entry(name.to_string()).or_default().add(&served);
+            }
+        }
+        let delta = ApiUsage {
+            requests: 1,
+            ..served
+        };
+        self.pending
+            .lock()
+            .unwrap()
+            .entry((name.to_string(), day))
+            .or_default()
+            .add(&delta);
+    }
+
+    /// Write the usage recorded since the last flush to the store. Usage that fails to be
+    /// written is kept for the next flush.
+    pub async fn flush(&self) {
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+        let mut failed = Vec::new();
+        for ((name, day), delta) in pending {
+            if let Err(e) = self.store.add_api_usage(&name, day, &delta).await {
+                warn!("Failed to persist the usage of API key {}: {}", name, e);
+                failed.push(((name, day), delta));
+            }
+        }
+        let mut pending = self.pending.lock().unwrap();
+        for (key, delta) in failed {
+            pending.entry(key).or_default().add(&delta);
+        }
+    }
+
+    /// Flush the usage every `interval`, until the meter is dropped by every other owner
+    pub async fn flush_periodically(self: Arc<Self>, interval: Duration) {
+        let mut ticks = tokio::time::interval(interval);
+        ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticks.tick().await;
+            self.flush().await;
+            if Arc::strong_count(&self) == 1 {
+                return;
+            }
+        }
+    }
+
+    /// Usage of every API key on `day`, including the keys that made no request and the usage
+    /// not flushed yet
+    pub async fn usage(&self, day: u64) -> Result<Vec<ApiKeyUsage>, anyhow::Error> {
+        let mut usage: HashMap<String, ApiUsage> =
+            self.store.get_api_usage(day).await?.into_iter().collect();
+        for ((name, pending_day), delta) in self.pending.lock().unwrap().iter() {
+            if *pending_day == day {
+                usage.entry(name.clone()).or_default().add(delta);
+            }
+        }
+        let mut report: Vec<ApiKeyUsage> = self
+            .keys
+            .iter()
+            .map(|key| ApiKeyUsage {
+                name: key.name.clone(),
+                day,
+                usage: usage.remove(&key.name).unwrap_or_default(),
+                max_requests: key.max_requests,
+                max_bytes: key.max_bytes,
+                max_proofs: key.max_proofs,
+            })
+            .collect();
+        // Keys removed from the configuration since
+        report.extend(usage.into_iter().map(|(name, usage)| ApiKeyUsage {
+            name,
+            day,
+            usage,
+            max_requests: None,
+            max_bytes: None,
+            max_proofs: None,
+        }));
+        report.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(report)
+    }
+}
+
+/// Middleware checking the API key of a request against its quotas and accounting for the
+/// response. Health, metrics, version and admin endpoints, and prover heartbeats, are not metered.
+pub async fn meter_requests(
+    State(meter): State<Arc<Meter>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path();
+    let is_heartbeat = request.method() == Method::POST && path == "/proving-status";
+    if ["/health", "/metrics", "/version"].contains(&path)
+        || path.starts_with("/admin/")
+        || is_heartbeat
+    {
+        return next.run(request).await;
+    }
+    let is_proof = PROOF_ENDPOINTS
+        .iter()
+        .any(|prefix| path.starts_with(prefix));
+    let key = request
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok());
+    let (name, day) = match meter.admit(key) {
+        Ok(admitted) => admitted,
+        Err(rejection) => return rejection.into_response(),
+    };
+
+    let response = next.run(request).await;
+    let bytes = response.body().size_hint().exact().unwrap_or(0);
+    meter.record(
+        &name,
+        day,
+        bytes,
+        is_proof && response.status().is_success(),
+    );
+    response
+}
@@ -2,79 +2,134 @@
 
 use accumulators::{
     hasher::flyclient::ZcashFlyclientHasher,
-    mmr::{
-        elements_count_to_leaf_count, leaf_count_to_mmr_size, map_leaf_index_to_element_index,
-        ProofOptions, MMR,
-    },
+    mmr::{ProofOptions, MMR},
+    store::StoreError,
 };
 
-/// Heartwood activation height - FlyClient MMR starts here
-const HEARTWOOD_ACTIVATION: u32 = 903_000;
-/// Canopy activation height (mainnet) - new epoch
-const CANOPY_ACTIVATION: u32 = 1_046_400;
-/// NU5 activation height (mainnet) - new epoch
-const NU5_ACTIVATION: u32 = 1_687_104;
-
 /// Get the epoch name for a height
 fn epoch_name_for_height(height: u32) -> &'static str {
-    if height >= NU5_ACTIVATION {
-        "nu5"
-    } else if height >= CANOPY_ACTIVATION {
-        "canopy"
-    } else {
-        "heartwood"
-    }
+    flyclient_epoch(height).name()
+}
+
+/// Identifier of the FlyClient MMR of the epoch containing `height`
+fn flyclient_mmr_id(height: u32) -> String {
+    format!("flyclient_{}", epoch_name_for_height(height))
 }
 
 /// Get the epoch start height
-fn epoch_start_height(height: u32) -> u32 {
-    if height >= NU5_ACTIVATION {
-        NU5_ACTIVATION
-    } else if height >= CANOPY_ACTIVATION {
-        CANOPY_ACTIVATION
-    } else {
-        HEARTWOOD_ACTIVATION
+pub(crate) fn epoch_start_height(height: u32) -> u32 {
+    flyclient_epoch(height).start_height
+}
+/// Get the last height of the epoch containing `height`, `None` for the current epoch
+fn epoch_end_height(height: u32) -> Option<u32> {
+    flyclient_epoch(height).end_height
+}
+
+/// Resolve the chain height a block inclusion proof is generated against.
+///
+/// Each epoch has its own MMR with leaf `i` holding block `epoch_start + i`, so a proof
+/// against chain height `C` uses `C - epoch_start + 1` leaves. Its root is the
+/// `hashChainHistoryRoot` committed by block `C + 1`, which lets auditors reproduce historical
+/// verifications against historical `blockcommitments`.
+///
+/// `chain_height` must not be before the block; heights past the end of the block's epoch are
+/// clamped to the epoch's last block, since the epoch MMR stops growing there.
+fn proof_chain_height(block_height: u32, chain_height: u32) -> Result<u32, String> {
+    if chain_height < block_height {
+        return Err(format!(
+            "Chain height {chain_height} is before block height {block_height}"
+        ));
     }
+    Ok(match epoch_end_height(block_height) {
+        Some(epoch_end) => chain_height.min(epoch_end),
+        None => chain_height,
+    })
 }
+
 use hex::FromHex;
 use tokio::net::TcpListener;
-use tokio::sync::broadcast;
-use tracing::{error, info};
-use zoro_zcash_client::ZcashClient;
+use tokio::sync::{broadcast, OwnedSemaphorePermit};
+use tracing::{error, info, warn};
+use zoro_zcash_client::{
+    DisplayOrderHash, InternalOrderHash, MerkleTree, RpcAuth, RpcTimeouts, ZcashClient,
+};
 
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
-    routing::get,
+    http::{HeaderMap, StatusCode},
+    middleware,
+    routing::{get, post},
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
-use std::{path::PathBuf, sync::Arc};
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc, time::Duration};
 use tower_http::{compression::CompressionLayer, cors::CorsLayer, trace::TraceLayer};
-use zebra_chain::{block::Header, transaction::Hash};
+use zebra_chain::{
+    block::{Hash as BlockHash, Header},
+    transaction::{Hash, Transaction},
+};
 
-use zoro_spv_verify::{ChainState, TransactionInclusionProof};
+use zoro_spv_verify::{
+    mmr_math::{elements_count_to_leaf_count, leaf_count_to_mmr_size, leaf_index_to_element_index},
+    BlockHeaderRecord, BlockInclusionProof, BuildInfo, ChainState, CoinbaseOutput,
+    CoinbaseProofResponse, ConfirmationStatus, FinalityPolicy, FlyClientNodeData,
+    FullProofResponse, MatchedTxId, MmrRootRecord, ProofSource, ProverHeartbeat, ProvingStatus,
+    SparseRootsRecord, TransactionAbsenceProof, TransactionBucketResponse,
+    TransactionInclusionProof, TransactionProofResponse, TxIdKind,
+};
 
-use crate::{chain_state::ChainStateStore, store::AppStore};
+use crate::{
+    audit::{AuditLog, AuditLogConfig, AuditRecord},
+    chain_state::ChainStateStore,
+    coalesce::Coalescer,
+    db_key::DbKey,
+    health::{AppHealth, HealthReport},
+    indexer::{flyclient_epoch, HEARTWOOD_ACTIVATION},
+    limiter::{ProofLimiter, ProofLimiterConfig, Unavailable},
+    metrics::QueryMetrics,
+    naming::{camel_case_responses, FieldNaming},
+    proving::{ProverTracker, PROVER_KEY_HEADER},
+    quota::{
+        key_matches, meter_requests, today, ApiKeyUsage, Meter, QuotaConfig, ADMIN_KEY_HEADER,
+        USAGE_FLUSH_INTERVAL,
+    },
+    shutdown::ShutdownTrigger,
+    snapshot::{Snapshot, SnapshotStores},
+    source::{ChainSource, ProofStore},
+    store::AppStore,
+};
 
 /// Query parameters for block inclusion proof generation and roots retrieval
 #[derive(Debug, Deserialize)]
 pub struct ChainHeightQuery {
+    #[serde(alias = "chainHeight")]
     pub chain_height: Option<u32>,
 }
-/// Proof data structure for demonstrating inclusion of a block in the MMR
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct BlockInclusionProof {
-    /// Block height
-    pub block_height: u32,
-    /// MMR peak hashes at the time of proof generation
-    pub peaks_hashes: Vec<String>,
-    /// Sibling hashes needed to reconstruct the path to the root
-    pub siblings_hashes: Vec<String>,
-    /// Leaf index of the block in the MMR
-    pub leaf_index: usize,
-    /// Total number of leaves in the MMR
-    pub leaf_count: usize,
+
+/// Query parameters for API key usage retrieval
+#[derive(Debug, Deserialize)]
+pub struct UsageQuery {
+    /// Days since the Unix epoch (defaults to today, UTC)
+    pub day: Option<u64>,
+}
+
+/// Query parameters for soft shutdowns
+#[derive(Debug, Deserialize)]
+pub struct ShutdownQuery {
+    /// Seconds given to the proofs being generated to complete (defaults to
+    /// [`DEFAULT_DRAIN_SECONDS`])
+    #[serde(alias = "drainSeconds")]
+    pub drain_seconds: Option<u64>,
+}
+
+/// Default number of seconds a soft shutdown waits for the proofs being generated
+pub const DEFAULT_DRAIN_SECONDS: u64 = 30;
+
+/// Accepted soft shutdown
+#[derive(Debug, Serialize)]
+pub struct ShutdownResponse {
+    /// Seconds the proofs being generated are given to complete before the shutdown
+    pub drain_seconds: u64,
 }
 
 /// Query parameters for block headers retrieval
@@ -84,6 +139,9 @@ pub struct BlockHeadersQuery {
     pub size: Option<u32>,
 }
 
+/// Default maximum number of headers returned by `/headers`
+pub const DEFAULT_MAX_HEADERS: u32 = 1000;
+
 /// Configuration for the RPC server
 #[derive(Clone)]
 pub struct RpcConfig {
@@ -95,21 +153,68 @@ pub struct RpcConfig {
     pub db_path: PathBuf,
     /// Zcash RPC URL
     pub rpc_url: String,
-    /// Zcash RPC user:password (optional)
-    pub rpc_userpwd: Option<String>,
+    /// Zcash RPC credentials
+    pub rpc_auth: RpcAuth,
+    /// Zcash RPC request timeouts
+    pub rpc_timeouts: RpcTimeouts,
+    /// Database encryption key (optional)
+    pub db_key: Option<DbKey>,
+    /// Query metrics of the RPC server store
+    pub query_metrics: Arc<QueryMetrics>,
+    /// URL of the chain state STARK proof for a height, with a `{height}` placeholder (optional)
+    pub stark_proof_url_template: Option<String>,
+    /// Finality policy advertised by `/full-proof`
+    pub finality: FinalityPolicy,
+    /// Audit log of the served proofs (optional)
+    pub audit_log: Option<AuditLogConfig>,
+    /// API key quotas, all requests are accepted if unset (optional)
+    pub quota: Option<QuotaConfig>,
+    /// Key required to push prover heartbeats, `/proving-status` is disabled if unset (optional)
+    pub prover_key: Option<String>,
+    /// Maximum number of headers returned by `/headers`
+    pub max_headers: u32,
+    /// Limits of the proof generation queue
+    pub proof_limits: ProofLimiterConfig,
+    /// Field naming of the JSON responses
+    pub field_naming: FieldNaming,
+    /// Shutdown of the node, requested by `/admin/shutdown`
+    pub shutdown: ShutdownTrigger,
+    /// Key required by `/admin/shutdown`, which is disabled if unset (optional)
+    pub shutdown_key: Option<String>,
 }
 
 /// HTTP RPC server that provides endpoints for header state operations
 pub struct RpcServer {
     config: RpcConfig,
+    health: Arc<AppHealth>,
     rx_shutdown: broadcast::Receiver<()>,
 }
 
 #[derive(Clone)]
 pub struct AppState {
-    store: Arc<AppStore>,
-    zcash_client: Arc<ZcashClient>,
+    store: Arc<dyn ProofStore>,
+    zcash_client: Arc<dyn ChainSource>,
+    health: Arc<AppHealth>,
     db_path: PathBuf,
+    stark_proof_url_template: Option<String>,
+    finality: FinalityPolicy,
+    /// In-flight block fetches, so that concurrent proofs for transactions of the same block
+    /// trigger a single `getblock` upstream
+    block_requests: Arc<Coalescer<BlockHash, Result<Arc<FetchedBlock>, String>>>,
+    audit_log: Option<Arc<AuditLog>>,
+    meter: Option<Arc<Meter>>,
+    prover: Option<Arc<ProverTracker>>,
+    max_headers: u32,
+    /// Generation slots shared by the proof endpoints
+    proof_limiter: Arc<ProofLimiter>,
+    /// Shutdown of the node, `/admin/shutdown` is disabled if unset
+    shutdown: Option<ShutdownTrigger>,
+    /// Key required by `/admin/shutdown`
+    shutdown_key: Option<String>,
+    /// Stores of the snapshot-consistent reads, reads are not isolated if unset
+    snapshots: Option<Arc<SnapshotStores>>,
+    /// Snapshot `store` reads from, see [`AppState::with_snapshot`]
+    snapshot: Option<Arc<Snapshot>>,
 }
 
 impl std::fmt::Debug for AppState {
@@ -121,34 +226,163 @@ impl std::fmt::Debug for AppState {
 }
 
 impl AppState {
-    pub async fn new(config: RpcConfig) -> Result<Self, anyhow::Error> {
+    pub async fn new(config: RpcConfig, health: Arc<AppHealth>) -> Result<Self, anyhow::Error> {
         let id = Some(config.id.clone());
         let store = Arc::new(AppStore::multiple_concurrent_readers(
             &config.db_path,
             id.clone(),
-        ));
-        let zcash_client =
-            ZcashClient::new(config.rpc_url.clone(), config.rpc_userpwd.clone()).await?;
-        Ok(Self {
-            zcash_client: Arc::new(zcash_client),
-            store: store.clone(),
-            db_path: config.db_path.clone(),
+            config.db_key.as_ref(),
+            config.query_metrics.clone(),
+        )?);
+        let zcash_client = ZcashClient::with_auth(config.rpc_url.clone(), config.rpc_auth.clone())
+            .await?
+            .with_timeouts(config.rpc_timeouts.clone())?;
+        let mut state = Self::with_sources(
+            store,
+            Arc::new(zcash_client),
+            health,
+            config.db_path.clone(),
+            config.stark_proof_url_template.clone(),
+        );
+        state.finality = config.finality;
+        state.max_headers = config.max_headers;
+        state.proof_limiter = Arc::new(ProofLimiter::new(config.proof_limits));
+        if config.shutdown_key.is_some() {
+            state.shutdown = Some(config.shutdown);
+            state.shutdown_key = config.shutdown_key;
+        }
+        state.snapshots = Some(Arc::new(SnapshotStores::new(
+            config.db_path.clone(),
+            id.clone(),
+            config.db_key.clone(),
+            config.query_metrics.clone(),
+        )));
+        state.prover = config
+            .prover_key
+            .map(|key| Arc::new(ProverTracker::new(key)));
+        if let Some(audit_log) = config.audit_log {
+            state.audit_log = Some(Arc::new(AuditLog::new(audit_log).await?));
+        }
+        if let Some(quota) = config.quota {
+            // Usage is written from the RPC server, next to the read-only pool
+            let usage_store = AppStore::single_atomic_writer(
+                &config.db_path,
+                id,
+                config.db_key.as_ref(),
+                config.query_metrics.clone(),
+            )
+            .await?;
+            state.meter = Some(Arc::new(Meter::new(quota, usage_store).await?));
+        }
+        Ok(state)
+    }
+
+    /// Create the state on top of the given data sources, e.g. mocks in handler tests
+    pub fn with_sources(
+        store: Arc<dyn ProofStore>,
+        zcash_client: Arc<dyn ChainSource>,
+        health: Arc<AppHealth>,
+        db_path: PathBuf,
+        stark_proof_url_template: Option<String>,
+    ) -> Self {
+        Self {
+            store,
+            zcash_client,
+            health,
+            db_path,
+            stark_proof_url_template,
+            finality: FinalityPolicy::default(),
+            block_requests: Default::default(),
+            audit_log: None,
+            meter: None,
+            prover: None,
+            max_headers: DEFAULT_MAX_HEADERS,
+            proof_limiter: Arc::new(ProofLimiter::new(ProofLimiterConfig::default())),
+            shutdown: None,
+            shutdown_key: None,
+            snapshots: None,
+            snapshot: None,
+        }
+    }
+
+    /// Take a proof generation slot, held until the permit is dropped. Fails with
+    /// `429 Too Many Requests` if the queue of waiting proofs is full, and with
+    /// `503 Service Unavailable` while the node drains before a shutdown.
+    async fn proof_slot(&self) -> Result<OwnedSemaphorePermit, (StatusCode, String)> {
+        self.proof_limiter.acquire().await.map_err(|e| match e {
+            Unavailable::Saturated => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "Too many proofs are being generated, retry later".to_string(),
+            ),
+            Unavailable::Draining => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "The node is shutting down, retry on another node".to_string(),
+            ),
+        })
+    }
+
+    /// State whose store reads from one consistent snapshot until it is dropped, for handlers
+    /// assembling a response from several reads. Without snapshot stores (e.g. on top of mocks),
+    /// the reads are not isolated.
+    async fn with_snapshot(&self) -> Result<AppState, (StatusCode, String)> {
+        let Some(snapshots) = &self.snapshots else {
+            return Ok(self.clone());
+        };
+        let snapshot = snapshots.begin().await.map_err(|e| {
+            error!("Failed to begin a store snapshot: {}", e);
+            internal_error("Failed to read the store")
+        })?;
+        let mut state = self.clone();
+        state.store = snapshot.store();
+        state.snapshot = Some(Arc::new(snapshot));
+        Ok(state)
+    }
+
+    /// Record a served proof in the audit log, if enabled.
+    ///
+    /// The proof is not served if it cannot be recorded, so that the log covers every response.
+    async fn audit(
+        &self,
+        record: impl FnOnce() -> Result<AuditRecord, serde_json::Error>,
+    ) -> Result<(), (StatusCode, String)> {
+        let Some(audit_log) = &self.audit_log else {
+            return Ok(());
+        };
+        let mut record =
+            record().map_err(|_| internal_error("Failed to serialize the response"))?;
+        if let Some(chain_height) = record.chain_height {
+            record.mmr_root = match self.store.get_mmr_root(chain_height).await {
+                Ok(root) => Some(root.root),
+                Err(StoreError::GetError) => None,
+                Err(_) => return Err(internal_error("Failed to read MMR root")),
+            };
+        }
+        audit_log.append(&record).await.map_err(|e| {
+            error!("Failed to write the audit log: {}", e);
+            internal_error("Failed to write the audit log")
         })
     }
 
     /// Get the FlyClient MMR for a specific block height (epoch-aware)
     fn get_flyclient_mmr(&self, block_height: u32) -> MMR {
-        let epoch = epoch_name_for_height(block_height);
-        let mmr_id = format!("flyclient_{}", epoch);
         let hasher = ZcashFlyclientHasher;
-        MMR::new(self.store.clone(), Arc::new(hasher), Some(mmr_id))
+        MMR::new(
+            self.store.clone(),
+            Arc::new(hasher),
+            Some(flyclient_mmr_id(block_height)),
+        )
     }
 }
 
 impl RpcServer {
-    pub fn new(config: RpcConfig, rx_shutdown: broadcast::Receiver<()>) -> Self {
+    pub fn new(
+        config: RpcConfig,
+        health: Arc<AppHealth>,
+        rx_shutdown: broadcast::Receiver<()>,
+    ) -> Self {
         Self {
             config,
+            health,
             rx_shutdown,
         }
     }
@@ -156,7 +390,7 @@ impl RpcServer {
     async fn run_inner(&self) -> Result<(), std::io::Error> {
         info!("Starting RPC server on {}", self.config.rpc_host);
 
-        let app_state = AppState::new(self.config.clone())
+        let app_state = AppState::new(self.config.clone(), self.health.clone())
             .await
             .map_err(std::io::Error::other)?;
 
@@ -166,11 +400,54 @@ impl RpcServer {
                 get(generate_block_inclusion_proof),
             )
             .route("/head", get(get_head))
+            .route("/health", get(get_health))
+            .route("/metrics", get(get_metrics))
+            .route("/version", get(get_version))
             .route("/headers", get(get_block_headers))
             .route("/transaction-proof/:tx_id", get(get_transaction_proof))
+            .route(
+                "/transaction-proofs/bucket/:prefix",
+                get(get_transaction_bucket),
+            )
+            .route(
+                "/transaction-absence/:block_height/:tx_id",
+                get(get_transaction_absence),
+            )
+            .route(
+                "/block/:block_height/coinbase-proof",
+                get(get_coinbase_proof),
+            )
+            .route("/full-proof/:tx_id", get(get_full_proof))
+            .route("/confirmation-status/:tx_id", get(get_confirmation_status))
             .route("/block-header/:block_height", get(get_block_header))
             .route("/chain-state/:block_height", get(get_chain_state))
-            .with_state(app_state)
+            .route("/mmr/root/:height", get(get_mmr_root))
+            .route("/mmr/sparse-roots", get(get_sparse_roots))
+            .route(
+                "/flyclient/node-data/:block_height",
+                get(get_flyclient_node_data),
+            )
+            .route("/admin/usage", get(get_api_usage))
+            .route("/admin/shutdown", post(post_shutdown))
+            .route(
+                "/proving-status",
+                get(get_proving_status).post(post_proving_status),
+            )
+            .with_state(app_state.clone());
+        // Renamed before metering, so that the size of the served body is accounted
+        let app = match self.config.field_naming {
+            FieldNaming::SnakeCase => app,
+            FieldNaming::CamelCase => app.layer(middleware::from_fn(camel_case_responses)),
+        };
+        // Metered before compression, so that the uncompressed response size is accounted
+        let app = match app_state.meter.clone() {
+            Some(meter) => {
+                tokio::spawn(meter.clone().flush_periodically(USAGE_FLUSH_INTERVAL));
+                app.layer(middleware::from_fn_with_state(meter, meter_requests))
+            }
+            None => app,
+        };
+        let app = app
             .layer(CompressionLayer::new())
             .layer(CorsLayer::permissive())
             .layer(TraceLayer::new_for_http());
@@ -180,7 +457,12 @@ impl RpcServer {
 
         axum::serve(listener, app)
             .with_graceful_shutdown(async move { rx_shutdown.recv().await.unwrap_or_default() })
-            .await
+            .await?;
+        // Usage recorded since the last periodic flush
+        if let Some(meter) = &app_state.meter {
+            meter.flush().await;
+        }
+        Ok(())
     }
 
     pub async fn run(&self) -> Result<(), ()> {
@@ -203,6 +485,9 @@ pub async fn generate_block_inclusion_proof(
     Path(block_hash): Path<String>,
     Query(query): Query<ChainHeightQuery>,
 ) -> Result<Json<BlockInclusionProof>, StatusCode> {
+    let _slot = state.proof_slot().await.map_err(|(status, _)| status)?;
+    // The MMR leaf count, nodes and recorded root are read from the same indexed height
+    let state = state.with_snapshot().await.map_err(|(status, _)| status)?;
     // Get block height from hash via Zcash RPC
     let block_height = state
         .zcash_client
@@ -213,56 +498,134 @@ pub async fn generate_block_inclusion_proof(
             StatusCode::NOT_FOUND
         })?;
 
+    let proof = build_block_inclusion_proof(&state, block_height, query.chain_height)
+        .await
+        .map_err(|(status, message)| {
+            error!("Block {}: {}", block_hash, message);
+            status
+        })?;
+    state
+        .audit(|| {
+            let mut record =
+                AuditRecord::new("block-inclusion-proof", &block_hash, block_height, &proof)?;
+            record.chain_height = Some(proof.chain_height());
+            Ok(record)
+        })
+        .await
+        .map_err(|(status, _)| status)?;
+    Ok(Json(proof))
+}
+
+/// Generate the FlyClient MMR proof for the block at `block_height` against `chain_height`
+/// (defaults to the latest indexed height of the block's epoch)
+async fn build_block_inclusion_proof(
+    state: &AppState,
+    block_height: u32,
+    chain_height: Option<u32>,
+) -> Result<BlockInclusionProof, (StatusCode, String)> {
     // FlyClient MMR starts at Heartwood
     if block_height < HEARTWOOD_ACTIVATION {
-        error!(
-            "Block {} is before Heartwood activation ({})",
-            block_hash, HEARTWOOD_ACTIVATION
-        );
-        return Err(StatusCode::BAD_REQUEST);
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Block {block_height} is before Heartwood activation ({HEARTWOOD_ACTIVATION})"),
+        ));
     }
 
     // Get epoch-specific MMR and calculate leaf index within that epoch
     let epoch_start = epoch_start_height(block_height);
     let leaf_index = (block_height - epoch_start) as usize;
-    let element_index = map_leaf_index_to_element_index(leaf_index);
+    let element_index = leaf_index_to_element_index(leaf_index as u64) as usize;
 
     // Get the epoch-specific MMR
     let flyclient_mmr = state.get_flyclient_mmr(block_height);
 
+    let leaf_count = match chain_height {
+        Some(chain_height) => {
+            let chain_height = proof_chain_height(block_height, chain_height)
+                .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+            let indexed_leaves = flyclient_mmr.leaves_count.get().await.map_err(|e| {
+                error!("Failed to get FlyClient MMR leaf count: {}", e);
+                internal_error("Failed to get FlyClient MMR leaf count")
+            })?;
+            let leaf_count = (chain_height - epoch_start) as usize + 1;
+            if leaf_count > indexed_leaves {
+                return Err((
+                    StatusCode::NOT_FOUND,
+                    format!(
+                        "Chain height {} is not indexed yet ({} leaves in the {} MMR)",
+                        chain_height,
+                        indexed_leaves,
+                        epoch_name_for_height(block_height)
+                    ),
+                ));
+            }
+            Some(leaf_count)
+        }
+        None => None,
+    };
     let options = ProofOptions {
-        elements_count: query
-            .chain_height
-            .map(|c| leaf_count_to_mmr_size((c - epoch_start) as usize + 1)),
+        elements_count: leaf_count
+            .map(|leaf_count| leaf_count_to_mmr_size(leaf_count as u64) as usize),
         ..Default::default()
     };
-    let proof = {
-        let pr = flyclient_mmr
-            .get_proof(element_index, Some(options))
-            .await
-            .map_err(|e| {
-                error!(
-                    "Failed to generate block proof for hash {}: {}",
-                    block_hash, e
-                );
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?;
-        let leaf_count = elements_count_to_leaf_count(pr.elements_count).map_err(|e| {
+    let pr = flyclient_mmr
+        .get_proof(element_index, Some(options))
+        .await
+        .map_err(|e| {
             error!(
-                "Failed to generate block proof for hash {}: {}",
-                block_hash, e
+                "Failed to generate block proof for height {}: {}",
+                block_height, e
             );
-            StatusCode::INTERNAL_SERVER_ERROR
+            internal_error("Failed to generate block inclusion proof")
         })?;
-        BlockInclusionProof {
-            block_height,
-            peaks_hashes: pr.peaks_hashes,
-            siblings_hashes: pr.siblings_hashes,
-            leaf_index,
-            leaf_count,
-        }
+    let leaf_count = elements_count_to_leaf_count(pr.elements_count as u64).ok_or_else(|| {
+        error!(
+            "Failed to generate block proof for height {}: invalid elements count {}",
+            block_height, pr.elements_count
+        );
+        internal_error("Failed to generate block inclusion proof")
+    })? as usize;
+    Ok(BlockInclusionProof {
+        block_height,
+        peaks_hashes: pr.peaks_hashes,
+        siblings_hashes: pr.siblings_hashes,
+        leaf_index,
+        leaf_count,
+        epoch: Some(epoch_name_for_height(block_height).to_string()),
+        epoch_start_height: Some(epoch_start),
+        mmr_id: Some(flyclient_mmr_id(block_height)),
+    })
+}
+
+/// Get the node health
+///
+/// # Returns
+/// * `Json<HealthReport>` - The health report, with `StatusCode::OK` if all components are
+///   healthy and `StatusCode::SERVICE_UNAVAILABLE` otherwise
+pub async fn get_health(State(state): State<AppState>) -> (StatusCode, Json<HealthReport>) {
+    let report = state.health.report();
+    let status = if report.is_healthy() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
     };
-    Ok(Json(proof))
+    (status, Json(report))
+}
+
+/// Get the build metadata of the node
+///
+/// # Returns
+/// * `Json<BuildInfo>` - Version, git commit, build timestamp, enabled features and default
+///   verifier configuration
+pub async fn get_version() -> Json<BuildInfo> {
+    Json(crate::node_build_info())
+}
+
+/// Get the node health and store query metrics in the Prometheus text exposition format
+pub async fn get_metrics(State(state): State<AppState>) -> String {
+    let mut out = state.health.metrics();
+    state.proof_limiter.render(&mut out);
+    out
 }
 
 /// Get the current head (latest processed block height) from the DB
@@ -315,16 +678,19 @@ pub async fn get_block_header(
 ///
 /// # Arguments
 /// * `offset` - The starting block height to get the headers for
-/// * `size` - The number of blocks to get the headers for
+/// * `size` - The number of blocks to get the headers for, capped to `--max-headers-per-request`
+///   (clients request the rest from the height after the last returned header)
 /// # Returns
-/// * `Json<Vec<BlockHeader>>>` - The block headers in JSON format
-/// * `StatusCode::INTERNAL_SERVER_ERROR` - If fetching the block headers fails
+/// * `Json<Vec<BlockHeaderRecord>>` - The block headers with their height and hash, ordered by
+///   height and chaining on each other
+/// * `StatusCode::INTERNAL_SERVER_ERROR` - If fetching the block headers fails, or the stored
+///   headers have a gap or do not chain
 pub async fn get_block_headers(
     State(state): State<AppState>,
     Query(query): Query<BlockHeadersQuery>,
-) -> Result<Json<Vec<Header>>, StatusCode> {
+) -> Result<Json<Vec<BlockHeaderRecord>>, StatusCode> {
     let offset = query.offset.unwrap_or(0);
-    let size = query.size.unwrap_or(10);
+    let size = query.size.unwrap_or(10).min(state.max_headers);
     let block_headers = state
         .store
         .get_block_headers(offset, size)
@@ -336,62 +702,679 @@ pub async fn get_block_headers(
             );
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
-    Ok(Json(block_headers))
+    if let Some((height, _)) = block_headers
+        .windows(2)
+        .zip(offset + 1..)
+        .find(|(pair, _)| pair[1].previous_block_hash != pair[0].hash())
+    {
+        error!(
+            "Stored block header at height {} does not chain on the previous one",
+            height
+        );
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    Ok(Json(
+        block_headers
+            .into_iter()
+            .zip(offset..)
+            .map(|(header, height)| BlockHeaderRecord::new(height, header))
+            .collect(),
+    ))
+}
+
+/// Query parameters for transaction proof lookups
+#[derive(Debug, Deserialize)]
+pub struct TransactionProofQuery {
+    /// How to interpret the identifier (defaults to `auto`)
+    #[serde(alias = "idType")]
+    pub id_type: Option<TxIdKind>,
+    /// Height of the block containing the transaction, required for auth digest lookups
+    #[serde(alias = "blockHeight")]
+    pub block_height: Option<u32>,
 }
 
 /// Get a transaction inclusion proof for a specific transaction
 ///
+/// The identifier may be a txid in either byte order or, when `block_height` is given, an NU5
+/// auth digest. The response reports which interpretation matched.
+///
 /// # Returns
-/// * `Json<TransactionInclusionProof>` - The transaction inclusion proof in JSON format
-/// * `StatusCode::BAD_REQUEST` - If the transaction ID is invalid
+/// * `Json<TransactionProofResponse>` - The transaction inclusion proof in JSON format
+/// * `StatusCode::BAD_REQUEST` - If the identifier is malformed
+/// * `StatusCode::NOT_FOUND` - If no transaction matches the identifier
 /// * `StatusCode::INTERNAL_SERVER_ERROR` - If proof generation fails
 pub async fn get_transaction_proof(
     State(state): State<AppState>,
     Path(tx_id): Path<String>,
-) -> Result<Json<TransactionInclusionProof>, StatusCode> {
-    let txid = Hash::from_hex(&tx_id).map_err(|_| StatusCode::BAD_REQUEST)?;
-    let block_height = state
-        .zcash_client
-        .get_transaction_block_height(&txid)
-        .await
-        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    Query(query): Query<TransactionProofQuery>,
+) -> Result<Json<TransactionProofResponse>, (StatusCode, String)> {
+    let _slot = state.proof_slot().await?;
+    let response = build_transaction_proof(&state, &tx_id, &query).await?;
+    state
+        .audit(|| {
+            let mut record = AuditRecord::new(
+                "transaction-proof",
+                &tx_id,
+                response.proof.block_height,
+                &response,
+            )?;
+            record.txid = response
+                .matched
+                .as_ref()
+                .map(|matched| matched.txid.clone());
+            Ok(record)
+        })
+        .await?;
+    Ok(Json(response))
+}
 
-    let block_header = state
+/// Maximum number of transactions in a bucket
+const MAX_BUCKET_SIZE: u32 = 256;
+
+/// Get the inclusion proofs of every stored transaction whose txid starts with a prefix
+///
+/// Clients pick their transaction locally, so the bridge only learns which bucket it is in.
+/// Transactions are looked up among the stored ones (`--store-transactions`).
+///
+/// # Returns
+/// * `Json<TransactionBucketResponse>` - The transaction inclusion proofs in JSON format
+/// * `StatusCode::BAD_REQUEST` - If the prefix is not hex or the bucket is too large
+/// * `StatusCode::INTERNAL_SERVER_ERROR` - If proof generation fails
+pub async fn get_transaction_bucket(
+    State(state): State<AppState>,
+    Path(prefix): Path<String>,
+) -> Result<Json<TransactionBucketResponse>, (StatusCode, String)> {
+    let prefix = prefix.to_ascii_lowercase();
+    if prefix.is_empty() || prefix.len() > 64 || !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Bucket prefix must be 1 to 64 hex digits, got {prefix:?}"),
+        ));
+    }
+    let _slot = state.proof_slot().await?;
+    let transactions = state
         .store
-        .get_block_headers(block_height, 1)
+        .get_transactions_by_prefix(&prefix, MAX_BUCKET_SIZE + 1)
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .pop()
-        .ok_or(StatusCode::NOT_FOUND)?;
+        .map_err(|_| internal_error("Failed to read transaction index"))?;
+    if transactions.len() > MAX_BUCKET_SIZE as usize {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Bucket {prefix} holds more than {MAX_BUCKET_SIZE} transactions, use a longer prefix"
+            ),
+        ));
+    }
 
-    let block_merkle_tree = state
-        .zcash_client
-        .build_block_merkle_tree(block_height)
+    let mut blocks: BTreeMap<u32, Vec<Hash>> = BTreeMap::new();
+    for (txid, height) in transactions {
+        blocks.entry(height).or_default().push(txid);
+    }
+    let mut proofs = Vec::new();
+    for (block_height, txids) in blocks {
+        let block_header = read_block_header(&state, block_height).await?;
+        let wanted = WantedTxs::Txids(txids.clone());
+        let (block, source) =
+            block_transactions(&state, block_height, &block_header, wanted).await?;
+        for txid in txids {
+            proofs.push(prove_transaction(
+                &block,
+                txid,
+                TxIdKind::Txid,
+                block_header,
+                block_height,
+                source,
+            )?);
+        }
+    }
+
+    let response = TransactionBucketResponse { prefix, proofs };
+    for proof in &response.proofs {
+        state
+            .audit(|| {
+                let mut record = AuditRecord::new(
+                    "transaction-bucket",
+                    &response.prefix,
+                    proof.proof.block_height,
+                    proof,
+                )?;
+                record.txid = proof.matched.as_ref().map(|matched| matched.txid.clone());
+                Ok(record)
+            })
+            .await?;
+    }
+    Ok(Json(response))
+}
+
+/// Get a proof that a transaction is not included in a block: the txids of every transaction
+/// of the block, with its coinbase transaction
+///
+/// # Returns
+/// * `Json<TransactionAbsenceProof>` - The transaction absence proof in JSON format
+/// * `StatusCode::BAD_REQUEST` - If the txid is malformed
+/// * `StatusCode::NOT_FOUND` - If the block is not indexed yet
+/// * `StatusCode::CONFLICT` - If the transaction is included in the block
+/// * `StatusCode::INTERNAL_SERVER_ERROR` - If proof generation fails
+pub async fn get_transaction_absence(
+    State(state): State<AppState>,
+    Path((block_height, tx_id)): Path<(u32, String)>,
+) -> Result<Json<TransactionAbsenceProof>, (StatusCode, String)> {
+    let txid: Hash = tx_id.parse().map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("Txid must be 32 bytes of hex in display order, got {tx_id:?}"),
+        )
+    })?;
+    let _slot = state.proof_slot().await?;
+    let block_header = read_block_header(&state, block_height).await?;
+    let (block, _) =
+        block_transactions(&state, block_height, &block_header, WantedTxs::Coinbase).await?;
+    if block.tree.get_transaction_index(txid).is_ok() {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("Transaction {txid} is included in block {block_height}"),
+        ));
+    }
+    let coinbase = &block.coinbase;
+
+    let response = TransactionAbsenceProof {
+        block_header,
+        block_height,
+        coinbase: coinbase.as_ref().clone(),
+        txids: block
+            .tree
+            .tx_hashes()
+            .iter()
+            .map(ToString::to_string)
+            .collect(),
+    };
+    state
+        .audit(|| {
+            let mut record =
+                AuditRecord::new("transaction-absence", &tx_id, block_height, &response)?;
+            record.txid = Some(txid.to_string());
+            Ok(record)
+        })
+        .await?;
+    Ok(Json(response))
+}
+
+/// Get the inclusion proof of the coinbase transaction of a block, with its transparent outputs
+///
+/// # Returns
+/// * `Json<CoinbaseProofResponse>` - The coinbase proof in JSON format
+/// * `StatusCode::NOT_FOUND` - If the block is not indexed yet
+/// * `StatusCode::INTERNAL_SERVER_ERROR` - If proof generation fails
+pub async fn get_coinbase_proof(
+    State(state): State<AppState>,
+    Path(block_height): Path<u32>,
+) -> Result<Json<CoinbaseProofResponse>, (StatusCode, String)> {
+    let _slot = state.proof_slot().await?;
+    let block_header = read_block_header(&state, block_height).await?;
+    let (block, source) =
+        block_transactions(&state, block_height, &block_header, WantedTxs::Coinbase).await?;
+    let coinbase = Some(&block.coinbase)
+        .filter(|tx| tx.is_coinbase())
+        .ok_or_else(|| internal_error("Block has no coinbase transaction"))?;
+    let transaction_proof = block
+        .tree
+        .generate_proof(0)
+        .map_err(|_| internal_error("Failed to generate Merkle proof"))?;
+
+    let outputs = CoinbaseOutput::from_transaction(coinbase);
+    let response = CoinbaseProofResponse {
+        proof: TransactionInclusionProof {
+            transaction: coinbase.as_ref().clone(),
+            transaction_proof,
+            block_header,
+            block_height,
+        },
+        total_value: outputs.iter().map(|output| output.value).sum(),
+        outputs,
+        source: Some(source),
+    };
+    state
+        .audit(|| {
+            let mut record = AuditRecord::new(
+                "coinbase-proof",
+                &block_height.to_string(),
+                block_height,
+                &response,
+            )?;
+            record.txid = Some(coinbase.hash().to_string());
+            Ok(record)
+        })
+        .await?;
+    Ok(Json(response))
+}
+
+/// Query parameters for full inclusion proof lookups
+#[derive(Debug, Deserialize)]
+pub struct FullProofQuery {
+    /// Chain height to prove against (defaults to the latest indexed height)
+    #[serde(alias = "chainHeight")]
+    pub chain_height: Option<u32>,
+    /// How to interpret the identifier (defaults to `auto`)
+    #[serde(alias = "idType")]
+    pub id_type: Option<TxIdKind>,
+    /// Height of the block containing the transaction, required for auth digest lookups
+    #[serde(alias = "blockHeight")]
+    pub block_height: Option<u32>,
+}
+
+/// Get every layer of a full inclusion proof for a transaction, except the chain state STARK
+/// proof
+///
+/// Combines the transaction proof, the FlyClient proof of its block against `chain_height` and
+/// the chain state at `chain_height`, along with the STARK proof URL if one is configured.
+///
+/// # Returns
+/// * `Json<FullProofResponse>` - The proof layers in JSON format
+/// * `StatusCode::BAD_REQUEST` - If the identifier is malformed, the block is before Heartwood
+///   or after the chain height
+/// * `StatusCode::NOT_FOUND` - If no transaction matches or the chain height is not indexed yet
+/// * `StatusCode::INTERNAL_SERVER_ERROR` - If proof generation fails
+pub async fn get_full_proof(
+    State(state): State<AppState>,
+    Path(tx_id): Path<String>,
+    Query(query): Query<FullProofQuery>,
+) -> Result<Json<FullProofResponse>, (StatusCode, String)> {
+    let _slot = state.proof_slot().await?;
+    // Chain head, chain state, block and MMR reads all see the same indexed height
+    let state = state.with_snapshot().await?;
+    let tx_query = TransactionProofQuery {
+        id_type: query.id_type,
+        block_height: query.block_height,
+    };
+    let TransactionProofResponse {
+        proof,
+        matched,
+        source,
+    } = build_transaction_proof(&state, &tx_id, &tx_query).await?;
+
+    let chain_height = match query.chain_height {
+        Some(chain_height) => chain_height,
+        None => state
+            .store
+            .get_latest_chain_state_height()
+            .await
+            .map_err(|_| internal_error("Failed to read chain head"))?,
+    };
+    if proof.block_height > chain_height {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Transaction is in block {} after chain height {}",
+                proof.block_height, chain_height
+            ),
+        ));
+    }
+    let chain_state = match state.store.get_chain_state(chain_height).await {
+        Ok(chain_state) => chain_state,
+        Err(StoreError::GetError) => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                format!("Chain height {chain_height} is not indexed yet"),
+            ))
+        }
+        Err(_) => return Err(internal_error("Failed to read chain state")),
+    };
+
+    let block_inclusion_proof =
+        build_block_inclusion_proof(&state, proof.block_height, Some(chain_height)).await?;
+
+    let chain_state_proof_url = state
+        .stark_proof_url_template
+        .as_ref()
+        .map(|template| template.replace("{height}", &chain_height.to_string()));
+
+    let response = FullProofResponse {
+        chain_state,
+        block_inclusion_proof,
+        transaction_proof: proof,
+        matched,
+        source,
+        chain_state_proof_url,
+        finality: Some(state.finality.clone()),
+    };
+    state
+        .audit(|| {
+            let mut record = AuditRecord::new(
+                "full-proof",
+                &tx_id,
+                response.transaction_proof.block_height,
+                &response,
+            )?;
+            record.txid = response
+                .matched
+                .as_ref()
+                .map(|matched| matched.txid.clone());
+            record.chain_height = Some(chain_height);
+            Ok(record)
+        })
+        .await?;
+    Ok(Json(response))
+}
+
+/// Get how far a transaction is from final, without building any proof
+///
+/// Takes the same identifiers as `/transaction-proof/:tx_id`. `proven_height` and
+/// `proof_available` are reported when the prover pushes its status (`--prover-key`).
+///
+/// # Returns
+/// * `Json<ConfirmationStatus>` - The confirmation status in JSON format
+/// * `StatusCode::BAD_REQUEST` - If the identifier is malformed
+/// * `StatusCode::NOT_FOUND` - If no transaction matches the identifier
+/// * `StatusCode::INTERNAL_SERVER_ERROR` - If reading the chain head fails
+pub async fn get_confirmation_status(
+    State(state): State<AppState>,
+    Path(tx_id): Path<String>,
+    Query(query): Query<TransactionProofQuery>,
+) -> Result<Json<ConfirmationStatus>, (StatusCode, String)> {
+    let (txid, block_height, _) = resolve_transaction_id(&state, &tx_id, &query).await?;
+    let chain_head = state
+        .store
+        .get_latest_chain_state_height()
         .await
-        .map_err(|_| StatusCode::BAD_REQUEST)?;
+        .map_err(|_| internal_error("Failed to read chain head"))?;
+    let proven_height = state
+        .prover
+        .as_ref()
+        .and_then(|prover| prover.status(chain_head))
+        .map(|status| status.proven_height);
+    Ok(Json(ConfirmationStatus::new(
+        txid.to_string(),
+        block_height,
+        chain_head,
+        &state.finality,
+        proven_height,
+    )))
+}
 
-    let tx_index = block_merkle_tree
-        .get_transaction_index(txid)
-        .map_err(|_| StatusCode::BAD_REQUEST)?;
+/// Build the inclusion proof of a transaction in its block
+async fn build_transaction_proof(
+    state: &AppState,
+    tx_id: &str,
+    query: &TransactionProofQuery,
+) -> Result<TransactionProofResponse, (StatusCode, String)> {
+    let (txid, block_height, kind) = resolve_transaction_id(state, tx_id, query).await?;
 
-    let proof = block_merkle_tree
+    let block_header = read_block_header(state, block_height).await?;
+    let wanted = WantedTxs::Txids(vec![txid]);
+    let (block, source) = block_transactions(state, block_height, &block_header, wanted).await?;
+    prove_transaction(&block, txid, kind, block_header, block_height, source)
+}
+
+/// Build the inclusion proof of a transaction from the Merkle tree of its block
+fn prove_transaction(
+    block: &BlockTransactions,
+    txid: Hash,
+    kind: TxIdKind,
+    block_header: Header,
+    block_height: u32,
+    source: ProofSource,
+) -> Result<TransactionProofResponse, (StatusCode, String)> {
+    let (tx_index, tx) = block.transaction(txid).ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            format!("Transaction {txid} not found in block {block_height}"),
+        )
+    })?;
+
+    let proof = block
+        .tree
         .generate_proof(tx_index)
-        .map_err(|_| StatusCode::BAD_REQUEST)?;
+        .map_err(|_| internal_error("Failed to generate Merkle proof"))?;
 
-    let tx = state
-        .zcash_client
-        .get_transaction(&txid)
+    Ok(TransactionProofResponse {
+        proof: TransactionInclusionProof {
+            transaction: tx.as_ref().clone(),
+            transaction_proof: proof,
+            block_header,
+            block_height,
+        },
+        matched: Some(MatchedTxId {
+            kind,
+            txid: txid.to_string(),
+        }),
+        source: Some(source),
+    })
+}
+
+/// Read an indexed block header
+async fn read_block_header(
+    state: &AppState,
+    block_height: u32,
+) -> Result<Header, (StatusCode, String)> {
+    state
+        .store
+        .get_block_headers(block_height, 1)
         .await
-        .map_err(|_| StatusCode::BAD_REQUEST)?;
+        .map_err(|_| internal_error("Failed to read block header"))?
+        .pop()
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!("Block {block_height} is not indexed yet"),
+            )
+        })
+}
 
-    let obj = TransactionInclusionProof {
-        transaction: tx,
-        transaction_proof: proof,
-        block_header,
-        block_height,
+/// Transactions of a block a request needs in full, besides the coinbase transaction
+#[derive(Debug)]
+enum WantedTxs {
+    /// The coinbase transaction only
+    Coinbase,
+    /// The transactions with these txids
+    Txids(Vec<Hash>),
+    /// The transaction with one of these auth digests
+    AuthDigest([[u8; 32]; 2]),
+}
+
+/// Transactions of a block with their Merkle tree, checked against the indexed block header.
+///
+/// Shared by the concurrent requests for the block while it is fetched, and dropped once each of
+/// them has picked its [`BlockTransactions`].
+#[derive(Debug)]
+struct FetchedBlock {
+    tree: Arc<MerkleTree>,
+    transactions: Vec<Arc<Transaction>>,
+}
+
+impl FetchedBlock {
+    fn new(transactions: Vec<Arc<Transaction>>, block_header: &Header) -> Result<Self, String> {
+        if transactions.is_empty() {
+            return Err("Block has no transactions".to_string());
+        }
+        let tree = MerkleTree::new(&transactions, block_header.merkle_root)?;
+        Ok(Self {
+            tree: Arc::new(tree),
+            transactions,
+        })
+    }
+}
+
+/// Merkle tree of the transactions of a block, with the coinbase and the transactions a request
+/// wants. The other transactions are not kept, so that large blocks are not held while proofs
+/// are generated.
+#[derive(Debug)]
+struct BlockTransactions {
+    tree: Arc<MerkleTree>,
+    coinbase: Arc<Transaction>,
+    /// Wanted transactions found in the block, with their index
+    wanted: Vec<(usize, Arc<Transaction>)>,
+}
+
+impl BlockTransactions {
+    /// Pick the coinbase and the `wanted` transactions of a fetched block
+    fn select(block: &FetchedBlock, wanted: &WantedTxs) -> Self {
+        let tree = &block.tree;
+        let transactions = &block.transactions;
+        let indices: Vec<usize> = match wanted {
+            WantedTxs::Coinbase => Vec::new(),
+            WantedTxs::Txids(txids) => txids
+                .iter()
+                .filter_map(|txid| tree.get_transaction_index(*txid).ok())
+                .collect(),
+            WantedTxs::AuthDigest(digests) => transactions
+                .iter()
+                .position(|tx| {
+                    tx.auth_digest()
+                        .is_some_and(|digest| digests.contains(&digest.0))
+                })
+                .into_iter()
+                .collect(),
+        };
+        Self {
+            tree: tree.clone(),
+            // Checked non-empty when fetched
+            coinbase: transactions[0].clone(),
+            wanted: indices
+                .into_iter()
+                .map(|index| (index, transactions[index].clone()))
+                .collect(),
+        }
+    }
+
+    /// Wanted transaction with the txid `txid`, with its index
+    fn transaction(&self, txid: Hash) -> Option<(usize, &Arc<Transaction>)> {
+        self.wanted
+            .iter()
+            .find(|(index, _)| self.tree.tx_hashes()[*index] == txid)
+            .map(|(index, tx)| (*index, tx))
+    }
+}
+
+/// Get the transactions of a block from the Zcash node or, while it is unavailable, from the
+/// stored block transactions, checked against the indexed block header.
+///
+/// Concurrent calls for the same block share a single upstream request and Merkle tree, each
+/// picking its `wanted` transactions from the shared result.
+async fn block_transactions(
+    state: &AppState,
+    block_height: u32,
+    block_header: &Header,
+    wanted: WantedTxs,
+) -> Result<(BlockTransactions, ProofSource), (StatusCode, String)> {
+    let rpc_result = state
+        .block_requests
+        .run(block_header.hash(), || async {
+            let transactions = state
+                .zcash_client
+                .get_block_transactions(block_height)
+                .await
+                .map_err(|e| e.to_string())?;
+            FetchedBlock::new(transactions, block_header).map(Arc::new)
+        })
+        .await;
+    let rpc_error = match rpc_result {
+        Ok(block) => return Ok((BlockTransactions::select(&block, &wanted), ProofSource::Rpc)),
+        Err(e) => e,
     };
+    let transactions = match state.store.get_block_transactions(block_height).await {
+        Ok(transactions) => transactions,
+        Err(StoreError::GetError) => {
+            error!(
+                "Failed to fetch block {} from the Zcash node: {}",
+                block_height, rpc_error
+            );
+            return Err((
+                StatusCode::SERVICE_UNAVAILABLE,
+                format!(
+                    "Block {block_height} is unavailable from the Zcash node and its transactions are not stored"
+                ),
+            ));
+        }
+        Err(_) => return Err(internal_error("Failed to read block transactions")),
+    };
+    warn!(
+        "Failed to fetch block {} from the Zcash node ({}), serving its stored transactions",
+        block_height, rpc_error
+    );
+    let block = FetchedBlock::new(transactions, block_header).map_err(|e| {
+        error!("Stored transactions of block {}: {}", block_height, e);
+        internal_error("Stored block transactions do not match the block header")
+    })?;
+    Ok((
+        BlockTransactions::select(&block, &wanted),
+        ProofSource::Local,
+    ))
+}
 
-    Ok(Json(obj))
+/// Height of the block including a transaction, from the Zcash node or else the stored
+/// transactions
+async fn transaction_height(state: &AppState, txid: &Hash) -> Option<u32> {
+    match state.zcash_client.get_transaction_block_height(txid).await {
+        Ok(height) => Some(height),
+        Err(_) => state.store.get_transaction_height(txid).await.ok(),
+    }
+}
+
+fn internal_error(message: &str) -> (StatusCode, String) {
+    (StatusCode::INTERNAL_SERVER_ERROR, message.to_string())
+}
+
+/// Resolve a user-supplied transaction identifier into a txid and the height of its block
+async fn resolve_transaction_id(
+    state: &AppState,
+    tx_id: &str,
+    query: &TransactionProofQuery,
+) -> Result<(Hash, u32, TxIdKind), (StatusCode, String)> {
+    let bytes = <[u8; 32]>::from_hex(tx_id).map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("Transaction identifier must be 32 bytes of hex, got {tx_id:?}"),
+        )
+    })?;
+    // The identifier read as a txid in display order, or in internal order
+    let displayed = DisplayOrderHash(bytes).to_internal();
+    let internal = InternalOrderHash(bytes);
+
+    let kind = query.id_type.unwrap_or_default();
+    let candidates: &[(TxIdKind, InternalOrderHash)] = match kind {
+        TxIdKind::Auto => &[
+            (TxIdKind::Txid, displayed),
+            (TxIdKind::TxidReversed, internal),
+        ],
+        TxIdKind::Txid => &[(TxIdKind::Txid, displayed)],
+        TxIdKind::TxidReversed => &[(TxIdKind::TxidReversed, internal)],
+        TxIdKind::AuthDigest => &[],
+    };
+    for (candidate_kind, txid) in candidates {
+        let txid = Hash::from(*txid);
+        if let Some(height) = transaction_height(state, &txid).await {
+            return Ok((txid, height, *candidate_kind));
+        }
+    }
+
+    if matches!(kind, TxIdKind::Auto | TxIdKind::AuthDigest) {
+        if let Some(block_height) = query.block_height {
+            let block_header = read_block_header(state, block_height).await?;
+            let wanted = WantedTxs::AuthDigest([bytes, displayed.0]);
+            let (block, _) = block_transactions(state, block_height, &block_header, wanted).await?;
+            if let Some((index, _)) = block.wanted.first() {
+                let txid = block.tree.tx_hashes()[*index];
+                return Ok((txid, block_height, TxIdKind::AuthDigest));
+            }
+        } else if kind == TxIdKind::AuthDigest {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Auth digest lookups require the block_height query parameter".to_string(),
+            ));
+        }
+    }
+
+    let tried = match kind {
+        TxIdKind::Auto if query.block_height.is_some() => "txid (both byte orders) or auth digest",
+        TxIdKind::Auto => "txid (both byte orders); pass block_height to also try auth digests",
+        TxIdKind::Txid => "txid (display byte order)",
+        TxIdKind::TxidReversed => "txid (internal byte order)",
+        TxIdKind::AuthDigest => "auth digest",
+    };
+    Err((
+        StatusCode::NOT_FOUND,
+        format!("No transaction matches {tx_id} as {tried}"),
+    ))
 }
 
 /// Get the chain state for a specific block height
@@ -416,3 +1399,568 @@ pub async fn get_chain_state(
         })?;
     Ok(Json(chain_state))
 }
+
+/// Get the FlyClient MMR root recorded right after the block at a specific height was indexed
+///
+/// # Returns
+/// * `Json<MmrRootRecord>` - The recorded MMR root in JSON format
+/// * `StatusCode::NOT_FOUND` - If no root is recorded at this height (pre-Heartwood or not indexed)
+/// * `StatusCode::INTERNAL_SERVER_ERROR` - If fetching the root fails
+pub async fn get_mmr_root(
+    State(state): State<AppState>,
+    Path(height): Path<u32>,
+) -> Result<Json<MmrRootRecord>, StatusCode> {
+    match state.store.get_mmr_root(height).await {
+        Ok(record) => Ok(Json(record)),
+        Err(StoreError::GetError) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to get MMR root for height {}: {}", height, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Get the FlyClient MMR leaf of a block, as appended by the indexer
+///
+/// # Returns
+/// * `Json<FlyClientNodeData>` - The leaf and its decoded `NodeData` fields in JSON format
+/// * `StatusCode::BAD_REQUEST` - If the block is before Heartwood activation
+/// * `StatusCode::NOT_FOUND` - If the block is not indexed yet
+/// * `StatusCode::INTERNAL_SERVER_ERROR` - If reading or decoding the leaf fails
+pub async fn get_flyclient_node_data(
+    State(state): State<AppState>,
+    Path(block_height): Path<u32>,
+) -> Result<Json<FlyClientNodeData>, (StatusCode, String)> {
+    if block_height < HEARTWOOD_ACTIVATION {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Block {block_height} is before Heartwood activation ({HEARTWOOD_ACTIVATION})"),
+        ));
+    }
+    let leaf_index = (block_height - epoch_start_height(block_height)) as usize;
+    let element_index = leaf_index_to_element_index(leaf_index as u64);
+    let flyclient_mmr = state.get_flyclient_mmr(block_height);
+    let key = format!("{}:hashes:{}", flyclient_mmr.mmr_id, element_index);
+    let encoded = flyclient_mmr
+        .store
+        .get(&key)
+        .await
+        .map_err(|e| {
+            error!(
+                "Failed to get FlyClient leaf for height {}: {}",
+                block_height, e
+            );
+            internal_error("Failed to get FlyClient leaf")
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!("Block {block_height} is not indexed yet"),
+            )
+        })?;
+    let node_data = FlyClientNodeData::decode(
+        block_height,
+        epoch_name_for_height(block_height),
+        leaf_index,
+        encoded,
+    )
+    .map_err(|e| {
+        error!("{}", e);
+        internal_error("Failed to decode FlyClient leaf")
+    })?;
+    Ok(Json(node_data))
+}
+
+/// Get the usage of every API key on a day, along with its quotas
+///
+/// # Returns
+/// * `Json<Vec<ApiKeyUsage>>` - The usage of each API key in JSON format
+/// * `StatusCode::UNAUTHORIZED` - If the admin key is missing or wrong
+/// * `StatusCode::NOT_FOUND` - If API key quotas are disabled
+/// * `StatusCode::INTERNAL_SERVER_ERROR` - If fetching the usage fails
+pub async fn get_api_usage(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<UsageQuery>,
+) -> Result<Json<Vec<ApiKeyUsage>>, StatusCode> {
+    let meter = state.meter.as_ref().ok_or(StatusCode::NOT_FOUND)?;
+    let admin_key = headers
+        .get(ADMIN_KEY_HEADER)
+        .and_then(|value| value.to_str().ok());
+    if !meter.is_admin(admin_key) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    let usage = meter
+        .usage(query.day.unwrap_or_else(today))
+        .await
+        .map_err(|e| {
+            error!("Failed to get API key usage: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(Json(usage))
+}
+
+/// Shut the node down softly: new proof requests are refused with `503 Service Unavailable` and
+/// `/health` reports the node as draining, then the node shuts down as on SIGTERM once the proofs
+/// being generated complete, or after `drain_seconds`
+///
+/// # Returns
+/// * `Json<ShutdownResponse>` - The drain timeout, with `StatusCode::ACCEPTED`
+/// * `StatusCode::UNAUTHORIZED` - If the shutdown key is missing or wrong
+/// * `StatusCode::NOT_FOUND` - If no shutdown key is configured
+/// * `StatusCode::CONFLICT` - If a shutdown is already in progress
+pub async fn post_shutdown(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ShutdownQuery>,
+) -> Result<(StatusCode, Json<ShutdownResponse>), StatusCode> {
+    let (Some(shutdown), Some(shutdown_key)) = (state.shutdown.clone(), &state.shutdown_key) else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+    let key = headers
+        .get(ADMIN_KEY_HEADER)
+        .and_then(|value| value.to_str().ok());
+    if !key_matches(shutdown_key, key) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    if !state.proof_limiter.start_draining() {
+        return Err(StatusCode::CONFLICT);
+    }
+    state.health.start_draining();
+
+    let drain_seconds = query.drain_seconds.unwrap_or(DEFAULT_DRAIN_SECONDS);
+    info!("Shutdown requested, draining proof generations for up to {drain_seconds}s");
+    let proof_limiter = state.proof_limiter.clone();
+    tokio::spawn(async move {
+        if proof_limiter
+            .drain(Duration::from_secs(drain_seconds))
+            .await
+        {
+            info!("Proof generations drained, shutting down");
+        } else {
+            warn!("Proofs still being generated after {drain_seconds}s, shutting down");
+        }
+        shutdown.trigger();
+    });
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(ShutdownResponse { drain_seconds }),
+    ))
+}
+
+/// Get the proving status reported by the prover, along with its lag behind the indexed head
+///
+/// # Returns
+/// * `Json<ProvingStatus>` - The proving status in JSON format
+/// * `StatusCode::NOT_FOUND` - If proving status is disabled or no heartbeat was received yet
+/// * `StatusCode::INTERNAL_SERVER_ERROR` - If fetching the indexed head fails
+pub async fn get_proving_status(
+    State(state): State<AppState>,
+) -> Result<Json<ProvingStatus>, StatusCode> {
+    let prover = state.prover.as_ref().ok_or(StatusCode::NOT_FOUND)?;
+    let indexed_head = state
+        .store
+        .get_latest_chain_state_height()
+        .await
+        .map_err(|e| {
+            error!("Failed to get latest chain state height: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    prover
+        .status(indexed_head)
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Record a heartbeat of the prover
+///
+/// # Returns
+/// * `StatusCode::NO_CONTENT` - If the heartbeat was recorded
+/// * `StatusCode::UNAUTHORIZED` - If the prover key is missing or wrong
+/// * `StatusCode::NOT_FOUND` - If proving status is disabled
+pub async fn post_proving_status(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(heartbeat): Json<ProverHeartbeat>,
+) -> StatusCode {
+    let Some(prover) = &state.prover else {
+        return StatusCode::NOT_FOUND;
+    };
+    let key = headers
+        .get(PROVER_KEY_HEADER)
+        .and_then(|value| value.to_str().ok());
+    if !prover.is_prover(key) {
+        return StatusCode::UNAUTHORIZED;
+    }
+    prover.record(heartbeat);
+    StatusCode::NO_CONTENT
+}
+
+/// Get the block header MMR roots in the Cairo-compatible sparse representation
+///
+/// The roots are taken right after the block at `chain_height` was added to the MMR (defaults to
+/// the latest indexed height).
+///
+/// # Returns
+/// * `Json<SparseRootsRecord>` - The height and its sparse roots in JSON format
+/// * `StatusCode::NOT_FOUND` - If the height is not indexed yet
+/// * `StatusCode::INTERNAL_SERVER_ERROR` - If fetching the roots fails
+pub async fn get_sparse_roots(
+    State(state): State<AppState>,
+    Query(query): Query<ChainHeightQuery>,
+) -> Result<Json<SparseRootsRecord>, StatusCode> {
+    let height = match query.chain_height {
+        Some(height) => height,
+        None => state
+            .store
+            .get_latest_sparse_roots_height()
+            .await
+            .map_err(|e| match e {
+                StoreError::GetError => StatusCode::NOT_FOUND,
+                e => {
+                    error!("Failed to get latest sparse roots height: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                }
+            })?,
+    };
+    match state.store.get_sparse_roots(height).await {
+        Ok(roots) => Ok(Json(SparseRootsRecord { height, roots })),
+        Err(StoreError::GetError) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to get sparse roots for height {}: {}", height, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use accumulators::store::Store as AccumulatorsStore;
+    use async_trait::async_trait;
+    use zebra_chain::serialization::ZcashDeserialize;
+    use zoro_spv_verify::SparseRoots;
+    use zoro_zcash_client::ZcashClientError;
+
+    use super::*;
+
+    /// Zcash node answering for the transactions and blocks it knows, and failing otherwise, as
+    /// if it were unreachable
+    #[derive(Default)]
+    struct MockChainSource {
+        transaction_heights: HashMap<Hash, u32>,
+        blocks: HashMap<u32, Vec<Arc<Transaction>>>,
+        /// Number of block fetches
+        block_fetches: AtomicUsize,
+    }
+
+    impl MockChainSource {
+        /// Node knowing the block at `height` and its transactions
+        fn serving(height: u32, transactions: &[Arc<Transaction>]) -> Self {
+            Self {
+                transaction_heights: transactions.iter().map(|tx| (tx.hash(), height)).collect(),
+                blocks: HashMap::from([(height, transactions.to_vec())]),
+                block_fetches: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    fn node_unavailable() -> ZcashClientError {
+        ZcashClientError::ZcashBlockHeaderRead(std::io::ErrorKind::ConnectionRefused.into())
+    }
+
+    #[async_trait]
+    impl ChainSource for MockChainSource {
+        async fn get_block_height_by_hash_str(&self, _: &str) -> Result<u32, ZcashClientError> {
+            Err(node_unavailable())
+        }
+
+        async fn get_transaction_block_height(&self, txid: &Hash) -> Result<u32, ZcashClientError> {
+            self.transaction_heights
+                .get(txid)
+                .copied()
+                .ok_or_else(node_unavailable)
+        }
+
+        async fn get_block_transactions(
+            &self,
+            block_height: u32,
+        ) -> Result<Vec<Arc<Transaction>>, ZcashClientError> {
+            self.block_fetches.fetch_add(1, Ordering::SeqCst);
+            // Leave concurrent requests time to join the fetch
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            self.blocks
+                .get(&block_height)
+                .cloned()
+                .ok_or_else(node_unavailable)
+        }
+    }
+
+    /// Indexed block headers and stored block transactions. Chain states, MMRs and roots are not
+    /// read by the transaction proof handlers.
+    #[derive(Default)]
+    struct MockProofStore {
+        headers: HashMap<u32, Header>,
+        transactions: HashMap<u32, Vec<Arc<Transaction>>>,
+    }
+
+    #[async_trait]
+    impl ChainStateStore for MockProofStore {
+        async fn add_block_header(&self, _: u32, _: &Header) -> Result<(), StoreError> {
+            Ok(())
+        }
+
+        async fn add_block_headers(&self, _: Vec<(u32, Header)>) -> Result<(), StoreError> {
+            Ok(())
+        }
+
+        async fn get_block_headers(
+            &self,
+            start_height: u32,
+            num_blocks: u32,
+        ) -> Result<Vec<Header>, StoreError> {
+            Ok((start_height..start_height.saturating_add(num_blocks))
+                .map_while(|height| self.headers.get(&height).copied())
+                .collect())
+        }
+
+        async fn _get_block_height(&self, _: &BlockHash) -> Result<u32, StoreError> {
+            Err(StoreError::GetError)
+        }
+
+        async fn add_chain_state(&self, _: u32, _: &ChainState) -> Result<(), StoreError> {
+            Ok(())
+        }
+
+        async fn get_chain_state(&self, _: u32) -> Result<ChainState, StoreError> {
+            Err(StoreError::GetError)
+        }
+
+        async fn get_latest_chain_state_height(&self) -> Result<u32, StoreError> {
+            Err(StoreError::GetError)
+        }
+    }
+
+    #[async_trait]
+    impl AccumulatorsStore for MockProofStore {
+        fn id(&self) -> String {
+            "mock".to_string()
+        }
+        async fn get(&self, _: &str) -> Result<Option<String>, StoreError> {
+            Ok(None)
+        }
+        async fn get_many(&self, _: Vec<&str>) -> Result<HashMap<String, String>, StoreError> {
+            Ok(HashMap::new())
+        }
+        async fn set(&self, _: &str, _: &str) -> Result<(), StoreError> {
+            Ok(())
+        }
+        async fn set_many(&self, _: HashMap<String, String>) -> Result<(), StoreError> {
+            Ok(())
+        }
+        async fn delete(&self, _: &str) -> Result<(), StoreError> {
+            Ok(())
+        }
+        async fn delete_many(&self, _: Vec<&str>) -> Result<(), StoreError> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl ProofStore for MockProofStore {
+        async fn get_mmr_root(&self, _: u32) -> Result<MmrRootRecord, StoreError> {
+            Err(StoreError::GetError)
+        }
+        async fn get_sparse_roots(&self, _: u32) -> Result<SparseRoots, StoreError> {
+            Err(StoreError::GetError)
+        }
+        async fn get_latest_sparse_roots_height(&self) -> Result<u32, StoreError> {
+            Err(StoreError::GetError)
+        }
+        async fn get_block_transactions(
+            &self,
+            height: u32,
+        ) -> Result<Vec<Arc<Transaction>>, StoreError> {
+            self.transactions
+                .get(&height)
+                .cloned()
+                .ok_or(StoreError::GetError)
+        }
+        async fn get_transaction_height(&self, txid: &Hash) -> Result<u32, StoreError> {
+            self.transactions
+                .iter()
+                .find(|(_, transactions)| transactions.iter().any(|tx| tx.hash() == *txid))
+                .map(|(height, _)| *height)
+                .ok_or(StoreError::GetError)
+        }
+        async fn get_transactions_by_prefix(
+            &self,
+            _: &str,
+            _: u32,
+        ) -> Result<Vec<(Hash, u32)>, StoreError> {
+            Ok(Vec::new())
+        }
+    }
+
+    const BLOCK_HEIGHT: u32 = 903_000;
+
+    /// Transparent v4 transaction without inputs, paying `value` to an empty script
+    fn transaction(value: u64) -> Arc<Transaction> {
+        // Header, version group ID, no inputs and one output
+        let mut bytes = hex::decode("0400008085202f890001").unwrap();
+        bytes.extend(value.to_le_bytes());
+        // Empty script, lock time, expiry height, value balance, and no Sapling spends, outputs
+        // or JoinSplits
+        bytes.extend([0; 1 + 4 + 4 + 8 + 3]);
+        Arc::new(Transaction::zcash_deserialize(bytes.as_slice()).unwrap())
+    }
+
+    /// Header committing to `transactions`
+    fn block_header(transactions: &[Arc<Transaction>]) -> Header {
+        let bytes = hex::decode(include_str!("../tests/data/genesis_header.hex").trim()).unwrap();
+        let mut header = Header::zcash_deserialize(bytes.as_slice()).unwrap();
+        header.merkle_root = transactions.iter().map(|tx| tx.hash()).collect();
+        header
+    }
+
+    fn app_state(node: impl Into<Arc<MockChainSource>>, store: MockProofStore) -> AppState {
+        AppState::with_sources(
+            Arc::new(store),
+            node.into(),
+            Arc::new(AppHealth::default()),
+            PathBuf::new(),
+            None,
+        )
+    }
+
+    async fn transaction_proof(
+        state: AppState,
+        txid: Hash,
+    ) -> Result<TransactionProofResponse, (StatusCode, String)> {
+        let query = TransactionProofQuery {
+            id_type: None,
+            block_height: None,
+        };
+        get_transaction_proof(State(state), Path(txid.to_string()), Query(query))
+            .await
+            .map(|Json(response)| response)
+    }
+
+    #[tokio::test]
+    async fn test_transaction_proof() {
+        let transactions = vec![transaction(0), transaction(1), transaction(2)];
+        let txid = transactions[2].hash();
+        let node = MockChainSource::serving(BLOCK_HEIGHT, &transactions);
+        let store = MockProofStore {
+            headers: HashMap::from([(BLOCK_HEIGHT, block_header(&transactions))]),
+            ..Default::default()
+        };
+
+        let Ok(response) = transaction_proof(app_state(node, store), txid).await else {
+            panic!("Transaction proof failed");
+        };
+        assert_eq!(response.source, Some(ProofSource::Rpc));
+        assert_eq!(response.proof.block_height, BLOCK_HEIGHT);
+        assert_eq!(response.proof.transaction.hash(), txid);
+        assert_eq!(response.proof.transaction_proof.index, 2);
+        assert!(response.proof.transaction_proof.verify(txid.0));
+    }
+
+    #[tokio::test]
+    async fn test_transaction_proofs_share_block_fetch() {
+        let transactions = vec![transaction(0), transaction(1), transaction(2)];
+        let node = Arc::new(MockChainSource::serving(BLOCK_HEIGHT, &transactions));
+        let store = MockProofStore {
+            headers: HashMap::from([(BLOCK_HEIGHT, block_header(&transactions))]),
+            ..Default::default()
+        };
+        let state = app_state(node.clone(), store);
+
+        // Proofs of different transactions of the same block
+        let (first, second) = tokio::join!(
+            transaction_proof(state.clone(), transactions[1].hash()),
+            transaction_proof(state.clone(), transactions[2].hash()),
+        );
+        assert_eq!(node.block_fetches.load(Ordering::SeqCst), 1);
+        let (Ok(first), Ok(second)) = (first, second) else {
+            panic!("Transaction proofs failed");
+        };
+        assert_eq!(first.proof.transaction_proof.index, 1);
+        assert_eq!(second.proof.transaction_proof.index, 2);
+    }
+
+    #[tokio::test]
+    async fn test_transaction_proof_not_found() {
+        let transactions = vec![transaction(0)];
+
+        // Unknown transaction
+        let node = MockChainSource::serving(BLOCK_HEIGHT, &transactions);
+        let store = MockProofStore {
+            headers: HashMap::from([(BLOCK_HEIGHT, block_header(&transactions))]),
+            ..Default::default()
+        };
+        let Err((status, message)) =
+            transaction_proof(app_state(node, store), transaction(1).hash()).await
+        else {
+            panic!("Proof of an unknown transaction");
+        };
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(message.starts_with("No transaction matches"), "{message}");
+
+        // Block not indexed yet
+        let node = MockChainSource::serving(BLOCK_HEIGHT, &transactions);
+        let Err((status, message)) =
+            transaction_proof(app_state(node, Default::default()), transactions[0].hash()).await
+        else {
+            panic!("Proof in a block that is not indexed");
+        };
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(message.contains("is not indexed yet"), "{message}");
+    }
+
+    #[tokio::test]
+    async fn test_transaction_proof_degraded() {
+        let transactions = vec![transaction(0), transaction(1)];
+        let txid = transactions[1].hash();
+        let header = block_header(&transactions);
+
+        // The node is unavailable, the stored transactions are served
+        let store = MockProofStore {
+            headers: HashMap::from([(BLOCK_HEIGHT, header)]),
+            transactions: HashMap::from([(BLOCK_HEIGHT, transactions.clone())]),
+        };
+        let Ok(response) =
+            transaction_proof(app_state(MockChainSource::default(), store), txid).await
+        else {
+            panic!("Transaction proof from the stored transactions failed");
+        };
+        assert_eq!(response.source, Some(ProofSource::Local));
+        assert!(response.proof.transaction_proof.verify(txid.0));
+
+        // Stored transactions not matching the indexed header are not served
+        let store = MockProofStore {
+            headers: HashMap::from([(BLOCK_HEIGHT, block_header(&transactions[..1]))]),
+            transactions: HashMap::from([(BLOCK_HEIGHT, transactions.clone())]),
+        };
+        let Err((status, _)) =
+            transaction_proof(app_state(MockChainSource::default(), store), txid).await
+        else {
+            panic!("Proof from stored transactions not matching the header");
+        };
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+
+        // The node finds the transaction but fails to serve its block, which is not stored
+        let mut node = MockChainSource::serving(BLOCK_HEIGHT, &transactions);
+        node.blocks.clear();
+        let store = MockProofStore {
+            headers: HashMap::from([(BLOCK_HEIGHT, header)]),
+            ..Default::default()
+        };
+        let Err((status, _)) = transaction_proof(app_state(node, store), txid).await else {
+            panic!("Proof of a block neither available nor stored");
+        };
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
@@ -0,0 +1,230 @@
+//! Deployment readiness checks run by `--self-test`
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use zoro_zcash_client::{RpcAuth, RpcTimeouts, ZcashClient};
+
+use crate::{
+    chain_state::ChainStateStore,
+    db_key::DbKey,
+    indexer::HEARTWOOD_ACTIVATION,
+    metrics::QueryMetrics,
+    node_version::{compatibility, Compatibility},
+    rpc::epoch_start_height,
+    store::{AppStore, SCHEMA_VERSION},
+};
+
+/// Time allowed for each check querying the Zcash node, retries included
+const RPC_CHECK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Configuration of the self-test
+#[derive(Debug, Clone)]
+pub struct SelfTestConfig {
+    /// Zcash RPC URL
+    pub rpc_url: String,
+    /// Zcash RPC credentials
+    pub rpc_auth: RpcAuth,
+    /// Zcash RPC request timeouts
+    pub rpc_timeouts: RpcTimeouts,
+    /// Path to the database storing the app state
+    pub db_path: PathBuf,
+    /// Accumulator store ID
+    pub id: String,
+    /// Database encryption key
+    pub db_key: Option<DbKey>,
+    /// Minimum free space required on the database volume, in megabytes
+    pub min_free_mb: u64,
+}
+
+/// Outcome of a single check
+enum Outcome {
+    Pass(String),
+    Skip(String),
+    Fail(String),
+}
+
+/// Run the readiness checks, printing a report on stdout. Returns whether all checks passed.
+pub async fn run(config: &SelfTestConfig) -> bool {
+    let mut report = Vec::new();
+
+    let zcash_client = ZcashClient::with_auth(config.rpc_url.clone(), config.rpc_auth.clone())
+        .await
+        .and_then(|client| client.with_timeouts(config.rpc_timeouts.clone()));
+    let upstream = match &zcash_client {
+        Ok(client) => check_upstream(client).await,
+        Err(e) => Outcome::Fail(format!("invalid client configuration: {e}")),
+    };
+    report.push(("upstream RPC", upstream));
+
+    // The store is opened read-only: a self-test never creates or migrates the database
+    let store = if config.db_path.exists() {
+        AppStore::multiple_concurrent_readers(
+            &config.db_path,
+            Some(config.id.clone()),
+            config.db_key.as_ref(),
+            Arc::new(QueryMetrics::new("self-test", None)),
+        )
+        .map_err(|e| format!("failed to open {}: {e}", config.db_path.display()))
+        .map(Some)
+    } else {
+        Ok(None)
+    };
+    let (schema, mmr_root) = match (&store, &zcash_client) {
+        (Err(e), _) => (
+            Outcome::Fail(e.clone()),
+            Outcome::Skip("store unavailable".to_string()),
+        ),
+        (Ok(None), _) => (
+            Outcome::Skip(format!(
+                "{} does not exist yet, it is created on start",
+                config.db_path.display()
+            )),
+            Outcome::Skip("store not created yet".to_string()),
+        ),
+        (Ok(Some(store)), Ok(client)) => (
+            check_schema(store).await,
+            check_mmr_root(client, store).await,
+        ),
+        (Ok(Some(store)), Err(_)) => (
+            check_schema(store).await,
+            Outcome::Skip("upstream RPC unavailable".to_string()),
+        ),
+    };
+    report.push(("store schema", schema));
+    report.push(("FlyClient MMR root", mmr_root));
+
+    report.push((
+        "disk space",
+        check_disk_space(&config.db_path, config.min_free_mb),
+    ));
+
+    let mut ready = true;
+    println!("Zoro bridge node readiness report");
+    for (name, outcome) in &report {
+        let (status, detail) = match outcome {
+            Outcome::Pass(detail) => ("ok", detail),
+            Outcome::Skip(detail) => ("skip", detail),
+            Outcome::Fail(detail) => {
+                ready = false;
+                ("FAIL", detail)
+            }
+        };
+        println!("  [{status:>4}] {name}: {detail}");
+    }
+    println!("{}", if ready { "READY" } else { "NOT READY" });
+    ready
+}
+
+/// The Zcash node answers and reports a supported version
+async fn check_upstream(client: &ZcashClient) -> Outcome {
+    let res = tokio::time::timeout(RPC_CHECK_TIMEOUT, async {
+        let version = client.get_node_version().await?;
+        let height = client.get_chain_height().await?;
+        Ok::<_, anyhow::Error>((version, height))
+    })
+    .await;
+    match res {
+        Ok(Ok((version, height))) => match compatibility(&version) {
+            Compatibility::Supported => Outcome::Pass(format!("{version} at height {height}")),
+            Compatibility::Unknown => Outcome::Pass(format!(
+                "{version} at height {height} (unknown implementation)"
+            )),
+            Compatibility::Unsupported(reason) => Outcome::Fail(format!("{version}: {reason}")),
+        },
+        Ok(Err(e)) => Outcome::Fail(e.to_string()),
+        Err(_) => Outcome::Fail(format!("no answer within {RPC_CHECK_TIMEOUT:?}")),
+    }
+}
+
+/// The store was written with a schema this node supports
+async fn check_schema(store: &AppStore) -> Outcome {
+    match store.schema_version().await {
+        Ok(version) if version > SCHEMA_VERSION => Outcome::Fail(format!(
+            "version {version} is newer than the supported version {SCHEMA_VERSION}"
+        )),
+        Ok(version) if version < SCHEMA_VERSION => Outcome::Pass(format!(
+            "version {version}, migrated to {SCHEMA_VERSION} on start"
+        )),
+        Ok(version) => Outcome::Pass(format!("version {version}")),
+        Err(e) => Outcome::Fail(format!("failed to read the schema version: {e}")),
+    }
+}
+
+/// The FlyClient MMR root recorded at the stored head matches the `blockcommitments` of the
+/// Zcash node, as checked by the indexer while indexing
+async fn check_mmr_root(client: &ZcashClient, store: &AppStore) -> Outcome {
+    let head = match store.get_latest_chain_state_height().await {
+        Ok(head) => head,
+        Err(_) => return Outcome::Skip("no block indexed yet".to_string()),
+    };
+    if head < HEARTWOOD_ACTIVATION {
+        return Outcome::Skip(format!("head {head} is before Heartwood activation"));
+    }
+    let record = match store.get_mmr_root(head).await {
+        Ok(record) => record,
+        Err(e) => return Outcome::Fail(format!("no MMR root recorded at head {head}: {e}")),
+    };
+    // The commitment of the next block in the epoch covers the blocks indexed so far
+    let verify_height = epoch_start_height(head) + record.leaf_count as u32;
+    let res = tokio::time::timeout(RPC_CHECK_TIMEOUT, async {
+        if client.get_chain_height().await? < verify_height {
+            return Ok(None);
+        }
+        Ok::<_, anyhow::Error>(Some(client.get_block_commitment(verify_height).await?))
+    })
+    .await;
+    match res {
+        Ok(Ok(Some(expected))) if expected == record.root => Outcome::Pass(format!(
+            "{} epoch root at head {head} matches block {verify_height}",
+            record.epoch
+        )),
+        Ok(Ok(Some(expected))) => Outcome::Fail(format!(
+            "{} epoch root at head {head} is {}, block {verify_height} commits to {expected}",
+            record.epoch, record.root
+        )),
+        Ok(Ok(None)) => Outcome::Skip(format!(
+            "block {verify_height} committing to head {head} is not mined yet"
+        )),
+        Ok(Err(e)) => Outcome::Fail(e.to_string()),
+        Err(_) => Outcome::Fail(format!("no answer within {RPC_CHECK_TIMEOUT:?}")),
+    }
+}
+
+/// The volume holding the database has at least `min_free_mb` megabytes available
+fn check_disk_space(db_path: &Path, min_free_mb: u64) -> Outcome {
+    // The database directory may not exist yet, measure the closest existing ancestor
+    let Some(dir) = db_path
+        .parent()
+        .into_iter()
+        .flat_map(Path::ancestors)
+        .map(|dir| {
+            if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            }
+        })
+        .find(|dir| dir.is_dir())
+    else {
+        return Outcome::Fail(format!("no directory found for {}", db_path.display()));
+    };
+    match fs2::available_space(dir) {
+        Ok(free) => {
+            let free_mb = free / (1024 * 1024);
+            if free_mb < min_free_mb {
+                Outcome::Fail(format!(
+                    "{free_mb} MB available on {}, {min_free_mb} MB required",
+                    dir.display()
+                ))
+            } else {
+                Outcome::Pass(format!("{free_mb} MB available on {}", dir.display()))
+            }
+        }
+        Err(e) => Outcome::Fail(format!(
+            "failed to get the free space of {}: {e}",
+            dir.display()
+        )),
+    }
+}
@@ -6,16 +6,34 @@ use tokio::{
 };
 use tracing::info;
 
-/// Manages graceful shutdown by listening for SIGTERM and SIGINT signals
-/// and broadcasting shutdown notifications to subscribers
+/// Manages graceful shutdown by listening for SIGTERM and SIGINT signals, or shutdown requests of
+/// the admin API, and broadcasting shutdown notifications to subscribers
 pub struct Shutdown {
     tx_shutdown: broadcast::Sender<()>,
+    /// Subscribed from the start, so that requests made before [`Shutdown::run`] are not missed
+    rx_requested: broadcast::Receiver<()>,
 }
 
 impl Default for Shutdown {
     fn default() -> Self {
-        let (tx_shutdown, _) = broadcast::channel(1);
-        Self { tx_shutdown }
+        let (tx_shutdown, rx_requested) = broadcast::channel(1);
+        Self {
+            tx_shutdown,
+            rx_requested,
+        }
+    }
+}
+
+/// Handle requesting a shutdown from within the node
+#[derive(Clone)]
+pub struct ShutdownTrigger {
+    tx_shutdown: broadcast::Sender<()>,
+}
+
+impl ShutdownTrigger {
+    /// Broadcast the shutdown notification to every subscriber
+    pub fn trigger(&self) {
+        let _ = self.tx_shutdown.send(());
     }
 }
 
@@ -24,13 +42,24 @@ impl Shutdown {
         self.tx_shutdown.subscribe()
     }
 
-    pub async fn run(&self) -> Result<(), ()> {
+    pub fn trigger(&self) -> ShutdownTrigger {
+        ShutdownTrigger {
+            tx_shutdown: self.tx_shutdown.clone(),
+        }
+    }
+
+    pub async fn run(&mut self) -> Result<(), ()> {
         let mut sigterm = signal(SignalKind::terminate()).unwrap();
         let mut sigint = signal(SignalKind::interrupt()).unwrap();
 
         tokio::select! {
             _ = sigterm.recv() => info!("Received SIGTERM, initiating shutdown..."),
             _ = sigint.recv() => info!("Received SIGINT, initiating shutdown..."),
+            // Already broadcast by the trigger
+            _ = self.rx_requested.recv() => {
+                info!("Shutdown requested, shutting down...");
+                return Ok(());
+            }
         };
 
         self.tx_shutdown.send(()).map(|_| ()).map_err(|_| ())
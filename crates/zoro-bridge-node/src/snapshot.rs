@@ -0,0 +1,98 @@
+//! Snapshot-consistent reads for proof assembly.
+//!
+//! Each query of the read-only store may run on another pooled connection, i.e. on another state
+//! of the database while the indexer commits blocks. A response assembled from several reads
+//! (chain head, chain state, block header, FlyClient MMR leaf count and nodes, recorded roots)
+//! could then mix data of two heights. A [`Snapshot`] reads through a dedicated connection
+//! holding a read transaction: in WAL mode, every query of the transaction sees the database as
+//! of its first read, whatever the indexer commits meanwhile.
+//!
+//! Data fetched from the Zcash node, like the transactions of a block, is not covered.
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use accumulators::store::StoreError;
+use tracing::warn;
+
+use crate::db_key::DbKey;
+use crate::metrics::QueryMetrics;
+use crate::store::AppStore;
+
+/// Single-connection read-only stores, reused across snapshots
+pub struct SnapshotStores {
+    db_path: PathBuf,
+    id: Option<String>,
+    db_key: Option<DbKey>,
+    metrics: Arc<QueryMetrics>,
+    /// Stores whose read transaction has ended
+    idle: Mutex<Vec<Arc<AppStore>>>,
+}
+
+impl SnapshotStores {
+    pub fn new(
+        db_path: PathBuf,
+        id: Option<String>,
+        db_key: Option<DbKey>,
+        metrics: Arc<QueryMetrics>,
+    ) -> Self {
+        Self {
+            db_path,
+            id,
+            db_key,
+            metrics,
+            idle: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Begin a snapshot on an idle store, opening one if none is idle. Snapshots are taken within
+    /// proof generation slots, so there are at most as many stores as slots.
+    pub async fn begin(self: &Arc<Self>) -> Result<Snapshot, StoreError> {
+        let idle = self.idle.lock().unwrap().pop();
+        let store = match idle {
+            Some(store) => store,
+            None => Arc::new(
+                AppStore::snapshot_reader(
+                    &self.db_path,
+                    self.id.clone(),
+                    self.db_key.as_ref(),
+                    self.metrics.clone(),
+                )
+                .map_err(StoreError::SQLite)?,
+            ),
+        };
+        // Deferred: the snapshot is taken by the first read
+        store.begin().await?;
+        Ok(Snapshot {
+            store,
+            stores: self.clone(),
+        })
+    }
+}
+
+/// Read transaction on a dedicated store, ended when dropped
+pub struct Snapshot {
+    store: Arc<AppStore>,
+    stores: Arc<SnapshotStores>,
+}
+
+impl Snapshot {
+    /// Store reading from the snapshot
+    pub fn store(&self) -> Arc<AppStore> {
+        self.store.clone()
+    }
+}
+
+impl Drop for Snapshot {
+    fn drop(&mut self) {
+        // Ending the read transaction lets the WAL be checkpointed past the snapshot
+        let store = self.store.clone();
+        let stores = self.stores.clone();
+        tokio::spawn(async move {
+            match store.commit().await {
+                Ok(()) => stores.idle.lock().unwrap().push(store),
+                Err(e) => warn!("Failed to end a snapshot read transaction: {}", e),
+            }
+        });
+    }
+}
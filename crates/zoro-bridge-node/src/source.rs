@@ -0,0 +1,69 @@
+//! Data sources of the RPC server
+
+use std::sync::Arc;
+
+use accumulators::store::{Store as AccumulatorsStore, StoreError};
+use async_trait::async_trait;
+use zebra_chain::transaction::{Hash, Transaction};
+use zoro_spv_verify::{MmrRootRecord, SparseRoots};
+use zoro_zcash_client::{ZcashClient, ZcashClientError};
+
+use crate::chain_state::ChainStateStore;
+
+/// Zcash node queries issued by the RPC handlers
+#[async_trait]
+pub trait ChainSource: Send + Sync {
+    async fn get_block_height_by_hash_str(&self, hash: &str) -> Result<u32, ZcashClientError>;
+    async fn get_transaction_block_height(&self, txid: &Hash) -> Result<u32, ZcashClientError>;
+    /// Get the transactions of the block at `block_height`
+    async fn get_block_transactions(
+        &self,
+        block_height: u32,
+    ) -> Result<Vec<Arc<Transaction>>, ZcashClientError>;
+}
+
+#[async_trait]
+impl ChainSource for ZcashClient {
+    async fn get_block_height_by_hash_str(&self, hash: &str) -> Result<u32, ZcashClientError> {
+        ZcashClient::get_block_height_by_hash_str(self, hash).await
+    }
+
+    async fn get_transaction_block_height(&self, txid: &Hash) -> Result<u32, ZcashClientError> {
+        ZcashClient::get_transaction_block_height(self, txid).await
+    }
+
+    async fn get_block_transactions(
+        &self,
+        block_height: u32,
+    ) -> Result<Vec<Arc<Transaction>>, ZcashClientError> {
+        let hash = self.get_block_hash(block_height).await?;
+        Ok(self.get_block(&hash).await?.transactions)
+    }
+}
+
+/// Indexed data read by the RPC handlers.
+///
+/// The FlyClient MMRs are opened on top of the store, hence the [`AccumulatorsStore`] bound.
+#[async_trait]
+pub trait ProofStore: ChainStateStore + AccumulatorsStore {
+    /// Get the FlyClient MMR root recorded at the given height
+    async fn get_mmr_root(&self, height: u32) -> Result<MmrRootRecord, StoreError>;
+    /// Get the block header MMR roots recorded at the given height
+    async fn get_sparse_roots(&self, height: u32) -> Result<SparseRoots, StoreError>;
+    /// Get the latest height for which block header MMR roots are recorded
+    async fn get_latest_sparse_roots_height(&self) -> Result<u32, StoreError>;
+    /// Get the stored transactions of the block at `height`
+    async fn get_block_transactions(
+        &self,
+        height: u32,
+    ) -> Result<Vec<Arc<Transaction>>, StoreError>;
+    /// Get the height of the stored block including the transaction
+    async fn get_transaction_height(&self, txid: &Hash) -> Result<u32, StoreError>;
+    /// Get the stored transactions whose display-order txid starts with `prefix`, with the
+    /// height of their block
+    async fn get_transactions_by_prefix(
+        &self,
+        prefix: &str,
+        limit: u32,
+    ) -> Result<Vec<(Hash, u32)>, StoreError>;
+}
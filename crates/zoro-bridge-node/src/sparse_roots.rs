@@ -0,0 +1,576 @@
+//! Shard files with the block header MMR roots, consumed by the Cairo proving pipeline
+
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tracing::info;
+use zoro_spv_verify::SparseRootsRecord;
+
+use zoro_chain_params::{Network, FLYCLIENT_UPGRADES};
+
+use crate::store::AppStore;
+
+/// Name of the index file listing the shards
+const INDEX_FILE: &str = "index.json";
+
+/// Number of records read from the store at once when rewriting a shard
+const REWRITE_BATCH_SIZE: u32 = 10_000;
+
+/// Name of the epoch before the first FlyClient epoch
+const PRE_HEARTWOOD: &str = "pre_heartwood";
+
+/// Epochs of `network` used by [`ShardLayout::Epoch`], as (start height, name)
+fn epochs(network: Network) -> Vec<(u32, &'static str)> {
+    std::iter::once((0, PRE_HEARTWOOD))
+        .chain(
+            FLYCLIENT_UPGRADES
+                .iter()
+                .map(|upgrade| (network.activation_height(*upgrade), upgrade.name())),
+        )
+        .collect()
+}
+
+/// How heights are grouped into shard files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShardLayout {
+    /// Fixed number of heights per shard
+    Fixed { size: u32 },
+    /// One shard per network upgrade epoch (the FlyClient MMR epochs, plus pre-Heartwood)
+    Epoch,
+}
+
+/// Compression applied to completed shards
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum ShardCompression {
+    None,
+    Gzip,
+}
+
+/// Configuration for the sparse roots file sink
+#[derive(Debug, Clone)]
+pub struct SparseRootsSinkConfig {
+    /// Network of the indexed chain, whose upgrades start the epochs
+    pub network: Network,
+    /// Directory the shard files are written to
+    pub output_dir: PathBuf,
+    /// How heights are grouped into shards
+    pub layout: ShardLayout,
+    /// Shard file path relative to `output_dir`, without extension. Supports the `{start}`,
+    /// `{end}`, `{shard}` and `{epoch}` placeholders and may contain subdirectories.
+    pub name_template: String,
+    /// Compression applied to completed shards
+    pub compression: ShardCompression,
+}
+
+/// A range of heights stored in one file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Shard {
+    /// Shard number
+    index: u32,
+    /// First height
+    start: u32,
+    /// Last height, `None` for the open-ended last epoch
+    end: Option<u32>,
+    /// Epoch of the first height
+    epoch: &'static str,
+}
+
+impl Shard {
+    /// Whether all heights of the shard are written once the store reaches `last_height`
+    fn is_complete(&self, last_height: u32) -> bool {
+        self.end.is_some_and(|end| end <= last_height)
+    }
+}
+
+/// Entry of the index file
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct IndexEntry {
+    pub(crate) start: u32,
+    pub(crate) end: Option<u32>,
+    pub(crate) file: String,
+    pub(crate) compression: ShardCompression,
+    pub(crate) complete: bool,
+}
+
+/// Index file listing all shards, so that consumers can locate the roots for a height without
+/// listing the output directory
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Index {
+    pub(crate) layout: ShardLayout,
+    pub(crate) last_height: u32,
+    pub(crate) shards: Vec<IndexEntry>,
+}
+
+/// Read the index file of a shard directory
+pub(crate) async fn read_index(output_dir: &Path) -> Result<Index, anyhow::Error> {
+    let path = output_dir.join(INDEX_FILE);
+    let data = fs::read(&path)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {e}", path.display()))?;
+    serde_json::from_slice(&data)
+        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {e}", path.display()))
+}
+
+/// Read the records of a shard listed in the index, in file order
+pub(crate) async fn read_shard(
+    output_dir: &Path,
+    entry: &IndexEntry,
+) -> Result<Vec<SparseRootsRecord>, anyhow::Error> {
+    let data = fs::read(output_dir.join(&entry.file)).await?;
+    let data = match entry.compression {
+        ShardCompression::None => data,
+        ShardCompression::Gzip => {
+            let mut decoded = Vec::new();
+            GzDecoder::new(data.as_slice()).read_to_end(&mut decoded)?;
+            decoded
+        }
+    };
+    data.split(|byte| *byte == b'\n')
+        .filter(|line| !line.is_empty())
+        .enumerate()
+        .map(|(i, line)| {
+            serde_json::from_slice(line)
+                .map_err(|e| anyhow::anyhow!("Invalid record on line {}: {e}", i + 1))
+        })
+        .collect()
+}
+
+/// Writes the sparse roots recorded at every height into JSON Lines shard files.
+///
+/// Line `i` of a shard is the [`SparseRootsRecord`] for height `start + i`. The shard being
+/// filled is a plain `.jsonl` file; once complete it is rewritten with the configured
+/// compression. The database is the source of truth: on startup the current shard and any
+/// missing shard are rewritten from it.
+#[derive(Debug)]
+pub struct SparseRootsSink {
+    config: SparseRootsSinkConfig,
+    /// Epochs of the configured network, as (start height, name)
+    epochs: Vec<(u32, &'static str)>,
+}
+
+impl SparseRootsSink {
+    pub async fn new(config: SparseRootsSinkConfig) -> Result<Self, anyhow::Error> {
+        if config.layout == (ShardLayout::Fixed { size: 0 }) {
+            anyhow::bail!("Sparse roots shard size must be positive");
+        }
+        fs::create_dir_all(&config.output_dir).await?;
+        Ok(Self {
+            epochs: epochs(config.network),
+            config,
+        })
+    }
+
+    /// Shard containing the given height
+    fn shard_for_height(&self, height: u32) -> Shard {
+        match self.config.layout {
+            ShardLayout::Fixed { size } => {
+                let start = height / size * size;
+                Shard {
+                    index: height / size,
+                    start,
+                    end: Some(start + (size - 1)),
+                    epoch: self.epoch_name_for_height(start),
+                }
+            }
+            ShardLayout::Epoch => {
+                let index = self
+                    .epochs
+                    .iter()
+                    .rposition(|(start, _)| *start <= height)
+                    .unwrap_or(0);
+                let (start, epoch) = self.epochs[index];
+                Shard {
+                    index: index as u32,
+                    start,
+                    end: self.epochs.get(index + 1).map(|(next, _)| next - 1),
+                    epoch,
+                }
+            }
+        }
+    }
+
+    /// Name of the epoch containing `height`
+    fn epoch_name_for_height(&self, height: u32) -> &'static str {
+        self.epochs
+            .iter()
+            .rev()
+            .find(|(start, _)| *start <= height)
+            .map_or(PRE_HEARTWOOD, |(_, name)| name)
+    }
+
+    /// Shards covering heights `0..=last_height`
+    fn shards(&self, last_height: u32) -> Vec<Shard> {
+        let mut shards = vec![self.shard_for_height(0)];
+        while let Some(end) = shards.last().and_then(|shard| shard.end) {
+            if end >= last_height {
+                break;
+            }
+            shards.push(self.shard_for_height(end + 1));
+        }
+        shards
+    }
+
+    /// Shard file path relative to the output directory
+    fn shard_file(&self, shard: &Shard, compression: ShardCompression) -> String {
+        let end = shard.end.map_or("tip".to_string(), |end| end.to_string());
+        let name = self
+            .config
+            .name_template
+            .replace("{start}", &shard.start.to_string())
+            .replace("{end}", &end)
+            .replace("{shard}", &shard.index.to_string())
+            .replace("{epoch}", shard.epoch);
+        match compression {
+            ShardCompression::None => format!("{name}.jsonl"),
+            ShardCompression::Gzip => format!("{name}.jsonl.gz"),
+        }
+    }
+
+    fn shard_path(&self, shard: &Shard, compression: ShardCompression) -> PathBuf {
+        self.config
+            .output_dir
+            .join(self.shard_file(shard, compression))
+    }
+
+    /// Compression of a shard file once the store reaches `last_height`
+    fn shard_compression(&self, shard: &Shard, last_height: u32) -> ShardCompression {
+        if shard.is_complete(last_height) {
+            self.config.compression
+        } else {
+            ShardCompression::None
+        }
+    }
+
+    /// Bring the shard files in sync with the store, up to `next_block_height - 1`
+    pub async fn resume(
+        &self,
+        store: &AppStore,
+        next_block_height: u32,
+    ) -> Result<(), anyhow::Error> {
+        let Some(last_height) = next_block_height.checked_sub(1) else {
+            return Ok(());
+        };
+        for shard in self.shards(last_height) {
+            let path = self.shard_path(&shard, self.shard_compression(&shard, last_height));
+            let is_current = shard.end.is_none_or(|end| end >= last_height);
+            if is_current || !fs::try_exists(&path).await? {
+                self.rewrite_shard(store, &shard, last_height).await?;
+            }
+        }
+        self.write_index(last_height).await?;
+        info!(
+            "Sparse roots shards at {:?} synced up to height {}",
+            self.config.output_dir, last_height
+        );
+        Ok(())
+    }
+
+    /// Rewrite a shard from the store, up to `last_height`, compressing it if complete
+    async fn rewrite_shard(
+        &self,
+        store: &AppStore,
+        shard: &Shard,
+        last_height: u32,
+    ) -> Result<(), anyhow::Error> {
+        let compression = self.shard_compression(shard, last_height);
+        let path = self.shard_path(shard, compression);
+        // Write to a temporary file first so that readers never see a partial shard
+        let tmp_path = path.with_extension("tmp");
+        create_parent_dir(&path).await?;
+        let mut file = fs::File::create(&tmp_path).await?;
+        let mut encoder = ShardEncoder::new(compression);
+
+        let last = shard.end.map_or(last_height, |end| end.min(last_height));
+        let mut height = shard.start;
+        while height <= last {
+            let num_blocks = (last - height + 1).min(REWRITE_BATCH_SIZE);
+            let records = store.get_sparse_roots_range(height, num_blocks).await?;
+            if records.len() != num_blocks as usize {
+                anyhow::bail!(
+                    "Missing sparse roots in store for heights {}..{}",
+                    height,
+                    height + num_blocks
+                );
+            }
+            let mut data = Vec::new();
+            for record in &records {
+                data.extend(serde_json::to_vec(record)?);
+                data.push(b'\n');
+            }
+            file.write_all(&encoder.encode(&data)?).await?;
+            height += num_blocks;
+        }
+        file.write_all(&encoder.finish()?).await?;
+        file.flush().await?;
+        fs::rename(&tmp_path, &path).await?;
+
+        // Drop the uncompressed copy left over from filling the shard
+        if compression != ShardCompression::None {
+            let plain_path = self.shard_path(shard, ShardCompression::None);
+            if fs::try_exists(&plain_path).await? {
+                fs::remove_file(&plain_path).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Rewrite the index file listing the shards up to `last_height`
+    async fn write_index(&self, last_height: u32) -> Result<(), anyhow::Error> {
+        let shards = self
+            .shards(last_height)
+            .iter()
+            .map(|shard| {
+                let compression = self.shard_compression(shard, last_height);
+                IndexEntry {
+                    start: shard.start,
+                    end: shard.end,
+                    file: self.shard_file(shard, compression),
+                    compression,
+                    complete: shard.is_complete(last_height),
+                }
+            })
+            .collect();
+        let index = Index {
+            layout: self.config.layout,
+            last_height,
+            shards,
+        };
+        let path = self.config.output_dir.join(INDEX_FILE);
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, serde_json::to_vec_pretty(&index)?).await?;
+        fs::rename(&tmp_path, &path).await?;
+        Ok(())
+    }
+
+    /// Append the sparse roots recorded at `record.height` to its shard.
+    ///
+    /// The index is updated when a shard is started or completed; a completed shard is
+    /// rewritten from the store with the configured compression.
+    pub async fn write(
+        &self,
+        store: &AppStore,
+        record: &SparseRootsRecord,
+    ) -> Result<(), anyhow::Error> {
+        let shard = self.shard_for_height(record.height);
+        let path = self.shard_path(&shard, ShardCompression::None);
+        create_parent_dir(&path).await?;
+
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await?;
+        file.write_all(&line).await?;
+        file.flush().await?;
+
+        let is_complete = shard.is_complete(record.height);
+        if is_complete && self.config.compression != ShardCompression::None {
+            self.rewrite_shard(store, &shard, record.height).await?;
+        }
+        if is_complete || record.height == shard.start {
+            self.write_index(record.height).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Incremental encoder for shard contents
+enum ShardEncoder {
+    Plain,
+    Gzip(GzEncoder<Vec<u8>>),
+}
+
+impl ShardEncoder {
+    fn new(compression: ShardCompression) -> Self {
+        match compression {
+            ShardCompression::None => Self::Plain,
+            ShardCompression::Gzip => {
+                Self::Gzip(GzEncoder::new(Vec::new(), Compression::default()))
+            }
+        }
+    }
+
+    /// Encode a chunk, returning the bytes ready to be written
+    fn encode(&mut self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            Self::Plain => Ok(data.to_vec()),
+            Self::Gzip(encoder) => {
+                encoder.write_all(data)?;
+                Ok(std::mem::take(encoder.get_mut()))
+            }
+        }
+    }
+
+    /// Flush the remaining bytes
+    fn finish(self) -> std::io::Result<Vec<u8>> {
+        match self {
+            Self::Plain => Ok(Vec::new()),
+            Self::Gzip(encoder) => encoder.finish(),
+        }
+    }
+}
+
+async fn create_parent_dir(path: &Path) -> Result<(), std::io::Error> {
+    match path.parent() {
+        Some(parent) => fs::create_dir_all(parent).await,
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use zoro_chain_params::NetworkUpgrade;
+    use zoro_spv_verify::{block_mmr::header_bytes_leaf, SparseRoots};
+
+    use super::*;
+    use crate::metrics::QueryMetrics;
+
+    async fn new_sink(
+        output_dir: &Path,
+        network: Network,
+        layout: ShardLayout,
+        compression: ShardCompression,
+    ) -> SparseRootsSink {
+        SparseRootsSink::new(SparseRootsSinkConfig {
+            network,
+            output_dir: output_dir.to_path_buf(),
+            layout,
+            name_template: "{epoch}/roots_{shard}_{start}_{end}".to_string(),
+            compression,
+        })
+        .await
+        .unwrap()
+    }
+
+    /// Store with the sparse roots of heights `0..num_blocks`, and their records
+    async fn store_with_roots(dir: &Path, num_blocks: u32) -> (AppStore, Vec<SparseRootsRecord>) {
+        let metrics = Arc::new(QueryMetrics::new("test", None));
+        let store = AppStore::single_atomic_writer(dir.join("app.db"), None, None, metrics)
+            .await
+            .unwrap();
+        let mut roots = SparseRoots::default();
+        let mut records = Vec::new();
+        for height in 0..num_blocks {
+            roots.add(header_bytes_leaf(&height.to_le_bytes()));
+            store.add_sparse_roots(height, &roots).await.unwrap();
+            records.push(SparseRootsRecord {
+                height,
+                roots: roots.clone(),
+            });
+        }
+        (store, records)
+    }
+
+    /// Records of all the shards listed in the index, in height order
+    async fn read_all(output_dir: &Path) -> Vec<SparseRootsRecord> {
+        let index = read_index(output_dir).await.unwrap();
+        let mut records = Vec::new();
+        for entry in &index.shards {
+            records.extend(read_shard(output_dir, entry).await.unwrap());
+        }
+        records
+    }
+
+    #[tokio::test]
+    async fn test_epoch_boundaries() {
+        let dir = tempfile::tempdir().unwrap();
+        for network in [Network::Mainnet, Network::Testnet] {
+            let sink = new_sink(
+                dir.path(),
+                network,
+                ShardLayout::Epoch,
+                ShardCompression::None,
+            )
+            .await;
+            let mut end = None;
+            for (index, upgrade) in FLYCLIENT_UPGRADES.into_iter().enumerate() {
+                let start = network.activation_height(upgrade);
+                let previous = sink.shard_for_height(start - 1);
+                assert_eq!(
+                    (previous.index, previous.end),
+                    (index as u32, Some(start - 1))
+                );
+                let shard = sink.shard_for_height(start);
+                assert_eq!(
+                    (shard.index, shard.start, shard.epoch),
+                    (index as u32 + 1, start, upgrade.name()),
+                    "{upgrade:?} on {network:?}"
+                );
+                end = shard.end;
+            }
+            // The last epoch is open-ended
+            assert_eq!(end, None);
+            assert_eq!(sink.shard_for_height(0).epoch, PRE_HEARTWOOD);
+            assert_eq!(sink.shards(u32::MAX).len(), FLYCLIENT_UPGRADES.len() + 1);
+        }
+
+        // Fixed shards are named after the epoch of their first height on the network
+        let layout = ShardLayout::Fixed { size: 1_000 };
+        let heartwood = Network::Mainnet.activation_height(NetworkUpgrade::Heartwood);
+        let mainnet = new_sink(dir.path(), Network::Mainnet, layout, ShardCompression::None).await;
+        assert_eq!(mainnet.shard_for_height(heartwood).epoch, "heartwood");
+        let testnet = new_sink(dir.path(), Network::Testnet, layout, ShardCompression::None).await;
+        assert_eq!(testnet.shard_for_height(heartwood).epoch, PRE_HEARTWOOD);
+    }
+
+    #[tokio::test]
+    async fn test_shards_round_trip() {
+        for compression in [ShardCompression::None, ShardCompression::Gzip] {
+            let dir = tempfile::tempdir().unwrap();
+            let (store, records) = store_with_roots(dir.path(), 10).await;
+            let output_dir = dir.path().join("shards");
+            let layout = ShardLayout::Fixed { size: 4 };
+            let sink = new_sink(&output_dir, Network::Mainnet, layout, compression).await;
+            for record in &records {
+                sink.write(&store, record).await.unwrap();
+            }
+
+            // The index is rewritten when a shard is started or completed
+            let index = read_index(&output_dir).await.unwrap();
+            assert_eq!((index.layout, index.last_height), (layout, 8));
+            let shards: Vec<_> = index
+                .shards
+                .iter()
+                .map(|entry| (entry.start, entry.end, entry.compression, entry.complete))
+                .collect();
+            assert_eq!(
+                shards,
+                [
+                    (0, Some(3), compression, true),
+                    (4, Some(7), compression, true),
+                    (8, Some(11), ShardCompression::None, false),
+                ]
+            );
+            assert_eq!(
+                index.shards[0].file,
+                sink.shard_file(&sink.shard_for_height(0), compression)
+            );
+            assert_eq!(read_all(&output_dir).await, records);
+            // Only the compressed copy of a completed shard is kept
+            let plain = sink.shard_path(&sink.shard_for_height(0), ShardCompression::None);
+            assert_eq!(
+                fs::try_exists(&plain).await.unwrap(),
+                compression == ShardCompression::None
+            );
+
+            // A missing shard is rewritten from the store on startup
+            fs::remove_file(output_dir.join(&index.shards[1].file))
+                .await
+                .unwrap();
+            let sink = SparseRootsSink::new(sink.config.clone()).await.unwrap();
+            sink.resume(&store, 10).await.unwrap();
+            assert_eq!(read_index(&output_dir).await.unwrap().last_height, 9);
+            assert_eq!(read_all(&output_dir).await, records);
+        }
+    }
+}
@@ -1,24 +1,35 @@
 use std::collections::HashMap;
+use std::future::Future;
 use std::ops::DerefMut;
 use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Instant;
 
 use accumulators::store::{sqlite::SQLiteStore, Store as AccumulatorsStore, StoreError};
 use async_trait::async_trait;
 
 use sqlx::sqlite::{
-    SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous,
-    SqliteTransactionManager,
+    SqliteConnectOptions, SqliteConnection, SqliteJournalMode, SqlitePool, SqlitePoolOptions,
+    SqliteRow, SqliteSynchronous, SqliteTransactionManager,
 };
 use sqlx::{Row, TransactionManager};
 
 use tokio::fs;
+use tracing::{info, warn};
 use zebra_chain::block::Hash;
 use zebra_chain::block::Header;
 use zebra_chain::serialization::ZcashDeserialize;
 use zebra_chain::serialization::ZcashSerialize;
-use zoro_spv_verify::ChainState;
+use zebra_chain::transaction::{Hash as TxHash, Transaction};
+use zoro_spv_verify::{proof::Target, ChainState, MmrRootRecord, SparseRoots, SparseRootsRecord};
 
 use crate::chain_state::ChainStateStore;
+use crate::checksum::{self, ChecksumReport, ChecksummedStore, CREATE_CHECKSUMS_TABLE};
+use crate::db_key::DbKey;
+use crate::metrics::QueryMetrics;
+use crate::quota::ApiUsage;
+use crate::source::ProofStore;
 
 /// SQLite busy timeout in milliseconds
 const SQLITE_BUSY_TIMEOUT: &str = "5000";
@@ -26,18 +37,88 @@ const SQLITE_BUSY_TIMEOUT: &str = "5000";
 /// Maximum number of concurrent readers (size of the connection pool)
 const SQLITE_MAX_CONCURRENT_READERS: u32 = 10;
 
+/// Version of the store schema, recorded in the `user_version` pragma once the tables are
+/// created and migrated. Stores written before versioning report 0.
+///
+/// Version 2 checksums the accumulator rows: older nodes would rewrite rows without updating
+/// their checksum, so they must refuse the store.
+pub const SCHEMA_VERSION: i64 = 2;
+
+/// Number of legacy chain states converted at once when migrating the `chain_states` table
+const CHAIN_STATES_MIGRATION_BATCH: u32 = 10_000;
+
+/// Chain states with one column per [`ChainState`] field, so that they can be queried with SQL.
+/// Fields added later are appended as columns with a default value, keeping older rows readable.
+const CREATE_CHAIN_STATES_TABLE: &str = r#"CREATE TABLE IF NOT EXISTS chain_states (
+    height INTEGER PRIMARY KEY,
+    total_work TEXT NOT NULL,
+    best_block_hash TEXT NOT NULL,
+    current_target TEXT NOT NULL,
+    prev_timestamps TEXT NOT NULL,
+    epoch_start_time INTEGER NOT NULL,
+    pow_target_history TEXT NOT NULL
+);"#;
+
+/// Apply the SQLCipher key, which must be the first statement run on a connection
+/// (`SqliteConnectOptions` issues the `key` pragma before any other).
+fn with_key(
+    options: SqliteConnectOptions,
+    key: Option<&DbKey>,
+) -> Result<SqliteConnectOptions, sqlx::Error> {
+    match key {
+        None => Ok(options),
+        Some(_) if !cfg!(feature = "sqlcipher") => Err(sqlx::Error::Configuration(
+            "database encryption requires building with the `sqlcipher` feature".into(),
+        )),
+        Some(key) => Ok(options.pragma("key", key.pragma_value())),
+    }
+}
+
+/// Begin a transaction on the connection of a single-writer accumulators store, so that the
+/// following writes are committed together
+pub async fn begin_transaction(store: &SQLiteStore) -> Result<(), StoreError> {
+    let mut conn = store.acquire_connection().await?;
+    SqliteTransactionManager::begin(&mut conn, None)
+        .await
+        .map_err(StoreError::SQLite)
+}
+
+/// Commit the transaction started with [`begin_transaction`]
+pub async fn commit_transaction(store: &SQLiteStore) -> Result<(), StoreError> {
+    let mut conn = store.acquire_connection().await?;
+    SqliteTransactionManager::commit(&mut conn)
+        .await
+        .map_err(StoreError::SQLite)
+}
+
+/// Roll back the transaction started with [`begin_transaction`]
+pub async fn rollback_transaction(store: &SQLiteStore) -> Result<(), StoreError> {
+    let mut conn = store.acquire_connection().await?;
+    SqliteTransactionManager::rollback(&mut conn)
+        .await
+        .map_err(StoreError::SQLite)
+}
+
 /// SQLite-backed store with single-writer and multi-reader pools.
 /// - WAL mode for concurrent readers during writes
 /// - Single writer (max_connections = 1)
 /// - Optional active write transaction encapsulated in the store
+/// - Per-query duration metrics and slow query logging on the read path
 #[derive(Debug)]
-pub struct AppStore(SQLiteStore);
+pub struct AppStore {
+    inner: SQLiteStore,
+    /// Pool of `inner`, shared with the accumulator stores opened on this store
+    pool: SqlitePool,
+    metrics: Arc<QueryMetrics>,
+}
 
 impl AppStore {
     /// Create a store for a single atomic writer
     pub async fn single_atomic_writer<P: AsRef<Path>>(
         path: P,
         id: Option<String>,
+        key: Option<&DbKey>,
+        metrics: Arc<QueryMetrics>,
     ) -> Result<Self, sqlx::Error> {
         if let Some(parent) = path.as_ref().parent() {
             fs::create_dir_all(parent).await?;
@@ -53,34 +134,85 @@ impl AppStore {
         // Writer pool: single connection ensures single-writer semantics
         let pool = SqlitePoolOptions::new()
             .max_connections(1)
-            .connect_with(options)
+            .connect_with(with_key(options, key)?)
             .await?;
 
-        let store = Self(SQLiteStore::with_pool(pool, id));
+        let store = Self {
+            inner: SQLiteStore::with_pool(pool.clone(), id),
+            pool,
+            metrics,
+        };
         store.init().await?;
 
         Ok(store)
     }
 
     /// Create a store for multiple concurrent readers
-    pub fn multiple_concurrent_readers<P: AsRef<Path>>(path: P, id: Option<String>) -> Self {
+    pub fn multiple_concurrent_readers<P: AsRef<Path>>(
+        path: P,
+        id: Option<String>,
+        key: Option<&DbKey>,
+        metrics: Arc<QueryMetrics>,
+    ) -> Result<Self, sqlx::Error> {
         let options = SqliteConnectOptions::new()
             .filename(path.as_ref())
             .read_only(true);
 
         let pool = SqlitePoolOptions::new()
             .max_connections(SQLITE_MAX_CONCURRENT_READERS)
-            .connect_lazy_with(options);
+            .connect_lazy_with(with_key(options, key)?);
 
-        Self(SQLiteStore::with_pool(pool, id))
+        Ok(Self {
+            inner: SQLiteStore::with_pool(pool.clone(), id),
+            pool,
+            metrics,
+        })
+    }
+
+    /// Create a read-only store on a single connection, so that a read transaction begun with
+    /// [`AppStore::begin`] covers every following query (see [`crate::snapshot`])
+    pub fn snapshot_reader<P: AsRef<Path>>(
+        path: P,
+        id: Option<String>,
+        key: Option<&DbKey>,
+        metrics: Arc<QueryMetrics>,
+    ) -> Result<Self, sqlx::Error> {
+        let options = SqliteConnectOptions::new()
+            .filename(path.as_ref())
+            .read_only(true);
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_lazy_with(with_key(options, key)?);
+
+        Ok(Self {
+            inner: SQLiteStore::with_pool(pool.clone(), id),
+            pool,
+            metrics,
+        })
     }
 
     /// Initialize the store by creating the tables if missing
     async fn init(&self) -> Result<(), sqlx::Error> {
-        // Create a key-value store table for header state
-        self.0.init().await?;
+        // Refuse stores written by a newer node, whose schema this one may not understand
+        let version =
+            read_schema_version(self.inner.acquire_connection().await?.deref_mut()).await?;
+        if version > SCHEMA_VERSION {
+            return Err(sqlx::Error::Configuration(
+                format!(
+                    "store schema version {version} is newer than the supported version \
+                     {SCHEMA_VERSION}"
+                )
+                .into(),
+            ));
+        }
+        // Create a key-value store table for header state, and the checksums of its rows
+        self.inner.init().await?;
+        let mut conn = self.inner.acquire_connection().await?;
+        sqlx::query(CREATE_CHECKSUMS_TABLE)
+            .execute(conn.deref_mut())
+            .await?;
         // Create a table for encoded block headers
-        let mut conn = self.0.acquire_connection().await?;
         sqlx::query(
             r#"CREATE TABLE IF NOT EXISTS block_headers (
                 height INTEGER PRIMARY KEY,
@@ -90,11 +222,57 @@ impl AppStore {
         )
         .execute(conn.deref_mut())
         .await?;
-        // Create a table for chain states
+        // Create a table for chain states, converting the bincode blobs of earlier versions
+        migrate_legacy_chain_states(conn.deref_mut()).await?;
+        sqlx::query(CREATE_CHAIN_STATES_TABLE)
+            .execute(conn.deref_mut())
+            .await?;
+        // Create a table for FlyClient MMR roots recorded per indexed height
+        sqlx::query(
+            r#"CREATE TABLE IF NOT EXISTS mmr_roots (
+                height INTEGER PRIMARY KEY,
+                epoch TEXT NOT NULL,
+                leaf_count INTEGER NOT NULL,
+                root TEXT NOT NULL
+            );"#,
+        )
+        .execute(conn.deref_mut())
+        .await?;
+        // Create a table for block header MMR roots (sparse representation) per indexed height
+        sqlx::query(
+            r#"CREATE TABLE IF NOT EXISTS sparse_roots (
+                height INTEGER PRIMARY KEY,
+                roots BLOB NOT NULL
+            );"#,
+        )
+        .execute(conn.deref_mut())
+        .await?;
+        // Create tables for the block transactions stored for degraded-mode proof serving
         sqlx::query(
-            r#"CREATE TABLE IF NOT EXISTS chain_states (
+            r#"CREATE TABLE IF NOT EXISTS block_transactions (
                 height INTEGER PRIMARY KEY,
-                state BLOB NOT NULL
+                transactions BLOB NOT NULL
+            );"#,
+        )
+        .execute(conn.deref_mut())
+        .await?;
+        sqlx::query(
+            r#"CREATE TABLE IF NOT EXISTS transaction_heights (
+                txid TEXT PRIMARY KEY,
+                height INTEGER NOT NULL
+            );"#,
+        )
+        .execute(conn.deref_mut())
+        .await?;
+        // Create a table for the usage of each API key per quota period
+        sqlx::query(
+            r#"CREATE TABLE IF NOT EXISTS api_usage (
+                name TEXT NOT NULL,
+                day INTEGER NOT NULL,
+                requests INTEGER NOT NULL,
+                bytes INTEGER NOT NULL,
+                proofs INTEGER NOT NULL,
+                PRIMARY KEY (name, day)
             );"#,
         )
         .execute(conn.deref_mut())
@@ -105,33 +283,492 @@ impl AppStore {
         )
         .execute(conn.deref_mut())
         .await?;
+        sqlx::query(&format!("PRAGMA user_version = {SCHEMA_VERSION}"))
+            .execute(conn.deref_mut())
+            .await?;
         Ok(())
     }
 
+    /// Get the schema version of the store, 0 if it was written before versioning
+    pub async fn schema_version(&self) -> Result<i64, StoreError> {
+        let mut conn = self.inner.acquire_connection().await?;
+        Ok(read_schema_version(conn.deref_mut()).await?)
+    }
+
+    /// Count the rows of each table, by table name
+    pub async fn table_row_counts(&self) -> Result<Vec<(String, i64)>, StoreError> {
+        let mut conn = self.inner.acquire_connection().await?;
+        let tables: Vec<String> =
+            sqlx::query_scalar("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name")
+                .fetch_all(conn.deref_mut())
+                .await?;
+        let mut counts = Vec::with_capacity(tables.len());
+        for table in tables {
+            let rows: i64 = sqlx::query_scalar(&format!(
+                "SELECT COUNT(*) FROM \"{}\"",
+                table.replace('"', "\"\"")
+            ))
+            .fetch_one(conn.deref_mut())
+            .await?;
+            counts.push((table, rows));
+        }
+        Ok(counts)
+    }
+
+    /// Verify every checksummed accumulator row, listing the corrupted ones
+    pub async fn verify_checksums(&self) -> Result<ChecksumReport, StoreError> {
+        checksum::verify_all(&self.inner).await
+    }
+
+    /// Query duration metrics of this store
+    pub fn metrics(&self) -> &QueryMetrics {
+        &self.metrics
+    }
+
+    /// Run a query, recording its duration. Slow queries are logged along with their query plan
+    /// when the SQL is known.
+    async fn timed<T>(
+        &self,
+        name: &'static str,
+        sql: Option<&'static str>,
+        query: impl Future<Output = Result<T, StoreError>>,
+    ) -> Result<T, StoreError> {
+        let start = Instant::now();
+        let res = query.await;
+        let elapsed = start.elapsed();
+        if self.metrics.observe(name, elapsed) {
+            let plan = match sql {
+                Some(sql) => self.explain(sql).await,
+                None => "n/a".to_string(),
+            };
+            warn!(
+                "Slow store query {} took {:?}, query plan: {}",
+                name, elapsed, plan
+            );
+        }
+        res
+    }
+
+    /// Capture the query plan of a statement, with its parameters unbound
+    async fn explain(&self, sql: &str) -> String {
+        let plan = async {
+            let mut conn = self.inner.acquire_connection().await?;
+            let rows = sqlx::query(&format!("EXPLAIN QUERY PLAN {sql}"))
+                .fetch_all(conn.deref_mut())
+                .await?;
+            Ok::<_, StoreError>(
+                rows.iter()
+                    .map(|row| row.get::<String, _>("detail"))
+                    .collect::<Vec<_>>()
+                    .join("; "),
+            )
+        };
+        plan.await
+            .unwrap_or_else(|e| format!("failed to capture query plan: {e}"))
+    }
+
+    /// Open an accumulators store (e.g. a FlyClient MMR) on the connection of this store,
+    /// checksumming its rows. On a single writer, its writes join the transaction begun with
+    /// [`AppStore::begin`], so that they are committed together with the app tables.
+    pub async fn accumulator_store(&self, id: &str) -> Result<ChecksummedStore, sqlx::Error> {
+        let store = SQLiteStore::with_pool(self.pool.clone(), Some(id.to_string()));
+        store.init().await?;
+        sqlx::query(CREATE_CHECKSUMS_TABLE)
+            .execute(store.acquire_connection().await?.deref_mut())
+            .await?;
+        Ok(ChecksummedStore::new(store))
+    }
+
     /// Begin a new transaction.
     /// NOTE that this function does not check if there is already a transaction in progress.
     pub async fn begin(&self) -> Result<(), StoreError> {
-        let mut conn = self.0.acquire_connection().await?;
-        SqliteTransactionManager::begin(&mut conn, None)
-            .await
-            .map_err(StoreError::SQLite)
+        begin_transaction(&self.inner).await
     }
 
     /// Commit the current transaction.
     /// NOTE that this function does not check if there is a transaction in progress.
     pub async fn commit(&self) -> Result<(), StoreError> {
-        let mut conn = self.0.acquire_connection().await?;
-        SqliteTransactionManager::commit(&mut conn)
-            .await
-            .map_err(StoreError::SQLite)
+        commit_transaction(&self.inner).await
+    }
+
+    /// Roll back the current transaction.
+    /// NOTE that this function does not check if there is a transaction in progress.
+    pub async fn rollback(&self) -> Result<(), StoreError> {
+        rollback_transaction(&self.inner).await
+    }
+
+    /// Record the FlyClient MMR root obtained right after appending the block at `record.height`
+    pub async fn add_mmr_root(&self, record: &MmrRootRecord) -> Result<(), StoreError> {
+        let mut conn = self.inner.acquire_connection().await?;
+        sqlx::query(
+            "INSERT OR REPLACE INTO mmr_roots (height, epoch, leaf_count, root) VALUES (?, ?, ?, ?)",
+        )
+        .bind(record.height)
+        .bind(&record.epoch)
+        .bind(record.leaf_count as i64)
+        .bind(&record.root)
+        .execute(conn.deref_mut())
+        .await?;
+        Ok(())
+    }
+
+    /// Get the FlyClient MMR root recorded at the given height
+    pub async fn get_mmr_root(&self, height: u32) -> Result<MmrRootRecord, StoreError> {
+        const SQL: &str = "SELECT height, epoch, leaf_count, root FROM mmr_roots WHERE height = ?";
+        self.timed("get_mmr_root", Some(SQL), async {
+            let mut conn = self.inner.acquire_connection().await?;
+            let row = sqlx::query(SQL)
+                .bind(height)
+                .fetch_optional(conn.deref_mut())
+                .await?
+                .ok_or(StoreError::GetError)?;
+            Ok(MmrRootRecord {
+                height: row.get("height"),
+                epoch: row.get("epoch"),
+                leaf_count: row.get::<i64, _>("leaf_count") as usize,
+                root: row.get("root"),
+            })
+        })
+        .await
     }
+
+    /// Record the block header MMR roots obtained right after adding the block at `height`
+    pub async fn add_sparse_roots(
+        &self,
+        height: u32,
+        sparse_roots: &SparseRoots,
+    ) -> Result<(), StoreError> {
+        let mut conn = self.inner.acquire_connection().await?;
+        sqlx::query("INSERT OR REPLACE INTO sparse_roots (height, roots) VALUES (?, ?)")
+            .bind(height)
+            .bind(sparse_roots.to_bytes())
+            .execute(conn.deref_mut())
+            .await?;
+        Ok(())
+    }
+
+    /// Get the block header MMR roots recorded at the given height
+    pub async fn get_sparse_roots(&self, height: u32) -> Result<SparseRoots, StoreError> {
+        const SQL: &str = "SELECT roots FROM sparse_roots WHERE height = ?";
+        self.timed("get_sparse_roots", Some(SQL), async {
+            let mut conn = self.inner.acquire_connection().await?;
+            let row = sqlx::query(SQL)
+                .bind(height)
+                .fetch_optional(conn.deref_mut())
+                .await?
+                .ok_or(StoreError::GetError)?;
+            decode_sparse_roots(&row.get::<Vec<u8>, _>("roots"))
+        })
+        .await
+    }
+
+    /// Get the block header MMR roots recorded for a range of heights
+    pub async fn get_sparse_roots_range(
+        &self,
+        start_height: u32,
+        num_blocks: u32,
+    ) -> Result<Vec<SparseRootsRecord>, StoreError> {
+        const SQL: &str = "SELECT height, roots FROM sparse_roots \
+            WHERE height >= ? AND height < ? ORDER BY height";
+        self.timed("get_sparse_roots_range", Some(SQL), async {
+            let mut conn = self.inner.acquire_connection().await?;
+            let rows = sqlx::query(SQL)
+                .bind(start_height)
+                .bind(start_height + num_blocks)
+                .fetch_all(conn.deref_mut())
+                .await?;
+            rows.iter()
+                .map(|row| {
+                    Ok(SparseRootsRecord {
+                        height: row.get("height"),
+                        roots: decode_sparse_roots(&row.get::<Vec<u8>, _>("roots"))?,
+                    })
+                })
+                .collect()
+        })
+        .await
+    }
+
+    /// Store the transactions of the block at `height`, so that proofs can be built without the
+    /// Zcash node
+    pub async fn add_block_transactions(
+        &self,
+        height: u32,
+        transactions: &[Arc<Transaction>],
+    ) -> Result<(), StoreError> {
+        let mut data = Vec::new();
+        transactions
+            .to_vec()
+            .zcash_serialize(&mut data)
+            .map_err(|e| StoreError::Custom(e.into()))?;
+        let mut conn = self.inner.acquire_connection().await?;
+        sqlx::query(
+            "INSERT OR REPLACE INTO block_transactions (height, transactions) VALUES (?, ?)",
+        )
+        .bind(height)
+        .bind(data)
+        .execute(conn.deref_mut())
+        .await?;
+        for tx in transactions {
+            sqlx::query("INSERT OR REPLACE INTO transaction_heights (txid, height) VALUES (?, ?)")
+                .bind(tx.hash().to_string())
+                .bind(height)
+                .execute(conn.deref_mut())
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Get the stored transactions of the block at `height`
+    pub async fn get_block_transactions(
+        &self,
+        height: u32,
+    ) -> Result<Vec<Arc<Transaction>>, StoreError> {
+        const SQL: &str = "SELECT transactions FROM block_transactions WHERE height = ?";
+        self.timed("get_block_transactions", Some(SQL), async {
+            let mut conn = self.inner.acquire_connection().await?;
+            let row = sqlx::query(SQL)
+                .bind(height)
+                .fetch_optional(conn.deref_mut())
+                .await?
+                .ok_or(StoreError::GetError)?;
+            let data: Vec<u8> = row.get("transactions");
+            Vec::<Arc<Transaction>>::zcash_deserialize(&mut data.as_slice())
+                .map_err(|e| StoreError::Custom(e.into()))
+        })
+        .await
+    }
+
+    /// Get the height of the stored block including the transaction
+    pub async fn get_transaction_height(&self, txid: &TxHash) -> Result<u32, StoreError> {
+        const SQL: &str = "SELECT height FROM transaction_heights WHERE txid = ?";
+        self.timed("get_transaction_height", Some(SQL), async {
+            let mut conn = self.inner.acquire_connection().await?;
+            let row = sqlx::query(SQL)
+                .bind(txid.to_string())
+                .fetch_optional(conn.deref_mut())
+                .await?;
+            row.map(|row| row.get("height")).ok_or(StoreError::GetError)
+        })
+        .await
+    }
+
+    /// Get the stored transactions whose txid (display order, lowercase hex) starts with
+    /// `prefix`, with the height of their block, ordered by height. At most `limit` are returned.
+    pub async fn get_transactions_by_prefix(
+        &self,
+        prefix: &str,
+        limit: u32,
+    ) -> Result<Vec<(TxHash, u32)>, StoreError> {
+        // Range on the primary key: 'g' sorts after every hex digit
+        const SQL: &str = "SELECT txid, height FROM transaction_heights WHERE txid >= ?1 AND txid < ?1 || 'g' ORDER BY height, txid LIMIT ?2";
+        self.timed("get_transactions_by_prefix", Some(SQL), async {
+            let mut conn = self.inner.acquire_connection().await?;
+            let rows = sqlx::query(SQL)
+                .bind(prefix)
+                .bind(limit)
+                .fetch_all(conn.deref_mut())
+                .await?;
+            rows.iter()
+                .map(|row| {
+                    let txid = TxHash::from_str(row.get("txid"))
+                        .map_err(|e| StoreError::Custom(e.into()))?;
+                    Ok((txid, row.get("height")))
+                })
+                .collect()
+        })
+        .await
+    }
+
+    /// Add to the usage of an API key on a day (days since the Unix epoch)
+    pub async fn add_api_usage(
+        &self,
+        name: &str,
+        day: u64,
+        usage: &ApiUsage,
+    ) -> Result<(), StoreError> {
+        let mut conn = self.inner.acquire_connection().await?;
+        sqlx::query(
+            "INSERT INTO api_usage (name, day, requests, bytes, proofs) VALUES (?, ?, ?, ?, ?) \
+            ON CONFLICT (name, day) DO UPDATE SET requests = requests + excluded.requests, \
+            bytes = bytes + excluded.bytes, proofs = proofs + excluded.proofs",
+        )
+        .bind(name)
+        .bind(day as i64)
+        .bind(usage.requests as i64)
+        .bind(usage.bytes as i64)
+        .bind(usage.proofs as i64)
+        .execute(conn.deref_mut())
+        .await?;
+        Ok(())
+    }
+
+    /// Get the usage of every API key on a day (days since the Unix epoch)
+    pub async fn get_api_usage(&self, day: u64) -> Result<Vec<(String, ApiUsage)>, StoreError> {
+        const SQL: &str = "SELECT name, requests, bytes, proofs FROM api_usage WHERE day = ?";
+        self.timed("get_api_usage", Some(SQL), async {
+            let mut conn = self.inner.acquire_connection().await?;
+            let rows = sqlx::query(SQL)
+                .bind(day as i64)
+                .fetch_all(conn.deref_mut())
+                .await?;
+            Ok(rows
+                .iter()
+                .map(|row| {
+                    let usage = ApiUsage {
+                        requests: row.get::<i64, _>("requests") as u64,
+                        bytes: row.get::<i64, _>("bytes") as u64,
+                        proofs: row.get::<i64, _>("proofs") as u64,
+                    };
+                    (row.get("name"), usage)
+                })
+                .collect())
+        })
+        .await
+    }
+
+    /// Get the latest height for which block header MMR roots are recorded
+    pub async fn get_latest_sparse_roots_height(&self) -> Result<u32, StoreError> {
+        const SQL: &str = "SELECT height FROM sparse_roots ORDER BY height DESC LIMIT 1";
+        self.timed("get_latest_sparse_roots_height", Some(SQL), async {
+            let mut conn = self.inner.acquire_connection().await?;
+            let row = sqlx::query(SQL).fetch_optional(conn.deref_mut()).await?;
+            row.map(|row| row.get("height")).ok_or(StoreError::GetError)
+        })
+        .await
+    }
+}
+
+async fn read_schema_version(conn: &mut SqliteConnection) -> Result<i64, sqlx::Error> {
+    let row = sqlx::query("PRAGMA user_version").fetch_one(conn).await?;
+    row.try_get(0)
+}
+
+/// Insert a chain state into the `chain_states` table.
+///
+/// The total work is stored as a decimal string since it does not fit into an SQLite integer,
+/// the targets as hex strings and the lists as JSON arrays.
+async fn insert_chain_state(
+    conn: &mut SqliteConnection,
+    height: u32,
+    chain_state: &ChainState,
+) -> Result<(), sqlx::Error> {
+    let pow_target_history: Vec<String> = chain_state
+        .pow_target_history
+        .iter()
+        .map(Target::to_hex)
+        .collect();
+    sqlx::query(
+        "INSERT INTO chain_states (height, total_work, best_block_hash, current_target, \
+         prev_timestamps, epoch_start_time, pow_target_history) VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(height)
+    .bind(chain_state.total_work.to_string())
+    .bind(chain_state.best_block_hash.to_string())
+    .bind(chain_state.current_target.to_hex())
+    .bind(
+        serde_json::to_string(&chain_state.prev_timestamps)
+            .map_err(|e| sqlx::Error::Encode(e.into()))?,
+    )
+    .bind(chain_state.epoch_start_time)
+    .bind(serde_json::to_string(&pow_target_history).map_err(|e| sqlx::Error::Encode(e.into()))?)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Decode a chain state from a row of the `chain_states` table
+fn decode_chain_state(row: &SqliteRow) -> Result<ChainState, sqlx::Error> {
+    let total_work: String = row.try_get("total_work")?;
+    let best_block_hash: String = row.try_get("best_block_hash")?;
+    let current_target: String = row.try_get("current_target")?;
+    let prev_timestamps: String = row.try_get("prev_timestamps")?;
+    let pow_target_history: String = row.try_get("pow_target_history")?;
+    let pow_target_history: Vec<String> =
+        serde_json::from_str(&pow_target_history).map_err(|e| sqlx::Error::Decode(e.into()))?;
+    Ok(ChainState {
+        block_height: row.try_get("height")?,
+        total_work: total_work
+            .parse()
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+        best_block_hash: Hash::from_str(&best_block_hash)
+            .map_err(|e| sqlx::Error::Decode(e.into()))?,
+        current_target: Target::from_hex(&current_target)
+            .map_err(|e| sqlx::Error::Decode(e.into()))?,
+        prev_timestamps: serde_json::from_str(&prev_timestamps)
+            .map_err(|e| sqlx::Error::Decode(e.into()))?,
+        epoch_start_time: row.try_get("epoch_start_time")?,
+        pow_target_history: pow_target_history
+            .iter()
+            .map(|target| Target::from_hex(target))
+            .collect::<Result<_, _>>()
+            .map_err(|e| sqlx::Error::Decode(e.into()))?,
+    })
+}
+
+/// Convert a `chain_states` table holding bincode blobs (as written by earlier versions) to
+/// the typed schema. Does nothing if the table is missing or already migrated.
+///
+/// The conversion runs in a single transaction; if it fails the node refuses to start and the
+/// connection is closed, which rolls it back.
+async fn migrate_legacy_chain_states(conn: &mut SqliteConnection) -> Result<(), sqlx::Error> {
+    let columns = sqlx::query("SELECT name FROM pragma_table_info('chain_states')")
+        .fetch_all(&mut *conn)
+        .await?;
+    if !columns
+        .iter()
+        .any(|row| row.get::<String, _>("name") == "state")
+    {
+        return Ok(());
+    }
+    info!("Migrating chain states to the typed schema");
+
+    SqliteTransactionManager::begin(conn, None).await?;
+    sqlx::query("ALTER TABLE chain_states RENAME TO chain_states_legacy")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query(CREATE_CHAIN_STATES_TABLE)
+        .execute(&mut *conn)
+        .await?;
+    let mut num_migrated = 0;
+    let mut next_height = 0;
+    loop {
+        let rows = sqlx::query(
+            "SELECT height, state FROM chain_states_legacy WHERE height >= ? ORDER BY height LIMIT ?",
+        )
+        .bind(next_height)
+        .bind(CHAIN_STATES_MIGRATION_BATCH)
+        .fetch_all(&mut *conn)
+        .await?;
+        let Some(last) = rows.last() else {
+            break;
+        };
+        next_height = last.get::<u32, _>("height") + 1;
+        for row in &rows {
+            let data: Vec<u8> = row.get("state");
+            let chain_state = bincode::deserialize::<ChainState>(&data)
+                .map_err(|e| sqlx::Error::Decode(e.into()))?;
+            insert_chain_state(conn, row.get("height"), &chain_state).await?;
+        }
+        num_migrated += rows.len();
+    }
+    sqlx::query("DROP TABLE chain_states_legacy")
+        .execute(&mut *conn)
+        .await?;
+    SqliteTransactionManager::commit(conn).await?;
+    info!("Migrated {} chain states", num_migrated);
+    Ok(())
+}
+
+fn decode_sparse_roots(bytes: &[u8]) -> Result<SparseRoots, StoreError> {
+    SparseRoots::from_bytes(bytes).map_err(|e| StoreError::Custom(e.into()))
 }
 
 #[async_trait]
 impl ChainStateStore for AppStore {
     /// Add a new block header to the store
     async fn add_block_header(&self, height: u32, block_header: &Header) -> Result<(), StoreError> {
-        let mut conn = self.0.acquire_connection().await?;
+        let mut conn = self.inner.acquire_connection().await?;
 
         let mut block_header_data = Vec::new();
         block_header
@@ -147,53 +784,121 @@ impl ChainStateStore for AppStore {
         Ok(())
     }
 
+    /// Add a batch of block headers to the store.
+    ///
+    /// The rows are inserted in a single transaction (a savepoint if one is already in
+    /// progress) with the same prepared statement, which sqlx caches on the connection.
+    async fn add_block_headers(&self, block_headers: Vec<(u32, Header)>) -> Result<(), StoreError> {
+        let rows = block_headers
+            .iter()
+            .map(|(height, block_header)| {
+                let mut block_header_data = Vec::new();
+                block_header
+                    .zcash_serialize(&mut block_header_data)
+                    .map_err(|e| StoreError::Custom(Box::new(e)))?;
+                Ok((*height, block_header.hash().to_string(), block_header_data))
+            })
+            .collect::<Result<Vec<_>, StoreError>>()?;
+
+        let mut conn = self.inner.acquire_connection().await?;
+        SqliteTransactionManager::begin(&mut conn, None)
+            .await
+            .map_err(StoreError::SQLite)?;
+        let res = async {
+            for (height, hash, block_header_data) in rows {
+                sqlx::query("INSERT INTO block_headers (height, hash, header) VALUES (?, ?, ?)")
+                    .bind(height)
+                    .bind(hash)
+                    .bind(block_header_data)
+                    .execute(conn.deref_mut())
+                    .await?;
+            }
+            Ok::<_, StoreError>(())
+        }
+        .await;
+        match res {
+            Ok(()) => SqliteTransactionManager::commit(&mut conn)
+                .await
+                .map_err(StoreError::SQLite),
+            Err(e) => {
+                SqliteTransactionManager::rollback(&mut conn)
+                    .await
+                    .map_err(StoreError::SQLite)?;
+                Err(e)
+            }
+        }
+    }
+
     /// Get a range of block headers from the store
     async fn get_block_headers(
         &self,
         start_height: u32,
         num_blocks: u32,
     ) -> Result<Vec<Header>, StoreError> {
-        let mut conn = self.0.acquire_connection().await?;
-        let rows = sqlx::query("SELECT header FROM block_headers WHERE height >= ? AND height < ?")
-            .bind(start_height)
-            .bind(start_height + num_blocks)
-            .fetch_all(conn.deref_mut())
-            .await?;
-        rows.iter()
-            .map(|row| {
-                let header: Vec<u8> = row.get("header");
-                Header::zcash_deserialize(&mut header.as_slice())
-                    .map_err(|e| StoreError::Custom(Box::new(e)))
-            })
-            .collect()
+        const SQL: &str = "SELECT height, header FROM block_headers \
+            WHERE height >= ? AND height < ? ORDER BY height";
+        self.timed("get_block_headers", Some(SQL), async {
+            let mut conn = self.inner.acquire_connection().await?;
+            let rows = sqlx::query(SQL)
+                .bind(start_height)
+                .bind(start_height + num_blocks)
+                .fetch_all(conn.deref_mut())
+                .await?;
+            // The range may end early when it is not fully indexed yet, but must not have gaps
+            rows.iter()
+                .zip(start_height..)
+                .map(|(row, expected)| {
+                    let height: u32 = row.get("height");
+                    if height != expected {
+                        return Err(StoreError::Custom(
+                            format!("Missing block header at height {expected}").into(),
+                        ));
+                    }
+                    let header: Vec<u8> = row.get("header");
+                    Header::zcash_deserialize(&mut header.as_slice())
+                        .map_err(|e| StoreError::Custom(Box::new(e)))
+                })
+                .collect()
+        })
+        .await
     }
 
     /// Get the height of a block by its hash
     async fn _get_block_height(&self, block_hash: &Hash) -> Result<u32, StoreError> {
-        let mut conn = self.0.acquire_connection().await?;
-        let row = sqlx::query("SELECT height FROM block_headers WHERE hash = ?")
-            .bind(block_hash.to_string())
-            .fetch_optional(conn.deref_mut())
-            .await?;
-        row.map(|row| row.get("height")).ok_or(StoreError::GetError)
+        const SQL: &str = "SELECT height FROM block_headers WHERE hash = ?";
+        self.timed("get_block_height", Some(SQL), async {
+            let mut conn = self.inner.acquire_connection().await?;
+            let row = sqlx::query(SQL)
+                .bind(block_hash.to_string())
+                .fetch_optional(conn.deref_mut())
+                .await?;
+            row.map(|row| row.get("height")).ok_or(StoreError::GetError)
+        })
+        .await
     }
 
     async fn get_chain_state(&self, height: u32) -> Result<ChainState, StoreError> {
-        let mut conn = self.0.acquire_connection().await?;
-        let row = sqlx::query("SELECT state FROM chain_states WHERE height = ?")
-            .bind(height)
-            .fetch_optional(conn.deref_mut())
-            .await?;
-        let data: Vec<u8> = row.ok_or(StoreError::GetError)?.get("state");
-        bincode::deserialize::<ChainState>(&data).map_err(|e| StoreError::Custom(Box::new(e)))
+        const SQL: &str = "SELECT height, total_work, best_block_hash, current_target, \
+            prev_timestamps, epoch_start_time, pow_target_history FROM chain_states WHERE height = ?";
+        self.timed("get_chain_state", Some(SQL), async {
+            let mut conn = self.inner.acquire_connection().await?;
+            let row = sqlx::query(SQL)
+                .bind(height)
+                .fetch_optional(conn.deref_mut())
+                .await?;
+            Ok(decode_chain_state(&row.ok_or(StoreError::GetError)?)?)
+        })
+        .await
     }
 
     async fn get_latest_chain_state_height(&self) -> Result<u32, StoreError> {
-        let mut conn = self.0.acquire_connection().await?;
-        let row = sqlx::query("SELECT height FROM chain_states ORDER BY height DESC LIMIT 1")
-            .fetch_optional(conn.deref_mut())
-            .await?;
-        row.map(|row| row.get("height")).ok_or(StoreError::GetError)
+        const SQL: &str = "SELECT height FROM chain_states ORDER BY height DESC LIMIT 1";
+        self.timed("get_latest_chain_state_height", Some(SQL), async {
+            let mut conn = self.inner.acquire_connection().await?;
+            let row = sqlx::query(SQL).fetch_optional(conn.deref_mut()).await?;
+            row.map(|row| row.get("height")).ok_or(StoreError::GetError)
+        })
+        .await
     }
 
     async fn add_chain_state(
@@ -201,13 +906,8 @@ impl ChainStateStore for AppStore {
         height: u32,
         chain_state: &ChainState,
     ) -> Result<(), StoreError> {
-        let mut conn = self.0.acquire_connection().await?;
-        let data = bincode::serialize(chain_state).map_err(|e| StoreError::Custom(Box::new(e)))?;
-        sqlx::query("INSERT INTO chain_states (height, state) VALUES (?, ?)")
-            .bind(height)
-            .bind(data)
-            .execute(conn.deref_mut())
-            .await?;
+        let mut conn = self.inner.acquire_connection().await?;
+        insert_chain_state(conn.deref_mut(), height, chain_state).await?;
         Ok(())
     }
 }
@@ -215,24 +915,59 @@ impl ChainStateStore for AppStore {
 #[async_trait]
 impl AccumulatorsStore for AppStore {
     fn id(&self) -> String {
-        self.0.id()
+        self.inner.id()
     }
     async fn get(&self, key: &str) -> Result<Option<String>, StoreError> {
-        self.0.get(key).await
+        self.timed("accumulator_get", None, checksum::get(&self.inner, key))
+            .await
     }
     async fn get_many(&self, keys: Vec<&str>) -> Result<HashMap<String, String>, StoreError> {
-        self.0.get_many(keys).await
+        self.timed(
+            "accumulator_get_many",
+            None,
+            checksum::get_many(&self.inner, keys),
+        )
+        .await
     }
     async fn set(&self, key: &str, value: &str) -> Result<(), StoreError> {
-        self.0.set(key, value).await
+        checksum::set_many(&self.inner, HashMap::from([(key.into(), value.into())])).await
     }
     async fn set_many(&self, entries: HashMap<String, String>) -> Result<(), StoreError> {
-        self.0.set_many(entries).await
+        checksum::set_many(&self.inner, entries).await
     }
     async fn delete(&self, key: &str) -> Result<(), StoreError> {
-        self.0.delete(key).await
+        checksum::delete_many(&self.inner, vec![key]).await
     }
     async fn delete_many(&self, keys: Vec<&str>) -> Result<(), StoreError> {
-        self.0.delete_many(keys).await
+        checksum::delete_many(&self.inner, keys).await
+    }
+}
+
+#[async_trait]
+impl ProofStore for AppStore {
+    async fn get_mmr_root(&self, height: u32) -> Result<MmrRootRecord, StoreError> {
+        AppStore::get_mmr_root(self, height).await
+    }
+    async fn get_sparse_roots(&self, height: u32) -> Result<SparseRoots, StoreError> {
+        AppStore::get_sparse_roots(self, height).await
+    }
+    async fn get_latest_sparse_roots_height(&self) -> Result<u32, StoreError> {
+        AppStore::get_latest_sparse_roots_height(self).await
+    }
+    async fn get_block_transactions(
+        &self,
+        height: u32,
+    ) -> Result<Vec<Arc<Transaction>>, StoreError> {
+        AppStore::get_block_transactions(self, height).await
+    }
+    async fn get_transaction_height(&self, txid: &TxHash) -> Result<u32, StoreError> {
+        AppStore::get_transaction_height(self, txid).await
+    }
+    async fn get_transactions_by_prefix(
+        &self,
+        prefix: &str,
+        limit: u32,
+    ) -> Result<Vec<(TxHash, u32)>, StoreError> {
+        AppStore::get_transactions_by_prefix(self, prefix, limit).await
     }
 }
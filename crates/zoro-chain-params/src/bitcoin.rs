@@ -0,0 +1,250 @@
+//! Bitcoin network parameters: message start bytes, genesis blocks, buried soft fork activation
+//! heights and difficulty adjustment.
+
+use crate::network::Network;
+
+/// Bitcoin network
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum BitcoinNetwork {
+    #[default]
+    Mainnet,
+    Testnet,
+    Signet,
+    Regtest,
+}
+
+/// Soft fork buried at a fixed activation height by Bitcoin Core
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BitcoinDeployment {
+    /// Height in coinbase
+    Bip34,
+    /// `OP_CHECKLOCKTIMEVERIFY`
+    Bip65,
+    /// Strict DER signatures
+    Bip66,
+    /// `OP_CHECKSEQUENCEVERIFY` and relative lock times (BIPs 68, 112 and 113)
+    Csv,
+    /// Segregated witness (BIPs 141, 143 and 147)
+    Segwit,
+}
+
+impl BitcoinDeployment {
+    /// Every buried deployment
+    pub const ALL: [BitcoinDeployment; 5] = [
+        BitcoinDeployment::Bip34,
+        BitcoinDeployment::Bip65,
+        BitcoinDeployment::Bip66,
+        BitcoinDeployment::Csv,
+        BitcoinDeployment::Segwit,
+    ];
+}
+
+/// Number of blocks between two difficulty adjustments
+pub const BITCOIN_DIFFICULTY_ADJUSTMENT_INTERVAL: u32 = 2016;
+/// Expected duration of a difficulty adjustment interval, in seconds
+pub const BITCOIN_POW_TARGET_TIMESPAN: u32 = 14 * 24 * 60 * 60;
+/// Expected time between blocks, in seconds
+pub const BITCOIN_POW_TARGET_SPACING: u32 = 10 * 60;
+
+/// Activation heights of [`BitcoinDeployment::ALL`] on mainnet
+const MAINNET_ACTIVATION_HEIGHTS: [u32; 5] = [227_931, 388_381, 363_725, 419_328, 481_824];
+/// Activation heights of [`BitcoinDeployment::ALL`] on testnet
+const TESTNET_ACTIVATION_HEIGHTS: [u32; 5] = [21_111, 581_885, 330_776, 770_112, 834_624];
+/// Activation heights of [`BitcoinDeployment::ALL`] on signet
+const SIGNET_ACTIVATION_HEIGHTS: [u32; 5] = [1, 1, 1, 1, 1];
+/// Activation heights of [`BitcoinDeployment::ALL`] on regtest
+const REGTEST_ACTIVATION_HEIGHTS: [u32; 5] = [1, 1, 1, 1, 0];
+
+impl BitcoinNetwork {
+    /// Every network
+    pub const ALL: [BitcoinNetwork; 4] = [
+        BitcoinNetwork::Mainnet,
+        BitcoinNetwork::Testnet,
+        BitcoinNetwork::Signet,
+        BitcoinNetwork::Regtest,
+    ];
+
+    /// Message start bytes of the peer-to-peer protocol
+    pub const fn magic(self) -> [u8; 4] {
+        match self {
+            BitcoinNetwork::Mainnet => [0xf9, 0xbe, 0xb4, 0xd9],
+            BitcoinNetwork::Testnet => [0x0b, 0x11, 0x09, 0x07],
+            BitcoinNetwork::Signet => [0x0a, 0x03, 0xcf, 0x40],
+            BitcoinNetwork::Regtest => [0xfa, 0xbf, 0xb5, 0xda],
+        }
+    }
+
+    /// Hash of the genesis block, in display order
+    pub const fn genesis_hash(self) -> &'static str {
+        match self {
+            BitcoinNetwork::Mainnet => {
+                "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f"
+            }
+            BitcoinNetwork::Testnet => {
+                "000000000933ea01ad0ee984209779baaec3ced90fa3f408719526f8d77f4943"
+            }
+            BitcoinNetwork::Signet => {
+                "00000008819873e925422c1ff0f99f7cc9bbb232af63a077a480a3633bee1ef6"
+            }
+            BitcoinNetwork::Regtest => {
+                "0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206"
+            }
+        }
+    }
+
+    /// Timestamp of the genesis block
+    pub const fn genesis_timestamp(self) -> u32 {
+        match self {
+            BitcoinNetwork::Mainnet => 1_231_006_505,
+            BitcoinNetwork::Testnet | BitcoinNetwork::Regtest => 1_296_688_602,
+            BitcoinNetwork::Signet => 1_598_918_400,
+        }
+    }
+
+    /// Compact target (`nBits`) of the genesis block
+    pub const fn genesis_bits(self) -> u32 {
+        match self {
+            BitcoinNetwork::Mainnet | BitcoinNetwork::Testnet => 0x1d00_ffff,
+            BitcoinNetwork::Signet => 0x1e03_77ae,
+            BitcoinNetwork::Regtest => 0x207f_ffff,
+        }
+    }
+
+    /// Easiest target allowed, big-endian
+    pub const fn pow_limit(self) -> [u8; 32] {
+        match self {
+            BitcoinNetwork::Mainnet | BitcoinNetwork::Testnet => {
+                pow_limit(&[0x00, 0x00, 0x00, 0x00], 0xff)
+            }
+            BitcoinNetwork::Signet => pow_limit(&[0x00, 0x00, 0x03, 0x77, 0xae], 0x00),
+            BitcoinNetwork::Regtest => pow_limit(&[0x7f], 0xff),
+        }
+    }
+
+    /// Height at which `deployment` activates
+    pub const fn activation_height(self, deployment: BitcoinDeployment) -> u32 {
+        self.activation_heights()[deployment as usize]
+    }
+
+    const fn activation_heights(self) -> &'static [u32; 5] {
+        match self {
+            BitcoinNetwork::Mainnet => &MAINNET_ACTIVATION_HEIGHTS,
+            BitcoinNetwork::Testnet => &TESTNET_ACTIVATION_HEIGHTS,
+            BitcoinNetwork::Signet => &SIGNET_ACTIVATION_HEIGHTS,
+            BitcoinNetwork::Regtest => &REGTEST_ACTIVATION_HEIGHTS,
+        }
+    }
+}
+
+/// `prefix || fill..`
+const fn pow_limit(prefix: &[u8], fill: u8) -> [u8; 32] {
+    let mut limit = [fill; 32];
+    let mut i = 0;
+    while i < prefix.len() {
+        limit[i] = prefix[i];
+        i += 1;
+    }
+    limit
+}
+
+/// Whether the message start bytes of the Bitcoin and Zcash networks are all distinct, so that a
+/// peer of the wrong chain is told apart on the first message
+const fn magics_distinct() -> bool {
+    let zcash = [Network::Mainnet.magic(), Network::Testnet.magic()];
+    let mut i = 0;
+    while i < BitcoinNetwork::ALL.len() {
+        let magic = u32::from_be_bytes(BitcoinNetwork::ALL[i].magic());
+        let mut j = i + 1;
+        while j < BitcoinNetwork::ALL.len() {
+            if magic == u32::from_be_bytes(BitcoinNetwork::ALL[j].magic()) {
+                return false;
+            }
+            j += 1;
+        }
+        if magic == u32::from_be_bytes(zcash[0]) || magic == u32::from_be_bytes(zcash[1]) {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+const _: () = assert!(magics_distinct(), "Network magics must be distinct");
+const _: () = assert!(
+    BITCOIN_DIFFICULTY_ADJUSTMENT_INTERVAL * BITCOIN_POW_TARGET_SPACING
+        == BITCOIN_POW_TARGET_TIMESPAN,
+    "A difficulty adjustment interval lasts its target timespan"
+);
+const _: () = assert!(
+    MAINNET_ACTIVATION_HEIGHTS.len() == BitcoinDeployment::ALL.len(),
+    "Every deployment must have an activation height"
+);
+
+#[cfg(test)]
+mod tests {
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::pow::expand;
+
+    /// Merkle root of the genesis block of every network, in internal byte order
+    const GENESIS_MERKLE_ROOT: &str =
+        "3ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a";
+
+    /// Header of the genesis block of `network`, mined with `nonce`
+    fn genesis_header(network: BitcoinNetwork, nonce: u32) -> Vec<u8> {
+        let mut header = 1u32.to_le_bytes().to_vec();
+        header.extend([0; 32]);
+        header.extend(hex::decode(GENESIS_MERKLE_ROOT).unwrap());
+        header.extend(network.genesis_timestamp().to_le_bytes());
+        header.extend(network.genesis_bits().to_le_bytes());
+        header.extend(nonce.to_le_bytes());
+        header
+    }
+
+    #[test]
+    fn test_genesis_blocks() {
+        let nonces = [2_083_236_893, 414_098_458, 52_613_770, 2];
+        for (network, nonce) in BitcoinNetwork::ALL.into_iter().zip(nonces) {
+            let mut hash: [u8; 32] =
+                Sha256::digest(Sha256::digest(genesis_header(network, nonce))).into();
+            hash.reverse();
+            assert_eq!(hex::encode(hash), network.genesis_hash(), "{network:?}");
+            assert!(
+                expand(network.genesis_bits()) <= network.pow_limit(),
+                "{network:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_pow_limits() {
+        let mainnet = BitcoinNetwork::Mainnet.pow_limit();
+        assert_eq!(&mainnet[..5], &[0x00, 0x00, 0x00, 0x00, 0xff]);
+        assert_eq!(mainnet, BitcoinNetwork::Testnet.pow_limit());
+        // The signet genesis target is the pow limit
+        assert_eq!(
+            expand(BitcoinNetwork::Signet.genesis_bits()),
+            BitcoinNetwork::Signet.pow_limit()
+        );
+        assert_eq!(BitcoinNetwork::Regtest.pow_limit()[..2], [0x7f, 0xff]);
+    }
+
+    #[test]
+    fn test_activation_heights() {
+        let mainnet = BitcoinNetwork::Mainnet;
+        assert_eq!(mainnet.activation_height(BitcoinDeployment::Bip34), 227_931);
+        assert_eq!(
+            mainnet.activation_height(BitcoinDeployment::Segwit),
+            481_824
+        );
+        assert_eq!(
+            BitcoinNetwork::Testnet.activation_height(BitcoinDeployment::Csv),
+            770_112
+        );
+        assert_eq!(
+            BitcoinNetwork::Regtest.activation_height(BitcoinDeployment::Segwit),
+            0
+        );
+    }
+}
@@ -0,0 +1,25 @@
+//! Zcash consensus parameters shared by the Zoro crates: network magic, genesis block, network
+//! upgrade activation heights and branch IDs, FlyClient epochs and proof of work parameters.
+//! The Bitcoin networks have their own [`BitcoinNetwork`] parameters: magic, genesis block,
+//! buried soft fork activation heights, pow limits and difficulty adjustment.
+//!
+//! The crate has no dependencies and every parameter is a constant, so that the indexer, the
+//! verifier and the prover adapters agree on them without pulling in a full node library. The
+//! tables are checked when the crate is compiled: activation heights increase with the upgrades,
+//! branch IDs and network magics are distinct and the FlyClient epochs follow each other.
+
+mod bitcoin;
+mod network;
+mod pow;
+mod upgrade;
+
+pub use bitcoin::{
+    BitcoinDeployment, BitcoinNetwork, BITCOIN_DIFFICULTY_ADJUSTMENT_INTERVAL,
+    BITCOIN_POW_TARGET_SPACING, BITCOIN_POW_TARGET_TIMESPAN,
+};
+pub use network::Network;
+pub use pow::{
+    EQUIHASH_INDEX_BITS, EQUIHASH_INDICES, EQUIHASH_K, EQUIHASH_N, EQUIHASH_SOLUTION_SIZE,
+    GENESIS_BITS, MAINNET_POW_LIMIT, POW_AVERAGING_WINDOW, TESTNET_POW_LIMIT,
+};
+pub use upgrade::{FlyClientEpoch, NetworkUpgrade, FLYCLIENT_UPGRADES};
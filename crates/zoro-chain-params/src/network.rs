@@ -0,0 +1,189 @@
+//! Network identifiers, genesis blocks and activation heights.
+
+use crate::upgrade::{FlyClientEpoch, NetworkUpgrade, FLYCLIENT_UPGRADES};
+
+/// Zcash network
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Network {
+    #[default]
+    Mainnet,
+    Testnet,
+}
+
+/// Activation heights of [`NetworkUpgrade::ALL`] on mainnet
+const MAINNET_ACTIVATION_HEIGHTS: [u32; 7] = [
+    347_500, 419_200, 653_600, 903_000, 1_046_400, 1_687_104, 2_726_400,
+];
+/// Activation heights of [`NetworkUpgrade::ALL`] on testnet
+const TESTNET_ACTIVATION_HEIGHTS: [u32; 7] = [
+    207_500, 280_000, 584_000, 903_800, 1_028_500, 1_842_420, 2_976_000,
+];
+
+impl Network {
+    /// Message start bytes of the peer-to-peer protocol
+    pub const fn magic(self) -> [u8; 4] {
+        match self {
+            Network::Mainnet => [0x24, 0xe9, 0x27, 0x64],
+            Network::Testnet => [0xfa, 0x1a, 0xf9, 0xbf],
+        }
+    }
+
+    /// Hash of the genesis block, in display order
+    pub const fn genesis_hash(self) -> &'static str {
+        match self {
+            Network::Mainnet => "00040fe8ec8471911baa1db1266ea15dd06b4a8a5c453883c000b031973dce08",
+            Network::Testnet => "05a60a92d99d85997cce3b87616c089f6124d7342af37106edc76126334a2c38",
+        }
+    }
+
+    /// Timestamp of the genesis block
+    pub const fn genesis_timestamp(self) -> u32 {
+        match self {
+            Network::Mainnet => 1_477_641_360,
+            Network::Testnet => 1_477_648_033,
+        }
+    }
+
+    /// Height at which `upgrade` activates
+    pub const fn activation_height(self, upgrade: NetworkUpgrade) -> u32 {
+        self.activation_heights()[upgrade as usize]
+    }
+
+    /// Latest upgrade active at `height`, `None` before Overwinter
+    pub fn upgrade_at(self, height: u32) -> Option<NetworkUpgrade> {
+        NetworkUpgrade::ALL
+            .into_iter()
+            .rev()
+            .find(|upgrade| self.activation_height(*upgrade) <= height)
+    }
+
+    /// FlyClient epoch containing `height`, `None` before Heartwood
+    pub fn flyclient_epoch(self, height: u32) -> Option<FlyClientEpoch> {
+        let index = FLYCLIENT_UPGRADES
+            .iter()
+            .rposition(|upgrade| self.activation_height(*upgrade) <= height)?;
+        let upgrade = FLYCLIENT_UPGRADES[index];
+        Some(FlyClientEpoch {
+            upgrade,
+            start_height: self.activation_height(upgrade),
+            end_height: FLYCLIENT_UPGRADES
+                .get(index + 1)
+                .map(|next| self.activation_height(*next) - 1),
+        })
+    }
+
+    /// First height after `height` starting a FlyClient epoch, `None` in the current epoch
+    pub fn next_flyclient_epoch_start(self, height: u32) -> Option<u32> {
+        FLYCLIENT_UPGRADES
+            .into_iter()
+            .map(|upgrade| self.activation_height(upgrade))
+            .find(|activation| *activation > height)
+    }
+
+    const fn activation_heights(self) -> &'static [u32; 7] {
+        match self {
+            Network::Mainnet => &MAINNET_ACTIVATION_HEIGHTS,
+            Network::Testnet => &TESTNET_ACTIVATION_HEIGHTS,
+        }
+    }
+}
+
+const fn increasing(heights: &[u32]) -> bool {
+    let mut i = 1;
+    while i < heights.len() {
+        if heights[i - 1] >= heights[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+const _: () = assert!(
+    increasing(&MAINNET_ACTIVATION_HEIGHTS) && increasing(&TESTNET_ACTIVATION_HEIGHTS),
+    "Activation heights must increase with the upgrades"
+);
+const _: () = assert!(
+    MAINNET_ACTIVATION_HEIGHTS.len() == NetworkUpgrade::ALL.len(),
+    "Every upgrade must have an activation height"
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zebra_chain::parameters;
+
+    fn zebra_network(network: Network) -> parameters::Network {
+        match network {
+            Network::Mainnet => parameters::Network::Mainnet,
+            Network::Testnet => parameters::Network::new_default_testnet(),
+        }
+    }
+
+    fn zebra_upgrade(upgrade: NetworkUpgrade) -> parameters::NetworkUpgrade {
+        match upgrade {
+            NetworkUpgrade::Overwinter => parameters::NetworkUpgrade::Overwinter,
+            NetworkUpgrade::Sapling => parameters::NetworkUpgrade::Sapling,
+            NetworkUpgrade::Blossom => parameters::NetworkUpgrade::Blossom,
+            NetworkUpgrade::Heartwood => parameters::NetworkUpgrade::Heartwood,
+            NetworkUpgrade::Canopy => parameters::NetworkUpgrade::Canopy,
+            NetworkUpgrade::Nu5 => parameters::NetworkUpgrade::Nu5,
+            NetworkUpgrade::Nu6 => parameters::NetworkUpgrade::Nu6,
+        }
+    }
+
+    #[test]
+    fn test_parameters_match_zebra() {
+        for network in [Network::Mainnet, Network::Testnet] {
+            let zebra = zebra_network(network);
+            assert_eq!(network.magic(), zebra.magic().0);
+            assert_eq!(network.genesis_hash(), zebra.genesis_hash().to_string());
+            for upgrade in NetworkUpgrade::ALL {
+                let zebra_upgrade = zebra_upgrade(upgrade);
+                assert_eq!(
+                    Some(network.activation_height(upgrade)),
+                    zebra_upgrade
+                        .activation_height(&zebra)
+                        .map(|height| height.0),
+                    "{upgrade:?} on {network:?}"
+                );
+                assert_eq!(
+                    Some(upgrade.branch_id()),
+                    zebra_upgrade.branch_id().map(u32::from),
+                    "{upgrade:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_flyclient_epochs() {
+        let network = Network::Mainnet;
+        assert_eq!(network.flyclient_epoch(902_999), None);
+        let heartwood = network.flyclient_epoch(903_000).unwrap();
+        assert_eq!(heartwood.name(), "heartwood");
+        assert_eq!(heartwood.end_height, Some(1_046_399));
+        let canopy = network.flyclient_epoch(1_687_103).unwrap();
+        assert_eq!((canopy.name(), canopy.start_height), ("canopy", 1_046_400));
+        // Blocks from NU6 on stay in the NU5 epoch
+        let nu5 = network.flyclient_epoch(3_000_000).unwrap();
+        assert_eq!((nu5.name(), nu5.end_height), ("nu5", None));
+
+        assert_eq!(network.next_flyclient_epoch_start(0), Some(903_000));
+        assert_eq!(network.next_flyclient_epoch_start(903_000), Some(1_046_400));
+        assert_eq!(network.next_flyclient_epoch_start(1_687_104), None);
+    }
+
+    #[test]
+    fn test_upgrade_at() {
+        assert_eq!(Network::Mainnet.upgrade_at(347_499), None);
+        assert_eq!(
+            Network::Mainnet.upgrade_at(1_687_104),
+            Some(NetworkUpgrade::Nu5)
+        );
+        assert_eq!(
+            Network::Testnet.upgrade_at(1_687_104),
+            Some(NetworkUpgrade::Canopy)
+        );
+    }
+}
@@ -0,0 +1,63 @@
+//! Proof of work parameters: Equihash and difficulty adjustment.
+
+/// Equihash `n` parameter
+pub const EQUIHASH_N: usize = 200;
+/// Equihash `k` parameter
+pub const EQUIHASH_K: usize = 9;
+/// Number of bits of each index of a solution
+pub const EQUIHASH_INDEX_BITS: usize = EQUIHASH_N / (EQUIHASH_K + 1) + 1;
+/// Number of indices of a solution
+pub const EQUIHASH_INDICES: usize = 1 << EQUIHASH_K;
+/// Size in bytes of a minimally encoded solution
+pub const EQUIHASH_SOLUTION_SIZE: usize = EQUIHASH_INDICES * EQUIHASH_INDEX_BITS / 8;
+
+/// Number of blocks whose targets are averaged by the difficulty adjustment
+pub const POW_AVERAGING_WINDOW: usize = 17;
+
+/// Compact target (`nBits`) of the mainnet genesis block
+pub const GENESIS_BITS: u32 = 0x1f07_ffff;
+
+/// Easiest target allowed on mainnet, big-endian
+pub const MAINNET_POW_LIMIT: [u8; 32] = pow_limit(0x00, 0x07);
+/// Easiest target allowed on testnet, big-endian
+pub const TESTNET_POW_LIMIT: [u8; 32] = pow_limit(0x07, 0xff);
+
+/// `first || second || 0xff..`
+const fn pow_limit(first: u8, second: u8) -> [u8; 32] {
+    let mut limit = [0xff; 32];
+    limit[0] = first;
+    limit[1] = second;
+    limit
+}
+
+const _: () = assert!(
+    EQUIHASH_SOLUTION_SIZE == 1344 && EQUIHASH_INDEX_BITS == 21,
+    "Equihash (200, 9) solutions are 512 indices of 21 bits"
+);
+const _: () = assert!(
+    (EQUIHASH_INDICES * EQUIHASH_INDEX_BITS).is_multiple_of(8),
+    "Equihash solutions are whole bytes"
+);
+
+/// Expand a compact target, big-endian
+#[cfg(test)]
+pub(crate) fn expand(bits: u32) -> [u8; 32] {
+    let exponent = (bits >> 24) as usize;
+    let mantissa = (bits & 0x007f_ffff).to_be_bytes();
+    let mut target = [0; 32];
+    target[32 - exponent..35 - exponent].copy_from_slice(&mantissa[1..]);
+    target
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_genesis_target_within_pow_limit() {
+        let genesis = expand(GENESIS_BITS);
+        assert_eq!(&genesis[..3], &[0x00, 0x07, 0xff]);
+        assert!(genesis <= MAINNET_POW_LIMIT);
+        assert!(MAINNET_POW_LIMIT < TESTNET_POW_LIMIT);
+    }
+}
@@ -0,0 +1,112 @@
+//! Network upgrades and the FlyClient epochs they start.
+
+/// Zcash network upgrade, in activation order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum NetworkUpgrade {
+    Overwinter,
+    Sapling,
+    Blossom,
+    Heartwood,
+    Canopy,
+    Nu5,
+    Nu6,
+}
+
+impl NetworkUpgrade {
+    /// Every upgrade, in activation order
+    pub const ALL: [NetworkUpgrade; 7] = [
+        NetworkUpgrade::Overwinter,
+        NetworkUpgrade::Sapling,
+        NetworkUpgrade::Blossom,
+        NetworkUpgrade::Heartwood,
+        NetworkUpgrade::Canopy,
+        NetworkUpgrade::Nu5,
+        NetworkUpgrade::Nu6,
+    ];
+
+    /// Consensus branch ID, committed by transactions and FlyClient MMR nodes (ZIP 200)
+    pub const fn branch_id(self) -> u32 {
+        match self {
+            NetworkUpgrade::Overwinter => 0x5ba8_1b19,
+            NetworkUpgrade::Sapling => 0x76b8_09bb,
+            NetworkUpgrade::Blossom => 0x2bb4_0e60,
+            NetworkUpgrade::Heartwood => 0xf5b9_230b,
+            NetworkUpgrade::Canopy => 0xe9ff_75a6,
+            NetworkUpgrade::Nu5 => 0xc2d6_d0b4,
+            NetworkUpgrade::Nu6 => 0xc8e7_1055,
+        }
+    }
+
+    /// Lowercase name, as used in FlyClient MMR identifiers and proof `epoch` fields
+    pub const fn name(self) -> &'static str {
+        match self {
+            NetworkUpgrade::Overwinter => "overwinter",
+            NetworkUpgrade::Sapling => "sapling",
+            NetworkUpgrade::Blossom => "blossom",
+            NetworkUpgrade::Heartwood => "heartwood",
+            NetworkUpgrade::Canopy => "canopy",
+            NetworkUpgrade::Nu5 => "nu5",
+            NetworkUpgrade::Nu6 => "nu6",
+        }
+    }
+}
+
+/// Upgrades starting a FlyClient MMR maintained by the bridge node, in activation order.
+///
+/// ZIP 221 starts a new chain history tree at every upgrade from Heartwood on. The bridge keeps
+/// one MMR per upgrade up to NU5; blocks from NU6 on are still appended to the NU5 MMR.
+pub const FLYCLIENT_UPGRADES: [NetworkUpgrade; 3] = [
+    NetworkUpgrade::Heartwood,
+    NetworkUpgrade::Canopy,
+    NetworkUpgrade::Nu5,
+];
+
+/// Range of heights covered by a FlyClient MMR
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlyClientEpoch {
+    /// Upgrade starting the epoch
+    pub upgrade: NetworkUpgrade,
+    /// Height of the block at leaf 0
+    pub start_height: u32,
+    /// Height of the last block, `None` for the current epoch
+    pub end_height: Option<u32>,
+}
+
+impl FlyClientEpoch {
+    /// Name of the epoch, the one of its upgrade
+    pub const fn name(&self) -> &'static str {
+        self.upgrade.name()
+    }
+}
+
+const fn branch_ids_distinct() -> bool {
+    let mut i = 0;
+    while i < NetworkUpgrade::ALL.len() {
+        let mut j = i + 1;
+        while j < NetworkUpgrade::ALL.len() {
+            if NetworkUpgrade::ALL[i].branch_id() == NetworkUpgrade::ALL[j].branch_id() {
+                return false;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn flyclient_upgrades_ordered() -> bool {
+    let mut i = 1;
+    while i < FLYCLIENT_UPGRADES.len() {
+        if FLYCLIENT_UPGRADES[i - 1] as u8 + 1 != FLYCLIENT_UPGRADES[i] as u8 {
+            return false;
+        }
+        i += 1;
+    }
+    FLYCLIENT_UPGRADES[0] as u8 == NetworkUpgrade::Heartwood as u8
+}
+
+const _: () = assert!(branch_ids_distinct(), "Branch IDs must be distinct");
+const _: () = assert!(
+    flyclient_upgrades_ordered(),
+    "FlyClient epochs must follow each other from Heartwood"
+);
@@ -0,0 +1,41 @@
+//! Throughput of the MMR pair hashing backends: `cargo bench -p zoro-spv-verify --bench hashing`
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use zoro_spv_verify::hashing::{Backend, CpuFeatures};
+
+/// Nodes of the hashed level: 2^16 pairs
+const NODES: usize = 1 << 17;
+const ITERATIONS: u32 = 20;
+
+fn bench(name: &str, f: impl Fn()) -> Duration {
+    f();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let elapsed = start.elapsed() / ITERATIONS;
+    let pairs_per_second = (NODES / 2) as f64 / elapsed.as_secs_f64();
+    println!("{name:<10} {elapsed:>12.3?} per level, {pairs_per_second:>12.0} pairs/s");
+    elapsed
+}
+
+fn main() {
+    println!("{:?}", CpuFeatures::detect());
+    let nodes: Vec<[u8; 32]> = (0..NODES)
+        .map(|i| std::array::from_fn(|j| (i as u8).wrapping_mul(31).wrapping_add(j as u8)))
+        .collect();
+    let portable = bench("portable", || {
+        black_box(Backend::Portable.hash_level(black_box(&nodes)));
+    });
+    if Backend::detect() == Backend::Avx2 {
+        let avx2 = bench("avx2", || {
+            black_box(Backend::Avx2.hash_level(black_box(&nodes)));
+        });
+        println!(
+            "speedup    {:.2}x",
+            portable.as_secs_f64() / avx2.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,47 @@
+//! Embeds the git commit and build timestamp read by `build_info`.
+//!
+//! The timestamp is `SOURCE_DATE_EPOCH` if set, else the commit time, so that rebuilding a commit
+//! yields the same metadata. Builds outside of a git checkout can set `ZORO_GIT_COMMIT`.
+
+use std::process::Command;
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8(output.stdout).ok()?.trim().to_string())
+}
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=ZORO_GIT_COMMIT");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    if let Some(git_dir) = git(&["rev-parse", "--absolute-git-dir"]) {
+        for path in ["HEAD", "logs/HEAD", "index"] {
+            println!("cargo:rerun-if-changed={git_dir}/{path}");
+        }
+    }
+
+    let commit = std::env::var("ZORO_GIT_COMMIT").ok().or_else(|| {
+        let commit = git(&["rev-parse", "HEAD"])?;
+        let dirty = git(&["status", "--porcelain", "--untracked-files=no"])
+            .is_some_and(|status| !status.is_empty());
+        Some(if dirty {
+            format!("{commit}-dirty")
+        } else {
+            commit
+        })
+    });
+    let timestamp = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .or_else(|| git(&["log", "-1", "--format=%ct"]));
+
+    println!(
+        "cargo:rustc-env=ZORO_GIT_COMMIT={}",
+        commit.as_deref().unwrap_or("unknown")
+    );
+    println!(
+        "cargo:rustc-env=ZORO_BUILD_TIMESTAMP={}",
+        timestamp.as_deref().unwrap_or("0")
+    );
+}
@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zoro_spv_verify::fuzz::CairoOutputFelts;
+
+fuzz_target!(|felts: CairoOutputFelts| {
+    let _ = felts.decode();
+});
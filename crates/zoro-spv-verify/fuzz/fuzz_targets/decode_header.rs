@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zoro_spv_verify::fuzz::HeaderBytes;
+
+fuzz_target!(|bytes: HeaderBytes| {
+    let _ = bytes.decode();
+});
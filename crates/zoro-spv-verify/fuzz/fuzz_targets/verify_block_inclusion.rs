@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zoro_spv_verify::fuzz::HeaderBytes;
+use zoro_spv_verify::{verify_block_inclusion, BlockInclusionProof};
+
+fuzz_target!(|input: (HeaderBytes, BlockInclusionProof)| {
+    let (bytes, proof) = input;
+    let Ok(header) = bytes.decode() else {
+        return;
+    };
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("failed to build runtime");
+    let _ = runtime.block_on(verify_block_inclusion(&header, &proof));
+});
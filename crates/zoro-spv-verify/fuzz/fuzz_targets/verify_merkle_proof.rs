@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zoro_zcash_client::MerkleProof;
+
+fuzz_target!(|input: (MerkleProof, [u8; 32])| {
+    let (proof, tx_hash) = input;
+    let _ = proof.verify(tx_hash);
+    // Proofs must also survive a JSON round trip as served by the bridge node
+    if let Ok(json) = serde_json::to_string(&proof) {
+        let _ = serde_json::from_str::<MerkleProof>(&json);
+    }
+});
@@ -0,0 +1,259 @@
+//! Zcash transparent addresses (t-addrs) and their output scripts.
+//!
+//! A t-addr is the Base58Check encoding of a two-byte network prefix followed by a HASH160: of a
+//! public key for P2PKH addresses (`t1…` on mainnet, `tm…` on testnet), of a redeem script for P2SH
+//! addresses (`t3…` and `t2…`). Regtest uses the testnet prefixes, so regtest addresses decode as
+//! testnet ones.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail};
+use zebra_chain::parameters::NetworkKind;
+
+/// Mainnet P2PKH prefix, `t1`
+const MAINNET_PUBKEY_PREFIX: [u8; 2] = [0x1c, 0xb8];
+/// Mainnet P2SH prefix, `t3`
+const MAINNET_SCRIPT_PREFIX: [u8; 2] = [0x1c, 0xbd];
+/// Testnet P2PKH prefix, `tm`
+const TESTNET_PUBKEY_PREFIX: [u8; 2] = [0x1d, 0x25];
+/// Testnet P2SH prefix, `t2`
+const TESTNET_SCRIPT_PREFIX: [u8; 2] = [0x1c, 0xba];
+
+const OP_DUP: u8 = 0x76;
+const OP_HASH160: u8 = 0xa9;
+const OP_EQUAL: u8 = 0x87;
+const OP_EQUALVERIFY: u8 = 0x88;
+const OP_CHECKSIG: u8 = 0xac;
+/// Push of the 20 bytes of a HASH160
+const PUSH_20: u8 = 0x14;
+
+/// What the HASH160 of a transparent address commits to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressKind {
+    /// Pay to public key hash
+    PubKeyHash,
+    /// Pay to script hash
+    ScriptHash,
+}
+
+/// Zcash transparent address
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct TransparentAddress {
+    network: NetworkKind,
+    kind: AddressKind,
+    hash: [u8; 20],
+}
+
+impl TransparentAddress {
+    pub fn new(network: NetworkKind, kind: AddressKind, hash: [u8; 20]) -> Self {
+        Self {
+            network: normalize(network),
+            kind,
+            hash,
+        }
+    }
+
+    /// Decode `s`, failing unless it is an address of `network`
+    pub fn parse_for(s: &str, network: NetworkKind) -> anyhow::Result<Self> {
+        let address: Self = s.parse()?;
+        if address.network != normalize(network) {
+            bail!(
+                "Address {s} is a {:?} address, expected a {:?} one",
+                address.network,
+                network
+            );
+        }
+        Ok(address)
+    }
+
+    /// Address paid by an output script, `None` unless it is a standard P2PKH or P2SH script
+    pub fn from_script(network: NetworkKind, script: &[u8]) -> Option<Self> {
+        match script {
+            [OP_DUP, OP_HASH160, PUSH_20, hash @ .., OP_EQUALVERIFY, OP_CHECKSIG]
+                if hash.len() == 20 =>
+            {
+                Some(Self::new(
+                    network,
+                    AddressKind::PubKeyHash,
+                    hash.try_into().ok()?,
+                ))
+            }
+            [OP_HASH160, PUSH_20, hash @ .., OP_EQUAL] if hash.len() == 20 => Some(Self::new(
+                network,
+                AddressKind::ScriptHash,
+                hash.try_into().ok()?,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Network of the address, testnet for regtest addresses
+    pub fn network(&self) -> NetworkKind {
+        self.network
+    }
+
+    pub fn kind(&self) -> AddressKind {
+        self.kind
+    }
+
+    pub fn hash(&self) -> &[u8; 20] {
+        &self.hash
+    }
+
+    /// Output script paying the address
+    pub fn script_pubkey(&self) -> Vec<u8> {
+        match self.kind {
+            AddressKind::PubKeyHash => [
+                &[OP_DUP, OP_HASH160, PUSH_20][..],
+                &self.hash,
+                &[OP_EQUALVERIFY, OP_CHECKSIG],
+            ]
+            .concat(),
+            AddressKind::ScriptHash => {
+                [&[OP_HASH160, PUSH_20][..], &self.hash, &[OP_EQUAL]].concat()
+            }
+        }
+    }
+
+    /// Whether `script` pays the address
+    pub fn matches_script(&self, script: &[u8]) -> bool {
+        Self::from_script(self.network, script).as_ref() == Some(self)
+    }
+
+    fn prefix(&self) -> [u8; 2] {
+        match (self.network, self.kind) {
+            (NetworkKind::Mainnet, AddressKind::PubKeyHash) => MAINNET_PUBKEY_PREFIX,
+            (NetworkKind::Mainnet, AddressKind::ScriptHash) => MAINNET_SCRIPT_PREFIX,
+            (_, AddressKind::PubKeyHash) => TESTNET_PUBKEY_PREFIX,
+            (_, AddressKind::ScriptHash) => TESTNET_SCRIPT_PREFIX,
+        }
+    }
+}
+
+/// Regtest shares the testnet prefixes, so addresses only distinguish mainnet and testnet
+fn normalize(network: NetworkKind) -> NetworkKind {
+    match network {
+        NetworkKind::Mainnet => NetworkKind::Mainnet,
+        _ => NetworkKind::Testnet,
+    }
+}
+
+impl FromStr for TransparentAddress {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let payload = bs58::decode(s.trim())
+            .with_check(None)
+            .into_vec()
+            .map_err(|e| anyhow!("Invalid transparent address {s}: {e}"))?;
+        let (prefix, hash) = payload
+            .split_first_chunk::<2>()
+            .ok_or_else(|| anyhow!("Invalid transparent address {s}: too short"))?;
+        let hash = hash
+            .try_into()
+            .map_err(|_| anyhow!("Invalid transparent address {s}: expected a 20-byte hash"))?;
+        let (network, kind) = match *prefix {
+            MAINNET_PUBKEY_PREFIX => (NetworkKind::Mainnet, AddressKind::PubKeyHash),
+            MAINNET_SCRIPT_PREFIX => (NetworkKind::Mainnet, AddressKind::ScriptHash),
+            TESTNET_PUBKEY_PREFIX => (NetworkKind::Testnet, AddressKind::PubKeyHash),
+            TESTNET_SCRIPT_PREFIX => (NetworkKind::Testnet, AddressKind::ScriptHash),
+            _ => bail!(
+                "Invalid transparent address {s}: unknown prefix {}",
+                hex::encode(prefix)
+            ),
+        };
+        Ok(Self::new(network, kind, hash))
+    }
+}
+
+impl fmt::Display for TransparentAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let payload = [&self.prefix()[..], &self.hash].concat();
+        f.write_str(&bs58::encode(payload).with_check().into_string())
+    }
+}
+
+impl fmt::Debug for TransparentAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "TransparentAddress({self})")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_address_prefixes_round_trip() {
+        let cases = [
+            (NetworkKind::Mainnet, AddressKind::PubKeyHash, "t1"),
+            (NetworkKind::Mainnet, AddressKind::ScriptHash, "t3"),
+            (NetworkKind::Testnet, AddressKind::PubKeyHash, "tm"),
+            (NetworkKind::Testnet, AddressKind::ScriptHash, "t2"),
+        ];
+        for (network, kind, prefix) in cases {
+            let address = TransparentAddress::new(network, kind, [0x42; 20]);
+            let encoded = address.to_string();
+            assert!(encoded.starts_with(prefix), "{encoded} for {prefix}");
+            assert_eq!(encoded.parse::<TransparentAddress>().unwrap(), address);
+            assert!(TransparentAddress::parse_for(&encoded, network).is_ok());
+        }
+
+        let zero: TransparentAddress = "t1Hsc1LR8yKnbbe3twRp88p6vFfC5t7DLbs".parse().unwrap();
+        assert_eq!(zero.kind(), AddressKind::PubKeyHash);
+        assert_eq!(zero.hash(), &[0; 20]);
+
+        let regtest =
+            TransparentAddress::new(NetworkKind::Regtest, AddressKind::PubKeyHash, [1; 20]);
+        assert_eq!(regtest.network(), NetworkKind::Testnet);
+        assert!(TransparentAddress::parse_for(&regtest.to_string(), NetworkKind::Regtest).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_addresses_are_rejected() {
+        let address =
+            TransparentAddress::new(NetworkKind::Mainnet, AddressKind::PubKeyHash, [7; 20])
+                .to_string();
+        assert!(TransparentAddress::parse_for(&address, NetworkKind::Testnet).is_err());
+
+        let mut corrupted = address.clone().into_bytes();
+        let last = corrupted.last_mut().unwrap();
+        *last = if *last == b'1' { b'2' } else { b'1' };
+        assert!(String::from_utf8(corrupted)
+            .unwrap()
+            .parse::<TransparentAddress>()
+            .is_err());
+
+        // A valid Base58Check payload with a Bitcoin version byte
+        let bitcoin = bs58::encode([[0u8][..].as_ref(), &[7; 20]].concat())
+            .with_check()
+            .into_string();
+        assert!(bitcoin.parse::<TransparentAddress>().is_err());
+        assert!("not an address".parse::<TransparentAddress>().is_err());
+    }
+
+    #[test]
+    fn test_scripts_match_addresses() {
+        let hash = [0xab; 20];
+        let p2pkh = TransparentAddress::new(NetworkKind::Mainnet, AddressKind::PubKeyHash, hash);
+        let script = p2pkh.script_pubkey();
+        assert_eq!(
+            hex::encode(&script),
+            format!("76a914{}88ac", hex::encode(hash))
+        );
+        assert_eq!(
+            TransparentAddress::from_script(NetworkKind::Mainnet, &script),
+            Some(p2pkh)
+        );
+        assert!(p2pkh.matches_script(&script));
+
+        let p2sh = TransparentAddress::new(NetworkKind::Mainnet, AddressKind::ScriptHash, hash);
+        assert_eq!(
+            hex::encode(p2sh.script_pubkey()),
+            format!("a914{}87", hex::encode(hash))
+        );
+        assert!(!p2sh.matches_script(&script));
+        assert!(TransparentAddress::from_script(NetworkKind::Mainnet, &script[1..]).is_none());
+    }
+}
@@ -0,0 +1,331 @@
+//! Append-only archive of verification results.
+//!
+//! The archive is a JSONL file with one [`ArchiveRecord`] per verified proof: the canonical
+//! digest of the proof, the result or the error it was rejected with, the verifier config and the
+//! build of the verifier. Records are hash-chained: each one holds the hash of the previous record
+//! (`prev_hash`, [`GENESIS_HASH`] for the first one) and its own `hash`, the Blake2s digest of the
+//! canonical JSON of the record without `hash`. Rewriting, removing or reordering past records
+//! breaks the chain, which [`verify_archive`] reports, so auditors get tamper evidence over the
+//! verification decisions (short of the whole archive being rewritten from the altered record
+//! on; publishing the head hash now and then pins it).
+//!
+//! Hashes are computed over the records as written rather than as decoded, so archives stay
+//! verifiable by later builds whose config or build info have other fields.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::build_info::BuildInfo;
+use crate::canonical::CanonicalSerialize;
+use crate::verify::{VerificationResult, VerifierConfig};
+
+/// Version of the record format
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// `prev_hash` of the first record
+pub const GENESIS_HASH: &str = "0x0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Result of a verification, as archived
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum ArchivedResult {
+    /// The proof passed every check
+    Verified {
+        /// Transaction hash (display order)
+        transaction_hash: String,
+        /// Hash of the block containing the transaction (display order)
+        block_hash: String,
+        block_height: u32,
+        chain_height: u32,
+        confirmations: u32,
+        /// Whether the chain state was verified against its STARK proof
+        chain_state_verified: bool,
+    },
+    /// The proof was rejected
+    Rejected {
+        /// Error the proof was rejected with
+        error: String,
+    },
+}
+
+impl ArchivedResult {
+    /// Archived form of the outcome of a verification
+    pub fn of(outcome: &anyhow::Result<VerificationResult>) -> Self {
+        match outcome {
+            Ok(result) => result.into(),
+            Err(error) => ArchivedResult::Rejected {
+                error: format!("{error:#}"),
+            },
+        }
+    }
+}
+
+impl From<&VerificationResult> for ArchivedResult {
+    fn from(result: &VerificationResult) -> Self {
+        ArchivedResult::Verified {
+            transaction_hash: result.transaction_hash.to_string(),
+            block_hash: result.block_hash.to_string(),
+            block_height: result.block_height,
+            chain_height: result.chain_height,
+            confirmations: result.confirmations,
+            chain_state_verified: result.chain_state_verified,
+        }
+    }
+}
+
+/// One line of the archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveRecord {
+    /// Version of the record format
+    pub version: u32,
+    /// Position of the record in the archive, from 0
+    pub seq: u64,
+    /// Hash of the previous record, [`GENESIS_HASH`] for the first one
+    pub prev_hash: String,
+    /// Unix timestamp of the verification
+    pub timestamp: u64,
+    /// Canonical digest of the verified proof
+    pub proof_digest: String,
+    pub result: ArchivedResult,
+    /// Verifier config the proof was verified with
+    pub config: VerifierConfig,
+    /// Build of the verifier
+    pub build: Value,
+    /// Hash of the record: Blake2s digest of its canonical JSON without `hash`
+    pub hash: String,
+}
+
+impl CanonicalSerialize for Map<String, Value> {}
+
+/// Hash of a record as written, ignoring its `hash` field
+fn record_hash(record: &Map<String, Value>) -> anyhow::Result<String> {
+    let mut unhashed = record.clone();
+    unhashed.remove("hash");
+    unhashed.canonical_digest()
+}
+
+/// Outcome of [`verify_archive`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ArchiveCheck {
+    /// Number of records
+    pub records: u64,
+    /// Hash of the last record, [`GENESIS_HASH`] for an empty archive
+    pub head: String,
+}
+
+/// Check the hash chain of archive contents
+pub fn check_records(data: &str) -> anyhow::Result<ArchiveCheck> {
+    let mut check = ArchiveCheck {
+        records: 0,
+        head: GENESIS_HASH.to_string(),
+    };
+    for (index, line) in data.lines().enumerate() {
+        let line_number = index + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: Map<String, Value> = serde_json::from_str(line)
+            .map_err(|e| anyhow::anyhow!("Line {line_number}: invalid record: {e}"))?;
+        let field = |name: &str| {
+            record
+                .get(name)
+                .ok_or_else(|| anyhow::anyhow!("Line {line_number}: record without `{name}`"))
+        };
+        if field("seq")?.as_u64() != Some(check.records) {
+            anyhow::bail!(
+                "Line {line_number}: expected record {}, found {}",
+                check.records,
+                field("seq")?
+            );
+        }
+        if field("prev_hash")?.as_str() != Some(check.head.as_str()) {
+            anyhow::bail!(
+                "Line {line_number}: record {} does not follow {}, a previous record was altered \
+                 or removed",
+                check.records,
+                check.head
+            );
+        }
+        let hash = record_hash(&record)?;
+        if field("hash")?.as_str() != Some(hash.as_str()) {
+            anyhow::bail!(
+                "Line {line_number}: record {} was altered, its hash is {hash}",
+                check.records
+            );
+        }
+        check.records += 1;
+        check.head = hash;
+    }
+    Ok(check)
+}
+
+/// Check the hash chain of the archive at `path`
+pub fn verify_archive(path: &Path) -> anyhow::Result<ArchiveCheck> {
+    let data = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read archive {}: {e}", path.display()))?;
+    check_records(&data)
+}
+
+/// Appends records to an archive file.
+///
+/// Only one writer may append to an archive at a time, concurrent writers would fork the chain.
+pub struct ArchiveWriter {
+    file: File,
+    next_seq: u64,
+    head: String,
+}
+
+impl ArchiveWriter {
+    /// Open the archive at `path` for appending, creating it if missing. The existing records are
+    /// checked first, so that a broken chain is not extended.
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let check = if path.exists() {
+            verify_archive(path)?
+        } else {
+            check_records("")?
+        };
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| anyhow::anyhow!("Failed to open archive {}: {e}", path.display()))?;
+        Ok(Self {
+            file,
+            next_seq: check.records,
+            head: check.head,
+        })
+    }
+
+    /// Hash of the last record, [`GENESIS_HASH`] for an empty archive
+    pub fn head(&self) -> &str {
+        &self.head
+    }
+
+    /// Append the record of a verification, synced to disk before returning
+    pub fn append(
+        &mut self,
+        proof_digest: String,
+        result: ArchivedResult,
+        config: &VerifierConfig,
+        build: &BuildInfo,
+    ) -> anyhow::Result<ArchiveRecord> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let mut record = ArchiveRecord {
+            version: ARCHIVE_VERSION,
+            seq: self.next_seq,
+            prev_hash: self.head.clone(),
+            timestamp,
+            proof_digest,
+            result,
+            config: config.clone(),
+            build: serde_json::to_value(build)?,
+            hash: String::new(),
+        };
+        let Value::Object(mut fields) = serde_json::to_value(&record)? else {
+            anyhow::bail!("Archive record is not a JSON object");
+        };
+        record.hash = record_hash(&fields)?;
+        fields.insert("hash".to_string(), Value::String(record.hash.clone()));
+
+        let mut line = serde_json::to_vec(&fields)?;
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        self.file.sync_data()?;
+        self.next_seq += 1;
+        self.head = record.hash.clone();
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_archive(name: &str, records: usize) -> std::path::PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("zoro-archive-{name}-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let build = BuildInfo::new("zoro-spv-verify", "0.1.0", &[]);
+        let mut writer = ArchiveWriter::open(&path).unwrap();
+        for i in 0..records {
+            let result = ArchivedResult::Rejected {
+                error: format!("Proof {i} is invalid"),
+            };
+            let digest = format!("0x{i:064x}");
+            writer
+                .append(digest, result, &VerifierConfig::default(), &build)
+                .unwrap();
+        }
+        path
+    }
+
+    #[test]
+    fn test_append_and_verify() {
+        let path = write_archive("append", 2);
+        let check = verify_archive(&path).unwrap();
+        assert_eq!(check.records, 2);
+
+        // Reopening continues the chain
+        let mut writer = ArchiveWriter::open(&path).unwrap();
+        assert_eq!(writer.head(), check.head);
+        let build = BuildInfo::new("zoro-spv-verify", "0.1.0", &[]);
+        let record = writer
+            .append(
+                "0x00".to_string(),
+                ArchivedResult::Rejected {
+                    error: "Cancelled".to_string(),
+                },
+                &VerifierConfig::default(),
+                &build,
+            )
+            .unwrap();
+        assert_eq!(record.seq, 2);
+        assert_eq!(record.prev_hash, check.head);
+        let check = verify_archive(&path).unwrap();
+        assert_eq!(check.records, 3);
+        assert_eq!(check.head, record.hash);
+
+        let data = std::fs::read_to_string(&path).unwrap();
+        let decoded: ArchiveRecord = serde_json::from_str(data.lines().last().unwrap()).unwrap();
+        assert_eq!(decoded.result, record.result);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_tampering_detected() {
+        let path = write_archive("tamper", 3);
+        let data = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = data.lines().collect();
+
+        // Altered record
+        let altered = data.replace("Proof 1 is invalid", "Proof 1 is valid");
+        let error = check_records(&altered).unwrap_err().to_string();
+        assert!(error.contains("Line 2: record 1 was altered"), "{error}");
+
+        // Altered record with its hash recomputed
+        let mut record: Map<String, Value> = serde_json::from_str(lines[1]).unwrap();
+        record.insert("timestamp".to_string(), Value::from(0));
+        let hash = record_hash(&record).unwrap();
+        record.insert("hash".to_string(), Value::String(hash));
+        let rehashed = [lines[0], &serde_json::to_string(&record).unwrap(), lines[2]].join("\n");
+        let error = check_records(&rehashed).unwrap_err().to_string();
+        assert!(error.contains("Line 3"), "{error}");
+
+        // Removed record
+        let removed = [lines[0], lines[2]].join("\n");
+        let error = check_records(&removed).unwrap_err().to_string();
+        assert!(error.contains("expected record 1, found 2"), "{error}");
+
+        // Truncated archive still verifies, up to its last record
+        let check = check_records(&lines[..2].join("\n")).unwrap();
+        assert_eq!(check.records, 2);
+    }
+}
@@ -0,0 +1,84 @@
+//! Conformance runner: verdicts of this verifier on a directory of proof fixtures
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+use zoro_spv_verify::conformance;
+
+#[derive(Parser)]
+#[command(name = "conformance")]
+#[command(about = "Run proof fixtures through the Rust verifier and compare verdicts")]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Write the reference fixtures
+    Generate {
+        /// Output directory
+        #[arg(short, long, default_value = "fixtures")]
+        out: PathBuf,
+    },
+
+    /// Verify every fixture of a directory, failing if a verdict differs from the expected one
+    Run {
+        /// Fixture directory
+        dir: PathBuf,
+
+        /// Print the outcomes as JSON lines instead of text
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("Error: {e:#}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run(cli: Cli) -> anyhow::Result<bool> {
+    match cli.command {
+        Commands::Generate { out } => {
+            let fixtures = conformance::generate()?;
+            conformance::write(&fixtures, &out)?;
+            println!("Wrote {} fixtures to {}", fixtures.len(), out.display());
+            Ok(true)
+        }
+        Commands::Run { dir, json } => {
+            let outcomes = conformance::run(&dir)?;
+            let failed = outcomes.iter().filter(|outcome| !outcome.passed()).count();
+            for outcome in &outcomes {
+                if json {
+                    println!("{}", serde_json::to_string(outcome)?);
+                    continue;
+                }
+                match outcome.expected {
+                    _ if outcome.passed() => {
+                        println!("PASS {} ({})", outcome.fixture, outcome.actual)
+                    }
+                    Some(expected) => println!(
+                        "FAIL {} (expected {}, got {})",
+                        outcome.fixture, expected, outcome.actual
+                    ),
+                    None => println!(
+                        "FAIL {} (no expected verdict, got {})",
+                        outcome.fixture, outcome.actual
+                    ),
+                }
+            }
+            if !json {
+                println!("{} passed, {} failed", outcomes.len() - failed, failed);
+            }
+            Ok(failed == 0)
+        }
+    }
+}
@@ -0,0 +1,572 @@
+//! Blake2s block header MMR in the sparse-roots representation used by the Cairo programs.
+//!
+//! This mirrors `utils::mmr` in the Cairo packages: the accumulator is a list of roots indexed by
+//! tree height, where `None` marks a gap and the list always ends with `None`. Digests are
+//! 256-bit values in the same word order as the Cairo `Blake2sDigest` to `u256` conversion, so
+//! they can be passed to the Cairo program as-is. A zero digest encodes `None`, matching the
+//! Cairo `Serde` implementation.
+//!
+//! [`BlockMmr`] additionally keeps the leaves, so that it can prove the inclusion of a range of
+//! consecutive block headers with a single proof.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use stwo::core::vcs::blake2_hash::Blake2sHasher;
+use zebra_chain::block::Header;
+use zebra_chain::serialization::ZcashSerialize;
+
+use crate::hashing::{hash_level, tree_root};
+
+/// Blake2s digest as a big-endian 256-bit value (Cairo `u256` word order)
+pub type Blake2sDigest = [u8; 32];
+
+/// Compute the MMR leaf for a block header (Cairo `Header::blake2s_digest`)
+pub fn header_leaf(header: &Header) -> anyhow::Result<Blake2sDigest> {
+    Ok(header_bytes_leaf(&header.zcash_serialize_to_vec()?))
+}
+
+/// Compute the MMR leaf for a serialized block header.
+///
+/// Cairo packs the header bytes into big-endian `u32` words (the last, partial word is
+/// right-aligned) and Blake2s reads each word in little-endian order, hence the byte swaps.
+pub fn header_bytes_leaf(bytes: &[u8]) -> Blake2sDigest {
+    let mut message = Vec::with_capacity(bytes.len().div_ceil(4) * 4);
+    for chunk in bytes.chunks(4) {
+        let mut word = [0u8; 4];
+        word[4 - chunk.len()..].copy_from_slice(chunk);
+        message.extend(u32::from_be_bytes(word).to_le_bytes());
+    }
+    blake2s(&message)
+}
+
+/// Hash two MMR nodes (Cairo `blake2s_hash_pair`)
+pub fn hash_pair(left: &Blake2sDigest, right: &Blake2sDigest) -> Blake2sDigest {
+    let mut message = Vec::with_capacity(64);
+    message.extend(swap_words(left));
+    message.extend(swap_words(right));
+    blake2s(&message)
+}
+
+fn blake2s(message: &[u8]) -> Blake2sDigest {
+    let mut hasher = Blake2sHasher::new();
+    hasher.update(message);
+    swap_words(&hasher.finalize().0)
+}
+
+/// Convert between Blake2s output bytes and the Cairo `u256` representation
+fn swap_words(bytes: &[u8; 32]) -> [u8; 32] {
+    let mut out = *bytes;
+    out.chunks_exact_mut(4).for_each(|chunk| chunk.reverse());
+    out
+}
+
+/// Maximum number of sparse roots, the trailing empty root included: the leaf count of the roots
+/// then fits in a `u64`
+pub const MAX_SPARSE_ROOTS: usize = 64;
+
+/// Block header MMR roots in the Cairo-compatible sparse representation.
+///
+/// Serialized as a list of `0x`-prefixed 64-digit hex strings, with the zero digest for gaps.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "Vec<String>", try_from = "Vec<String>")]
+pub struct SparseRoots {
+    /// Roots of the perfect binary trees, indexed by height; `None` means a gap
+    pub roots: Vec<Option<Blake2sDigest>>,
+}
+
+impl Default for SparseRoots {
+    fn default() -> Self {
+        Self { roots: vec![None] }
+    }
+}
+
+impl SparseRoots {
+    /// Add a leaf to the accumulator (Cairo `MMRTrait::add`)
+    pub fn add(&mut self, leaf: Blake2sDigest) {
+        let mut node = leaf;
+        for root in self.roots.iter_mut() {
+            match root.take() {
+                Some(existing) => node = hash_pair(&existing, &node),
+                None => {
+                    *root = Some(node);
+                    break;
+                }
+            }
+        }
+        if self.roots.last().is_some_and(Option::is_some) {
+            self.roots.push(None);
+        }
+    }
+
+    /// Number of leaves added to the accumulator, an error if it does not fit in a `u64`
+    pub fn leaf_count(&self) -> anyhow::Result<u64> {
+        self.roots
+            .iter()
+            .enumerate()
+            .filter(|(_, root)| root.is_some())
+            .try_fold(0u64, |count, (height, _)| {
+                u32::try_from(height)
+                    .ok()
+                    .and_then(|height| 1u64.checked_shl(height))
+                    .and_then(|leaves| count.checked_add(leaves))
+            })
+            .ok_or_else(|| {
+                anyhow::anyhow!("Leaf count of {} sparse roots overflows", self.roots.len())
+            })
+    }
+
+    /// Encode the roots as concatenated digests, with the zero digest for gaps
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.roots
+            .iter()
+            .flat_map(|root| root.unwrap_or([0; 32]))
+            .collect()
+    }
+
+    /// Decode roots encoded with [`SparseRoots::to_bytes`]
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        if bytes.is_empty() || bytes.len() % 32 != 0 {
+            anyhow::bail!("Invalid sparse roots length: {}", bytes.len());
+        }
+        let roots = bytes
+            .chunks_exact(32)
+            .map(|chunk| {
+                let digest: Blake2sDigest = chunk.try_into()?;
+                Ok((digest != [0; 32]).then_some(digest))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Self::from_roots(roots)
+    }
+
+    fn from_roots(roots: Vec<Option<Blake2sDigest>>) -> anyhow::Result<Self> {
+        if roots.len() > MAX_SPARSE_ROOTS {
+            anyhow::bail!(
+                "Too many sparse roots: {} (max {})",
+                roots.len(),
+                MAX_SPARSE_ROOTS
+            );
+        }
+        if roots.last() != Some(&None) {
+            anyhow::bail!("Sparse roots must end with an empty root");
+        }
+        Ok(Self { roots })
+    }
+}
+
+impl From<SparseRoots> for Vec<String> {
+    fn from(value: SparseRoots) -> Self {
+        value
+            .roots
+            .iter()
+            .map(|root| format!("0x{}", hex::encode(root.unwrap_or([0; 32]))))
+            .collect()
+    }
+}
+
+impl TryFrom<Vec<String>> for SparseRoots {
+    type Error = anyhow::Error;
+
+    fn try_from(value: Vec<String>) -> Result<Self, Self::Error> {
+        let roots = value
+            .iter()
+            .map(|root| {
+                let digest = crate::untrusted::decode_hash32(root)?;
+                Ok((digest != [0; 32]).then_some(digest))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Self::from_roots(roots)
+    }
+}
+
+/// Block header MMR keeping all leaves, so that inclusion proofs can be generated.
+///
+/// Leaf `i` is the header of the block at height `i`, as the MMR starts at genesis.
+#[derive(Debug, Clone, Default)]
+pub struct BlockMmr {
+    leaves: Vec<Blake2sDigest>,
+    roots: SparseRoots,
+}
+
+impl BlockMmr {
+    /// Add a block header
+    pub fn add_block_header(&mut self, header: &Header) -> anyhow::Result<()> {
+        self.add(header_leaf(header)?);
+        Ok(())
+    }
+
+    /// Add consecutive block headers.
+    ///
+    /// All leaves are hashed before the MMR is updated, so it is left unchanged if any header
+    /// fails to encode.
+    pub fn add_block_headers(&mut self, headers: &[Header]) -> anyhow::Result<()> {
+        let leaves = headers
+            .iter()
+            .map(header_leaf)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        self.leaves.reserve(leaves.len());
+        for leaf in leaves {
+            self.add(leaf);
+        }
+        Ok(())
+    }
+
+    /// Add a leaf
+    pub fn add(&mut self, leaf: Blake2sDigest) {
+        self.roots.add(leaf);
+        self.leaves.push(leaf);
+    }
+
+    /// Number of leaves
+    pub fn leaf_count(&self) -> u64 {
+        self.leaves.len() as u64
+    }
+
+    /// Current roots
+    pub fn roots(&self) -> &SparseRoots {
+        &self.roots
+    }
+
+    /// Generate a proof that the block headers at heights `start_height..=end_height` are
+    /// included in the MMR.
+    ///
+    /// Siblings shared by the leaves of the range are included once, so the proof holds at most
+    /// two digests per tree level instead of one path per leaf.
+    pub fn generate_range_proof(
+        &self,
+        start_height: u32,
+        end_height: u32,
+    ) -> anyhow::Result<RangeProof> {
+        let leaf_count = self.leaf_count();
+        if start_height > end_height || u64::from(end_height) >= leaf_count {
+            anyhow::bail!(
+                "Invalid range {}..={} for an MMR with {} leaves",
+                start_height,
+                end_height,
+                leaf_count
+            );
+        }
+        let mut siblings = Vec::new();
+        for tree in trees(leaf_count) {
+            let Some((start, end)) = tree.local_range(start_height, end_height) else {
+                continue;
+            };
+            let leaves = &self.leaves[tree.offset as usize..][..1 << tree.height];
+            let (mut left, mut right) = (start, end);
+            for level in 0..tree.height {
+                let width = 1usize << level;
+                if left % 2 == 1 {
+                    siblings.push(tree_root(&leaves[(left - 1) * width..][..width]));
+                }
+                if right % 2 == 0 {
+                    siblings.push(tree_root(&leaves[(right + 1) * width..][..width]));
+                }
+                left /= 2;
+                right /= 2;
+            }
+        }
+        Ok(RangeProof {
+            leaf_count,
+            start_height,
+            end_height,
+            siblings,
+        })
+    }
+}
+
+/// Proof that a range of consecutive block headers is included in the block header MMR
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct RangeProof {
+    /// Number of leaves of the MMR the proof was generated against
+    #[serde(alias = "leafCount")]
+    pub leaf_count: u64,
+    /// Height of the first block of the range
+    #[serde(alias = "startHeight")]
+    pub start_height: u32,
+    /// Height of the last block of the range (inclusive)
+    #[serde(alias = "endHeight")]
+    pub end_height: u32,
+    /// Sibling digests, tree by tree from the oldest, bottom-up and left before right within
+    /// a level
+    #[serde(with = "digest_list")]
+    #[schemars(with = "Vec<String>")]
+    pub siblings: Vec<Blake2sDigest>,
+}
+
+/// Verify that `leaves` are the leaves at heights `proof.start_height..=proof.end_height` of
+/// the MMR with the given roots
+pub fn verify_range_proof(
+    roots: &SparseRoots,
+    leaves: &[Blake2sDigest],
+    proof: &RangeProof,
+) -> anyhow::Result<()> {
+    let RangeProof {
+        leaf_count,
+        start_height,
+        end_height,
+        ..
+    } = *proof;
+    let roots_leaf_count = roots.leaf_count()?;
+    if leaf_count != roots_leaf_count {
+        anyhow::bail!(
+            "Range proof is for {} leaves, the MMR has {}",
+            leaf_count,
+            roots_leaf_count
+        );
+    }
+    if start_height > end_height || u64::from(end_height) >= leaf_count {
+        anyhow::bail!(
+            "Invalid range {}..={} for an MMR with {} leaves",
+            start_height,
+            end_height,
+            leaf_count
+        );
+    }
+    if leaves.len() != (end_height - start_height) as usize + 1 {
+        anyhow::bail!(
+            "Expected {} leaves for range {}..={}, got {}",
+            end_height - start_height + 1,
+            start_height,
+            end_height,
+            leaves.len()
+        );
+    }
+
+    let mut siblings = proof.siblings.iter();
+    let mut next_sibling = || {
+        siblings
+            .next()
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("Range proof is missing siblings"))
+    };
+    let mut leaves = leaves;
+    for tree in trees(leaf_count) {
+        let Some((start, end)) = tree.local_range(start_height, end_height) else {
+            continue;
+        };
+        let (tree_leaves, rest) = leaves.split_at(end - start + 1);
+        leaves = rest;
+
+        let mut nodes = tree_leaves.to_vec();
+        let (mut left, mut right) = (start, end);
+        for _ in 0..tree.height {
+            if left % 2 == 1 {
+                nodes.insert(0, next_sibling()?);
+                left -= 1;
+            }
+            if right % 2 == 0 {
+                nodes.push(next_sibling()?);
+            }
+            nodes = hash_level(&nodes);
+            left /= 2;
+            right /= 2;
+        }
+        if roots.roots.get(tree.height as usize).copied().flatten() != Some(nodes[0]) {
+            anyhow::bail!(
+                "Range proof does not match the MMR root of height {}",
+                tree.height
+            );
+        }
+    }
+    if next_sibling().is_ok() {
+        anyhow::bail!("Range proof has unused siblings");
+    }
+    Ok(())
+}
+
+/// Perfect binary tree of an MMR
+#[derive(Debug, Clone, Copy)]
+struct Tree {
+    /// Height of the tree: it has `2^height` leaves
+    height: u32,
+    /// Index of the first leaf of the tree in the MMR
+    offset: u64,
+}
+
+impl Tree {
+    /// Intersection of the tree with the leaves `start..=end`, as indexes within the tree
+    fn local_range(&self, start: u32, end: u32) -> Option<(usize, usize)> {
+        let last = self.offset + (1 << self.height) - 1;
+        let start = u64::from(start).max(self.offset);
+        let end = u64::from(end).min(last);
+        (start <= end).then(|| ((start - self.offset) as usize, (end - self.offset) as usize))
+    }
+}
+
+/// Trees of an MMR with `leaf_count` leaves, from the oldest (highest) to the newest
+fn trees(leaf_count: u64) -> Vec<Tree> {
+    let mut offset = 0;
+    (0..u64::BITS)
+        .rev()
+        .filter(|height| leaf_count & (1 << height) != 0)
+        .map(|height| {
+            let tree = Tree { height, offset };
+            offset += 1 << height;
+            tree
+        })
+        .collect()
+}
+
+/// Serde for digest lists as `0x`-prefixed hex strings
+mod digest_list {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::Blake2sDigest;
+
+    pub fn serialize<S: Serializer>(digests: &[Blake2sDigest], s: S) -> Result<S::Ok, S::Error> {
+        s.collect_seq(
+            digests
+                .iter()
+                .map(|digest| format!("0x{}", hex::encode(digest))),
+        )
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<Blake2sDigest>, D::Error> {
+        Vec::<String>::deserialize(d)?
+            .iter()
+            .map(|digest| crate::untrusted::decode_hash32(digest))
+            .collect::<anyhow::Result<_>>()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Block header MMR roots recorded right after the block at `height` was added
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SparseRootsRecord {
+    /// Height of the last block added to the MMR
+    pub height: u32,
+    /// MMR roots in the sparse representation
+    #[schemars(with = "Vec<String>")]
+    pub roots: SparseRoots,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zebra_chain::serialization::ZcashDeserialize;
+
+    fn digest(hex_str: &str) -> Blake2sDigest {
+        crate::untrusted::decode_hash32(hex_str).unwrap()
+    }
+
+    #[test]
+    fn test_genesis_header_leaf() {
+        let bytes = hex::decode(include_str!("../tests/data/genesis_header.hex").trim()).unwrap();
+        let header = Header::zcash_deserialize(bytes.as_slice()).unwrap();
+        let mut mmr = SparseRoots::default();
+        mmr.add(header_leaf(&header).unwrap());
+        // Matches `test_genesis_block_mmr` in the assumevalid Cairo package
+        assert_eq!(
+            mmr.roots,
+            vec![
+                Some(digest(
+                    "0x0d6195eb80a1a9dcdf5fb7aaf820639b76b96fe9448b2fce9e117b6385c69c37"
+                )),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_add_block_headers_matches_add_block_header() {
+        let bytes = hex::decode(include_str!("../tests/data/genesis_header.hex").trim()).unwrap();
+        let header = Header::zcash_deserialize(bytes.as_slice()).unwrap();
+        let headers = vec![header; 3];
+
+        let mut batched = BlockMmr::default();
+        batched.add_block_headers(&headers).unwrap();
+        let mut sequential = BlockMmr::default();
+        for header in &headers {
+            sequential.add_block_header(header).unwrap();
+        }
+        assert_eq!(batched.leaf_count(), 3);
+        assert_eq!(batched.roots(), sequential.roots());
+    }
+
+    #[test]
+    fn test_add_carries_like_cairo() {
+        let leaf = digest("0xc713e33d89122b85e2f646cc518c2e6ef88b06d3b016104faa95f84f878dab66");
+        let pair = digest("0x693aa1ab81c6362fe339fc4c7f6d8ddb1e515701e58c5bb2fb54a193c8287fdc");
+        assert_eq!(hash_pair(&leaf, &leaf), pair);
+
+        let mut mmr = SparseRoots::default();
+        mmr.add(leaf);
+        mmr.add(leaf);
+        assert_eq!(mmr.roots, vec![None, Some(pair), None]);
+        mmr.add(leaf);
+        assert_eq!(mmr.roots, vec![Some(leaf), Some(pair), None]);
+        assert_eq!(mmr.leaf_count().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_range_proofs() {
+        let leaves: Vec<Blake2sDigest> = (0..13u8).map(|i| [i + 1; 32]).collect();
+        let mut mmr = BlockMmr::default();
+        for leaf in &leaves {
+            mmr.add(*leaf);
+        }
+        assert_eq!(mmr.leaf_count(), 13);
+        for start in 0..13u32 {
+            for end in start..13u32 {
+                let range = &leaves[start as usize..=end as usize];
+                let proof = mmr.generate_range_proof(start, end).unwrap();
+                verify_range_proof(mmr.roots(), range, &proof).unwrap();
+
+                let mut tampered = range.to_vec();
+                tampered[0][0] ^= 1;
+                assert!(verify_range_proof(mmr.roots(), &tampered, &proof).is_err());
+            }
+        }
+        assert!(mmr.generate_range_proof(3, 13).is_err());
+        assert!(mmr.generate_range_proof(4, 3).is_err());
+    }
+
+    #[test]
+    fn test_range_proof_shares_siblings() {
+        let mut mmr = BlockMmr::default();
+        for i in 0..16u8 {
+            mmr.add([i; 32]);
+        }
+        // The 8 leaves of the left half only need the root of the right half
+        let proof = mmr.generate_range_proof(0, 7).unwrap();
+        assert_eq!(proof.siblings.len(), 1);
+        let json = serde_json::to_string(&proof).unwrap();
+        assert_eq!(serde_json::from_str::<RangeProof>(&json).unwrap(), proof);
+
+        let mut extra = proof.clone();
+        extra.siblings.push([0; 32]);
+        let leaves: Vec<Blake2sDigest> = (0..8u8).map(|i| [i; 32]).collect();
+        verify_range_proof(mmr.roots(), &leaves, &proof).unwrap();
+        assert!(verify_range_proof(mmr.roots(), &leaves, &extra).is_err());
+    }
+
+    #[test]
+    fn test_sparse_roots_encoding_roundtrip() {
+        let mut mmr = SparseRoots::default();
+        for i in 0..5u8 {
+            mmr.add([i + 1; 32]);
+        }
+        assert_eq!(SparseRoots::from_bytes(&mmr.to_bytes()).unwrap(), mmr);
+        let json = serde_json::to_string(&mmr).unwrap();
+        assert_eq!(serde_json::from_str::<SparseRoots>(&json).unwrap(), mmr);
+        assert!(
+            serde_json::from_str::<SparseRoots>(&format!("[\"0x{}\"]", "11".repeat(32))).is_err()
+        );
+    }
+
+    #[test]
+    fn test_sparse_roots_leaf_count_bounded() {
+        let mut roots = vec![Some([1; 32]); MAX_SPARSE_ROOTS];
+        roots.push(None);
+        let bytes: Vec<u8> = roots
+            .iter()
+            .flat_map(|root| root.unwrap_or([0; 32]))
+            .collect();
+        assert!(SparseRoots::from_bytes(&bytes).is_err());
+        assert!(SparseRoots { roots }.leaf_count().is_err());
+
+        let mut roots = vec![None; MAX_SPARSE_ROOTS];
+        roots[MAX_SPARSE_ROOTS - 2] = Some([1; 32]);
+        let roots = SparseRoots::from_bytes(&SparseRoots { roots }.to_bytes()).unwrap();
+        assert_eq!(roots.leaf_count().unwrap(), 1 << (MAX_SPARSE_ROOTS - 2));
+    }
+}
@@ -0,0 +1,121 @@
+//! Build metadata embedded in the binaries, so that verification behavior can be traced back to
+//! the exact build that produced it.
+
+use serde::Serialize;
+
+use crate::verify::VerifierConfig;
+
+/// Git commit the build was made from, suffixed with `-dirty` for uncommitted changes
+pub const GIT_COMMIT: &str = env!("ZORO_GIT_COMMIT");
+
+/// Build metadata of a binary
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildInfo {
+    /// Cargo package of the binary
+    pub package: &'static str,
+    /// Version of the package
+    pub version: &'static str,
+    /// Git commit the build was made from
+    pub git_commit: &'static str,
+    /// Unix timestamp of the build: `SOURCE_DATE_EPOCH`, else the commit time
+    pub build_timestamp: u64,
+    /// Enabled cargo features, as `<package>/<feature>`
+    pub features: Vec<String>,
+    /// Default verifier configuration of the build
+    pub verifier_config: VerifierConfig,
+}
+
+impl BuildInfo {
+    /// Build metadata of a binary of `package`, given the state of its cargo features. Pass
+    /// `env!("CARGO_PKG_NAME")`, `env!("CARGO_PKG_VERSION")` and `cfg!(feature = ...)`.
+    pub fn new(package: &'static str, version: &'static str, features: &[(&str, bool)]) -> Self {
+        let own_features = [
+            ("stark", cfg!(feature = "stark")),
+            ("flyclient", cfg!(feature = "flyclient")),
+            ("client", cfg!(feature = "client")),
+            ("arbitrary", cfg!(feature = "arbitrary")),
+        ];
+        let features = features
+            .iter()
+            .map(|(name, enabled)| (package, *name, *enabled))
+            .chain(
+                own_features
+                    .iter()
+                    .map(|(name, enabled)| (env!("CARGO_PKG_NAME"), *name, *enabled)),
+            )
+            .filter(|(_, _, enabled)| *enabled)
+            .map(|(package, name, _)| format!("{package}/{name}"))
+            .collect();
+        Self {
+            package,
+            version,
+            git_commit: GIT_COMMIT,
+            build_timestamp: env!("ZORO_BUILD_TIMESTAMP").parse().unwrap_or(0),
+            features,
+            verifier_config: VerifierConfig::default(),
+        }
+    }
+
+    /// Multi-line description printed by `--version --verbose`
+    pub fn verbose_version(&self) -> String {
+        let features = if self.features.is_empty() {
+            "none".to_string()
+        } else {
+            self.features.join(", ")
+        };
+        let verifier_config = serde_json::to_string_pretty(&self.verifier_config)
+            .unwrap_or_else(|e| format!("<{e}>"));
+        format!(
+            "{} {}\ncommit: {}\nbuild timestamp: {}\nfeatures: {}\nverifier config: {}",
+            self.package,
+            self.version,
+            self.git_commit,
+            self.build_timestamp,
+            features,
+            verifier_config
+        )
+    }
+}
+
+/// Whether the command line asks for `--version --verbose`, which clap's version flag does not
+/// support
+pub fn verbose_version_requested(args: impl IntoIterator<Item = String>) -> bool {
+    let (mut version, mut verbose) = (false, false);
+    for arg in args {
+        match arg.as_str() {
+            "--version" | "-V" => version = true,
+            "--verbose" => verbose = true,
+            // Anything past `--` is positional
+            "--" => break,
+            _ => {}
+        }
+    }
+    version && verbose
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn requested(command_line: &str) -> bool {
+        verbose_version_requested(command_line.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn test_verbose_version_requested() {
+        assert!(requested("spv-cli --version --verbose"));
+        assert!(requested("spv-cli --verbose -V"));
+        assert!(!requested("spv-cli --version"));
+        assert!(!requested("spv-cli -- --version --verbose"));
+    }
+
+    #[test]
+    fn test_build_info_features() {
+        let features = [("sqlcipher", true), ("other", false)];
+        let info = BuildInfo::new("zoro-bridge-node", "0.1.0", &features);
+        assert_eq!(info.features[0], "zoro-bridge-node/sqlcipher");
+        assert!(!info.features.iter().any(|name| name.ends_with("/other")));
+        let version = info.verbose_version();
+        assert!(version.starts_with("zoro-bridge-node 0.1.0\ncommit: "));
+    }
+}
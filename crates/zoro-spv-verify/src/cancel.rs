@@ -0,0 +1,117 @@
+//! Cancellation of a running verification.
+//!
+//! A [`CancellationToken`] is passed in [`VerifyOptions`](crate::VerifyOptions) and checked
+//! between the verification stages. Once it is cancelled, or its deadline has passed, the
+//! verification returns a [`Cancelled`] error at the next check and drops the proof it owns. The
+//! STARK verification itself is not interruptible: a cancellation during it takes effect when it
+//! returns.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Handle to cancel a verification, shared by its clones
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl CancellationToken {
+    /// Token cancelled by [`cancel`](Self::cancel) only
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Token also cancelled once `timeout` has elapsed from now
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self {
+            cancelled: Arc::default(),
+            deadline: Some(Instant::now() + timeout),
+        }
+    }
+
+    /// Cancel the verifications using this token or one of its clones
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the token is cancelled or past its deadline
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// Fail with [`Cancelled`] if the token is cancelled, before running `stage`
+    pub fn check(&self, stage: &'static str) -> Result<(), Cancelled> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            Err(Cancelled {
+                stage,
+                timed_out: false,
+            })
+        } else if self.is_cancelled() {
+            Err(Cancelled {
+                stage,
+                timed_out: true,
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Error of a cancelled verification, to tell it apart from a failed one with
+/// `error.downcast_ref::<Cancelled>()`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cancelled {
+    /// Stage that was about to run
+    pub stage: &'static str,
+    /// Whether the deadline of the token passed, rather than an explicit cancellation
+    pub timed_out: bool,
+}
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.timed_out {
+            write!(f, "Verification timed out before {}", self.stage)
+        } else {
+            write!(f, "Verification cancelled before {}", self.stage)
+        }
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_is_shared_by_clones() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(token.check("block inclusion").is_ok());
+        clone.cancel();
+        assert!(token.is_cancelled());
+        let err = token.check("block inclusion").unwrap_err();
+        assert!(!err.timed_out);
+        assert_eq!(
+            err.to_string(),
+            "Verification cancelled before block inclusion"
+        );
+    }
+
+    #[test]
+    fn test_deadline() {
+        assert!(!CancellationToken::with_timeout(Duration::from_secs(3600)).is_cancelled());
+        let err = CancellationToken::with_timeout(Duration::ZERO)
+            .check("chain state proof")
+            .unwrap_err();
+        assert!(err.timed_out);
+        let err = anyhow::Error::new(err);
+        assert!(err.downcast_ref::<Cancelled>().is_some());
+    }
+}
@@ -0,0 +1,158 @@
+//! Canonical JSON encoding for chain states and proofs.
+//!
+//! The canonical form is the serde JSON representation with:
+//! - object keys sorted lexicographically (by UTF-8 bytes),
+//! - no insignificant whitespace,
+//! - integers written in plain decimal (floats are rejected),
+//! - hex strings lowercased (including the `0x` prefix).
+//!
+//! Hashes, signatures and content-addressed identifiers must be computed over
+//! [`CanonicalSerialize::canonical_bytes`] rather than over `serde_json::to_string` output,
+//! whose key order and number formatting depend on crate features and versions.
+
+use serde::Serialize;
+use serde_json::{Number, Value};
+use stwo::core::vcs::blake2_hash::Blake2sHasher;
+use zoro_zcash_client::MerkleProof;
+
+#[cfg(feature = "stark")]
+use crate::proof::FullInclusionProof;
+use crate::proof::{BlockInclusionProof, ChainState, MmrRootRecord, TransactionInclusionProof};
+
+/// Deterministic serialization shared by all proof types
+pub trait CanonicalSerialize: Serialize {
+    /// Encode `self` as canonical JSON bytes
+    fn canonical_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        to_canonical_json(self)
+    }
+
+    /// Blake2s digest of the canonical bytes, as a `0x`-prefixed lowercase hex string
+    fn canonical_digest(&self) -> anyhow::Result<String> {
+        let bytes = self.canonical_bytes()?;
+        let mut hasher = Blake2sHasher::new();
+        hasher.update(&bytes);
+        Ok(format!("0x{}", hex::encode(hasher.finalize().0)))
+    }
+}
+
+impl CanonicalSerialize for ChainState {}
+impl CanonicalSerialize for BlockInclusionProof {}
+impl CanonicalSerialize for TransactionInclusionProof {}
+#[cfg(feature = "stark")]
+impl CanonicalSerialize for FullInclusionProof {}
+impl CanonicalSerialize for MmrRootRecord {}
+impl CanonicalSerialize for MerkleProof {}
+
+/// Encode any serializable value as canonical JSON bytes
+pub fn to_canonical_json<T: Serialize + ?Sized>(value: &T) -> anyhow::Result<Vec<u8>> {
+    let value = serde_json::to_value(value)?;
+    let mut out = Vec::new();
+    write_value(&value, &mut out)?;
+    Ok(out)
+}
+
+fn write_value(value: &Value, out: &mut Vec<u8>) -> anyhow::Result<()> {
+    match value {
+        Value::Null => out.extend_from_slice(b"null"),
+        Value::Bool(b) => out.extend_from_slice(if *b { b"true" } else { b"false" }),
+        Value::Number(n) => write_number(n, out)?,
+        Value::String(s) => write_string(&canonical_string(s), out)?,
+        Value::Array(items) => {
+            out.push(b'[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write_value(item, out)?;
+            }
+            out.push(b']');
+        }
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.as_bytes().cmp(b.as_bytes()));
+            out.push(b'{');
+            for (i, (key, item)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write_string(key, out)?;
+                out.push(b':');
+                write_value(item, out)?;
+            }
+            out.push(b'}');
+        }
+    }
+    Ok(())
+}
+
+fn write_number(n: &Number, out: &mut Vec<u8>) -> anyhow::Result<()> {
+    // With `arbitrary_precision` the number keeps its textual form, so check it explicitly
+    let repr = n.to_string();
+    let digits = repr.strip_prefix('-').unwrap_or(&repr);
+    let is_integer = !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit());
+    if !is_integer {
+        anyhow::bail!("Canonical encoding only supports integers, got {repr}");
+    }
+    let digits = digits.trim_start_matches('0');
+    if digits.is_empty() {
+        out.push(b'0');
+    } else {
+        if repr.starts_with('-') {
+            out.push(b'-');
+        }
+        out.extend_from_slice(digits.as_bytes());
+    }
+    Ok(())
+}
+
+fn write_string(s: &str, out: &mut Vec<u8>) -> anyhow::Result<()> {
+    serde_json::to_writer(&mut *out, s)?;
+    Ok(())
+}
+
+/// Lowercase strings that are entirely hex (optionally `0x`-prefixed), leave others untouched
+fn canonical_string(s: &str) -> String {
+    let body = s
+        .strip_prefix("0x")
+        .or_else(|| s.strip_prefix("0X"))
+        .unwrap_or(s);
+    if !body.is_empty() && body.bytes().all(|b| b.is_ascii_hexdigit()) {
+        s.to_ascii_lowercase()
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_canonical_json_sorts_keys_and_lowercases_hex() {
+        let value = json!({
+            "b": "0XABCDEF",
+            "a": [1, {"z": "DEADBEEF", "y": "Heartwood"}],
+        });
+        let bytes = to_canonical_json(&value).unwrap();
+        assert_eq!(
+            String::from_utf8(bytes).unwrap(),
+            r#"{"a":[1,{"y":"Heartwood","z":"deadbeef"}],"b":"0xabcdef"}"#
+        );
+    }
+
+    #[test]
+    fn test_canonical_json_is_key_order_independent() {
+        let lhs = json!({"height": 1, "root": "0xAA", "epoch": "nu5"});
+        let rhs = json!({"epoch": "nu5", "root": "0xaa", "height": 1});
+        assert_eq!(
+            to_canonical_json(&lhs).unwrap(),
+            to_canonical_json(&rhs).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_canonical_json_rejects_floats() {
+        assert!(to_canonical_json(&json!({"x": 1.5})).is_err());
+    }
+}
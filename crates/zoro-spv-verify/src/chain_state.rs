@@ -0,0 +1,222 @@
+//! Chain state transitions computed from block headers.
+//!
+//! These are the transitions the bridge node and the Cairo program apply to every block. A client
+//! can replay exported headers (e.g. from the bridge's `/headers`) with [`fold_headers`] on top of a
+//! chain state it trusts, and compare the result with a `/chain-state` response using
+//! [`check_chain_state`].
+
+use hex::{FromHex, ToHex};
+use zebra_chain::block::{Hash, Header};
+use zebra_chain::work::difficulty::{CompactDifficulty, ExpandedDifficulty, Work};
+use zoro_chain_params::{Network, POW_AVERAGING_WINDOW};
+
+use crate::canonical::CanonicalSerialize;
+use crate::proof::{ChainState, Target};
+
+const BLOCKS_PER_EPOCH: u32 = 2016;
+const MAX_TIMESTAMP_HISTORY: usize = 28;
+
+impl ChainState {
+    /// Chain state of the genesis block
+    pub fn genesis() -> Self {
+        // Genesis block bits = 0x1f07ffff -> target = 0x07ffff << (8 * (0x1f - 3))
+        // = 0x7ffff00000000000000000000000000000000000000000000000000000000
+        let current_target =
+            Target::from_hex("0007ffff00000000000000000000000000000000000000000000000000000000")
+                .unwrap();
+
+        // Genesis pow_target_history must have POW_AVERAGING_WINDOW (17) entries
+        // to match Cairo's Default::default() for ChainState
+        let pow_target_history = (0..POW_AVERAGING_WINDOW)
+            .map(|_| current_target.clone())
+            .collect();
+
+        // Genesis block hash in display order
+        ChainState {
+            block_height: 0,
+            // Work = 2^256 / (target + 1) ≈ 0x2000 for genesis target
+            total_work: 0x2000,
+            best_block_hash: Hash::from_hex(Network::Mainnet.genesis_hash()).unwrap(),
+            current_target,
+            prev_timestamps: vec![Network::Mainnet.genesis_timestamp()],
+            // Use actual genesis block time as epoch start
+            epoch_start_time: Network::Mainnet.genesis_timestamp(),
+            pow_target_history,
+        }
+    }
+
+    /// Chain state after applying the block header at `block_height` on top of `self`.
+    ///
+    /// The header is not checked against `self` (see [`fold_headers`]), and the genesis header
+    /// leaves the state unchanged.
+    pub fn apply_header(&self, block_height: u32, block_header: &Header) -> anyhow::Result<Self> {
+        if block_height == 0 {
+            return Ok(self.clone());
+        }
+        let block_time = block_header.time.timestamp() as u32;
+
+        // Update recent timestamps with a capped history window.
+        let mut prev_timestamps = self.prev_timestamps.clone();
+        if prev_timestamps.len() == MAX_TIMESTAMP_HISTORY {
+            prev_timestamps.remove(0);
+        }
+        prev_timestamps.push(block_time);
+
+        // Convert compact difficulty (nBits) into an expanded 256‑bit target.
+        let compact: CompactDifficulty = block_header.difficulty_threshold;
+        let expanded = compact
+            .to_expanded()
+            .ok_or_else(|| anyhow::anyhow!("invalid difficulty threshold in header"))?;
+
+        // Store the target in the chain state in big‑endian byte order.
+        let target_hex: String = expanded.encode_hex();
+        let current_target = Target::from_hex(&target_hex)?;
+
+        // Accumulate total work using the Zcash work definition.
+        let total_work = add_work(self.total_work, expanded)?;
+
+        // Best block hash for the updated chain tip.
+        let best_block_hash = block_header.hash();
+
+        // Update PoW target history as a sliding window over recent targets.
+        // History grows from 1 to POW_AVERAGING_WINDOW, then slides.
+        let mut pow_target_history = self.pow_target_history.clone();
+        if pow_target_history.len() >= POW_AVERAGING_WINDOW {
+            pow_target_history.remove(0);
+        }
+        pow_target_history.push(current_target.clone());
+
+        // Added to match cairo code
+        let epoch_start_time = if block_height % BLOCKS_PER_EPOCH == 0 {
+            block_time
+        } else {
+            self.epoch_start_time
+        };
+
+        Ok(ChainState {
+            block_height,
+            total_work,
+            best_block_hash,
+            current_target,
+            prev_timestamps,
+            epoch_start_time,
+            pow_target_history,
+        })
+    }
+}
+
+fn add_work(prev_total_work: u128, expanded_target: ExpandedDifficulty) -> anyhow::Result<u128> {
+    // Work::try_from implements the same 2^256 / (target + 1) formula as the Cairo code,
+    // but stores the result as a u128.
+    let work = Work::try_from(expanded_target)
+        .map_err(|_| anyhow::anyhow!("difficulty threshold has no finite work"))?;
+    prev_total_work
+        .checked_add(work.as_u128())
+        .ok_or_else(|| anyhow::anyhow!("total work overflows u128"))
+}
+
+/// Fold the headers following `state` into the chain state of the last one.
+///
+/// Each header must extend the best block of the state it is applied to. With `state` at
+/// [`ChainState::genesis`], the headers start at height 1. Proof of work and difficulty
+/// adjustments are not checked.
+pub fn fold_headers<'a>(
+    mut state: ChainState,
+    headers: impl IntoIterator<Item = &'a Header>,
+) -> anyhow::Result<ChainState> {
+    for header in headers {
+        let block_height = state
+            .block_height
+            .checked_add(1)
+            .ok_or_else(|| anyhow::anyhow!("Block height overflows u32"))?;
+        if header.previous_block_hash != state.best_block_hash {
+            anyhow::bail!(
+                "Header at height {} does not extend block {}: its previous block is {}",
+                block_height,
+                state.best_block_hash,
+                header.previous_block_hash
+            );
+        }
+        state = state.apply_header(block_height, header)?;
+    }
+    Ok(state)
+}
+
+/// Compare a recomputed chain state with one reported by a bridge node, naming the fields that
+/// differ
+pub fn check_chain_state(recomputed: &ChainState, reported: &ChainState) -> anyhow::Result<()> {
+    if recomputed.canonical_bytes()? == reported.canonical_bytes()? {
+        return Ok(());
+    }
+    let recomputed = serde_json::to_value(recomputed)?;
+    let reported = serde_json::to_value(reported)?;
+    let fields: Vec<&str> = recomputed
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(field, value)| reported.get(field.as_str()) != Some(value))
+        .map(|(field, _)| field.as_str())
+        .collect();
+    anyhow::bail!(
+        "Reported chain state differs from the recomputed one in {}",
+        fields.join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zebra_chain::serialization::ZcashDeserialize;
+
+    /// Headers chained on top of the genesis header, with its difficulty
+    fn headers(count: usize) -> (Header, Vec<Header>) {
+        let bytes = hex::decode(include_str!("../tests/data/genesis_header.hex").trim()).unwrap();
+        let genesis = Header::zcash_deserialize(bytes.as_slice()).unwrap();
+        let mut headers = Vec::new();
+        let mut previous = genesis;
+        for _ in 0..count {
+            previous = Header {
+                previous_block_hash: previous.hash(),
+                ..genesis
+            };
+            headers.push(previous);
+        }
+        (genesis, headers)
+    }
+
+    #[test]
+    fn test_fold_headers_from_genesis() {
+        let (genesis, headers) = headers(3);
+        assert_eq!(ChainState::genesis().best_block_hash, genesis.hash());
+
+        let state = fold_headers(ChainState::genesis(), &headers).unwrap();
+        assert_eq!(state.block_height, 3);
+        assert_eq!(state.best_block_hash, headers[2].hash());
+        assert_eq!(state.total_work, 0x2000 * 4);
+        assert_eq!(state.prev_timestamps, vec![1477641360; 4]);
+        assert_eq!(state.pow_target_history.len(), POW_AVERAGING_WINDOW);
+
+        // Folding in two parts gives the same state
+        let middle = fold_headers(ChainState::genesis(), &headers[..1]).unwrap();
+        let resumed = fold_headers(middle, &headers[1..]).unwrap();
+        check_chain_state(&resumed, &state).unwrap();
+    }
+
+    #[test]
+    fn test_fold_headers_rejects_unlinked_header() {
+        let (_, mut headers) = headers(3);
+        headers.remove(1);
+        let error = fold_headers(ChainState::genesis(), &headers).unwrap_err();
+        assert!(error.to_string().contains("height 2"));
+    }
+
+    #[test]
+    fn test_check_chain_state_names_fields() {
+        let (_, headers) = headers(2);
+        let state = fold_headers(ChainState::genesis(), &headers).unwrap();
+        let mut reported = state.clone();
+        reported.total_work += 1;
+        let error = check_chain_state(&state, &reported).unwrap_err();
+        assert!(error.to_string().ends_with("in total_work"));
+    }
+}
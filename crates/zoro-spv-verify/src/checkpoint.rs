@@ -0,0 +1,153 @@
+//! Chain checkpoints embedded in the verifier config.
+//!
+//! A checkpoint pins the hash of the block at a height and the minimum cumulative work of the
+//! chain up to it. Chain states at or above the height must carry at least that work, and blocks
+//! or chain states at the height must have that hash. This rejects a fully fabricated chain, even
+//! with valid-looking proofs, unless it forks off the real chain after the last checkpoint.
+
+use std::str::FromStr;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use zebra_chain::block::Hash;
+
+use crate::proof::ChainState;
+
+/// Block hash and cumulative work the chain must have at a height
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Checkpoint {
+    /// Height of the checkpointed block
+    pub height: u32,
+    /// Hash of the checkpointed block (hex string, display order)
+    pub hash: String,
+    /// Minimum cumulative work of the chain up to the checkpointed block (decimal string)
+    pub min_work: String,
+}
+
+impl Checkpoint {
+    /// Check that a chain state is consistent with the checkpoint
+    pub fn check_chain_state(&self, chain_state: &ChainState) -> anyhow::Result<()> {
+        if chain_state.block_height < self.height {
+            return Ok(());
+        }
+        let min_work = u128::from_str(&self.min_work).map_err(|_| {
+            anyhow::anyhow!(
+                "Invalid minimum work {:?} of the checkpoint at height {}",
+                self.min_work,
+                self.height
+            )
+        })?;
+        if chain_state.total_work < min_work {
+            anyhow::bail!(
+                "Chain state at height {} has less work than the checkpoint at height {}: {} < {}",
+                chain_state.block_height,
+                self.height,
+                chain_state.total_work,
+                min_work
+            );
+        }
+        if chain_state.block_height == self.height {
+            self.check_block(self.height, &chain_state.best_block_hash)?;
+        }
+        Ok(())
+    }
+
+    /// Check that the block at `height` is the checkpointed block, if at the checkpoint height
+    pub fn check_block(&self, height: u32, hash: &Hash) -> anyhow::Result<()> {
+        if height != self.height {
+            return Ok(());
+        }
+        let expected = Hash::from_str(&self.hash).map_err(|_| {
+            anyhow::anyhow!(
+                "Invalid hash {:?} of the checkpoint at height {}",
+                self.hash,
+                self.height
+            )
+        })?;
+        if *hash != expected {
+            anyhow::bail!(
+                "Block {} at height {} doesn't match the checkpoint {}",
+                hash,
+                height,
+                expected
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Check that a chain state, and optionally a block of its chain given as `(height, hash)`, are
+/// consistent with every checkpoint
+pub fn check_checkpoints(
+    checkpoints: &[Checkpoint],
+    chain_state: &ChainState,
+    block: Option<(u32, &Hash)>,
+) -> anyhow::Result<()> {
+    for checkpoint in checkpoints {
+        checkpoint.check_chain_state(chain_state)?;
+        if let Some((height, hash)) = block {
+            checkpoint.check_block(height, hash)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkpoint() -> Checkpoint {
+        Checkpoint {
+            height: 100,
+            hash: "11".repeat(32),
+            min_work: "1000".to_string(),
+        }
+    }
+
+    fn chain_state(block_height: u32, total_work: u128, best_block_hash: Hash) -> ChainState {
+        ChainState {
+            block_height,
+            total_work,
+            best_block_hash,
+            ..ChainState::genesis()
+        }
+    }
+
+    #[test]
+    fn test_check_chain_state() {
+        let checkpoint = checkpoint();
+        let hash = Hash([0x11; 32]);
+        let other = Hash([0x22; 32]);
+
+        // Below the checkpoint, anything goes
+        checkpoint
+            .check_chain_state(&chain_state(99, 0, other))
+            .unwrap();
+        checkpoint
+            .check_chain_state(&chain_state(100, 1000, hash))
+            .unwrap();
+        checkpoint
+            .check_chain_state(&chain_state(200, 5000, other))
+            .unwrap();
+
+        let error = checkpoint
+            .check_chain_state(&chain_state(200, 999, other))
+            .unwrap_err();
+        assert!(error.to_string().contains("less work"));
+        let error = checkpoint
+            .check_chain_state(&chain_state(100, 1000, other))
+            .unwrap_err();
+        assert!(error.to_string().contains("doesn't match the checkpoint"));
+    }
+
+    #[test]
+    fn test_check_checkpoints() {
+        let checkpoints = [checkpoint()];
+        let chain_state = chain_state(200, 5000, Hash([0x33; 32]));
+        check_checkpoints(&checkpoints, &chain_state, Some((100, &Hash([0x11; 32])))).unwrap();
+        check_checkpoints(&checkpoints, &chain_state, Some((101, &Hash([0x22; 32])))).unwrap();
+        assert!(
+            check_checkpoints(&checkpoints, &chain_state, Some((100, &Hash([0x22; 32])))).is_err()
+        );
+    }
+}
@@ -0,0 +1,510 @@
+//! Verification conformance fixtures.
+//!
+//! Unlike the [`vectors`](crate::vectors), which pin encodings and digests, a fixture is a proof
+//! (or chain state) along with the verdict a verifier must reach on it: `valid`, or the
+//! [`Verdict`] code of the check that rejects it. The `conformance` binary writes the fixtures
+//! of [`generate`] and runs a directory of fixtures through this verifier with [`run`]; other
+//! implementations run the same directory through theirs and compare verdicts, so that they
+//! accept and reject the same proofs for the same reasons.
+//!
+//! A fixture is a JSON file `{ "name", "kind", "input", "expected" }`, where `kind` and `input`
+//! are one of the [`Case`]s.
+
+use std::path::{Path, PathBuf};
+
+use hex::FromHex;
+use serde::{Deserialize, Serialize};
+use zebra_chain::block::{Hash, Header};
+use zebra_chain::serialization::{ZcashDeserialize, ZcashSerialize};
+use zebra_chain::transaction;
+use zoro_zcash_client::MerkleProof;
+
+use crate::block_mmr::{verify_range_proof, RangeProof, SparseRoots};
+use crate::chain_state::{check_chain_state, fold_headers};
+use crate::checkpoint::{check_checkpoints, Checkpoint};
+use crate::finality::FinalityPolicy;
+use crate::proof::{ChainState, TransactionInclusionProof};
+use crate::untrusted::{decode_hash32, ProofLimits};
+use crate::vectors;
+use crate::verify::verify_transaction;
+
+/// Outcome of the verification of a fixture
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    /// Every check passes
+    Valid,
+    /// The input does not decode as the fixture kind
+    Malformed,
+    /// The input exceeds the default [`ProofLimits`]
+    LimitExceeded,
+    /// The Merkle proof does not lead from the transaction to the block Merkle root
+    MerkleProofInvalid,
+    /// The transaction, its Merkle proof or the block header are inconsistent
+    TransactionProofInvalid,
+    /// The leaves are not in the MMR described by the roots
+    MmrProofInvalid,
+    /// The headers do not lead from one chain state to the other
+    ChainStateMismatch,
+    /// The block is not final under the policy
+    NotFinal,
+    /// The chain state or the block contradicts a checkpoint
+    CheckpointMismatch,
+}
+
+impl Verdict {
+    /// Reason code of the verdict, as serialized in fixtures
+    pub fn code(self) -> &'static str {
+        match self {
+            Verdict::Valid => "valid",
+            Verdict::Malformed => "malformed",
+            Verdict::LimitExceeded => "limit_exceeded",
+            Verdict::MerkleProofInvalid => "merkle_proof_invalid",
+            Verdict::TransactionProofInvalid => "transaction_proof_invalid",
+            Verdict::MmrProofInvalid => "mmr_proof_invalid",
+            Verdict::ChainStateMismatch => "chain_state_mismatch",
+            Verdict::NotFinal => "not_final",
+            Verdict::CheckpointMismatch => "checkpoint_mismatch",
+        }
+    }
+}
+
+impl std::fmt::Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// Proof or chain state to verify, by fixture `kind`
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", content = "input", rename_all = "snake_case")]
+pub enum Case {
+    /// Merkle proof of the transaction `txid` (display order)
+    MerkleProof { txid: String, proof: MerkleProof },
+    /// Transaction inclusion proof, with the transaction and its block header
+    TransactionInclusion(TransactionInclusionProof),
+    /// Block header MMR range proof of `leaves` against `roots`
+    MmrRangeProof {
+        roots: SparseRoots,
+        leaves: Vec<String>,
+        proof: RangeProof,
+    },
+    /// Chain state `to` reached by applying `headers` (consensus encoding, hex) on top of `from`
+    ChainStateTransition {
+        from: ChainState,
+        headers: Vec<String>,
+        to: ChainState,
+    },
+    /// Finality of the block at `block_height` at the height of `chain_state`, `proven` telling
+    /// whether the chain state is verified against its STARK proof
+    Finality {
+        policy: FinalityPolicy,
+        block_height: u32,
+        chain_state: ChainState,
+        proven: bool,
+    },
+    /// Consistency of a chain state, and optionally of a block (hash in display order), with
+    /// checkpoints
+    Checkpoints {
+        checkpoints: Vec<Checkpoint>,
+        chain_state: ChainState,
+        #[serde(default)]
+        block: Option<(u32, String)>,
+    },
+}
+
+/// Fixture as written to disk
+#[derive(Serialize, Deserialize)]
+pub struct Fixture {
+    /// Name of the fixture, also its file stem
+    pub name: String,
+    #[serde(flatten)]
+    pub case: Case,
+    /// Verdict a conforming verifier reaches
+    pub expected: Verdict,
+}
+
+/// Verdict reached on a fixture file
+#[derive(Debug, Clone, Serialize)]
+pub struct Outcome {
+    /// Name of the fixture, or its file name if it has none
+    pub fixture: String,
+    /// Verdict of the fixture, `None` if the file has none
+    pub expected: Option<Verdict>,
+    /// Verdict of this implementation
+    pub actual: Verdict,
+}
+
+impl Outcome {
+    /// Whether this implementation reaches the expected verdict
+    pub fn passed(&self) -> bool {
+        self.expected == Some(self.actual)
+    }
+}
+
+/// Verify a case, returning the verdict
+pub fn verify(case: &Case) -> Verdict {
+    let limits = ProofLimits::default();
+    match case {
+        Case::MerkleProof { txid, proof } => {
+            let Ok(txid) = txid.parse::<transaction::Hash>() else {
+                return Verdict::Malformed;
+            };
+            if limits.check_merkle_proof(proof).is_err() {
+                Verdict::LimitExceeded
+            } else if proof.verify(txid.0) {
+                Verdict::Valid
+            } else {
+                Verdict::MerkleProofInvalid
+            }
+        }
+        Case::TransactionInclusion(proof) => {
+            if limits.check_transaction_inclusion(proof).is_err() {
+                return Verdict::LimitExceeded;
+            }
+            match verify_transaction(
+                &proof.transaction,
+                &proof.block_header,
+                proof.transaction_proof.clone(),
+            ) {
+                Ok(()) => Verdict::Valid,
+                Err(_) => Verdict::TransactionProofInvalid,
+            }
+        }
+        Case::MmrRangeProof {
+            roots,
+            leaves,
+            proof,
+        } => {
+            let Ok(leaves) = leaves
+                .iter()
+                .map(|leaf| decode_hash32(leaf))
+                .collect::<anyhow::Result<Vec<_>>>()
+            else {
+                return Verdict::Malformed;
+            };
+            match verify_range_proof(roots, &leaves, proof) {
+                Ok(()) => Verdict::Valid,
+                Err(_) => Verdict::MmrProofInvalid,
+            }
+        }
+        Case::ChainStateTransition { from, headers, to } => {
+            let Ok(headers) = headers
+                .iter()
+                .map(|header| Ok(Header::zcash_deserialize(hex::decode(header)?.as_slice())?))
+                .collect::<anyhow::Result<Vec<_>>>()
+            else {
+                return Verdict::Malformed;
+            };
+            match fold_headers(from.clone(), &headers)
+                .and_then(|recomputed| check_chain_state(&recomputed, to))
+            {
+                Ok(()) => Verdict::Valid,
+                Err(_) => Verdict::ChainStateMismatch,
+            }
+        }
+        Case::Finality {
+            policy,
+            block_height,
+            chain_state,
+            proven,
+        } => match policy.check(*block_height, chain_state, *proven) {
+            Ok(()) => Verdict::Valid,
+            Err(_) => Verdict::NotFinal,
+        },
+        Case::Checkpoints {
+            checkpoints,
+            chain_state,
+            block,
+        } => {
+            let block = match block {
+                Some((height, hash)) => match Hash::from_hex(hash) {
+                    Ok(hash) => Some((*height, hash)),
+                    Err(_) => return Verdict::Malformed,
+                },
+                None => None,
+            };
+            let block = block.as_ref().map(|(height, hash)| (*height, hash));
+            match check_checkpoints(checkpoints, chain_state, block) {
+                Ok(()) => Verdict::Valid,
+                Err(_) => Verdict::CheckpointMismatch,
+            }
+        }
+    }
+}
+
+/// Run every `*.json` fixture of `dir` through this verifier, in file name order
+pub fn run(dir: &Path) -> anyhow::Result<Vec<Outcome>> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {e}", dir.display()))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+    paths.retain(|path| {
+        path.extension()
+            .is_some_and(|extension| extension == "json")
+    });
+    paths.sort();
+    paths.iter().map(|path| run_file(path)).collect()
+}
+
+/// Run a fixture file, which must be JSON. A fixture whose kind is unknown or whose input does
+/// not decode is `malformed`.
+fn run_file(path: &Path) -> anyhow::Result<Outcome> {
+    #[derive(Deserialize)]
+    struct Labels {
+        name: Option<String>,
+        expected: Option<Verdict>,
+    }
+
+    let data = std::fs::read(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {e}", path.display()))?;
+    let value: serde_json::Value = serde_json::from_slice(&data)
+        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {e}", path.display()))?;
+    let labels = Labels::deserialize(&value).unwrap_or(Labels {
+        name: None,
+        expected: None,
+    });
+    let actual = match Case::deserialize(&value) {
+        Ok(case) => verify(&case),
+        Err(_) => Verdict::Malformed,
+    };
+    Ok(Outcome {
+        fixture: labels.name.unwrap_or_else(|| path.display().to_string()),
+        expected: labels.expected,
+        actual,
+    })
+}
+
+/// Generate the fixtures, derived from the conformance [`vectors`] with tampered copies for
+/// each verdict
+pub fn generate() -> anyhow::Result<Vec<Fixture>> {
+    let vectors = vectors::generate()?;
+    let mut fixtures = Vec::new();
+    let mut push = |name: String, case: Case, expected: Verdict| {
+        fixtures.push(Fixture {
+            name,
+            case,
+            expected,
+        })
+    };
+
+    for (i, vector) in vectors.merkle_proofs.iter().enumerate() {
+        let case = Case::MerkleProof {
+            txid: vector.txids[vector.index].clone(),
+            proof: vector.proof.clone(),
+        };
+        push(format!("merkle_proof_{i}"), case, Verdict::Valid);
+        if vector.txids.len() > 1 {
+            let other = (vector.index + 1) % vector.txids.len();
+            let case = Case::MerkleProof {
+                txid: vector.txids[other].clone(),
+                proof: vector.proof.clone(),
+            };
+            push(
+                format!("merkle_proof_{i}_wrong_txid"),
+                case,
+                Verdict::MerkleProofInvalid,
+            );
+        }
+    }
+    let vector = &vectors.merkle_proofs[0];
+    let mut proof = vector.proof.clone();
+    proof.path = vec![[0; 32]; ProofLimits::default().max_merkle_path + 1];
+    let case = Case::MerkleProof {
+        txid: vector.txids[0].clone(),
+        proof,
+    };
+    push(
+        "merkle_proof_path_too_long".to_string(),
+        case,
+        Verdict::LimitExceeded,
+    );
+
+    for (i, vector) in vectors.mmr_proofs.iter().enumerate() {
+        let case = Case::MmrRangeProof {
+            roots: vector.roots.clone(),
+            leaves: vector.leaves.clone(),
+            proof: vector.proof.clone(),
+        };
+        push(format!("mmr_range_proof_{i}"), case, Verdict::Valid);
+    }
+    let vector = &vectors.mmr_proofs[1];
+    let mut leaves = vector.leaves.clone();
+    leaves.reverse();
+    let case = Case::MmrRangeProof {
+        roots: vector.roots.clone(),
+        leaves,
+        proof: vector.proof.clone(),
+    };
+    push(
+        "mmr_range_proof_reordered_leaves".to_string(),
+        case,
+        Verdict::MmrProofInvalid,
+    );
+
+    let genesis = ChainState::genesis();
+    let headers: Vec<&Header> = vectors.headers[1..4]
+        .iter()
+        .map(|vector| &vector.header)
+        .collect();
+    let to = fold_headers(genesis.clone(), headers.iter().copied())?;
+    let encoded = |headers: &[&Header]| -> anyhow::Result<Vec<String>> {
+        headers
+            .iter()
+            .map(|header| Ok(hex::encode(header.zcash_serialize_to_vec()?)))
+            .collect()
+    };
+    push(
+        "chain_state_transition".to_string(),
+        Case::ChainStateTransition {
+            from: genesis.clone(),
+            headers: encoded(&headers)?,
+            to: to.clone(),
+        },
+        Verdict::Valid,
+    );
+    push(
+        "chain_state_transition_missing_header".to_string(),
+        Case::ChainStateTransition {
+            from: genesis.clone(),
+            headers: encoded(&headers[1..])?,
+            to: to.clone(),
+        },
+        Verdict::ChainStateMismatch,
+    );
+    push(
+        "chain_state_transition_wrong_work".to_string(),
+        Case::ChainStateTransition {
+            from: genesis.clone(),
+            headers: encoded(&headers)?,
+            to: ChainState {
+                total_work: to.total_work + 1,
+                ..to.clone()
+            },
+        },
+        Verdict::ChainStateMismatch,
+    );
+
+    let policy = FinalityPolicy::Confirmations { confirmations: 2 };
+    for (block_height, expected) in [(2, Verdict::Valid), (3, Verdict::NotFinal)] {
+        push(
+            format!("finality_confirmations_block_{block_height}"),
+            Case::Finality {
+                policy: policy.clone(),
+                block_height,
+                chain_state: to.clone(),
+                proven: false,
+            },
+            expected,
+        );
+    }
+    push(
+        "finality_proof_covered_unproven".to_string(),
+        Case::Finality {
+            policy: FinalityPolicy::ProofCovered,
+            block_height: 1,
+            chain_state: to.clone(),
+            proven: false,
+        },
+        Verdict::NotFinal,
+    );
+
+    let checkpoint = Checkpoint {
+        height: 1,
+        hash: vectors.headers[1].block_hash.clone(),
+        min_work: genesis.total_work.to_string(),
+    };
+    push(
+        "checkpoints".to_string(),
+        Case::Checkpoints {
+            checkpoints: vec![checkpoint.clone()],
+            chain_state: to.clone(),
+            block: Some((1, vectors.headers[1].block_hash.clone())),
+        },
+        Verdict::Valid,
+    );
+    push(
+        "checkpoints_wrong_block".to_string(),
+        Case::Checkpoints {
+            checkpoints: vec![checkpoint.clone()],
+            chain_state: to.clone(),
+            block: Some((1, vectors.headers[2].block_hash.clone())),
+        },
+        Verdict::CheckpointMismatch,
+    );
+    push(
+        "checkpoints_insufficient_work".to_string(),
+        Case::Checkpoints {
+            checkpoints: vec![Checkpoint {
+                min_work: (to.total_work + 1).to_string(),
+                ..checkpoint
+            }],
+            chain_state: to.clone(),
+            block: None,
+        },
+        Verdict::CheckpointMismatch,
+    );
+
+    Ok(fixtures)
+}
+
+/// Write the fixtures to `dir`, one `<name>.json` file each
+pub fn write(fixtures: &[Fixture], dir: &Path) -> anyhow::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    for fixture in fixtures {
+        let path = dir.join(format!("{}.json", fixture.name));
+        std::fs::write(&path, serde_json::to_vec_pretty(fixture)?)
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {e}", path.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_fixtures() {
+        let fixtures = generate().unwrap();
+        for fixture in &fixtures {
+            assert_eq!(verify(&fixture.case), fixture.expected, "{}", fixture.name);
+        }
+        // Every verdict the generated cases can reach is covered
+        for verdict in [
+            Verdict::Valid,
+            Verdict::LimitExceeded,
+            Verdict::MerkleProofInvalid,
+            Verdict::MmrProofInvalid,
+            Verdict::ChainStateMismatch,
+            Verdict::NotFinal,
+            Verdict::CheckpointMismatch,
+        ] {
+            assert!(fixtures.iter().any(|fixture| fixture.expected == verdict));
+        }
+    }
+
+    #[test]
+    fn test_fixture_format() {
+        let fixture = Fixture {
+            name: "finality".to_string(),
+            case: Case::Finality {
+                policy: FinalityPolicy::ProofCovered,
+                block_height: 0,
+                chain_state: ChainState::genesis(),
+                proven: true,
+            },
+            expected: Verdict::Valid,
+        };
+        let value = serde_json::to_value(&fixture).unwrap();
+        assert_eq!(value["kind"], "finality");
+        assert_eq!(value["expected"], "valid");
+        assert_eq!(value["input"]["policy"]["kind"], "proof_covered");
+
+        let fixture: Fixture = serde_json::from_value(value).unwrap();
+        assert_eq!(verify(&fixture.case), Verdict::Valid);
+        assert_eq!(
+            serde_json::to_value(Verdict::CheckpointMismatch).unwrap(),
+            Verdict::CheckpointMismatch.code()
+        );
+    }
+}
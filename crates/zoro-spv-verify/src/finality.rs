@@ -0,0 +1,195 @@
+//! Finality policies: when a block counts as final.
+//!
+//! The bridge node advertises the policy its proofs are served for, and the verifier checks
+//! proofs against its own. Both default to [`DEFAULT_CONFIRMATIONS`] confirmations.
+
+use std::fmt;
+use std::str::FromStr;
+
+use num_bigint::BigUint;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::proof::ChainState;
+use crate::report::WithHint;
+use crate::verify::INSUFFICIENT_CONFIRMATIONS_HINT;
+use crate::work::subchain_work;
+
+/// Default confirmation depth, the block itself included
+pub const DEFAULT_CONFIRMATIONS: u32 = 6;
+
+pub(crate) const PROOF_COVERAGE_HINT: &str =
+    "The finality policy requires a verified chain state STARK proof: provide one, or use a confirmation depth policy";
+
+/// When a block counts as final
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FinalityPolicy {
+    /// The block has at least `confirmations` confirmations at the chain height, the block
+    /// itself included
+    Confirmations { confirmations: u32 },
+    /// The blocks from the block to the chain height carry at least `min_work` work (decimal
+    /// string), estimated from the chain state target
+    Work { min_work: String },
+    /// The block is at or below the height of a chain state verified against its STARK proof
+    ProofCovered,
+}
+
+impl Default for FinalityPolicy {
+    fn default() -> Self {
+        FinalityPolicy::Confirmations {
+            confirmations: DEFAULT_CONFIRMATIONS,
+        }
+    }
+}
+
+impl FinalityPolicy {
+    /// Check that the block at `block_height` is final at the height of `chain_state`.
+    ///
+    /// `proven` tells whether `chain_state` is verified against its STARK proof.
+    pub fn check(
+        &self,
+        block_height: u32,
+        chain_state: &ChainState,
+        proven: bool,
+    ) -> anyhow::Result<()> {
+        let confirmations = chain_state.block_height.saturating_sub(block_height) + 1;
+        match self {
+            FinalityPolicy::Confirmations {
+                confirmations: required,
+            } => {
+                if confirmations < *required {
+                    return Err(anyhow::anyhow!(
+                        "Insufficient confirmations: {} < {} required",
+                        confirmations,
+                        required
+                    ))
+                    .hint(INSUFFICIENT_CONFIRMATIONS_HINT);
+                }
+            }
+            FinalityPolicy::Work { min_work } => {
+                let required = BigUint::from_str(min_work)
+                    .map_err(|_| anyhow::anyhow!("Invalid minimum work {min_work:?}"))?;
+                let work = subchain_work(confirmations, chain_state);
+                if work < required {
+                    return Err(anyhow::anyhow!(
+                        "Insufficient work: {} < {} required",
+                        work,
+                        required
+                    ))
+                    .hint(INSUFFICIENT_CONFIRMATIONS_HINT);
+                }
+            }
+            FinalityPolicy::ProofCovered => {
+                if !proven || block_height > chain_state.block_height {
+                    return Err(anyhow::anyhow!(
+                        "Block {} is not covered by a verified chain state proof",
+                        block_height
+                    ))
+                    .hint(PROOF_COVERAGE_HINT);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for FinalityPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FinalityPolicy::Confirmations { confirmations } => {
+                write!(f, "confirmations:{confirmations}")
+            }
+            FinalityPolicy::Work { min_work } => write!(f, "work:{min_work}"),
+            FinalityPolicy::ProofCovered => f.write_str("proof_covered"),
+        }
+    }
+}
+
+impl FromStr for FinalityPolicy {
+    type Err = anyhow::Error;
+
+    /// Parse `confirmations:<N>`, `work:<decimal>` or `proof_covered`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some(("confirmations", confirmations)) => Ok(FinalityPolicy::Confirmations {
+                confirmations: confirmations
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("Invalid confirmation depth {confirmations:?}"))?,
+            }),
+            Some(("work", min_work)) => {
+                BigUint::from_str(min_work)
+                    .map_err(|_| anyhow::anyhow!("Invalid minimum work {min_work:?}"))?;
+                Ok(FinalityPolicy::Work {
+                    min_work: min_work.to_string(),
+                })
+            }
+            None if s == "proof_covered" => Ok(FinalityPolicy::ProofCovered),
+            _ => anyhow::bail!(
+                "Unsupported finality policy {s:?} (expected confirmations:<N>, work:<decimal> or proof_covered)"
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof::Target;
+    use zebra_chain::block::Hash;
+
+    fn chain_state(block_height: u32) -> ChainState {
+        ChainState {
+            block_height,
+            total_work: 0,
+            best_block_hash: Hash([0; 32]),
+            // 2^240 - 1, whose blocks carry 2^16 work each
+            current_target: Target::from_hex(&format!("0000{}", "ff".repeat(30))).unwrap(),
+            prev_timestamps: vec![],
+            epoch_start_time: 0,
+            pow_target_history: vec![],
+        }
+    }
+
+    #[test]
+    fn test_parse_policy() {
+        for policy in [
+            FinalityPolicy::default(),
+            FinalityPolicy::Work {
+                min_work: "1813388729421943762059264".to_string(),
+            },
+            FinalityPolicy::ProofCovered,
+        ] {
+            assert_eq!(
+                policy.to_string().parse::<FinalityPolicy>().unwrap(),
+                policy
+            );
+        }
+        assert!("confirmations:six".parse::<FinalityPolicy>().is_err());
+        assert!("work:0x10".parse::<FinalityPolicy>().is_err());
+        assert!("final".parse::<FinalityPolicy>().is_err());
+        assert_eq!(
+            serde_json::to_string(&FinalityPolicy::default()).unwrap(),
+            r#"{"kind":"confirmations","confirmations":6}"#
+        );
+    }
+
+    #[test]
+    fn test_check_policy() {
+        let state = chain_state(105);
+        let policy = FinalityPolicy::default();
+        assert!(policy.check(100, &state, false).is_ok());
+        assert!(policy.check(101, &state, false).is_err());
+
+        let policy = FinalityPolicy::Work {
+            min_work: (6u32 << 16).to_string(),
+        };
+        assert!(policy.check(100, &state, false).is_ok());
+        assert!(policy.check(101, &state, false).is_err());
+
+        let policy = FinalityPolicy::ProofCovered;
+        assert!(policy.check(105, &state, true).is_ok());
+        assert!(policy.check(105, &state, false).is_err());
+        assert!(policy.check(106, &state, true).is_err());
+    }
+}
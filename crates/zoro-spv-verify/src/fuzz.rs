@@ -0,0 +1,68 @@
+//! `arbitrary` input generators for fuzzing the decode and verification paths.
+//!
+//! Enabled with the `arbitrary` feature. Proof types that are entirely local derive or implement
+//! [`arbitrary::Arbitrary`] directly; inputs that are foreign types (Cairo output felts, raw
+//! header bytes) get newtype wrappers here.
+
+use arbitrary::{Arbitrary, Unstructured};
+use starknet_ff::FieldElement;
+use zebra_chain::block::Header;
+use zebra_chain::serialization::ZcashDeserialize;
+
+use crate::proof::BootloaderOutput;
+
+/// Size of a serialized Zcash block header with an Equihash (200, 9) solution
+pub const HEADER_SIZE: usize = 4 + 32 + 32 + 32 + 4 + 4 + 32 + 3 + SOLUTION_SIZE;
+/// Size of an Equihash (200, 9) solution
+const SOLUTION_SIZE: usize = 1344;
+
+/// Cairo public output felts as emitted by the bootloader
+#[derive(Debug, Clone)]
+pub struct CairoOutputFelts(pub Vec<FieldElement>);
+
+impl<'a> Arbitrary<'a> for CairoOutputFelts {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let len = u.arbitrary_len::<[u8; 32]>()?;
+        let mut felts = Vec::with_capacity(len);
+        for _ in 0..len {
+            let mut bytes: [u8; 32] = u.arbitrary()?;
+            // Clear the top bits so that the value is always below the field modulus
+            bytes[0] &= 0x07;
+            let felt = FieldElement::from_bytes_be(&bytes)
+                .map_err(|_| arbitrary::Error::IncorrectFormat)?;
+            felts.push(felt);
+        }
+        Ok(Self(felts))
+    }
+}
+
+impl CairoOutputFelts {
+    /// Run the bootloader output decoder on the felts
+    pub fn decode(self) -> anyhow::Result<BootloaderOutput> {
+        BootloaderOutput::decode(self.0)
+    }
+}
+
+/// Raw block header bytes with a well-formed length prefix for the Equihash solution, so that
+/// fuzzing reaches past the framing checks of the header decoder
+#[derive(Debug, Clone)]
+pub struct HeaderBytes(pub Vec<u8>);
+
+impl<'a> Arbitrary<'a> for HeaderBytes {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE);
+        bytes.extend_from_slice(u.bytes(4 + 32 + 32 + 32 + 4 + 4 + 32)?);
+        // CompactSize encoding of the solution length
+        bytes.push(0xfd);
+        bytes.extend_from_slice(&(SOLUTION_SIZE as u16).to_le_bytes());
+        bytes.extend_from_slice(u.bytes(SOLUTION_SIZE)?);
+        Ok(Self(bytes))
+    }
+}
+
+impl HeaderBytes {
+    /// Run the header decoder on the bytes
+    pub fn decode(&self) -> anyhow::Result<Header> {
+        Ok(Header::zcash_deserialize(self.0.as_slice())?)
+    }
+}
@@ -0,0 +1,270 @@
+//! Runtime-dispatched hashing for the MMR and Merkle hot paths.
+//!
+//! MMR levels are hashed a pair at a time by the portable implementation, or eight pairs at a
+//! time with AVX2 when the CPU supports it. Both produce the digests of
+//! [`block_mmr::hash_pair`]. SHA-256d (the transaction Merkle paths) goes through `sha2`, which
+//! already selects the SHA extensions at runtime; [`CpuFeatures`] reports whether they are used.
+
+use std::sync::OnceLock;
+
+use crate::block_mmr::{hash_pair, Blake2sDigest};
+
+/// SIMD features of the CPU relevant to hashing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CpuFeatures {
+    /// 256-bit integer vectors, used for 8-way Blake2s
+    pub avx2: bool,
+    /// SHA extensions, used by `sha2` for SHA-256
+    pub sha: bool,
+}
+
+impl CpuFeatures {
+    /// Features of the running CPU, detected once
+    pub fn detect() -> Self {
+        static FEATURES: OnceLock<CpuFeatures> = OnceLock::new();
+        *FEATURES.get_or_init(|| {
+            #[cfg(target_arch = "x86_64")]
+            {
+                CpuFeatures {
+                    avx2: std::arch::is_x86_feature_detected!("avx2"),
+                    sha: std::arch::is_x86_feature_detected!("sha"),
+                }
+            }
+            #[cfg(not(target_arch = "x86_64"))]
+            {
+                CpuFeatures::default()
+            }
+        })
+    }
+}
+
+/// Implementation of the Blake2s pair hashing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// One pair at a time
+    Portable,
+    /// Eight pairs at a time with AVX2, falling back to [`Backend::Portable`] on CPUs without it
+    Avx2,
+}
+
+impl Backend {
+    /// Fastest backend supported by the running CPU
+    pub fn detect() -> Self {
+        if CpuFeatures::detect().avx2 {
+            Backend::Avx2
+        } else {
+            Backend::Portable
+        }
+    }
+
+    /// Hash consecutive pairs of `nodes`: `hash_pair(nodes[0], nodes[1])`,
+    /// `hash_pair(nodes[2], nodes[3])`, ...
+    ///
+    /// # Panics
+    ///
+    /// If `nodes` has an odd length.
+    pub fn hash_level(self, nodes: &[Blake2sDigest]) -> Vec<Blake2sDigest> {
+        assert!(nodes.len() % 2 == 0, "Odd number of MMR nodes");
+        let mut parents = Vec::with_capacity(nodes.len() / 2);
+        let mut rest = nodes;
+        #[cfg(target_arch = "x86_64")]
+        if self == Backend::Avx2 && CpuFeatures::detect().avx2 {
+            let mut chunks = nodes.chunks_exact(2 * avx2::LANES);
+            for chunk in &mut chunks {
+                // SAFETY: AVX2 support was checked above
+                let digests = unsafe { avx2::hash_pairs(chunk.try_into().unwrap()) };
+                parents.extend(digests);
+            }
+            rest = chunks.remainder();
+        }
+        parents.extend(
+            rest.chunks_exact(2)
+                .map(|pair| hash_pair(&pair[0], &pair[1])),
+        );
+        parents
+    }
+}
+
+/// Hash consecutive pairs of `nodes` with the fastest backend, see [`Backend::hash_level`]
+pub fn hash_level(nodes: &[Blake2sDigest]) -> Vec<Blake2sDigest> {
+    Backend::detect().hash_level(nodes)
+}
+
+/// Root of the perfect binary tree with the given leaves, hashed level by level
+///
+/// # Panics
+///
+/// If `leaves` is empty: a perfect binary tree has at least one leaf.
+pub fn tree_root(leaves: &[Blake2sDigest]) -> Blake2sDigest {
+    assert!(!leaves.is_empty(), "tree_root of an empty set of leaves");
+    let backend = Backend::detect();
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = backend.hash_level(&level);
+    }
+    level[0]
+}
+
+/// 8-way Blake2s of 64-byte messages, the size of an MMR pair.
+///
+/// Lane `i` of each vector holds the state or message word of pair `i`. Messages and digests are
+/// read and written as big-endian words, which folds the word swaps of `hash_pair` into the loads
+/// and stores.
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+    use std::arch::x86_64::*;
+
+    use crate::block_mmr::Blake2sDigest;
+
+    pub const LANES: usize = 8;
+
+    const IV: [u32; 8] = [
+        0x6A09E667, 0xBB67AE85, 0x3C6EF372, 0xA54FF53A, 0x510E527F, 0x9B05688C, 0x1F83D9AB,
+        0x5BE0CD19,
+    ];
+
+    const SIGMA: [[usize; 16]; 10] = [
+        [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+        [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+        [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+        [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+        [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+        [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+        [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+        [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+        [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+        [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+    ];
+
+    /// Parameter block of an unkeyed 32-byte digest
+    const PARAMS: u32 = 0x0101_0020;
+    /// Length of the single message block
+    const BLOCK_LEN: u32 = 64;
+
+    macro_rules! rotr {
+        ($x:expr, $n:literal) => {
+            _mm256_or_si256(
+                _mm256_srli_epi32::<$n>($x),
+                _mm256_slli_epi32::<{ 32 - $n }>($x),
+            )
+        };
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn g(
+        v: &mut [__m256i; 16],
+        a: usize,
+        b: usize,
+        c: usize,
+        d: usize,
+        x: __m256i,
+        y: __m256i,
+    ) {
+        v[a] = _mm256_add_epi32(_mm256_add_epi32(v[a], v[b]), x);
+        v[d] = rotr!(_mm256_xor_si256(v[d], v[a]), 16);
+        v[c] = _mm256_add_epi32(v[c], v[d]);
+        v[b] = rotr!(_mm256_xor_si256(v[b], v[c]), 12);
+        v[a] = _mm256_add_epi32(_mm256_add_epi32(v[a], v[b]), y);
+        v[d] = rotr!(_mm256_xor_si256(v[d], v[a]), 8);
+        v[c] = _mm256_add_epi32(v[c], v[d]);
+        v[b] = rotr!(_mm256_xor_si256(v[b], v[c]), 7);
+    }
+
+    /// Hash the pairs `(nodes[2i], nodes[2i + 1])`
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn hash_pairs(nodes: &[Blake2sDigest; 2 * LANES]) -> [Blake2sDigest; LANES] {
+        let mut words = [[0u32; LANES]; 16];
+        for (lane, pair) in nodes.chunks_exact(2).enumerate() {
+            let message = pair[0].chunks_exact(4).chain(pair[1].chunks_exact(4));
+            for (word, bytes) in message.enumerate() {
+                words[word][lane] = u32::from_be_bytes(bytes.try_into().unwrap());
+            }
+        }
+        let mut m = [_mm256_setzero_si256(); 16];
+        for (vector, word) in m.iter_mut().zip(&words) {
+            *vector = _mm256_loadu_si256(word.as_ptr().cast());
+        }
+
+        let mut h = [_mm256_setzero_si256(); 8];
+        for (vector, word) in h.iter_mut().zip(IV) {
+            *vector = _mm256_set1_epi32(word as i32);
+        }
+        h[0] = _mm256_set1_epi32((IV[0] ^ PARAMS) as i32);
+        let mut v = [_mm256_setzero_si256(); 16];
+        v[..8].copy_from_slice(&h);
+        for (vector, word) in v[8..].iter_mut().zip(IV) {
+            *vector = _mm256_set1_epi32(word as i32);
+        }
+        // Counter of the last (and only) block, and the finalization flag
+        v[12] = _mm256_set1_epi32((IV[4] ^ BLOCK_LEN) as i32);
+        v[14] = _mm256_set1_epi32(!IV[6] as i32);
+
+        for s in &SIGMA {
+            g(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+            g(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+            g(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+            g(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+            g(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+            g(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+            g(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+            g(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+        }
+
+        let mut digests = [[0u8; 32]; LANES];
+        for (i, (state, (low, high))) in h.iter().zip(v[..8].iter().zip(&v[8..])).enumerate() {
+            let word = _mm256_xor_si256(*state, _mm256_xor_si256(*low, *high));
+            let mut lanes = [0u32; LANES];
+            _mm256_storeu_si256(lanes.as_mut_ptr().cast(), word);
+            for (digest, lane) in digests.iter_mut().zip(lanes) {
+                digest[4 * i..4 * i + 4].copy_from_slice(&lane.to_be_bytes());
+            }
+        }
+        digests
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nodes(count: u8) -> Vec<Blake2sDigest> {
+        (0..count)
+            .map(|i| std::array::from_fn(|j| i.wrapping_mul(31).wrapping_add(j as u8)))
+            .collect()
+    }
+
+    #[test]
+    fn test_backends_match() {
+        // 19 pairs: two full AVX2 chunks and a remainder
+        let nodes = nodes(38);
+        let expected: Vec<Blake2sDigest> = nodes
+            .chunks_exact(2)
+            .map(|pair| hash_pair(&pair[0], &pair[1]))
+            .collect();
+        assert_eq!(Backend::Portable.hash_level(&nodes), expected);
+        assert_eq!(Backend::Avx2.hash_level(&nodes), expected);
+        assert_eq!(hash_level(&[]), Vec::<Blake2sDigest>::new());
+    }
+
+    #[test]
+    fn test_tree_root() {
+        let leaves = nodes(4);
+        let expected = hash_pair(
+            &hash_pair(&leaves[0], &leaves[1]),
+            &hash_pair(&leaves[2], &leaves[3]),
+        );
+        assert_eq!(tree_root(&leaves), expected);
+        assert_eq!(tree_root(&leaves[..1]), leaves[0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "tree_root of an empty set of leaves")]
+    fn test_tree_root_empty() {
+        tree_root(&[]);
+    }
+}
@@ -0,0 +1,302 @@
+//! Message catalog of the user-facing verification results and error hints.
+//!
+//! As with gettext, messages are looked up by their English text, so call sites stay readable
+//! and untranslated messages fall back to English. Error messages themselves are not translated:
+//! they are meant for bug reports and logs, while hints tell the user what to do.
+
+use std::str::FromStr;
+
+use crate::finality::PROOF_COVERAGE_HINT;
+use crate::manifest::{MANIFEST_RELEASE_HINT, MANIFEST_SIGNATURE_HINT, MANIFEST_STALE_HINT};
+use crate::report::{
+    BRIDGE_AUTH_HINT, BRIDGE_DOWN_HINT, BRIDGE_MALFORMED_HINT, BRIDGE_TIMEOUT_HINT,
+    BRIDGE_TLS_HINT, BRIDGE_UNREACHABLE_HINT, NOT_FOUND_HINT, PRE_HEARTWOOD_HINT,
+};
+use crate::untrusted::LIMIT_HINT;
+use crate::verify::{
+    CHAIN_STATE_HASH_HINT, CHAIN_STATE_HEIGHT_HINT, INSUFFICIENT_CONFIRMATIONS_HINT,
+    MMR_MISMATCH_HINT, PROGRAM_VERSION_HINT, PROOF_LAG_HINT,
+};
+
+/// Language of the user-facing messages
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    English,
+    Spanish,
+    French,
+}
+
+impl Locale {
+    /// Locale of the environment: the first of `LC_ALL`, `LC_MESSAGES` and `LANG` that is set,
+    /// English if it is not supported
+    pub fn from_env() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_default()
+    }
+
+    /// Translation of an English message, the message itself if it has none
+    pub fn tr(self, message: &'static str) -> &'static str {
+        let catalog = match self {
+            Locale::English => return message,
+            Locale::Spanish => SPANISH,
+            Locale::French => FRENCH,
+        };
+        catalog
+            .iter()
+            .find(|(english, _)| *english == message)
+            .map_or(message, |(_, translation)| translation)
+    }
+}
+
+impl FromStr for Locale {
+    type Err = anyhow::Error;
+
+    /// Parse a language tag or POSIX locale, such as `es`, `fr-CA` or `fr_FR.UTF-8`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let language = s
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match language.as_str() {
+            "en" | "c" | "posix" => Ok(Locale::English),
+            "es" => Ok(Locale::Spanish),
+            "fr" => Ok(Locale::French),
+            _ => anyhow::bail!("Unsupported locale {s:?} (supported: en, es, fr)"),
+        }
+    }
+}
+
+const SPANISH: &[(&str, &str)] = &[
+    ("Error", "Error"),
+    ("Caused by", "Causado por"),
+    ("hint", "sugerencia"),
+    (
+        "Transaction {transaction} confirmed in block {block} (height {height}) with 1 confirmation",
+        "Transacción {transaction} confirmada en el bloque {block} (altura {height}) con 1 confirmación",
+    ),
+    (
+        "Transaction {transaction} confirmed in block {block} (height {height}) with {confirmations} confirmations",
+        "Transacción {transaction} confirmada en el bloque {block} (altura {height}) con {confirmations} confirmaciones",
+    ),
+    ("chain validity STARK-verified", "validez de la cadena verificada por STARK"),
+    ("chain validity not verified", "validez de la cadena no verificada"),
+    ("Block", "Bloque"),
+    ("Confirmations", "Confirmaciones"),
+    ("Expiry height", "Altura de expiración"),
+    ("Proof lag", "Retraso de la prueba"),
+    ("blocks", "bloques"),
+    (
+        CHAIN_STATE_HEIGHT_HINT,
+        "Verifique contra un estado de la cadena a la altura del bloque o posterior",
+    ),
+    (
+        INSUFFICIENT_CONFIRMATIONS_HINT,
+        "Espere a que se minen más bloques, o verifique contra una altura de cadena posterior",
+    ),
+    (
+        MMR_MISMATCH_HINT,
+        "El puente puede seguir otra cadena o servir datos obsoletos: no confíe en esta prueba y reintente con otro puente",
+    ),
+    (
+        CHAIN_STATE_HASH_HINT,
+        "Use el estado de la cadena a la altura para la que se generó la prueba STARK",
+    ),
+    (
+        PROGRAM_VERSION_HINT,
+        "La prueba STARK proviene de otra versión del programa: revise bootloader_hash y task_program_hash en la configuración del verificador",
+    ),
+    (
+        PROOF_LAG_HINT,
+        "El probador va por detrás del puente: reintente cuando se publique una prueba del estado de la cadena más reciente, o aumente --max-proof-lag",
+    ),
+    (
+        PROOF_COVERAGE_HINT,
+        "La política de finalidad requiere una prueba STARK del estado de la cadena verificada: proporcione una, o use una política de profundidad de confirmaciones",
+    ),
+    (
+        LIMIT_HINT,
+        "Si la prueba es legítima, aumente el límite en la sección `limits` de la configuración del verificador",
+    ),
+    (
+        BRIDGE_TLS_HINT,
+        "El certificado del puente fue rechazado: revise --bridge-pin y --bridge-ca-cert",
+    ),
+    (
+        BRIDGE_UNREACHABLE_HINT,
+        "Revise la URL del puente (--bridge-url) y que el nodo puente esté en ejecución",
+    ),
+    (
+        BRIDGE_TIMEOUT_HINT,
+        "El nodo puente no respondió a tiempo: reintente, o use otro puente",
+    ),
+    (
+        BRIDGE_MALFORMED_HINT,
+        "La respuesta del puente está mal formada: compruebe que su versión coincide (GET /version)",
+    ),
+    (
+        PRE_HEARTWOOD_HINT,
+        "Los bloques anteriores a Heartwood no tienen prueba FlyClient: use verify-tx sin --verify-block-proof, o elija un bloque posterior",
+    ),
+    (
+        NOT_FOUND_HINT,
+        "Puede que el puente aún no lo haya indexado: revise el identificador y su orden de bytes (--id-type), o reintente más tarde",
+    ),
+    (
+        BRIDGE_DOWN_HINT,
+        "El nodo Zcash del puente no está disponible: reintente más tarde, o use otro puente",
+    ),
+    (
+        BRIDGE_AUTH_HINT,
+        "El puente requiere una clave de API o su cuota está agotada: use otro puente",
+    ),
+    (
+        MANIFEST_SIGNATURE_HINT,
+        "El manifiesto no está firmado con la clave de publicación: revise --manifest-key y --manifest-url",
+    ),
+    (
+        MANIFEST_RELEASE_HINT,
+        "Compruebe --prover-version con las versiones listadas en el manifiesto",
+    ),
+    (
+        MANIFEST_STALE_HINT,
+        "El servidor devolvió un manifiesto obsoleto: revise --manifest-url, o espere a que el editor firme uno nuevo",
+    ),
+];
+
+const FRENCH: &[(&str, &str)] = &[
+    ("Error", "Erreur"),
+    ("Caused by", "Causé par"),
+    ("hint", "conseil"),
+    (
+        "Transaction {transaction} confirmed in block {block} (height {height}) with 1 confirmation",
+        "Transaction {transaction} confirmée dans le bloc {block} (hauteur {height}) avec 1 confirmation",
+    ),
+    (
+        "Transaction {transaction} confirmed in block {block} (height {height}) with {confirmations} confirmations",
+        "Transaction {transaction} confirmée dans le bloc {block} (hauteur {height}) avec {confirmations} confirmations",
+    ),
+    ("chain validity STARK-verified", "validité de la chaîne vérifiée par STARK"),
+    ("chain validity not verified", "validité de la chaîne non vérifiée"),
+    ("Block", "Bloc"),
+    ("Confirmations", "Confirmations"),
+    ("Expiry height", "Hauteur d'expiration"),
+    ("Proof lag", "Retard de la preuve"),
+    ("blocks", "blocs"),
+    (
+        CHAIN_STATE_HEIGHT_HINT,
+        "Vérifiez par rapport à un état de la chaîne à la hauteur du bloc ou au-delà",
+    ),
+    (
+        INSUFFICIENT_CONFIRMATIONS_HINT,
+        "Attendez que d'autres blocs soient minés, ou vérifiez par rapport à une hauteur de chaîne ultérieure",
+    ),
+    (
+        MMR_MISMATCH_HINT,
+        "Le pont suit peut-être une autre chaîne ou sert des données périmées : ne vous fiez pas à cette preuve et réessayez avec un autre pont",
+    ),
+    (
+        CHAIN_STATE_HASH_HINT,
+        "Utilisez l'état de la chaîne à la hauteur pour laquelle la preuve STARK a été générée",
+    ),
+    (
+        PROGRAM_VERSION_HINT,
+        "La preuve STARK provient d'une autre version du programme : vérifiez bootloader_hash et task_program_hash dans la configuration du vérificateur",
+    ),
+    (
+        PROOF_LAG_HINT,
+        "Le prouveur est en retard sur le pont : réessayez quand une preuve plus récente de l'état de la chaîne sera publiée, ou augmentez --max-proof-lag",
+    ),
+    (
+        PROOF_COVERAGE_HINT,
+        "La politique de finalité exige une preuve STARK vérifiée de l'état de la chaîne : fournissez-en une, ou utilisez une politique de profondeur de confirmations",
+    ),
+    (
+        LIMIT_HINT,
+        "Si la preuve est légitime, augmentez la limite dans la section `limits` de la configuration du vérificateur",
+    ),
+    (
+        BRIDGE_TLS_HINT,
+        "Le certificat du pont a été rejeté : vérifiez --bridge-pin et --bridge-ca-cert",
+    ),
+    (
+        BRIDGE_UNREACHABLE_HINT,
+        "Vérifiez l'URL du pont (--bridge-url) et que le nœud du pont est en cours d'exécution",
+    ),
+    (
+        BRIDGE_TIMEOUT_HINT,
+        "Le nœud du pont n'a pas répondu à temps : réessayez, ou utilisez un autre pont",
+    ),
+    (
+        BRIDGE_MALFORMED_HINT,
+        "La réponse du pont est mal formée : vérifiez que sa version correspond (GET /version)",
+    ),
+    (
+        PRE_HEARTWOOD_HINT,
+        "Les blocs antérieurs à Heartwood n'ont pas de preuve FlyClient : utilisez verify-tx sans --verify-block-proof, ou choisissez un bloc ultérieur",
+    ),
+    (
+        NOT_FOUND_HINT,
+        "Le pont ne l'a peut-être pas encore indexé : vérifiez l'identifiant et son ordre d'octets (--id-type), ou réessayez plus tard",
+    ),
+    (
+        BRIDGE_DOWN_HINT,
+        "Le nœud Zcash du pont est indisponible : réessayez plus tard, ou utilisez un autre pont",
+    ),
+    (
+        BRIDGE_AUTH_HINT,
+        "Le pont exige une clé d'API ou son quota est épuisé : utilisez un autre pont",
+    ),
+    (
+        MANIFEST_SIGNATURE_HINT,
+        "Le manifeste n'est pas signé par la clé de publication : vérifiez --manifest-key et --manifest-url",
+    ),
+    (
+        MANIFEST_RELEASE_HINT,
+        "Vérifiez --prover-version parmi les versions listées dans le manifeste",
+    ),
+    (
+        MANIFEST_STALE_HINT,
+        "Le serveur a renvoyé un manifeste périmé : vérifiez --manifest-url, ou attendez que l'éditeur en signe un nouveau",
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_locale() {
+        assert_eq!("es".parse::<Locale>().unwrap(), Locale::Spanish);
+        assert_eq!("fr_FR.UTF-8".parse::<Locale>().unwrap(), Locale::French);
+        assert_eq!("fr-CA".parse::<Locale>().unwrap(), Locale::French);
+        assert_eq!("C".parse::<Locale>().unwrap(), Locale::English);
+        assert_eq!("en_US".parse::<Locale>().unwrap(), Locale::English);
+        assert!("de_DE".parse::<Locale>().is_err());
+    }
+
+    #[test]
+    fn test_translate() {
+        assert_eq!(Locale::Spanish.tr("Confirmations"), "Confirmaciones");
+        assert_eq!(Locale::French.tr("Block"), "Bloc");
+        assert_eq!(Locale::English.tr("Block"), "Block");
+        // Untranslated messages fall back to English
+        assert_eq!(
+            Locale::French.tr("Not in the catalog"),
+            "Not in the catalog"
+        );
+    }
+
+    #[test]
+    fn test_catalogs_match() {
+        let english: Vec<&str> = SPANISH.iter().map(|(english, _)| *english).collect();
+        let french: Vec<&str> = FRENCH.iter().map(|(english, _)| *english).collect();
+        assert_eq!(english, french);
+        assert_ne!(Locale::Spanish.tr(PRE_HEARTWOOD_HINT), PRE_HEARTWOOD_HINT);
+    }
+}
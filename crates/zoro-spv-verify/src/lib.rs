@@ -12,18 +12,83 @@
 //!
 //! This allows verifying that a transaction is confirmed with N confirmations
 //! without trusting any third party.
+//!
+//! # Cargo features
+//!
+//! - `stark`: STARK proofs of the chain state, verified with the stwo-cairo verifier
+//! - `flyclient`: block inclusion in the FlyClient MMR
+//! - `client`: HTTP client for the bridge node and release manifests, with TLS pinning
+//! - `local-transport`: bridge nodes reached through a Unix domain socket or in the same process
+//! - `cli`: the `spv-cli` binary, enabling all of the above (default)
+//!
+//! Without features, the crate keeps the transaction Merkle proofs, header and chain state checks,
+//! block MMR range proofs, schemas and test vectors.
 
+pub mod addresses;
+pub mod archive;
+pub mod block_mmr;
+pub mod build_info;
+pub mod cancel;
+pub mod canonical;
+pub mod chain_state;
+pub mod checkpoint;
+pub mod conformance;
+pub mod finality;
+#[cfg(feature = "arbitrary")]
+pub mod fuzz;
+pub mod hashing;
+pub mod i18n;
+pub mod manifest;
+pub mod mmr_math;
+#[cfg(feature = "flyclient")]
+pub mod mmr_view;
+#[cfg(feature = "client")]
+pub mod pinning;
 pub mod proof;
+pub mod report;
+pub mod schema;
+pub mod summary;
+#[cfg(feature = "client")]
+pub mod transport;
+pub mod untrusted;
+pub mod vectors;
 pub mod verify;
 pub mod work;
 
+pub use addresses::{AddressKind, TransparentAddress};
+pub use archive::{verify_archive, ArchiveRecord, ArchiveWriter, ArchivedResult};
+pub use block_mmr::{verify_range_proof, BlockMmr, RangeProof, SparseRoots, SparseRootsRecord};
+pub use build_info::BuildInfo;
+pub use cancel::{CancellationToken, Cancelled};
+pub use canonical::{to_canonical_json, CanonicalSerialize};
+pub use chain_state::{check_chain_state, fold_headers};
+pub use checkpoint::{check_checkpoints, Checkpoint};
+pub use finality::FinalityPolicy;
+pub use i18n::Locale;
+pub use manifest::{ManifestKey, Release, ReleaseManifest, SignedManifest};
+#[cfg(feature = "flyclient")]
+pub use mmr_view::{MmrView, ViewedRoot};
 pub use proof::{
-    BlockInclusionProof, BootloaderOutput, ChainState, ChainStateProof, CompressedSpvProof,
-    FullInclusionProof, TaskResult, TransactionInclusionProof,
+    BlockHeaderRecord, BlockInclusionProof, BootloaderOutput, ChainState, CoinbaseOutput,
+    CoinbaseProofResponse, ConfirmationStatus, FlyClientNodeData, FullProofResponse, MatchedTxId,
+    MmrRootRecord, ProofSource, ProverHeartbeat, ProvingBatch, ProvingStatus, TaskResult,
+    TransactionAbsenceProof, TransactionBucketResponse, TransactionInclusionProof,
+    TransactionProofResponse, TxIdKind,
+};
+#[cfg(feature = "stark")]
+pub use proof::{ChainStateProof, CompressedSpvProof, FullInclusionProof};
+pub use untrusted::ProofLimits;
+#[cfg(feature = "flyclient")]
+pub use verify::verify_block_inclusion;
+pub use verify::{
+    check_proof_lag, check_transaction_expiry, verify_block_timestamp, verify_coinbase_proof,
+    verify_mmr_root_history, verify_transaction, verify_transaction_absence, ExpiryPolicy,
+    VerificationResult, VerifierConfig, VerifyOptions,
 };
+#[cfg(all(feature = "stark", feature = "flyclient"))]
 pub use verify::{
-    verify_block_inclusion, verify_chain_state, verify_full_inclusion_proof,
-    verify_full_inclusion_proof_with_options, verify_proof, verify_transaction, VerificationResult,
-    VerifierConfig, VerifyOptions,
+    quick_verify, verify_full_inclusion_proof, verify_full_inclusion_proof_with_options,
 };
+#[cfg(feature = "stark")]
+pub use verify::{verify_chain_state, verify_proof};
 pub use work::verify_subchain_work;
@@ -1,42 +1,47 @@
 //! CLI tool for querying transaction inclusion proofs from a bridge node.
 
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 use std::sync::Arc;
+use std::time::Duration;
 
 use accumulators::{
-    hasher::flyclient::ZcashFlyclientHasher,
-    mmr::{leaf_count_to_mmr_size, MMR},
-    store::memory::InMemoryStore,
+    hasher::flyclient::ZcashFlyclientHasher, mmr::MMR, store::memory::InMemoryStore,
 };
 use cairo_air::utils::{deserialize_proof_from_file, ProofFormat};
-use clap::{Parser, Subcommand};
-use serde::{Deserialize, Serialize};
-use tracing::info;
+use clap::{Args, Parser, Subcommand};
+use hex::FromHex;
+use tracing::{info, warn};
 use tracing_subscriber::EnvFilter;
+use zoro_spv_verify::archive::{verify_archive, ArchiveWriter, ArchivedResult};
+use zoro_spv_verify::build_info::{self, BuildInfo};
+use zoro_spv_verify::manifest::{self, ManifestKey, ReleaseManifest, SequenceFile};
+use zoro_spv_verify::mmr_math::leaf_count_to_mmr_size;
+use zoro_spv_verify::pinning::{load_ca_certs, SpkiPin, TlsPinning};
+use zoro_spv_verify::report::{report, status_hint, WithHint};
+use zoro_spv_verify::summary::render_human_in;
+use zoro_spv_verify::transport::BridgeClient;
+use zoro_spv_verify::untrusted::parse_json_limited;
 use zoro_spv_verify::{
-    verify_chain_state, verify_transaction, ChainState, TransactionInclusionProof, VerifierConfig,
+    check_checkpoints, check_proof_lag, verify_chain_state, verify_coinbase_proof,
+    verify_mmr_root_history, verify_transaction, verify_transaction_absence, BlockInclusionProof,
+    CancellationToken, CanonicalSerialize, ChainState, CoinbaseProofResponse, ConfirmationStatus,
+    FinalityPolicy, FullProofResponse, Locale, MatchedTxId, MmrRootRecord, ProofLimits,
+    ProofSource, ProvingStatus, TransactionAbsenceProof, TransactionBucketResponse,
+    TransactionInclusionProof, TransactionProofResponse, TxIdKind, VerificationResult,
+    VerifierConfig,
 };
-
-/// Block inclusion proof from the bridge node (FlyClient MMR)
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct BlockInclusionProof {
-    /// Block height
-    pub block_height: u32,
-    /// MMR peak hashes at the time of proof generation
-    pub peaks_hashes: Vec<String>,
-    /// Sibling hashes needed to reconstruct the path to the root
-    pub siblings_hashes: Vec<String>,
-    /// Leaf index of the block in the MMR
-    pub leaf_index: usize,
-    /// Total number of leaves in the MMR
-    pub leaf_count: usize,
-}
+use zoro_spv_verify::{schema, vectors};
+use zoro_zcash_client::{DisplayOrderHash, InternalOrderHash};
 
 /// SPV verification CLI for Zcash transaction proofs
 #[derive(Parser)]
 #[command(name = "spv-cli")]
 #[command(about = "Query and verify Zcash transaction inclusion proofs", long_about = None)]
+#[command(version)]
 struct Cli {
-    /// Bridge node URL (e.g., http://127.0.0.1:5000)
+    /// Bridge node URL (e.g., http://127.0.0.1:5000, or unix:/run/zoro/bridge.sock for a bridge
+    /// behind a Unix domain socket)
     #[arg(
         short,
         long,
@@ -45,10 +50,71 @@ struct Cli {
     )]
     bridge_url: String,
 
+    /// PEM file of the certificates trusted for the bridge node instead of the built-in roots
+    #[arg(long, env = "BRIDGE_CA_CERT")]
+    bridge_ca_cert: Option<PathBuf>,
+
+    /// Hex SHA-256 digest of a SubjectPublicKeyInfo the bridge certificate chain must present
+    /// (repeatable)
+    #[arg(long = "bridge-pin", env = "BRIDGE_SPKI_PINS", value_delimiter = ',')]
+    bridge_pins: Vec<SpkiPin>,
+
+    /// Proxy for the bridge node requests (e.g., socks5h://127.0.0.1:9050)
+    #[arg(long, env = "BRIDGE_PROXY")]
+    proxy: Option<String>,
+
+    /// Route the bridge node requests through the local Tor SOCKS proxy
+    #[arg(long, conflicts_with = "proxy")]
+    tor: bool,
+
+    /// Language of the results and hints (en, es, fr), defaults to the locale of the environment
+    #[arg(long, global = true)]
+    lang: Option<Locale>,
+
+    /// Append-only archive the outcomes of `verify-full` and `verify-file` are recorded to, with
+    /// their proof digest, verifier config and build (optional)
+    #[arg(long, env = "ZORO_ARCHIVE", global = true)]
+    archive: Option<PathBuf>,
+
+    #[command(flatten)]
+    manifest: ManifestArgs,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+/// Where to take the verifier config from when no `--config` file is given
+#[derive(Args)]
+struct ManifestArgs {
+    /// HTTPS URL of the signed release manifest providing the verifier config (defaults to the
+    /// built-in config)
+    #[arg(
+        long,
+        env = "ZORO_MANIFEST_URL",
+        global = true,
+        requires = "manifest_key"
+    )]
+    manifest_url: Option<String>,
+
+    /// Hex Ed25519 public key the release manifest must be signed with
+    #[arg(long, env = "ZORO_MANIFEST_KEY", global = true)]
+    manifest_key: Option<ManifestKey>,
+
+    /// Prover version to take the verifier config of from the manifest (defaults to the newest)
+    #[arg(long, global = true, requires = "manifest_url")]
+    prover_version: Option<String>,
+
+    /// File recording the sequence number of the newest release manifest accepted, so that older
+    /// manifests are rejected
+    #[arg(
+        long,
+        env = "ZORO_MANIFEST_SEQUENCE_FILE",
+        global = true,
+        requires = "manifest_url"
+    )]
+    manifest_sequence_file: Option<PathBuf>,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Get a transaction inclusion proof from the bridge node
@@ -56,6 +122,9 @@ enum Commands {
         /// Transaction ID (hex string)
         tx_id: String,
 
+        #[command(flatten)]
+        lookup: TxLookupArgs,
+
         /// Output file path (optional, defaults to stdout)
         #[arg(short, long)]
         output: Option<String>,
@@ -65,6 +134,35 @@ enum Commands {
     Verify {
         /// Transaction ID (hex string)
         tx_id: String,
+
+        #[command(flatten)]
+        lookup: TxLookupArgs,
+    },
+
+    /// Get and verify a proof that a transaction is not included in a block
+    VerifyAbsent {
+        /// Transaction ID (hex string, display order)
+        tx_id: String,
+
+        /// Height of the block
+        #[arg(long)]
+        block_height: u32,
+    },
+
+    /// Get and verify the coinbase transaction of a block, and list its transparent outputs
+    VerifyCoinbase {
+        /// Height of the block
+        block_height: u32,
+    },
+
+    /// Get the confirmations of a transaction and whether a STARK proof covers it, without
+    /// fetching any proof
+    ConfirmationStatus {
+        /// Transaction ID (hex string)
+        tx_id: String,
+
+        #[command(flatten)]
+        lookup: TxLookupArgs,
     },
 
     /// Get chain state at a specific block height
@@ -126,6 +224,9 @@ enum Commands {
         /// Transaction ID (hex string)
         tx_id: String,
 
+        #[command(flatten)]
+        lookup: TxLookupArgs,
+
         /// Path to the Cairo STARK proof JSON file for chain state
         #[arg(short, long)]
         chain_state_proof: String,
@@ -152,6 +253,11 @@ enum Commands {
         #[arg(long)]
         min_confirmations: Option<u32>,
 
+        /// Finality policy: confirmations:<N>, work:<decimal> or proof_covered (overrides config
+        /// if provided)
+        #[arg(long, conflicts_with = "min_confirmations")]
+        finality: Option<FinalityPolicy>,
+
         /// Skip block inclusion proof verification (for testing when FlyClient not synced)
         #[arg(long)]
         skip_block_proof: bool,
@@ -159,6 +265,60 @@ enum Commands {
         /// Skip chain state STARK proof verification (for testing)
         #[arg(long)]
         skip_chain_proof: bool,
+
+        /// Check the block timestamp against the chain state's recent timestamps
+        #[arg(long)]
+        check_timestamps: bool,
+
+        /// Give up on the verification after this many seconds, checked between its layers
+        #[arg(long, value_name = "SECS")]
+        timeout_secs: Option<u64>,
+    },
+
+    /// Verify a transaction from local proof files, without contacting a bridge node
+    VerifyFile {
+        /// Path to the transaction inclusion proof JSON file (as written by `get-proof`)
+        #[arg(long)]
+        tx_proof: String,
+
+        /// Path to the block inclusion proof JSON file (as returned by the bridge's
+        /// `/block-inclusion-proof/:hash`)
+        #[arg(long)]
+        block_proof: String,
+
+        /// Path to the chain state JSON file (as written by `chain-state`)
+        #[arg(long)]
+        chain_state: String,
+
+        /// Path to the Cairo STARK proof JSON file for the chain state
+        #[arg(long)]
+        stark: String,
+
+        /// Path to the FlyClient MMR root record JSON file (as returned by `/mmr/root/:height`)
+        /// to check the block inclusion proof against (optional)
+        #[arg(long)]
+        mmr_root: Option<String>,
+
+        /// Path to verifier config JSON file (optional, uses defaults if not provided)
+        #[arg(short, long)]
+        config: Option<String>,
+
+        /// Minimum confirmations required (overrides config if provided)
+        #[arg(long)]
+        min_confirmations: Option<u32>,
+
+        /// Finality policy: confirmations:<N>, work:<decimal> or proof_covered (overrides config
+        /// if provided)
+        #[arg(long, conflicts_with = "min_confirmations")]
+        finality: Option<FinalityPolicy>,
+
+        /// Check the block timestamp against the chain state's recent timestamps
+        #[arg(long)]
+        check_timestamps: bool,
+
+        /// Give up on the verification after this many seconds, checked between its layers
+        #[arg(long, value_name = "SECS")]
+        timeout_secs: Option<u64>,
     },
 
     /// Verify a transaction with full inclusion proof (fetches and verifies all layers)
@@ -171,6 +331,9 @@ enum Commands {
         /// Transaction ID (hex string)
         tx_id: String,
 
+        #[command(flatten)]
+        lookup: TxLookupArgs,
+
         /// Path to the Cairo STARK proof JSON file for chain state verification
         #[arg(long)]
         stark_proof: Option<String>,
@@ -180,41 +343,121 @@ enum Commands {
         proof_height: Option<u32>,
 
         /// Minimum confirmations required (default: 6)
-        #[arg(long, default_value = "6")]
-        min_confirmations: u32,
+        #[arg(long)]
+        min_confirmations: Option<u32>,
+
+        /// Finality policy: confirmations:<N>, work:<decimal> or proof_covered
+        #[arg(long, conflicts_with = "min_confirmations")]
+        finality: Option<FinalityPolicy>,
 
         /// Verify block inclusion proof (FlyClient MMR) - disabled by default
         #[arg(long)]
         verify_block_proof: bool,
+
+        /// Fail if the latest proven chain state is more than this many blocks behind the bridge
+        /// head
+        #[arg(long)]
+        max_proof_lag: Option<u32>,
+    },
+
+    /// Check the hash chain of a verification archive written with `--archive`
+    VerifyArchive {
+        /// Path to the archive file
+        archive_file: PathBuf,
+    },
+
+    /// Write the conformance test vectors of the proof formats, checked against this verifier
+    GenVectors {
+        /// Output directory
+        #[arg(short, long, default_value = "vectors")]
+        out: PathBuf,
+    },
+
+    /// Write the JSON Schemas of the proof, bridge response and config formats
+    GenSchemas {
+        /// Output directory
+        #[arg(short, long, default_value = "schemas")]
+        out: PathBuf,
+    },
+
+    /// Sign a release manifest for publishing
+    SignManifest {
+        /// Path to the release manifest JSON file
+        manifest: PathBuf,
+
+        /// File holding the Ed25519 signing key as 32 bytes of hex
+        #[arg(long, env = "ZORO_MANIFEST_SIGNING_KEY_FILE")]
+        key_file: PathBuf,
+
+        /// Output file path (optional, defaults to stdout)
+        #[arg(short, long)]
+        output: Option<String>,
     },
 }
 
+/// How the bridge node should interpret a transaction identifier
+#[derive(Args)]
+struct TxLookupArgs {
+    /// Identifier type: txid in display order, txid in internal byte order, or NU5 auth digest
+    /// (defaults to trying both txid byte orders)
+    #[arg(long, value_parser = ["auto", "txid", "txid_reversed", "auth_digest"])]
+    id_type: Option<String>,
+
+    /// Height of the block containing the transaction (required for auth digest lookups)
+    #[arg(long)]
+    block_height: Option<u32>,
+
+    /// Fetch the proofs of every transaction whose txid starts with the same N hex digits and
+    /// pick the transaction locally, so the bridge only learns which bucket it is in
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u8).range(1..=64))]
+    bucket: Option<u8>,
+}
+
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
+async fn main() -> ExitCode {
+    if build_info::verbose_version_requested(std::env::args()) {
+        let info = BuildInfo::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"), &[]);
+        println!("{}", info.verbose_version());
+        return ExitCode::SUCCESS;
+    }
+
+    let cli = Cli::parse();
+    let locale = cli.lang.unwrap_or_else(Locale::from_env);
+    match run(cli, locale).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{}", report(&error, locale));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli, locale: Locale) -> anyhow::Result<()> {
     // Initialize logging
     tracing_subscriber::fmt()
         .with_env_filter(EnvFilter::from_default_env().add_directive("info".parse()?))
         .init();
 
-    let cli = Cli::parse();
-    let client = reqwest::Client::new();
+    let client = bridge_client(&cli)?;
+    let verifier_configs = VerifierConfigSource::new(&cli)?;
 
     match cli.command {
-        Commands::GetProof { tx_id, output } => {
+        Commands::GetProof {
+            tx_id,
+            lookup,
+            output,
+        } => {
             info!("Fetching transaction inclusion proof for {}", tx_id);
 
-            let url = format!("{}/transaction-proof/{}", cli.bridge_url, tx_id);
-            let response = client.get(&url).send().await?;
-
-            if !response.status().is_success() {
-                anyhow::bail!(
-                    "Failed to get transaction proof: {} - {}",
-                    response.status(),
-                    response.text().await.unwrap_or_default()
-                );
-            }
-
-            let proof: TransactionInclusionProof = response.json().await?;
+            let limits = verifier_configs.limits().await?;
+            let TransactionProofResponse {
+                proof,
+                matched,
+                source,
+            } = fetch_transaction_proof(&client, &cli.bridge_url, &tx_id, &lookup, &limits).await?;
+            limits.check_transaction_inclusion(&proof)?;
+            log_matched_id(&tx_id, matched.as_ref());
+            log_proof_source(source);
             let json = serde_json::to_string_pretty(&proof)?;
 
             if let Some(path) = output {
@@ -225,24 +468,21 @@ async fn main() -> anyhow::Result<()> {
             }
         }
 
-        Commands::Verify { tx_id } => {
+        Commands::Verify { tx_id, lookup } => {
             info!(
                 "Fetching and verifying transaction inclusion proof for {}",
                 tx_id
             );
 
-            let url = format!("{}/transaction-proof/{}", cli.bridge_url, tx_id);
-            let response = client.get(&url).send().await?;
-
-            if !response.status().is_success() {
-                anyhow::bail!(
-                    "Failed to get transaction proof: {} - {}",
-                    response.status(),
-                    response.text().await.unwrap_or_default()
-                );
-            }
-
-            let proof: TransactionInclusionProof = response.json().await?;
+            let limits = verifier_configs.limits().await?;
+            let TransactionProofResponse {
+                proof,
+                matched,
+                source,
+            } = fetch_transaction_proof(&client, &cli.bridge_url, &tx_id, &lookup, &limits).await?;
+            limits.check_transaction_inclusion(&proof)?;
+            log_matched_id(&tx_id, matched.as_ref());
+            log_proof_source(source);
 
             info!("Transaction: {}", proof.transaction.hash());
             info!("Block height: {}", proof.block_height);
@@ -264,6 +504,112 @@ async fn main() -> anyhow::Result<()> {
             );
         }
 
+        Commands::VerifyAbsent {
+            tx_id,
+            block_height,
+        } => {
+            info!(
+                "Fetching and verifying absence proof for {} in block {}",
+                tx_id, block_height
+            );
+
+            let txid: zebra_chain::transaction::Hash = tx_id.parse().map_err(|_| {
+                anyhow::anyhow!("Transaction ID must be 32 bytes of hex, got {tx_id:?}")
+            })?;
+            let limits = verifier_configs.limits().await?;
+            let url = format!(
+                "{}/transaction-absence/{}/{}",
+                cli.bridge_url, block_height, txid
+            );
+            let data = fetch_bytes(&client, &url, "transaction absence proof", &limits).await?;
+            let proof: TransactionAbsenceProof =
+                parse_json_limited(&data, "transaction absence proof", &limits)?;
+            limits.check_transaction_absence(&proof)?;
+            if proof.block_height != block_height {
+                anyhow::bail!(
+                    "Absence proof is for block {}, requested {}",
+                    proof.block_height,
+                    block_height
+                );
+            }
+
+            verify_transaction_absence(&txid, &proof)?;
+
+            info!(
+                "✓ Transaction absence proof verified against {} transactions",
+                proof.txids.len()
+            );
+            info!("The block header is not checked against the chain, see verify-block");
+            println!(
+                "Transaction {} is not included in block {} (height {})",
+                txid,
+                proof.block_header.hash(),
+                proof.block_height
+            );
+        }
+
+        Commands::VerifyCoinbase { block_height } => {
+            info!(
+                "Fetching and verifying coinbase proof for block {}",
+                block_height
+            );
+
+            let limits = verifier_configs.limits().await?;
+            let url = format!("{}/block/{}/coinbase-proof", cli.bridge_url, block_height);
+            let data = fetch_bytes(&client, &url, "coinbase proof", &limits).await?;
+            let response: CoinbaseProofResponse =
+                parse_json_limited(&data, "coinbase proof", &limits)?;
+            limits.check_transaction_inclusion(&response.proof)?;
+            log_proof_source(response.source);
+            if response.proof.block_height != block_height {
+                anyhow::bail!(
+                    "Coinbase proof is for block {}, requested {}",
+                    response.proof.block_height,
+                    block_height
+                );
+            }
+
+            verify_coinbase_proof(&response)?;
+
+            info!("✓ Coinbase proof verified");
+            info!("The block header is not checked against the chain, see verify-block");
+            println!(
+                "Coinbase transaction {} of block {} (height {}) pays {} zatoshis:",
+                response.proof.transaction.hash(),
+                response.proof.block_header.hash(),
+                block_height,
+                response.total_value
+            );
+            for output in &response.outputs {
+                println!(
+                    "  #{} {} zatoshis to {}",
+                    output.index,
+                    output.value,
+                    output.address.as_deref().unwrap_or(&output.script_pubkey)
+                );
+            }
+        }
+
+        Commands::ConfirmationStatus { tx_id, lookup } => {
+            let url = with_query(
+                format!("{}/confirmation-status/{}", cli.bridge_url, tx_id),
+                lookup_params(&lookup),
+            );
+            let limits = verifier_configs.limits().await?;
+            let response = client.get(&url, &limits).await?;
+
+            if !response.is_success() {
+                anyhow::bail!(
+                    "Failed to get confirmation status: {} - {}",
+                    response.status,
+                    response.text()
+                );
+            }
+
+            let status: ConfirmationStatus = response.json("confirmation status", &limits)?;
+            println!("{}", serde_json::to_string_pretty(&status)?);
+        }
+
         Commands::ChainState {
             block_height,
             output,
@@ -271,17 +617,18 @@ async fn main() -> anyhow::Result<()> {
             info!("Fetching chain state at height {}", block_height);
 
             let url = format!("{}/chain-state/{}", cli.bridge_url, block_height);
-            let response = client.get(&url).send().await?;
+            let limits = verifier_configs.limits().await?;
+            let response = client.get(&url, &limits).await?;
 
-            if !response.status().is_success() {
+            if !response.is_success() {
                 anyhow::bail!(
                     "Failed to get chain state: {} - {}",
-                    response.status(),
-                    response.text().await.unwrap_or_default()
+                    response.status,
+                    response.text()
                 );
             }
 
-            let chain_state: zoro_spv_verify::ChainState = response.json().await?;
+            let chain_state: zoro_spv_verify::ChainState = response.json("chain state", &limits)?;
             let json = serde_json::to_string_pretty(&chain_state)?;
 
             if let Some(path) = output {
@@ -296,33 +643,35 @@ async fn main() -> anyhow::Result<()> {
             info!("Fetching block header at height {}", block_height);
 
             let url = format!("{}/block-header/{}", cli.bridge_url, block_height);
-            let response = client.get(&url).send().await?;
+            let limits = verifier_configs.limits().await?;
+            let response = client.get(&url, &limits).await?;
 
-            if !response.status().is_success() {
+            if !response.is_success() {
                 anyhow::bail!(
                     "Failed to get block header: {} - {}",
-                    response.status(),
-                    response.text().await.unwrap_or_default()
+                    response.status,
+                    response.text()
                 );
             }
 
-            let header: serde_json::Value = response.json().await?;
+            let header: serde_json::Value = response.json("block header", &limits)?;
             println!("{}", serde_json::to_string_pretty(&header)?);
         }
 
         Commands::Head => {
             let url = format!("{}/head", cli.bridge_url);
-            let response = client.get(&url).send().await?;
+            let limits = verifier_configs.limits().await?;
+            let response = client.get(&url, &limits).await?;
 
-            if !response.status().is_success() {
+            if !response.is_success() {
                 anyhow::bail!(
                     "Failed to get head: {} - {}",
-                    response.status(),
-                    response.text().await.unwrap_or_default()
+                    response.status,
+                    response.text()
                 );
             }
 
-            let head: u32 = response.json().await?;
+            let head: u32 = response.json("chain head", &limits)?;
             println!("Current chain head: {}", head);
         }
 
@@ -337,28 +686,21 @@ async fn main() -> anyhow::Result<()> {
                 ProofFormat::CairoSerde,
             )?;
 
+            let verifier_config = verifier_configs.load(config.as_deref()).await?;
+
             info!("Fetching chain state at height {} from bridge node", height);
             let url = format!("{}/chain-state/{}", cli.bridge_url, height);
-            let response = client.get(&url).send().await?;
+            let response = client.get(&url, &verifier_config.limits).await?;
 
-            if !response.status().is_success() {
+            if !response.is_success() {
                 anyhow::bail!(
                     "Failed to get chain state: {} - {}",
-                    response.status(),
-                    response.text().await.unwrap_or_default()
+                    response.status,
+                    response.text()
                 );
             }
 
-            let chain_state: ChainState = response.json().await?;
-
-            let verifier_config = if let Some(config_path) = config {
-                info!("Loading verifier config from {}", config_path);
-                let config_data = std::fs::read_to_string(&config_path)?;
-                serde_json::from_str(&config_data)?
-            } else {
-                info!("Using default verifier config");
-                VerifierConfig::default()
-            };
+            let chain_state: ChainState = response.json("chain state", &verifier_config.limits)?;
 
             info!("Chain state at height: {}", chain_state.block_height);
             info!("Best block hash: {:?}", chain_state.best_block_hash);
@@ -389,17 +731,11 @@ async fn main() -> anyhow::Result<()> {
                 format!("{}/block-inclusion-proof/{}", cli.bridge_url, block_hash)
             };
 
-            let response = client.get(&url).send().await?;
-
-            if !response.status().is_success() {
-                anyhow::bail!(
-                    "Failed to get block inclusion proof: {} - {}",
-                    response.status(),
-                    response.text().await.unwrap_or_default()
-                );
-            }
-
-            let proof: BlockInclusionProof = response.json().await?;
+            let limits = verifier_configs.limits().await?;
+            let data = fetch_bytes(&client, &url, "block inclusion proof", &limits).await?;
+            let proof: BlockInclusionProof =
+                parse_json_limited(&data, "block inclusion proof", &limits)?;
+            limits.check_block_inclusion(&proof)?;
             println!("{}", serde_json::to_string_pretty(&proof)?);
         }
 
@@ -422,17 +758,11 @@ async fn main() -> anyhow::Result<()> {
                 format!("{}/block-inclusion-proof/{}", cli.bridge_url, block_hash)
             };
 
-            let proof_response = client.get(&proof_url).send().await?;
-
-            if !proof_response.status().is_success() {
-                anyhow::bail!(
-                    "Failed to get block inclusion proof: {} - {}",
-                    proof_response.status(),
-                    proof_response.text().await.unwrap_or_default()
-                );
-            }
-
-            let proof: BlockInclusionProof = proof_response.json().await?;
+            let limits = verifier_configs.limits().await?;
+            let data = fetch_bytes(&client, &proof_url, "block inclusion proof", &limits).await?;
+            let proof: BlockInclusionProof =
+                parse_json_limited(&data, "block inclusion proof", &limits)?;
+            limits.check_block_inclusion(&proof)?;
             info!("Block height: {}", proof.block_height);
             info!(
                 "Leaf index: {}, Leaf count: {}",
@@ -442,7 +772,7 @@ async fn main() -> anyhow::Result<()> {
             // Create MMR from peaks to verify the proof
             let store = Arc::new(InMemoryStore::new(Some("verify")));
             let hasher = Arc::new(ZcashFlyclientHasher);
-            let elements_count = leaf_count_to_mmr_size(proof.leaf_count);
+            let elements_count = leaf_count_to_mmr_size(proof.leaf_count as u64) as usize;
 
             let mmr = MMR::create_from_peaks(
                 store,
@@ -459,6 +789,14 @@ async fn main() -> anyhow::Result<()> {
             // Get the root hash from the MMR
             let root = mmr.root_hash.get(accumulators::store::SubKey::None).await?;
             if let Some(root_hash) = root {
+                let record =
+                    fetch_mmr_root(&client, &cli.bridge_url, proof.chain_height(), &limits).await?;
+                verify_mmr_root_history(&proof, &root_hash, &record)?;
+                info!(
+                    "✓ MMR root matches the root recorded at height {}",
+                    record.height
+                );
+
                 info!("✓ Block inclusion proof structure valid!");
                 println!(
                     "Block {} (height {}) is included in MMR with {} leaves. Root: {}",
@@ -471,84 +809,59 @@ async fn main() -> anyhow::Result<()> {
 
         Commands::FullProof {
             tx_id,
+            lookup,
             chain_state_proof: proof_file,
             chain_height,
             output,
         } => {
             info!("Generating full inclusion proof for transaction {}", tx_id);
-
-            // 1. Get transaction inclusion proof
-            info!("Fetching transaction inclusion proof...");
-            let tx_url = format!("{}/transaction-proof/{}", cli.bridge_url, tx_id);
-            let tx_response = client.get(&tx_url).send().await?;
-            if !tx_response.status().is_success() {
-                anyhow::bail!(
-                    "Failed to get transaction proof: {} - {}",
-                    tx_response.status(),
-                    tx_response.text().await.unwrap_or_default()
-                );
+            if lookup.bucket.is_some() {
+                anyhow::bail!("--bucket is not supported by full-proof, use verify-tx instead");
             }
-            let tx_proof: TransactionInclusionProof = tx_response.json().await?;
+
+            // 1. Get every layer but the STARK proof from the bridge node
+            info!("Fetching proof layers at chain height {}...", chain_height);
+            let url = full_proof_url(&cli.bridge_url, &tx_id, &lookup, chain_height);
+            let limits = verifier_configs.limits().await?;
+            let data = fetch_bytes(&client, &url, "full proof", &limits).await?;
+            let full_proof_response: FullProofResponse =
+                parse_json_limited(&data, "full proof", &limits)?;
+            limits.check_block_inclusion(&full_proof_response.block_inclusion_proof)?;
+            limits.check_transaction_inclusion(&full_proof_response.transaction_proof)?;
+            log_matched_id(&tx_id, full_proof_response.matched.as_ref());
+            log_proof_source(full_proof_response.source);
             info!(
                 "Transaction found in block {} at height {}",
-                tx_proof.block_header.hash(),
-                tx_proof.block_height
+                full_proof_response.transaction_proof.block_header.hash(),
+                full_proof_response.transaction_proof.block_height
             );
-
-            // 2. Get block inclusion proof
-            info!("Fetching block inclusion proof...");
-            let block_hash = tx_proof.block_header.hash().to_string();
-            let block_url = format!("{}/block-inclusion-proof/{}", cli.bridge_url, block_hash);
-            let block_response = client.get(&block_url).send().await?;
-            if !block_response.status().is_success() {
-                anyhow::bail!(
-                    "Failed to get block inclusion proof: {} - {}",
-                    block_response.status(),
-                    block_response.text().await.unwrap_or_default()
-                );
+            if let Some(proof_url) = &full_proof_response.chain_state_proof_url {
+                info!("Bridge advertises the chain state proof at {}", proof_url);
             }
-            let block_proof: BlockInclusionProof = block_response.json().await?;
-            info!(
-                "Block inclusion proof: leaf {} of {}",
-                block_proof.leaf_index, block_proof.leaf_count
-            );
-
-            // 3. Get chain state
-            info!("Fetching chain state at height {}...", chain_height);
-            let cs_url = format!("{}/chain-state/{}", cli.bridge_url, chain_height);
-            let cs_response = client.get(&cs_url).send().await?;
-            if !cs_response.status().is_success() {
-                anyhow::bail!(
-                    "Failed to get chain state: {} - {}",
-                    cs_response.status(),
-                    cs_response.text().await.unwrap_or_default()
-                );
+            if let Some(finality) = &full_proof_response.finality {
+                // The STARK proof is verified by verify-full
+                match finality.check(
+                    full_proof_response.transaction_proof.block_height,
+                    &full_proof_response.chain_state,
+                    true,
+                ) {
+                    Ok(()) => info!("Final under the bridge's {} finality policy", finality),
+                    Err(e) => warn!(
+                        "Not final under the bridge's {} finality policy: {}",
+                        finality, e
+                    ),
+                }
             }
-            let chain_state: ChainState = cs_response.json().await?;
 
-            // 4. Load chain state STARK proof
+            // 2. Load chain state STARK proof
             info!("Loading chain state proof from {}...", proof_file);
             let chain_state_proof = deserialize_proof_from_file(
                 std::path::Path::new(&proof_file),
                 ProofFormat::CairoSerde,
             )?;
 
-            // 5. Build full inclusion proof
-            let full_proof = zoro_spv_verify::FullInclusionProof {
-                chain_state,
-                chain_state_proof,
-                block_header: tx_proof.block_header,
-                block_height: tx_proof.block_height,
-                block_inclusion_proof: zoro_spv_verify::BlockInclusionProof {
-                    block_height: block_proof.block_height,
-                    peaks_hashes: block_proof.peaks_hashes,
-                    siblings_hashes: block_proof.siblings_hashes,
-                    leaf_index: block_proof.leaf_index,
-                    leaf_count: block_proof.leaf_count,
-                },
-                transaction: tx_proof.transaction,
-                transaction_proof: tx_proof.transaction_proof,
-            };
+            // 3. Build full inclusion proof
+            let full_proof = full_proof_response.into_full_proof(chain_state_proof);
 
             let confirmations = full_proof.confirmations();
             info!(
@@ -569,27 +882,21 @@ async fn main() -> anyhow::Result<()> {
             proof_file,
             config,
             min_confirmations,
+            finality,
             skip_block_proof,
             skip_chain_proof,
+            check_timestamps,
+            timeout_secs,
         } => {
             info!("Verifying full inclusion proof from {}...", proof_file);
 
-            // Load proof
-            let proof_data = std::fs::read_to_string(&proof_file)?;
-            let proof: zoro_spv_verify::FullInclusionProof = serde_json::from_str(&proof_data)?;
-
             // Load or use default config
-            let mut verifier_config = if let Some(config_path) = config {
-                let config_data = std::fs::read_to_string(&config_path)?;
-                serde_json::from_str(&config_data)?
-            } else {
-                VerifierConfig::default()
-            };
+            let mut verifier_config = verifier_configs.load(config.as_deref()).await?;
 
-            // Override min_confirmations if provided
-            if let Some(min_conf) = min_confirmations {
-                verifier_config.min_confirmations = min_conf;
-            }
+            let proof: zoro_spv_verify::FullInclusionProof =
+                read_json_file(&proof_file, "full inclusion proof", &verifier_config.limits)?;
+
+            override_finality(&mut verifier_config, min_confirmations, finality);
 
             info!("Transaction: {}", proof.transaction_hash());
             info!(
@@ -607,6 +914,8 @@ async fn main() -> anyhow::Result<()> {
             let options = zoro_spv_verify::VerifyOptions {
                 skip_chain_proof,
                 skip_block_proof,
+                check_timestamps,
+                cancel: cancellation(timeout_secs),
             };
 
             if skip_chain_proof {
@@ -616,48 +925,132 @@ async fn main() -> anyhow::Result<()> {
                 info!("⚠ Skipping block inclusion (FlyClient) proof verification");
             }
 
+            // Digest taken before the proof is consumed by the verification
+            let proof_digest = cli
+                .archive
+                .as_ref()
+                .map(|_| proof.canonical_digest())
+                .transpose()?;
+
             // Verify with options
-            let result = zoro_spv_verify::verify_full_inclusion_proof_with_options(
+            let outcome = zoro_spv_verify::verify_full_inclusion_proof_with_options(
                 proof,
                 &verifier_config,
                 options,
             )
-            .await?;
+            .await;
+            archive_outcome(
+                cli.archive.as_deref(),
+                proof_digest,
+                &outcome,
+                &verifier_config,
+            )?;
+            let result = outcome?;
 
-            println!("\n✓ VERIFICATION SUCCESSFUL");
-            println!("  Transaction: {}", result.transaction_hash);
-            println!(
-                "  Block: {} (height {})",
-                result.block_hash, result.block_height
-            );
-            println!("  Chain height: {}", result.chain_height);
-            println!("  Confirmations: {}", result.confirmations);
+            print_verification_result(&result, locale);
+        }
+
+        Commands::VerifyFile {
+            tx_proof,
+            block_proof,
+            chain_state,
+            stark,
+            mmr_root,
+            config,
+            min_confirmations,
+            finality,
+            check_timestamps,
+            timeout_secs,
+        } => {
+            info!("Verifying transaction from local proof files...");
+
+            let mut verifier_config = verifier_configs.load(config.as_deref()).await?;
+            override_finality(&mut verifier_config, min_confirmations, finality);
+            let limits = &verifier_config.limits;
+
+            let tx_proof: TransactionInclusionProof =
+                read_json_file(&tx_proof, "transaction proof", limits)?;
+            let block_proof: BlockInclusionProof =
+                read_json_file(&block_proof, "block inclusion proof", limits)?;
+            let chain_state: ChainState = read_json_file(&chain_state, "chain state", limits)?;
+            let chain_state_proof =
+                deserialize_proof_from_file(std::path::Path::new(&stark), ProofFormat::CairoSerde)?;
+
+            // Without a bridge node the recorded root has to be supplied alongside the proof
+            if let Some(mmr_root) = mmr_root {
+                limits.check_block_inclusion(&block_proof)?;
+                let record: MmrRootRecord = read_json_file(&mmr_root, "MMR root record", limits)?;
+                let root =
+                    zoro_spv_verify::verify_block_inclusion(&tx_proof.block_header, &block_proof)
+                        .await?;
+                verify_mmr_root_history(&block_proof, &root, &record)?;
+                info!(
+                    "✓ MMR root matches the root recorded at height {}",
+                    record.height
+                );
+            }
+
+            let proof = zoro_spv_verify::FullInclusionProof {
+                chain_state,
+                chain_state_proof,
+                block_header: tx_proof.block_header,
+                block_height: tx_proof.block_height,
+                block_inclusion_proof: block_proof,
+                transaction: tx_proof.transaction,
+                transaction_proof: tx_proof.transaction_proof,
+            };
+            let options = zoro_spv_verify::VerifyOptions {
+                check_timestamps,
+                cancel: cancellation(timeout_secs),
+                ..Default::default()
+            };
+            let proof_digest = cli
+                .archive
+                .as_ref()
+                .map(|_| proof.canonical_digest())
+                .transpose()?;
+            let outcome = zoro_spv_verify::verify_full_inclusion_proof_with_options(
+                proof,
+                &verifier_config,
+                options,
+            )
+            .await;
+            archive_outcome(
+                cli.archive.as_deref(),
+                proof_digest,
+                &outcome,
+                &verifier_config,
+            )?;
+            let result = outcome?;
+
+            print_verification_result(&result, locale);
         }
 
         Commands::VerifyTx {
             tx_id,
+            lookup,
             stark_proof,
             proof_height,
             min_confirmations,
+            finality,
             verify_block_proof,
+            max_proof_lag,
         } => {
             info!("=== Full Transaction Verification ===");
             info!("Transaction ID: {}", tx_id);
 
-            let has_stark_proof = stark_proof.is_some();
-
             // === Step 1: Fetch transaction inclusion proof ===
             info!("\n[1/4] Fetching transaction inclusion proof...");
-            let tx_url = format!("{}/transaction-proof/{}", cli.bridge_url, tx_id);
-            let tx_response = client.get(&tx_url).send().await?;
-            if !tx_response.status().is_success() {
-                anyhow::bail!(
-                    "Failed to get transaction proof: {} - {}",
-                    tx_response.status(),
-                    tx_response.text().await.unwrap_or_default()
-                );
-            }
-            let tx_proof: TransactionInclusionProof = tx_response.json().await?;
+            let verifier_config = verifier_configs.load(None).await?;
+            let limits = &verifier_config.limits;
+            let TransactionProofResponse {
+                proof: tx_proof,
+                matched,
+                source,
+            } = fetch_transaction_proof(&client, &cli.bridge_url, &tx_id, &lookup, limits).await?;
+            log_matched_id(&tx_id, matched.as_ref());
+            log_proof_source(source);
+            limits.check_transaction_inclusion(&tx_proof)?;
             info!(
                 "  ✓ Transaction found in block {} (height {})",
                 tx_proof.block_header.hash(),
@@ -676,9 +1069,11 @@ async fn main() -> anyhow::Result<()> {
                 None
             } else {
                 let block_url = format!("{}/block-inclusion-proof/{}", cli.bridge_url, block_hash);
-                let block_response = client.get(&block_url).send().await?;
-                if block_response.status().is_success() {
-                    let proof: BlockInclusionProof = block_response.json().await?;
+                let block_response = client.get(&block_url, limits).await?;
+                if block_response.is_success() {
+                    let proof: BlockInclusionProof =
+                        block_response.json("block inclusion proof", limits)?;
+                    limits.check_block_inclusion(&proof)?;
                     info!(
                         "  ✓ Block in FlyClient MMR: leaf {} of {}",
                         proof.leaf_index, proof.leaf_count
@@ -696,6 +1091,13 @@ async fn main() -> anyhow::Result<()> {
             // === Step 3: Fetch/Load chain state and STARK proof ===
             info!("\n[3/4] Loading chain state...");
 
+            let head_url = format!("{}/head", cli.bridge_url);
+            let head_response = client.get(&head_url, limits).await?;
+            if !head_response.is_success() {
+                anyhow::bail!("Failed to get chain head");
+            }
+            let chain_height: u32 = head_response.json("chain head", limits)?;
+
             // Determine which chain state to use
             let (chain_state, stark_proof_data) = if let Some(proof_file) = &stark_proof {
                 let height = proof_height.ok_or_else(|| {
@@ -713,15 +1115,15 @@ async fn main() -> anyhow::Result<()> {
                     height
                 );
                 let cs_url = format!("{}/chain-state/{}", cli.bridge_url, height);
-                let cs_response = client.get(&cs_url).send().await?;
-                if !cs_response.status().is_success() {
+                let cs_response = client.get(&cs_url, limits).await?;
+                if !cs_response.is_success() {
                     anyhow::bail!(
                         "Failed to get chain state at height {}: {}",
                         height,
-                        cs_response.text().await.unwrap_or_default()
+                        cs_response.text()
                     );
                 }
-                let cs: ChainState = cs_response.json().await?;
+                let cs: ChainState = cs_response.json("chain state", limits)?;
                 info!(
                     "  ✓ Chain state at height {} with STARK proof",
                     cs.block_height
@@ -729,22 +1131,12 @@ async fn main() -> anyhow::Result<()> {
                 (cs, Some(proof_data))
             } else {
                 // No STARK proof - just fetch current chain state
-                let head_url = format!("{}/head", cli.bridge_url);
-                let head_response = client.get(&head_url).send().await?;
-                if !head_response.status().is_success() {
-                    anyhow::bail!("Failed to get chain head");
-                }
-                let chain_height: u32 = head_response.json().await?;
-
                 let cs_url = format!("{}/chain-state/{}", cli.bridge_url, chain_height);
-                let cs_response = client.get(&cs_url).send().await?;
-                if !cs_response.status().is_success() {
-                    anyhow::bail!(
-                        "Failed to get chain state: {}",
-                        cs_response.text().await.unwrap_or_default()
-                    );
+                let cs_response = client.get(&cs_url, limits).await?;
+                if !cs_response.is_success() {
+                    anyhow::bail!("Failed to get chain state: {}", cs_response.text());
                 }
-                let cs: ChainState = cs_response.json().await?;
+                let cs: ChainState = cs_response.json("chain state", limits)?;
                 info!(
                     "  ✓ Chain state at height {} (no STARK proof)",
                     cs.block_height
@@ -752,6 +1144,29 @@ async fn main() -> anyhow::Result<()> {
                 (cs, None)
             };
 
+            // Lag of the latest chain state proof behind the head: the one being verified, or the
+            // one the bridge's prover reports
+            let proven_height = match (&stark_proof_data, proof_height) {
+                (Some(_), Some(height)) => Some(height),
+                _ => fetch_proven_height(&client, &cli.bridge_url, limits).await?,
+            };
+            let proof_lag = match proven_height {
+                Some(proven_height) => {
+                    let lag = check_proof_lag(proven_height, chain_height, max_proof_lag)?;
+                    if lag > 0 {
+                        warn!(
+                            "  ⚠ Latest proven chain state (height {}) is {} blocks behind the head",
+                            proven_height, lag
+                        );
+                    }
+                    Some(lag)
+                }
+                None if max_proof_lag.is_some() => anyhow::bail!(
+                    "Proof lag unknown: the bridge does not report its proving status, pass --stark-proof"
+                ),
+                None => None,
+            };
+
             // Calculate confirmations
             let confirmations = chain_state
                 .block_height
@@ -761,19 +1176,23 @@ async fn main() -> anyhow::Result<()> {
 
             // === Step 4: Verify all proofs ===
             info!("\n[4/4] Verifying proofs...");
+            check_checkpoints(
+                &verifier_config.checkpoints,
+                &chain_state,
+                Some((tx_proof.block_height, &tx_proof.block_header.hash())),
+            )?;
 
-            // Check confirmations
-            if confirmations < min_confirmations {
-                anyhow::bail!(
-                    "Insufficient confirmations: {} < {} required",
-                    confirmations,
-                    min_confirmations
-                );
-            }
-            info!(
-                "  ✓ Confirmations: {} >= {} required",
-                confirmations, min_confirmations
-            );
+            // Check finality, the STARK proof being verified below
+            let finality = finality
+                .or(min_confirmations
+                    .map(|confirmations| FinalityPolicy::Confirmations { confirmations }))
+                .unwrap_or_default();
+            finality.check(
+                tx_proof.block_height,
+                &chain_state,
+                stark_proof_data.is_some(),
+            )?;
+            info!("  ✓ Final under the {} finality policy", finality);
 
             // Verify transaction is in block (Merkle proof)
             info!("  Verifying transaction Merkle proof...");
@@ -790,16 +1209,15 @@ async fn main() -> anyhow::Result<()> {
             // Verify block is in chain (FlyClient MMR)
             if let Some(ref bp) = block_proof {
                 info!("  Verifying block FlyClient MMR proof...");
-                let lib_block_proof = zoro_spv_verify::BlockInclusionProof {
-                    block_height: bp.block_height,
-                    peaks_hashes: bp.peaks_hashes.clone(),
-                    siblings_hashes: bp.siblings_hashes.clone(),
-                    leaf_index: bp.leaf_index,
-                    leaf_count: bp.leaf_count,
-                };
-                zoro_spv_verify::verify_block_inclusion(&tx_proof.block_header, &lib_block_proof)
-                    .await?;
-                info!("  ✓ Block {} is in FlyClient MMR", block_hash);
+                let root =
+                    zoro_spv_verify::verify_block_inclusion(&tx_proof.block_header, bp).await?;
+                let record =
+                    fetch_mmr_root(&client, &cli.bridge_url, bp.chain_height(), limits).await?;
+                verify_mmr_root_history(bp, &root, &record)?;
+                info!(
+                    "  ✓ Block {} is in FlyClient MMR (root recorded at height {})",
+                    block_hash, record.height
+                );
             } else {
                 // Already logged as verified in step 2
                 info!("  ✓ Block inclusion verified (implicit for pre-Heartwood blocks)");
@@ -808,8 +1226,7 @@ async fn main() -> anyhow::Result<()> {
             // Verify chain state STARK proof
             let stark_verified = if let Some(proof_data) = stark_proof_data {
                 info!("  Verifying chain state STARK proof...");
-                let config = VerifierConfig::default();
-                let result = verify_chain_state(&chain_state, proof_data, &config)?;
+                let result = verify_chain_state(&chain_state, proof_data, &verifier_config)?;
                 info!("  ✓ Chain state verified: {}", result);
                 true
             } else {
@@ -818,36 +1235,413 @@ async fn main() -> anyhow::Result<()> {
             };
 
             // === Success ===
-            println!("\n╔══════════════════════════════════════════════════════════════╗");
-            println!("║              ✓ FULL VERIFICATION SUCCESSFUL                  ║");
-            println!("╠══════════════════════════════════════════════════════════════╣");
-            println!("║ Transaction: {}  ║", tx_id);
-            println!(
-                "║ Block:       {} (height {})  ║",
-                block_hash, tx_proof.block_height
-            );
-            println!(
-                "║ Chain State: height {}                                    ║",
-                chain_state.block_height
+            let result = VerificationResult {
+                transaction_hash: tx_proof.transaction.hash(),
+                block_hash: tx_proof.block_header.hash(),
+                block_height: tx_proof.block_height,
+                chain_height: chain_state.block_height,
+                confirmations,
+                expiry_height: tx_proof.transaction.expiry_height().map(|height| height.0),
+                chain_state_verified: stark_verified,
+            };
+            print_verification_result(&result, locale);
+            if let Some(lag) = proof_lag {
+                println!(
+                    "  {}: {lag} {}",
+                    locale.tr("Proof lag"),
+                    locale.tr("blocks")
+                );
+            }
+        }
+
+        Commands::VerifyArchive { archive_file } => {
+            let check = verify_archive(&archive_file)?;
+            info!(
+                "✓ {} records of {} are intact, head {}",
+                check.records,
+                archive_file.display(),
+                check.head
             );
-            println!(
-                "║ Confirmations: {}                                            ║",
-                confirmations
+            println!("{}", serde_json::to_string_pretty(&check)?);
+        }
+
+        Commands::GenVectors { out } => {
+            let vectors = vectors::generate()?;
+            vectors::write(&vectors, &out)?;
+            let cases = vectors::check(&out)?;
+            info!("✓ Wrote {} test vectors to {}", cases, out.display());
+        }
+
+        Commands::GenSchemas { out } => {
+            let count = schema::write(&out)?;
+            info!("✓ Wrote {} schemas to {}", count, out.display());
+        }
+
+        Commands::SignManifest {
+            manifest,
+            key_file,
+            output,
+        } => {
+            let key = manifest::parse_signing_key(&std::fs::read_to_string(&key_file)?)?;
+            let release_manifest: ReleaseManifest =
+                serde_json::from_str(&std::fs::read_to_string(&manifest)?)?;
+            let signed = release_manifest.sign(&key)?;
+            // Check that the manifest survives the roundtrip clients go through
+            signed.verify(&ManifestKey::of(&key))?;
+            let json = serde_json::to_string_pretty(&signed)?;
+
+            info!(
+                "✓ Signed manifest {} ({} releases, expiring at {}) with key {}",
+                release_manifest.sequence,
+                release_manifest.releases.len(),
+                release_manifest.expires_at,
+                ManifestKey::of(&key)
             );
-            println!("╠══════════════════════════════════════════════════════════════╣");
-            println!("║ Proofs Verified:                                             ║");
-            println!("║   [✓] Transaction in Block (Merkle Proof)                    ║");
-            println!("║   [✓] Block in Chain (FlyClient MMR)                         ║");
-            if stark_verified {
-                println!("║   [✓] Chain State Valid (STARK Proof)                        ║");
-            } else if has_stark_proof {
-                println!("║   [✗] Chain State Valid (STARK Proof) - FAILED              ║");
+            if let Some(path) = output {
+                std::fs::write(&path, &json)?;
+                info!("Signed manifest written to {}", path);
             } else {
-                println!("║   [⚠] Chain State Valid (STARK Proof) - NOT PROVIDED        ║");
+                println!("{}", json);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Source of the verifier configs of the commands, fetching the release manifest if configured
+struct VerifierConfigSource {
+    manifest: Option<(reqwest::Client, String, ManifestKey)>,
+    prover_version: Option<String>,
+    sequence_file: Option<SequenceFile>,
+}
+
+impl VerifierConfigSource {
+    fn new(cli: &Cli) -> anyhow::Result<Self> {
+        let args = &cli.manifest;
+        let manifest = match (&args.manifest_url, args.manifest_key) {
+            (Some(url), Some(key)) => {
+                let mut builder = reqwest::Client::builder().https_only(true);
+                if let Some(proxy) = proxy_url(cli) {
+                    builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+                }
+                Some((builder.build()?, url.clone(), key))
+            }
+            _ => None,
+        };
+        Ok(Self {
+            manifest,
+            prover_version: args.prover_version.clone(),
+            sequence_file: args.manifest_sequence_file.clone().map(SequenceFile::new),
+        })
+    }
+
+    /// Verifier config of the `config` file, else of the release manifest if configured, else
+    /// the built-in one
+    async fn load(&self, config: Option<&str>) -> anyhow::Result<VerifierConfig> {
+        if let Some(config_path) = config {
+            if self.manifest.is_some() {
+                anyhow::bail!("--config and --manifest-url cannot be used together");
             }
-            println!("╚══════════════════════════════════════════════════════════════╝");
+            info!("Loading verifier config from {}", config_path);
+            let config_data = std::fs::read_to_string(config_path)?;
+            return Ok(serde_json::from_str(&config_data)?);
+        }
+        let Some((client, url, key)) = &self.manifest else {
+            info!("Using default verifier config");
+            return Ok(VerifierConfig::default());
+        };
+        info!("Fetching release manifest from {}", url);
+        let last_sequence = match &self.sequence_file {
+            Some(file) => file.load()?,
+            None => None,
+        };
+        // The manifest carries the configured limits, so it is fetched within the built-in ones
+        let release_manifest =
+            manifest::fetch(client, url, key, &ProofLimits::default(), last_sequence).await?;
+        if let Some(file) = &self.sequence_file {
+            file.store(release_manifest.sequence)?;
         }
+        let release = release_manifest.release(self.prover_version.as_deref())?;
+        info!(
+            "Using verifier config of prover release {}",
+            release.version
+        );
+        Ok(release.verifier_config.clone())
+    }
+
+    /// Proof limits of the verifier config, for commands without `--config`
+    async fn limits(&self) -> anyhow::Result<ProofLimits> {
+        Ok(self.load(None).await?.limits)
+    }
+}
+
+/// Override the finality policy of a verifier config with the command line flags
+/// Cancellation token of a verification, bounded by `timeout_secs` if set
+fn cancellation(timeout_secs: Option<u64>) -> CancellationToken {
+    match timeout_secs {
+        Some(secs) => CancellationToken::with_timeout(Duration::from_secs(secs)),
+        None => CancellationToken::new(),
+    }
+}
+
+fn override_finality(
+    config: &mut VerifierConfig,
+    min_confirmations: Option<u32>,
+    finality: Option<FinalityPolicy>,
+) {
+    if let Some(confirmations) = min_confirmations {
+        config.finality = Some(FinalityPolicy::Confirmations { confirmations });
     }
+    if finality.is_some() {
+        config.finality = finality;
+    }
+}
+
+/// Read and parse an untrusted JSON file, rejecting oversized files before parsing
+fn read_json_file<T: serde::de::DeserializeOwned>(
+    path: &str,
+    what: &str,
+    limits: &ProofLimits,
+) -> anyhow::Result<T> {
+    let file_size = std::fs::metadata(path)?.len();
+    limits.check_document_size(usize::try_from(file_size).unwrap_or(usize::MAX))?;
+    let data = std::fs::read(path)?;
+    parse_json_limited(&data, what, limits)
+}
 
+/// Append the outcome of a verification to the archive, if `--archive` is set
+fn archive_outcome(
+    archive: Option<&Path>,
+    proof_digest: Option<String>,
+    outcome: &anyhow::Result<VerificationResult>,
+    config: &VerifierConfig,
+) -> anyhow::Result<()> {
+    let (Some(path), Some(proof_digest)) = (archive, proof_digest) else {
+        return Ok(());
+    };
+    let build = BuildInfo::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"), &[]);
+    let record = ArchiveWriter::open(path)?.append(
+        proof_digest,
+        ArchivedResult::of(outcome),
+        config,
+        &build,
+    )?;
+    info!(
+        "Recorded the outcome in {} (record {}, hash {})",
+        path.display(),
+        record.seq,
+        record.hash
+    );
     Ok(())
 }
+
+/// Print the outcome of a full inclusion proof verification
+fn print_verification_result(result: &VerificationResult, locale: Locale) {
+    println!("\n✓ {}", render_human_in(result, locale));
+    if let Some(expiry_height) = result.expiry_height {
+        println!("  {}: {}", locale.tr("Expiry height"), expiry_height);
+    }
+}
+
+/// Fetch the latest proven height reported on the bridge's `/proving-status`, `None` if the
+/// bridge does not report it
+async fn fetch_proven_height(
+    client: &BridgeClient,
+    bridge_url: &str,
+    limits: &ProofLimits,
+) -> anyhow::Result<Option<u32>> {
+    let url = format!("{bridge_url}/proving-status");
+    let response = client.get(&url, limits).await?;
+    if response.status == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !response.is_success() {
+        anyhow::bail!("Failed to get proving status: {}", response.status);
+    }
+    let status: ProvingStatus = response.json("proving status", limits)?;
+    Ok(Some(status.proven_height))
+}
+
+/// Fetch the FlyClient MMR root the bridge node recorded at `height`
+async fn fetch_mmr_root(
+    client: &BridgeClient,
+    bridge_url: &str,
+    height: u32,
+    limits: &ProofLimits,
+) -> anyhow::Result<MmrRootRecord> {
+    let url = format!("{}/mmr/root/{}", bridge_url, height);
+    let response = client.get(&url, limits).await?;
+
+    if !response.is_success() {
+        anyhow::bail!(
+            "Failed to get MMR root at height {}: {} - {}",
+            height,
+            response.status,
+            response.text()
+        );
+    }
+
+    response.json("MMR root record", limits)
+}
+
+/// SOCKS proxy of a local Tor daemon, resolving host names through Tor
+const TOR_PROXY: &str = "socks5h://127.0.0.1:9050";
+
+/// Proxy of the requests, `--proxy` or the local Tor daemon with `--tor`
+fn proxy_url(cli: &Cli) -> Option<&str> {
+    if cli.tor {
+        Some(TOR_PROXY)
+    } else {
+        cli.proxy.as_deref()
+    }
+}
+
+/// Client for the bridge node: over HTTP(S) through the proxy and enforcing the TLS pinning if
+/// configured, or over a Unix domain socket for `unix:` bridge URLs
+fn bridge_client(cli: &Cli) -> anyhow::Result<BridgeClient> {
+    let mut builder = reqwest::Client::builder();
+    if let Some(proxy) = proxy_url(cli) {
+        if proxy.starts_with("socks5://") {
+            warn!("Host names are resolved locally with {proxy}, use socks5h:// to hide them");
+        }
+        builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+    }
+
+    let pinning = TlsPinning {
+        ca_certs: match &cli.bridge_ca_cert {
+            Some(path) => load_ca_certs(path)?,
+            None => Vec::new(),
+        },
+        spki_pins: cli.bridge_pins.clone(),
+    };
+    if pinning.is_enabled() {
+        if !cli.bridge_url.starts_with("https://") {
+            anyhow::bail!(
+                "TLS pinning requires an https:// bridge URL, got {}",
+                cli.bridge_url
+            );
+        }
+        builder = builder.use_preconfigured_tls(pinning.client_config()?);
+    }
+    BridgeClient::for_url(builder.build()?, &cli.bridge_url)
+}
+
+/// Fetch the inclusion proof of a transaction, from its bucket with `--bucket`
+async fn fetch_transaction_proof(
+    client: &BridgeClient,
+    bridge_url: &str,
+    tx_id: &str,
+    lookup: &TxLookupArgs,
+    limits: &ProofLimits,
+) -> anyhow::Result<TransactionProofResponse> {
+    let Some(prefix_len) = lookup.bucket else {
+        let url = transaction_proof_url(bridge_url, tx_id, lookup);
+        let data = fetch_bytes(client, &url, "transaction proof", limits).await?;
+        return parse_json_limited(&data, "transaction proof", limits);
+    };
+    let txid = display_txid(tx_id, lookup)?;
+    let prefix = &txid[..usize::from(prefix_len)];
+    let url = format!("{}/transaction-proofs/bucket/{}", bridge_url, prefix);
+    let data = fetch_bytes(client, &url, "transaction bucket", limits).await?;
+    let bucket: TransactionBucketResponse =
+        parse_json_limited(&data, "transaction bucket", limits)?;
+    info!(
+        "Bucket {} holds {} transactions",
+        prefix,
+        bucket.proofs.len()
+    );
+    bucket
+        .into_transaction(&txid)
+        .ok_or_else(|| anyhow::anyhow!("Transaction {txid} is not in bucket {prefix}"))
+}
+
+/// Fetch the body of a bridge response, failing on error statuses and oversized bodies
+async fn fetch_bytes(
+    client: &BridgeClient,
+    url: &str,
+    what: &str,
+    limits: &ProofLimits,
+) -> anyhow::Result<Vec<u8>> {
+    let response = client.get(url, limits).await?;
+    let status = response.status;
+    if !status.is_success() {
+        let body = response.text();
+        let error = anyhow::anyhow!("Failed to get {}: {} - {}", what, status, body);
+        return match status_hint(status, &body) {
+            Some(hint) => Err(error).hint(hint),
+            None => Err(error),
+        };
+    }
+    Ok(response.body)
+}
+
+/// Txid in display order (lowercase hex) of a bucketed lookup
+fn display_txid(tx_id: &str, lookup: &TxLookupArgs) -> anyhow::Result<String> {
+    let bytes = <[u8; 32]>::from_hex(tx_id)
+        .map_err(|_| anyhow::anyhow!("Transaction ID must be 32 bytes of hex, got {tx_id:?}"))?;
+    let txid = match lookup.id_type.as_deref() {
+        Some("txid_reversed") => InternalOrderHash(bytes).to_display(),
+        Some("auth_digest") => anyhow::bail!("--bucket requires a txid, not an auth digest"),
+        _ => DisplayOrderHash(bytes),
+    };
+    Ok(txid.to_string())
+}
+
+/// Build the bridge URL for a transaction proof lookup
+fn transaction_proof_url(bridge_url: &str, tx_id: &str, lookup: &TxLookupArgs) -> String {
+    let url = format!("{}/transaction-proof/{}", bridge_url, tx_id);
+    with_query(url, lookup_params(lookup))
+}
+
+/// Build the bridge URL for a full proof lookup against `chain_height`
+fn full_proof_url(
+    bridge_url: &str,
+    tx_id: &str,
+    lookup: &TxLookupArgs,
+    chain_height: u32,
+) -> String {
+    let url = format!("{}/full-proof/{}", bridge_url, tx_id);
+    let mut params = lookup_params(lookup);
+    params.push(format!("chain_height={chain_height}"));
+    with_query(url, params)
+}
+
+/// Query parameters of a transaction lookup
+fn lookup_params(lookup: &TxLookupArgs) -> Vec<String> {
+    let mut params = Vec::new();
+    if let Some(id_type) = &lookup.id_type {
+        params.push(format!("id_type={id_type}"));
+    }
+    if let Some(block_height) = lookup.block_height {
+        params.push(format!("block_height={block_height}"));
+    }
+    params
+}
+
+fn with_query(mut url: String, params: Vec<String>) -> String {
+    if !params.is_empty() {
+        url.push('?');
+        url.push_str(&params.join("&"));
+    }
+    url
+}
+
+/// Log how the bridge interpreted the identifier when it was not a plain display-order txid
+fn log_matched_id(tx_id: &str, matched: Option<&MatchedTxId>) {
+    if let Some(matched) = matched {
+        if matched.kind != TxIdKind::Txid {
+            info!(
+                "Identifier {} matched as {:?}, canonical txid is {}",
+                tx_id, matched.kind, matched.txid
+            );
+        }
+    }
+}
+
+/// Note proofs the bridge built from its local storage while its Zcash node was unavailable
+fn log_proof_source(source: Option<ProofSource>) {
+    if source == Some(ProofSource::Local) {
+        info!("Proof served from the bridge's local storage (its Zcash node is unavailable)");
+    }
+}
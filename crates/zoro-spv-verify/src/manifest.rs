@@ -0,0 +1,364 @@
+//! Signed release manifests of the verification parameters.
+//!
+//! A release manifest maps the versions of the prover to the verifier configuration matching the
+//! proofs they produce (bootloader and program hashes, output size and default policies). It is
+//! published over HTTPS next to the releases and signed with Ed25519, so clients fetching it pick
+//! up the parameters of a new prover without trusting the server that hosts the manifest.
+//!
+//! The signature covers the [canonical JSON](crate::canonical) encoding of the `manifest` object
+//! as published, including fields this verifier does not know about.
+//!
+//! A validly signed manifest stays validly signed forever, so a server could keep serving an old
+//! one, e.g. to hold clients on a release with a known flaw. Manifests therefore carry a sequence
+//! number, increased by every publication, and an expiry: clients reject expired manifests and,
+//! given a [`SequenceFile`], manifests older than the newest one they accepted.
+
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::canonical::to_canonical_json;
+use crate::report::WithHint;
+#[cfg(feature = "client")]
+use crate::transport::read_body;
+#[cfg(feature = "client")]
+use crate::untrusted::{parse_json_limited, ProofLimits};
+use crate::verify::VerifierConfig;
+
+pub(crate) const MANIFEST_SIGNATURE_HINT: &str =
+    "The manifest is not signed by the release key: check --manifest-key and --manifest-url";
+pub(crate) const MANIFEST_RELEASE_HINT: &str =
+    "Check --prover-version against the releases listed in the manifest";
+pub(crate) const MANIFEST_STALE_HINT: &str =
+    "The server returned an outdated manifest: check --manifest-url, or wait for the publisher to sign a new one";
+
+/// Verifier configuration of the proofs produced by a prover version
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Release {
+    /// Version of the prover crate (`zoro-assumevalid`)
+    pub version: String,
+    /// Parameters verifying the proofs of this version
+    pub verifier_config: VerifierConfig,
+}
+
+/// Releases of the prover, from the oldest to the newest
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ReleaseManifest {
+    /// Sequence number of the manifest, increased by every publication
+    pub sequence: u64,
+    /// Unix timestamp after which the manifest is rejected
+    pub expires_at: u64,
+    pub releases: Vec<Release>,
+}
+
+impl ReleaseManifest {
+    /// Reject the manifest if it expired at `now` (Unix timestamp), or if it is older than the
+    /// manifest of sequence `last_sequence` accepted before
+    pub fn check_fresh(&self, last_sequence: Option<u64>, now: u64) -> anyhow::Result<()> {
+        if let Some(last_sequence) = last_sequence {
+            if self.sequence < last_sequence {
+                return Err(anyhow::anyhow!(
+                    "Release manifest {} is older than manifest {} accepted before",
+                    self.sequence,
+                    last_sequence
+                ))
+                .hint(MANIFEST_STALE_HINT);
+            }
+        }
+        if now >= self.expires_at {
+            return Err(anyhow::anyhow!(
+                "Release manifest {} expired at {}",
+                self.sequence,
+                self.expires_at
+            ))
+            .hint(MANIFEST_STALE_HINT);
+        }
+        Ok(())
+    }
+
+    /// Release of the prover version `version`, the newest one if `None`
+    pub fn release(&self, version: Option<&str>) -> anyhow::Result<&Release> {
+        match version {
+            Some(version) => self
+                .releases
+                .iter()
+                .find(|release| release.version == version)
+                .ok_or_else(|| anyhow::anyhow!("No release {version} in the manifest"))
+                .hint(MANIFEST_RELEASE_HINT),
+            None => self
+                .releases
+                .last()
+                .ok_or_else(|| anyhow::anyhow!("The manifest lists no release")),
+        }
+    }
+
+    /// Sign the manifest with the release key
+    pub fn sign(&self, key: &SigningKey) -> anyhow::Result<SignedManifest> {
+        let manifest = serde_json::to_value(self)?;
+        let signature = key.sign(&to_canonical_json(&manifest)?);
+        Ok(SignedManifest {
+            manifest,
+            signature: hex::encode(signature.to_bytes()),
+        })
+    }
+}
+
+/// Release manifest as published, with the signature of its canonical encoding
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SignedManifest {
+    /// The [`ReleaseManifest`], kept as published for the signature check
+    pub manifest: Value,
+    /// Ed25519 signature of the canonical JSON of `manifest` (hex string)
+    pub signature: String,
+}
+
+impl SignedManifest {
+    /// Check the signature against the release key, then decode the manifest
+    pub fn verify(&self, key: &ManifestKey) -> anyhow::Result<ReleaseManifest> {
+        let signature = <[u8; 64]>::try_from(hex::decode(&self.signature).unwrap_or_default())
+            .map_err(|_| anyhow::anyhow!("Manifest signature must be 64 bytes of hex"))?;
+        key.0
+            .verify_strict(
+                &to_canonical_json(&self.manifest)?,
+                &Signature::from_bytes(&signature),
+            )
+            .map_err(|_| anyhow::anyhow!("Invalid release manifest signature"))
+            .hint(MANIFEST_SIGNATURE_HINT)?;
+        serde_json::from_value(self.manifest.clone())
+            .map_err(|e| anyhow::anyhow!("Malformed release manifest: {e}"))
+    }
+}
+
+/// Ed25519 public key the release manifests are signed with, as 32 bytes of hex
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct ManifestKey(VerifyingKey);
+
+impl ManifestKey {
+    /// Public key of a signing key
+    pub fn of(key: &SigningKey) -> Self {
+        Self(key.verifying_key())
+    }
+}
+
+impl FromStr for ManifestKey {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = <[u8; 32]>::try_from(hex::decode(s.trim()).unwrap_or_default())
+            .map_err(|_| anyhow::anyhow!("Invalid manifest key {s}: expected 32 bytes of hex"))?;
+        let key = VerifyingKey::from_bytes(&bytes)
+            .map_err(|e| anyhow::anyhow!("Invalid manifest key {s}: {e}"))?;
+        Ok(Self(key))
+    }
+}
+
+impl fmt::Display for ManifestKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&hex::encode(self.0.as_bytes()))
+    }
+}
+
+impl fmt::Debug for ManifestKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ed25519:{self}")
+    }
+}
+
+/// File holding the sequence number of the newest release manifest accepted, so that older ones
+/// are rejected
+#[derive(Debug, Clone)]
+pub struct SequenceFile(PathBuf);
+
+impl SequenceFile {
+    pub fn new(path: PathBuf) -> Self {
+        Self(path)
+    }
+
+    /// Sequence number of the newest manifest accepted, `None` if none was
+    pub fn load(&self) -> anyhow::Result<Option<u64>> {
+        match std::fs::read_to_string(&self.0) {
+            Ok(data) => data.trim().parse().map(Some).map_err(|e| {
+                anyhow::anyhow!("Invalid manifest sequence file {}: {e}", self.0.display())
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(anyhow::anyhow!(
+                "Failed to read manifest sequence file {}: {e}",
+                self.0.display()
+            )),
+        }
+    }
+
+    /// Record the sequence number of an accepted manifest, if newer than the recorded one
+    pub fn store(&self, sequence: u64) -> anyhow::Result<()> {
+        if self.load()?.is_some_and(|last| last >= sequence) {
+            return Ok(());
+        }
+        // Written aside then renamed, so that an interrupted write does not lose the sequence
+        let tmp = self.0.with_extension("tmp");
+        std::fs::write(&tmp, format!("{sequence}\n"))
+            .and_then(|()| std::fs::rename(&tmp, &self.0))
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "Failed to write manifest sequence file {}: {e}",
+                    self.0.display()
+                )
+            })
+    }
+}
+
+/// Parse a signing key from 32 bytes of hex, as generated by `openssl rand -hex 32`
+pub fn parse_signing_key(s: &str) -> anyhow::Result<SigningKey> {
+    let bytes = <[u8; 32]>::try_from(hex::decode(s.trim()).unwrap_or_default())
+        .map_err(|_| anyhow::anyhow!("Signing key must be 32 bytes of hex"))?;
+    Ok(SigningKey::from_bytes(&bytes))
+}
+
+/// Fetch a signed release manifest over HTTPS, check its signature and that it is fresh, see
+/// [`ReleaseManifest::check_fresh`]
+#[cfg(feature = "client")]
+pub async fn fetch(
+    client: &reqwest::Client,
+    url: &str,
+    key: &ManifestKey,
+    limits: &ProofLimits,
+    last_sequence: Option<u64>,
+) -> anyhow::Result<ReleaseManifest> {
+    check_url(url)?;
+    let response = client.get(url).send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        anyhow::bail!("Failed to get release manifest from {url}: {status}");
+    }
+    let data = read_body(response, limits).await?;
+    let signed: SignedManifest = parse_json_limited(&data, "release manifest", limits)?;
+    let manifest = signed.verify(key)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    manifest.check_fresh(last_sequence, now)?;
+    Ok(manifest)
+}
+
+/// Reject manifest URLs that are not HTTPS
+#[cfg(feature = "client")]
+fn check_url(url: &str) -> anyhow::Result<()> {
+    if !url.starts_with("https://") {
+        anyhow::bail!("Release manifests are fetched over https:// only, got {url}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest() -> ReleaseManifest {
+        let mut next = VerifierConfig::default();
+        next.task_program_hash = "0x01".to_string();
+        ReleaseManifest {
+            sequence: 2,
+            expires_at: 2_000_000_000,
+            releases: vec![
+                Release {
+                    version: "0.1.0".to_string(),
+                    verifier_config: VerifierConfig::default(),
+                },
+                Release {
+                    version: "0.2.0".to_string(),
+                    verifier_config: next,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_sign_and_verify_manifest() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let signed = manifest().sign(&key).unwrap();
+        let manifest = signed.verify(&ManifestKey::of(&key)).unwrap();
+        assert_eq!(manifest.release(None).unwrap().version, "0.2.0");
+        assert_eq!(
+            manifest
+                .release(Some("0.1.0"))
+                .unwrap()
+                .verifier_config
+                .task_program_hash,
+            VerifierConfig::default().task_program_hash
+        );
+        assert!(manifest.release(Some("9.9.9")).is_err());
+    }
+
+    #[test]
+    fn test_reject_tampered_manifest() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let mut signed = manifest().sign(&key).unwrap();
+        signed.manifest["releases"][1]["verifier_config"]["min_confirmations"] = 1.into();
+        assert!(signed.verify(&ManifestKey::of(&key)).is_err());
+
+        let signed = manifest().sign(&key).unwrap();
+        let other = ManifestKey::of(&SigningKey::from_bytes(&[8; 32]));
+        assert!(signed.verify(&other).is_err());
+    }
+
+    #[test]
+    fn test_signature_ignores_formatting() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let signed = manifest().sign(&key).unwrap();
+        // Republishing with another layout keeps the signature valid
+        let pretty = serde_json::to_string_pretty(&signed).unwrap();
+        let reparsed: SignedManifest = serde_json::from_str(&pretty).unwrap();
+        assert!(reparsed.verify(&ManifestKey::of(&key)).is_ok());
+    }
+
+    #[test]
+    fn test_reject_stale_manifest() {
+        let manifest = manifest();
+        assert!(manifest.check_fresh(None, 1_900_000_000).is_ok());
+        assert!(manifest.check_fresh(Some(2), 1_900_000_000).is_ok());
+        // Rolled back
+        let error = manifest.check_fresh(Some(3), 1_900_000_000).unwrap_err();
+        assert!(
+            error.to_string().contains("older than manifest 3"),
+            "{error}"
+        );
+        // Expired
+        let error = manifest.check_fresh(Some(1), 2_000_000_000).unwrap_err();
+        assert!(error.to_string().contains("expired"), "{error}");
+    }
+
+    #[test]
+    fn test_sequence_file() {
+        let path =
+            std::env::temp_dir().join(format!("zoro-manifest-sequence-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let file = SequenceFile::new(path.clone());
+        assert_eq!(file.load().unwrap(), None);
+        file.store(5).unwrap();
+        // Never goes back
+        file.store(4).unwrap();
+        assert_eq!(file.load().unwrap(), Some(5));
+        file.store(6).unwrap();
+        assert_eq!(file.load().unwrap(), Some(6));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_manifest_key_roundtrip() {
+        let key = ManifestKey::of(&SigningKey::from_bytes(&[7; 32]));
+        assert_eq!(key.to_string().parse::<ManifestKey>().unwrap(), key);
+        assert!("abcd".parse::<ManifestKey>().is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "client")]
+    fn test_manifest_url_is_https() {
+        assert!(check_url("http://example.com/manifest.json").is_err());
+        assert!(check_url("https://example.com/manifest.json").is_ok());
+    }
+}
@@ -0,0 +1,156 @@
+//! Positional math of Merkle Mountain Ranges
+//!
+//! Elements (leaves and parents) are numbered from 1 in insertion order, as in the FlyClient MMR
+//! store; leaves are numbered from 0. Functions take and return `u64` so that positions never
+//! depend on the target pointer width, and have no dependencies beyond `core`.
+
+/// Number of bits needed to represent `n`
+fn bit_length(n: u64) -> u32 {
+    u64::BITS - n.leading_zeros()
+}
+
+/// Whether all the bits of `n` below its highest set bit are set, i.e. `n = 2^k - 1`
+fn all_ones(n: u64) -> bool {
+    n != 0 && n.count_ones() == bit_length(n)
+}
+
+/// Height of the element at `element_index`, leaves being at height 0.
+///
+/// `element_index` must not be 0.
+pub fn element_height(element_index: u64) -> u32 {
+    let mut index = element_index;
+    // Jump to the same height in the leftmost perfect tree until the index is its rightmost node
+    while !all_ones(index) {
+        index -= (1u64 << (bit_length(index) - 1)) - 1;
+    }
+    bit_length(index) - 1
+}
+
+/// Number of elements in an MMR of `leaf_count` leaves
+pub fn leaf_count_to_mmr_size(leaf_count: u64) -> u64 {
+    2 * leaf_count - u64::from(leaf_count.count_ones())
+}
+
+/// Number of leaves in an MMR of `elements_count` elements, `None` if no MMR has that size
+pub fn elements_count_to_leaf_count(elements_count: u64) -> Option<u64> {
+    let mut leaf_count = 0;
+    let mut remaining = elements_count;
+    // Peel off the perfect trees from the highest down; each size occurs at most once
+    for height in (0..u64::BITS - 1).rev() {
+        let tree_size = (1u64 << (height + 1)) - 1;
+        if remaining >= tree_size {
+            remaining -= tree_size;
+            leaf_count += 1u64 << height;
+        }
+    }
+    (remaining == 0).then_some(leaf_count)
+}
+
+/// Number of peaks of an MMR of `leaf_count` leaves
+pub fn leaf_count_to_peaks_count(leaf_count: u64) -> u32 {
+    leaf_count.count_ones()
+}
+
+/// Element index of the leaf at `leaf_index`
+pub fn leaf_index_to_element_index(leaf_index: u64) -> u64 {
+    leaf_count_to_mmr_size(leaf_index) + 1
+}
+
+/// Leaf index of the element at `element_index`, `None` if it is not a leaf
+pub fn element_index_to_leaf_index(element_index: u64) -> Option<u64> {
+    if element_index == 0 || element_height(element_index) != 0 {
+        return None;
+    }
+    elements_count_to_leaf_count(element_index - 1)
+}
+
+/// Element indexes of the peaks of an MMR of `elements_count` elements, from the highest tree
+/// (oldest leaves) downwards. Empty if no MMR has that size.
+pub fn find_peaks(elements_count: u64) -> Vec<u64> {
+    let Some(leaf_count) = elements_count_to_leaf_count(elements_count) else {
+        return Vec::new();
+    };
+    let mut peaks = Vec::with_capacity(leaf_count_to_peaks_count(leaf_count) as usize);
+    let mut offset = 0;
+    for height in (0..u64::BITS - 1).rev() {
+        if leaf_count & (1u64 << height) != 0 {
+            offset += (1u64 << (height + 1)) - 1;
+            peaks.push(offset);
+        }
+    }
+    peaks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_element_heights() {
+        // 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15
+        // 0 0 1 0 0 1 2 0 0  1  0  0  1  2  3
+        let heights: Vec<u32> = (1..=15).map(element_height).collect();
+        assert_eq!(heights, vec![0, 0, 1, 0, 0, 1, 2, 0, 0, 1, 0, 0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_leaf_element_index_roundtrip() {
+        for leaf_index in 0..4096u64 {
+            let element_index = leaf_index_to_element_index(leaf_index);
+            assert_eq!(element_height(element_index), 0);
+            assert_eq!(element_index_to_leaf_index(element_index), Some(leaf_index));
+        }
+        assert_eq!(element_index_to_leaf_index(0), None);
+        assert_eq!(element_index_to_leaf_index(3), None);
+        assert_eq!(element_index_to_leaf_index(7), None);
+    }
+
+    #[test]
+    fn test_sizes_roundtrip() {
+        for leaf_count in 0..4096u64 {
+            let size = leaf_count_to_mmr_size(leaf_count);
+            assert_eq!(elements_count_to_leaf_count(size), Some(leaf_count));
+            assert_eq!(
+                find_peaks(size).len() as u32,
+                leaf_count_to_peaks_count(leaf_count)
+            );
+        }
+        // Sizes in between are not valid MMRs
+        assert_eq!(elements_count_to_leaf_count(2), None);
+        assert_eq!(elements_count_to_leaf_count(5), None);
+        assert!(find_peaks(5).is_empty());
+    }
+
+    #[test]
+    fn test_u64_positions() {
+        let leaf_count = 1u64 << 40;
+        assert_eq!(leaf_count_to_mmr_size(leaf_count), (1u64 << 41) - 1);
+        assert_eq!(find_peaks((1u64 << 41) - 1), vec![(1u64 << 41) - 1]);
+        assert_eq!(element_height((1u64 << 41) - 1), 40);
+        let last_leaf = leaf_index_to_element_index(leaf_count - 1);
+        assert_eq!(element_index_to_leaf_index(last_leaf), Some(leaf_count - 1));
+    }
+
+    #[test]
+    #[cfg(feature = "flyclient")]
+    fn test_matches_accumulators() {
+        use accumulators::mmr::{
+            elements_count_to_leaf_count as acc_leaf_count, helpers::find_peaks as acc_find_peaks,
+            leaf_count_to_mmr_size as acc_mmr_size,
+            map_leaf_index_to_element_index as acc_element_index,
+        };
+        for n in 0..2048usize {
+            assert_eq!(leaf_count_to_mmr_size(n as u64), acc_mmr_size(n) as u64);
+            assert_eq!(
+                leaf_index_to_element_index(n as u64),
+                acc_element_index(n) as u64
+            );
+            assert_eq!(
+                elements_count_to_leaf_count(n as u64),
+                acc_leaf_count(n).ok().map(|count| count as u64)
+            );
+            let peaks: Vec<u64> = acc_find_peaks(n).into_iter().map(|p| p as u64).collect();
+            assert_eq!(find_peaks(n as u64), peaks);
+        }
+    }
+}
@@ -0,0 +1,206 @@
+//! Cache of FlyClient MMR states verified against a trusted root.
+//!
+//! [`verify_block_inclusion`] rebuilds an in-memory MMR from the peaks of every proof it is given.
+//! A wallet checking several blocks against the same chain height gets the same peaks each time,
+//! so [`MmrView`] keeps the peaks of the MMR states whose root was checked against the bridge
+//! record ([`MmrView::trust`]). Proofs at a cached chain height with the same peaks then verify
+//! from the cache, without rebuilding the MMR or fetching the recorded root again.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use zebra_chain::block::Header;
+
+use crate::proof::{BlockInclusionProof, MmrRootRecord};
+use crate::untrusted::check_block_inclusion_shape;
+use crate::verify::{verify_block_inclusion, verify_mmr_root_history};
+
+/// Default number of MMR states kept by an [`MmrView`]
+pub const DEFAULT_MMR_VIEW_CAPACITY: usize = 64;
+
+/// MMR state identified by its epoch and the height of its last block
+type ViewKey = (Option<String>, u32);
+
+/// Peaks of an MMR state and the root they were verified against
+#[derive(Debug)]
+struct VerifiedPeaks {
+    leaf_count: usize,
+    peaks_hashes: Vec<String>,
+    root: String,
+}
+
+#[derive(Debug, Default)]
+struct Views {
+    peaks: HashMap<ViewKey, VerifiedPeaks>,
+    /// Keys in insertion order, the oldest first
+    order: VecDeque<ViewKey>,
+}
+
+/// Root of the MMR state a block inclusion proof was verified against
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ViewedRoot {
+    /// MMR root hash
+    pub root: String,
+    /// Whether the root was already checked against the bridge record. If not, check it with
+    /// [`MmrView::trust`].
+    pub trusted: bool,
+}
+
+/// Verified peaks of recent FlyClient MMR states, shared by the block inclusion verifications of
+/// a client
+#[derive(Debug)]
+pub struct MmrView {
+    views: Mutex<Views>,
+    capacity: usize,
+}
+
+impl Default for MmrView {
+    fn default() -> Self {
+        Self::new(DEFAULT_MMR_VIEW_CAPACITY)
+    }
+}
+
+impl MmrView {
+    /// View keeping up to `capacity` MMR states, evicting the oldest first
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            views: Mutex::new(Views::default()),
+            capacity,
+        }
+    }
+
+    /// Verify a block inclusion proof, from the cached peaks if its MMR state is known.
+    ///
+    /// Falls back to [`verify_block_inclusion`] for unknown states and for proofs whose peaks
+    /// differ from the cached ones.
+    pub async fn verify_block_inclusion(
+        &self,
+        block_header: &Header,
+        proof: &BlockInclusionProof,
+    ) -> anyhow::Result<ViewedRoot> {
+        if let Some(root) = self.trusted_root(proof) {
+            check_block_inclusion_shape(proof)?;
+            if proof.siblings_hashes.is_empty() && proof.leaf_count > 1 {
+                anyhow::bail!("Block inclusion proof has no siblings but leaf_count > 1");
+            }
+            return Ok(ViewedRoot {
+                root,
+                trusted: true,
+            });
+        }
+        let root = verify_block_inclusion(block_header, proof).await?;
+        Ok(ViewedRoot {
+            root,
+            trusted: false,
+        })
+    }
+
+    /// Check the root reconstructed from `proof` against the root recorded by the bridge node,
+    /// and keep the peaks of the proof on success
+    pub fn trust(
+        &self,
+        proof: &BlockInclusionProof,
+        root: &str,
+        record: &MmrRootRecord,
+    ) -> anyhow::Result<()> {
+        verify_mmr_root_history(proof, root, record)?;
+        let key = view_key(proof);
+        let mut views = self.views.lock().unwrap();
+        let Views { peaks, order } = &mut *views;
+        let verified = VerifiedPeaks {
+            leaf_count: proof.leaf_count,
+            peaks_hashes: proof.peaks_hashes.clone(),
+            root: root.to_string(),
+        };
+        if peaks.insert(key.clone(), verified).is_none() {
+            order.push_back(key);
+        }
+        while order.len() > self.capacity {
+            if let Some(oldest) = order.pop_front() {
+                peaks.remove(&oldest);
+            }
+        }
+        Ok(())
+    }
+
+    /// Trusted root of the MMR state of `proof`, if the state is cached with the same peaks
+    pub fn trusted_root(&self, proof: &BlockInclusionProof) -> Option<String> {
+        let views = self.views.lock().unwrap();
+        let verified = views.peaks.get(&view_key(proof))?;
+        (verified.leaf_count == proof.leaf_count && verified.peaks_hashes == proof.peaks_hashes)
+            .then(|| verified.root.clone())
+    }
+
+    /// Number of cached MMR states
+    pub fn len(&self) -> usize {
+        self.views.lock().unwrap().peaks.len()
+    }
+
+    /// Whether no MMR state is cached
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+fn view_key(proof: &BlockInclusionProof) -> ViewKey {
+    (proof.epoch.clone(), proof.chain_height())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proof(block_height: u32, leaf_index: usize, peak: &str) -> BlockInclusionProof {
+        BlockInclusionProof {
+            block_height,
+            peaks_hashes: vec![peak.to_string()],
+            siblings_hashes: vec![],
+            leaf_index,
+            leaf_count: 4,
+            epoch: Some("nu5".to_string()),
+            epoch_start_height: None,
+            mmr_id: None,
+        }
+    }
+
+    fn record(height: u32, root: &str) -> MmrRootRecord {
+        MmrRootRecord {
+            height,
+            epoch: "nu5".to_string(),
+            leaf_count: 4,
+            root: root.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_trusted_root_requires_same_peaks() {
+        let view = MmrView::default();
+        assert!(view
+            .trust(&proof(100, 0, "aa"), "11", &record(103, "22"))
+            .is_err());
+        assert!(view.is_empty());
+
+        view.trust(&proof(100, 0, "aa"), "11", &record(103, "11"))
+            .unwrap();
+        // Another block of the same MMR state
+        assert_eq!(
+            view.trusted_root(&proof(102, 2, "aa")),
+            Some("11".to_string())
+        );
+        assert_eq!(view.trusted_root(&proof(102, 2, "bb")), None);
+        // Another MMR state
+        assert_eq!(view.trusted_root(&proof(102, 1, "aa")), None);
+    }
+
+    #[test]
+    fn test_capacity() {
+        let view = MmrView::new(2);
+        for height in [100, 101, 102] {
+            view.trust(&proof(height, 0, "aa"), "11", &record(height + 3, "11"))
+                .unwrap();
+        }
+        assert_eq!(view.len(), 2);
+        assert_eq!(view.trusted_root(&proof(100, 0, "aa")), None);
+        assert!(view.trusted_root(&proof(102, 0, "aa")).is_some());
+    }
+}
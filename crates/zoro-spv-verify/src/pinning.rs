@@ -0,0 +1,231 @@
+//! TLS pinning of the bridge node certificates.
+//!
+//! Pinned connections still validate the certificate chain and host name against the trust
+//! roots. The handshake is then rejected unless the leaf certificate has a pinned
+//! SubjectPublicKeyInfo, or chains up to an intermediate with a pinned one, so a rogue CA cannot
+//! substitute proofs. Intermediates are only matched once the leaf is verified against them:
+//! presenting a pinned intermediate next to a leaf it did not issue does not pass.
+
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use rustls::client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier};
+use rustls::{Certificate, ClientConfig, OwnedTrustAnchor, RootCertStore, ServerName};
+use sha2::{Digest, Sha256};
+
+/// SHA-256 digest of a DER-encoded SubjectPublicKeyInfo, as printed in hex by
+/// `openssl x509 -pubkey -noout | openssl pkey -pubin -outform der | openssl dgst -sha256`
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SpkiPin([u8; 32]);
+
+impl SpkiPin {
+    /// Pin of a DER-encoded certificate, `None` if it cannot be parsed
+    pub fn of_certificate(der: &[u8]) -> Option<Self> {
+        let (_, cert) = x509_parser::parse_x509_certificate(der).ok()?;
+        Some(Self(Sha256::digest(cert.public_key().raw).into()))
+    }
+}
+
+impl FromStr for SpkiPin {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = hex::decode(s.trim().trim_start_matches("sha256:"))
+            .map_err(|e| anyhow::anyhow!("Invalid SPKI pin {s}: {e}"))?;
+        let digest = bytes
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Invalid SPKI pin {s}: expected 32 bytes"))?;
+        Ok(Self(digest))
+    }
+}
+
+impl fmt::Debug for SpkiPin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "sha256:{}", hex::encode(self.0))
+    }
+}
+
+/// Trust configuration of the connections to the bridge node
+#[derive(Debug, Clone, Default)]
+pub struct TlsPinning {
+    /// Certificates trusted instead of the built-in roots (e.g. a private CA or a self-signed
+    /// bridge certificate)
+    pub ca_certs: Vec<Certificate>,
+    /// SPKI pins, one of which the leaf or an intermediate it chains up to must match
+    pub spki_pins: Vec<SpkiPin>,
+}
+
+impl TlsPinning {
+    /// Whether the default trust configuration is overridden
+    pub fn is_enabled(&self) -> bool {
+        !self.ca_certs.is_empty() || !self.spki_pins.is_empty()
+    }
+
+    /// Build the rustls configuration enforcing the pinning
+    pub fn client_config(&self) -> Result<ClientConfig, anyhow::Error> {
+        Ok(ClientConfig::builder()
+            .with_safe_defaults()
+            .with_custom_certificate_verifier(Arc::new(self.verifier()?))
+            .with_no_client_auth())
+    }
+
+    /// Verifier of the bridge certificates: the chain against the trust roots, then the pins
+    fn verifier(&self) -> Result<PinnedVerifier, anyhow::Error> {
+        let mut roots = RootCertStore::empty();
+        if self.ca_certs.is_empty() {
+            roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+                OwnedTrustAnchor::from_subject_spki_name_constraints(
+                    anchor.subject,
+                    anchor.spki,
+                    anchor.name_constraints,
+                )
+            }));
+        }
+        for cert in &self.ca_certs {
+            roots
+                .add(cert)
+                .map_err(|e| anyhow::anyhow!("Invalid CA certificate: {e}"))?;
+        }
+        Ok(PinnedVerifier {
+            inner: WebPkiVerifier::new(roots, None),
+            pins: self.spki_pins.clone(),
+        })
+    }
+}
+
+/// Load the PEM-encoded certificates of a file
+pub fn load_ca_certs(path: &Path) -> Result<Vec<Certificate>, anyhow::Error> {
+    let pem = std::fs::read(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {e}", path.display()))?;
+    let certs = rustls_pemfile::certs(&mut pem.as_slice())
+        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {e}", path.display()))?;
+    if certs.is_empty() {
+        anyhow::bail!("No certificate in {}", path.display());
+    }
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+/// Validates the chain with WebPKI, then checks the SPKI pins
+struct PinnedVerifier {
+    inner: WebPkiVerifier,
+    pins: Vec<SpkiPin>,
+}
+
+impl ServerCertVerifier for PinnedVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let verified = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            scts,
+            ocsp_response,
+            now,
+        )?;
+        if self.pins.is_empty() {
+            return Ok(verified);
+        }
+        let pinned = |cert: &Certificate| {
+            SpkiPin::of_certificate(&cert.0).is_some_and(|pin| self.pins.contains(&pin))
+        };
+        // A pinned intermediate must be on the path of the leaf: the leaf must verify with it as
+        // the only trust anchor
+        let issued_by = |intermediate: &Certificate| {
+            let mut anchor = RootCertStore::empty();
+            anchor.add(intermediate).is_ok()
+                && WebPkiVerifier::new(anchor, None)
+                    .verify_server_cert(
+                        end_entity,
+                        intermediates,
+                        server_name,
+                        &mut std::iter::empty(),
+                        ocsp_response,
+                        now,
+                    )
+                    .is_ok()
+        };
+        if pinned(end_entity)
+            || intermediates
+                .iter()
+                .any(|intermediate| pinned(intermediate) && issued_by(intermediate))
+        {
+            Ok(verified)
+        } else {
+            Err(rustls::Error::General(
+                "Bridge certificate chain matches none of the SPKI pins".to_string(),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_spki_pin() {
+        let hex = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+        let pin: SpkiPin = hex.parse().unwrap();
+        assert_eq!(format!("{pin:?}"), format!("sha256:{hex}"));
+        assert_eq!(format!("sha256:{hex}").parse::<SpkiPin>().unwrap(), pin);
+
+        assert!("9f86d0".parse::<SpkiPin>().is_err());
+        assert!("not hex".parse::<SpkiPin>().is_err());
+    }
+
+    #[test]
+    fn test_pinning_disabled_by_default() {
+        let pinning = TlsPinning::default();
+        assert!(!pinning.is_enabled());
+        assert!(pinning.client_config().is_ok());
+        assert!(SpkiPin::of_certificate(b"not a certificate").is_none());
+    }
+
+    fn cert(pem: &str) -> Certificate {
+        Certificate(
+            rustls_pemfile::certs(&mut pem.as_bytes())
+                .unwrap()
+                .remove(0),
+        )
+    }
+
+    #[test]
+    fn test_pins_match_the_verified_chain() {
+        let root = cert(include_str!("../tests/data/pinning/root.pem"));
+        let intermediate = cert(include_str!("../tests/data/pinning/intermediate.pem"));
+        // Issued by the root, but not the issuer of the leaf
+        let other = cert(include_str!("../tests/data/pinning/other_intermediate.pem"));
+        let leaf = cert(include_str!("../tests/data/pinning/leaf.pem"));
+        let server_name = ServerName::try_from("bridge.test").unwrap();
+
+        let verify = |pinned: &Certificate, intermediates: &[Certificate]| {
+            let pinning = TlsPinning {
+                ca_certs: vec![root.clone()],
+                spki_pins: vec![SpkiPin::of_certificate(&pinned.0).unwrap()],
+            };
+            pinning.verifier().unwrap().verify_server_cert(
+                &leaf,
+                intermediates,
+                &server_name,
+                &mut std::iter::empty(),
+                &[],
+                SystemTime::now(),
+            )
+        };
+        assert!(verify(&leaf, &[intermediate.clone()]).is_ok());
+        assert!(verify(&intermediate, &[intermediate.clone()]).is_ok());
+        assert!(verify(&root, &[intermediate.clone()]).is_err());
+        // A pinned intermediate presented next to a leaf it did not issue
+        assert!(verify(&other, &[intermediate.clone(), other.clone()]).is_err());
+    }
+}
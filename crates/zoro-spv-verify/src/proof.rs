@@ -1,61 +1,469 @@
 //! Types representing the compressed SPV proof and helpers to decode Cairo outputs
 //! and compute chain state digests used during verification.
 
+#[cfg(feature = "stark")]
 use cairo_air::CairoProof;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use starknet_ff::FieldElement;
 use stwo::core::vcs::blake2_hash::Blake2sHasher;
+#[cfg(feature = "stark")]
 use stwo::core::vcs::blake2_merkle::Blake2sMerkleHasher;
 use zebra_chain::block::Hash;
 use zebra_chain::block::Header;
-use zebra_chain::transaction::Transaction;
+use zebra_chain::parameters::NetworkKind;
+use zebra_chain::transaction::{self, Transaction};
+use zebra_chain::transparent;
 use zoro_zcash_client::serialize::{
     deserialize_header, deserialize_transaction, serialize_header, serialize_transaction,
 };
 use zoro_zcash_client::MerkleProof;
 
+use crate::addresses::TransparentAddress;
+use crate::finality::FinalityPolicy;
+
 /// Zcash transaction inclusion data in a specific block
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct TransactionInclusionProof {
     /// The full Zcash transaction being proven
     #[serde(
         serialize_with = "serialize_transaction",
         deserialize_with = "deserialize_transaction"
     )]
+    #[schemars(with = "String")]
     pub transaction: Transaction,
     /// Encoded PartialMerkleTree containing the Merkle path for the transaction
+    #[serde(alias = "transactionProof")]
     pub transaction_proof: MerkleProof,
     /// Header of the block that includes the transaction
     #[serde(
         serialize_with = "serialize_header",
-        deserialize_with = "deserialize_header"
+        deserialize_with = "deserialize_header",
+        alias = "blockHeader"
     )]
+    #[schemars(with = "String")]
     pub block_header: Header,
     /// Height of the block that includes the transaction
+    #[serde(alias = "blockHeight")]
+    pub block_height: u32,
+}
+
+/// Kind of identifier used to look up a transaction
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TxIdKind {
+    /// Try the txid in display order, then in internal byte order, then as an auth digest
+    /// (the latter only when the block height is known)
+    #[default]
+    Auto,
+    /// Txid in display (RPC / block explorer) byte order
+    Txid,
+    /// Txid in internal (reversed) byte order
+    TxidReversed,
+    /// NU5 authorizing data digest (`wtxid`), in either byte order
+    AuthDigest,
+}
+
+/// Identifier that matched a transaction lookup
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct MatchedTxId {
+    /// Which interpretation of the supplied identifier matched (never `auto`)
+    pub kind: TxIdKind,
+    /// Canonical txid of the matched transaction, in display order
+    pub txid: String,
+}
+
+/// Where the bridge read the block transactions of a transaction proof from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProofSource {
+    /// Live Zcash node RPC
+    Rpc,
+    /// Transactions stored by the bridge, served while the Zcash node is unavailable
+    Local,
+}
+
+/// Bridge response for a transaction proof lookup
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct TransactionProofResponse {
+    /// The transaction inclusion proof
+    #[serde(flatten)]
+    pub proof: TransactionInclusionProof,
+    /// Identifier that matched, absent for bridges that predate identifier disambiguation
+    #[serde(default)]
+    pub matched: Option<MatchedTxId>,
+    /// Where the block transactions were read from, absent for bridges that predate degraded
+    /// mode
+    #[serde(default)]
+    pub source: Option<ProofSource>,
+}
+
+/// Bridge response for a bucketed transaction proof lookup: the proofs of every transaction
+/// whose txid starts with the prefix
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct TransactionBucketResponse {
+    /// Txid prefix of the bucket, in display order
+    pub prefix: String,
+    /// Proofs of the transactions in the bucket, ordered by block height
+    pub proofs: Vec<TransactionProofResponse>,
+}
+
+impl TransactionBucketResponse {
+    /// Take the proof of the transaction with the given txid (display order hex)
+    pub fn into_transaction(self, txid: &str) -> Option<TransactionProofResponse> {
+        self.proofs.into_iter().find(|response| {
+            response
+                .proof
+                .transaction
+                .hash()
+                .to_string()
+                .eq_ignore_ascii_case(txid)
+        })
+    }
+}
+
+/// Proof that a transaction is not included in a specific block.
+///
+/// The txids of every transaction of the block, in block order, hash to the block Merkle root.
+/// A txid list alone is not enough: the inner nodes of a level of the Merkle tree hash to the
+/// same root, and would hide the transactions below them. The coinbase transaction pins the
+/// list to the leaves, since its txid is the first one and no transaction serializes to a
+/// 64-byte node pair.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct TransactionAbsenceProof {
+    /// Header of the block
+    #[serde(
+        serialize_with = "serialize_header",
+        deserialize_with = "deserialize_header",
+        alias = "blockHeader"
+    )]
+    #[schemars(with = "String")]
+    pub block_header: Header,
+    /// Height of the block
+    #[serde(alias = "blockHeight")]
     pub block_height: u32,
+    /// The coinbase transaction of the block
+    #[serde(
+        serialize_with = "serialize_transaction",
+        deserialize_with = "deserialize_transaction"
+    )]
+    #[schemars(with = "String")]
+    pub coinbase: Transaction,
+    /// Txids of every transaction of the block, in block order and display byte order
+    pub txids: Vec<String>,
+}
+
+impl TransactionAbsenceProof {
+    /// Parse the txids of the block
+    pub fn tx_hashes(&self) -> anyhow::Result<Vec<transaction::Hash>> {
+        self.txids
+            .iter()
+            .map(|txid| {
+                txid.parse()
+                    .map_err(|_| anyhow::anyhow!("Invalid txid {txid:?} in absence proof"))
+            })
+            .collect()
+    }
+}
+
+/// Transparent output of a coinbase transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct CoinbaseOutput {
+    /// Index of the output in the transaction
+    pub index: u32,
+    /// Value in zatoshis
+    pub value: u64,
+    /// Locking script, in hex
+    #[serde(alias = "scriptPubkey")]
+    pub script_pubkey: String,
+    /// Mainnet transparent address paid by the script, absent for non-standard scripts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+}
+
+impl CoinbaseOutput {
+    pub fn new(index: u32, output: &transparent::Output) -> Self {
+        Self {
+            index,
+            value: u64::from(output.value),
+            script_pubkey: hex::encode(output.lock_script.as_raw_bytes()),
+            address: TransparentAddress::from_script(
+                NetworkKind::Mainnet,
+                output.lock_script.as_raw_bytes(),
+            )
+            .map(|address| address.to_string()),
+        }
+    }
+
+    /// Transparent outputs of a transaction, in order
+    pub fn from_transaction(transaction: &Transaction) -> Vec<Self> {
+        transaction
+            .outputs()
+            .iter()
+            .zip(0..)
+            .map(|(output, index)| Self::new(index, output))
+            .collect()
+    }
+}
+
+/// Bridge response for a coinbase proof lookup: the inclusion proof of the coinbase transaction
+/// of a block, with its transparent outputs.
+///
+/// The outputs are parsed by the bridge for convenience; verifiers recompute them from the
+/// transaction. Shielded coinbase outputs are not listed.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct CoinbaseProofResponse {
+    /// The inclusion proof of the coinbase transaction, always at index 0
+    #[serde(flatten)]
+    pub proof: TransactionInclusionProof,
+    /// Transparent outputs of the coinbase transaction
+    pub outputs: Vec<CoinbaseOutput>,
+    /// Total value of the transparent outputs in zatoshis
+    #[serde(alias = "totalValue")]
+    pub total_value: u64,
+    /// Where the block transactions were read from
+    #[serde(default)]
+    pub source: Option<ProofSource>,
 }
 
 /// FlyClient MMR inclusion proof for a block
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct BlockInclusionProof {
     /// Block height
+    #[serde(alias = "blockHeight")]
     pub block_height: u32,
     /// MMR peak hashes at the time of proof generation
+    #[serde(alias = "peaksHashes")]
     pub peaks_hashes: Vec<String>,
     /// Sibling hashes needed to reconstruct the path to the root
+    #[serde(alias = "siblingsHashes")]
     pub siblings_hashes: Vec<String>,
     /// Leaf index of the block in the MMR (within its epoch)
+    #[serde(alias = "leafIndex")]
     pub leaf_index: usize,
     /// Total number of leaves in the MMR
+    #[serde(alias = "leafCount")]
     pub leaf_count: usize,
+    /// Name of the network upgrade epoch of the MMR, absent for bridges that predate it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epoch: Option<String>,
+    /// Height of the block at leaf 0 of the epoch MMR, absent for bridges that predate it
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        alias = "epochStartHeight"
+    )]
+    pub epoch_start_height: Option<u32>,
+    /// Identifier of the epoch MMR in the bridge store, absent for bridges that predate it
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "mmrId")]
+    pub mmr_id: Option<String>,
+}
+
+impl BlockInclusionProof {
+    /// Height of the last block in the MMR state the proof was generated against.
+    ///
+    /// The epoch starts at `block_height - leaf_index`, so the last leaf sits at
+    /// `epoch_start + leaf_count - 1`.
+    pub fn chain_height(&self) -> u32 {
+        let height = (self.block_height as usize)
+            .saturating_sub(self.leaf_index)
+            .saturating_add(self.leaf_count)
+            .saturating_sub(1);
+        u32::try_from(height).unwrap_or(u32::MAX)
+    }
+}
+
+/// Indexed block header, as listed by the bridge node's `/headers`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BlockHeaderRecord {
+    /// Height of the block
+    pub height: u32,
+    /// Hash of the block, in display order
+    pub hash: String,
+    /// Consensus encoding of the header, in hex
+    #[serde(
+        serialize_with = "serialize_header",
+        deserialize_with = "deserialize_header"
+    )]
+    #[schemars(with = "String")]
+    pub header: Header,
+}
+
+impl BlockHeaderRecord {
+    pub fn new(height: u32, header: Header) -> Self {
+        Self {
+            height,
+            hash: header.hash().to_string(),
+            header,
+        }
+    }
+
+    /// Check that `hash` is the hash of `header`
+    pub fn check_hash(&self) -> anyhow::Result<()> {
+        let hash = self.header.hash().to_string();
+        if hash != self.hash {
+            anyhow::bail!(
+                "Header at height {} hashes to {}, listed as {}",
+                self.height,
+                hash,
+                self.hash
+            );
+        }
+        Ok(())
+    }
+}
+
+/// FlyClient MMR root recorded by the bridge node right after appending a block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct MmrRootRecord {
+    /// Height of the last block appended to the MMR
+    pub height: u32,
+    /// Name of the network upgrade epoch the MMR belongs to
+    pub epoch: String,
+    /// Number of leaves in the epoch MMR
+    #[serde(alias = "leafCount")]
+    pub leaf_count: usize,
+    /// MMR root hash
+    pub root: String,
+}
+
+/// FlyClient MMR leaf of a block: the ZIP 221 `NodeData` the bridge node appended to the MMR of
+/// the block's epoch.
+///
+/// A client can rebuild the leaf from the block header, Sapling root and Sapling transaction
+/// count, and hash it up to the root with the siblings of a [`BlockInclusionProof`] instead of
+/// trusting the peaks. Hashes are in internal byte order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct FlyClientNodeData {
+    /// Block height
+    #[serde(alias = "blockHeight")]
+    pub block_height: u32,
+    /// Name of the network upgrade epoch of the MMR
+    pub epoch: String,
+    /// Leaf index of the block in the epoch MMR
+    #[serde(alias = "leafIndex")]
+    pub leaf_index: usize,
+    /// Leaf as stored in the MMR, which the other fields are decoded from
+    pub encoded: String,
+    /// Consensus branch ID of the epoch
+    #[serde(alias = "consensusBranchId")]
+    pub consensus_branch_id: u32,
+    /// Block hash
+    #[schemars(with = "[u8; 32]")]
+    #[serde(alias = "subtreeCommitment")]
+    pub subtree_commitment: Hash,
+    /// Block time
+    #[serde(alias = "startTime")]
+    pub start_time: u32,
+    /// Block time
+    #[serde(alias = "endTime")]
+    pub end_time: u32,
+    /// Compact target (`nBits`) of the block
+    #[serde(alias = "startTarget")]
+    pub start_target: u32,
+    /// Compact target (`nBits`) of the block
+    #[serde(alias = "endTarget")]
+    pub end_target: u32,
+    /// Sapling note commitment tree root after the block
+    #[serde(alias = "startSaplingRoot")]
+    pub start_sapling_root: [u8; 32],
+    /// Sapling note commitment tree root after the block
+    #[serde(alias = "endSaplingRoot")]
+    pub end_sapling_root: [u8; 32],
+    /// Work of the block, as a decimal string
+    #[serde(alias = "subtreeTotalWork")]
+    pub subtree_total_work: String,
+    /// Block height
+    #[serde(alias = "startHeight")]
+    pub start_height: u64,
+    /// Block height
+    #[serde(alias = "endHeight")]
+    pub end_height: u64,
+    /// Number of Sapling transactions in the block
+    #[serde(alias = "saplingTx")]
+    pub sapling_tx: u64,
+}
+
+#[cfg(feature = "flyclient")]
+impl FlyClientNodeData {
+    /// Decode the leaf of the block at `block_height`, stored at `leaf_index` in the `epoch` MMR
+    pub fn decode(
+        block_height: u32,
+        epoch: &str,
+        leaf_index: usize,
+        encoded: String,
+    ) -> anyhow::Result<Self> {
+        let node = accumulators::hasher::flyclient::decode_node_data(&encoded)
+            .map_err(|_| anyhow::anyhow!("Invalid FlyClient leaf for block {block_height}"))?;
+        Ok(FlyClientNodeData {
+            block_height,
+            epoch: epoch.to_string(),
+            leaf_index,
+            consensus_branch_id: node.consensus_branch_id,
+            subtree_commitment: Hash(node.subtree_commitment),
+            start_time: node.start_time,
+            end_time: node.end_time,
+            start_target: node.start_target,
+            end_target: node.end_target,
+            start_sapling_root: node.start_sapling_root,
+            end_sapling_root: node.end_sapling_root,
+            subtree_total_work: node.subtree_total_work.to_string(),
+            start_height: node.start_height,
+            end_height: node.end_height,
+            sapling_tx: node.sapling_tx,
+            encoded,
+        })
+    }
+
+    /// Check that the leaf commits to `header` at its height, under the branch ID of its epoch
+    pub fn check_header(&self, header: &Header) -> anyhow::Result<()> {
+        let epoch = zoro_chain_params::Network::Mainnet
+            .flyclient_epoch(self.block_height)
+            .ok_or_else(|| anyhow::anyhow!("Block {} is before Heartwood", self.block_height))?;
+        let time = header.time.timestamp() as u32;
+        let bits = u32::from_be_bytes(header.difficulty_threshold.bytes_in_display_order());
+        let height = self.block_height as u64;
+        let checks = [
+            (
+                "consensus branch ID",
+                self.consensus_branch_id == epoch.upgrade.branch_id(),
+            ),
+            ("block hash", self.subtree_commitment == header.hash()),
+            ("time", self.start_time == time && self.end_time == time),
+            (
+                "target",
+                self.start_target == bits && self.end_target == bits,
+            ),
+            (
+                "height",
+                self.start_height == height && self.end_height == height,
+            ),
+            (
+                "Sapling root",
+                self.start_sapling_root == self.end_sapling_root,
+            ),
+        ];
+        match checks.iter().find(|(_, ok)| !ok) {
+            Some((field, _)) => anyhow::bail!(
+                "FlyClient leaf of block {} has a wrong {}",
+                self.block_height,
+                field
+            ),
+            None => Ok(()),
+        }
+    }
 }
 
 /// Chain state proof wrapper containing the STARK proof and the chain state it proves
-#[derive(Serialize, Deserialize)]
+#[cfg(feature = "stark")]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct ChainStateProof {
     /// The chain state being proven
+    #[serde(alias = "chainState")]
     pub chain_state: ChainState,
     /// The Cairo STARK proof that this chain state is valid
+    #[schemars(with = "serde_json::Value")]
     pub proof: CairoProof<Blake2sMerkleHasher>,
 }
 
@@ -64,24 +472,32 @@ pub struct ChainStateProof {
 /// 1. Chain State Proof: STARK proof that the chain state at height H is valid
 /// 2. Block Inclusion Proof: FlyClient MMR proof that block B is in the chain
 /// 3. Transaction Inclusion Proof: Merkle proof that tx T is in block B
-#[derive(Serialize, Deserialize)]
+#[cfg(feature = "stark")]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct FullInclusionProof {
     // === Layer 1: Chain State ===
     /// The verified chain state (at the tip of the proven chain)
+    #[serde(alias = "chainState")]
     pub chain_state: ChainState,
     /// Cairo STARK proof that chain_state is valid
+    #[schemars(with = "serde_json::Value")]
+    #[serde(alias = "chainStateProof")]
     pub chain_state_proof: CairoProof<Blake2sMerkleHasher>,
 
     // === Layer 2: Block Inclusion ===
     /// The block header containing the transaction
     #[serde(
         serialize_with = "serialize_header",
-        deserialize_with = "deserialize_header"
+        deserialize_with = "deserialize_header",
+        alias = "blockHeader"
     )]
+    #[schemars(with = "String")]
     pub block_header: Header,
     /// Height of the block containing the transaction
+    #[serde(alias = "blockHeight")]
     pub block_height: u32,
     /// FlyClient MMR proof that this block is included in the chain
+    #[serde(alias = "blockInclusionProof")]
     pub block_inclusion_proof: BlockInclusionProof,
 
     // === Layer 3: Transaction Inclusion ===
@@ -90,11 +506,14 @@ pub struct FullInclusionProof {
         serialize_with = "serialize_transaction",
         deserialize_with = "deserialize_transaction"
     )]
+    #[schemars(with = "String")]
     pub transaction: Transaction,
     /// Merkle proof that transaction is in block_header's merkle root
+    #[serde(alias = "transactionProof")]
     pub transaction_proof: MerkleProof,
 }
 
+#[cfg(feature = "stark")]
 impl FullInclusionProof {
     /// Calculate the number of confirmations for this transaction
     /// Confirmations = chain_state.block_height - block_height + 1
@@ -116,20 +535,166 @@ impl FullInclusionProof {
     }
 }
 
+/// Bridge response for a full inclusion proof lookup: every layer of a [`FullInclusionProof`]
+/// except the chain state STARK proof, which is produced out of band
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct FullProofResponse {
+    /// Chain state at the requested chain height
+    #[serde(alias = "chainState")]
+    pub chain_state: ChainState,
+    /// FlyClient MMR proof that the block is included in the chain, generated against the
+    /// chain height
+    #[serde(alias = "blockInclusionProof")]
+    pub block_inclusion_proof: BlockInclusionProof,
+    /// Transaction inclusion proof
+    #[serde(flatten)]
+    pub transaction_proof: TransactionInclusionProof,
+    /// Identifier that matched the transaction lookup
+    #[serde(default)]
+    pub matched: Option<MatchedTxId>,
+    /// Where the block transactions were read from
+    #[serde(default)]
+    pub source: Option<ProofSource>,
+    /// Where the STARK proof for the chain state can be downloaded, if the bridge knows
+    #[serde(default, alias = "chainStateProofUrl")]
+    pub chain_state_proof_url: Option<String>,
+    /// Finality policy the bridge serves proofs for, absent for bridges that predate finality
+    /// policies
+    #[serde(default)]
+    pub finality: Option<FinalityPolicy>,
+}
+
+impl FullProofResponse {
+    /// Combine the response with the STARK proof of its chain state
+    #[cfg(feature = "stark")]
+    pub fn into_full_proof(
+        self,
+        chain_state_proof: CairoProof<Blake2sMerkleHasher>,
+    ) -> FullInclusionProof {
+        FullInclusionProof {
+            chain_state: self.chain_state,
+            chain_state_proof,
+            block_header: self.transaction_proof.block_header,
+            block_height: self.transaction_proof.block_height,
+            block_inclusion_proof: self.block_inclusion_proof,
+            transaction: self.transaction_proof.transaction,
+            transaction_proof: self.transaction_proof.transaction_proof,
+        }
+    }
+}
+
+/// Range of blocks a prover batch proves on top of the chain state at `start_height`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ProvingBatch {
+    #[serde(alias = "startHeight")]
+    pub start_height: u32,
+    #[serde(alias = "blockCount")]
+    pub block_count: u32,
+}
+
+/// Heartbeat a prover pushes to the bridge node's `/proving-status`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ProverHeartbeat {
+    /// Height of the latest chain state with a STARK proof
+    #[serde(alias = "provenHeight")]
+    pub proven_height: u32,
+    /// Batch being proven, if any
+    #[serde(alias = "inProgress")]
+    pub in_progress: Option<ProvingBatch>,
+}
+
+/// Bridge response for `/proving-status`: how fresh a STARK-verifiable chain state can be
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ProvingStatus {
+    /// Height of the latest chain state with a STARK proof
+    #[serde(alias = "provenHeight")]
+    pub proven_height: u32,
+    /// Batch being proven, if any
+    #[serde(alias = "inProgress")]
+    pub in_progress: Option<ProvingBatch>,
+    /// Time of the last heartbeat (UNIX seconds)
+    #[serde(alias = "lastHeartbeat")]
+    pub last_heartbeat: u64,
+    /// Latest indexed height of the bridge node
+    #[serde(alias = "indexedHead")]
+    pub indexed_head: u32,
+    /// Number of indexed blocks past the latest proven chain state
+    pub lag: u32,
+}
+
+/// Bridge response for `/confirmation-status/:tx_id`: how far a transaction is from final,
+/// without any proof
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ConfirmationStatus {
+    /// Txid of the transaction, in display order
+    pub txid: String,
+    /// Height of the block including the transaction
+    #[serde(alias = "blockHeight")]
+    pub block_height: u32,
+    /// Latest indexed height of the bridge node
+    #[serde(alias = "chainHead")]
+    pub chain_head: u32,
+    /// Confirmations of the block at the chain head, the block itself included
+    pub confirmations: u32,
+    /// Confirmations required by the bridge's finality policy, if it is a confirmation depth
+    pub required: Option<u32>,
+    /// Whether a chain state STARK proof covers the block
+    #[serde(alias = "proofAvailable")]
+    pub proof_available: bool,
+    /// Height of the latest chain state with a STARK proof, if the bridge tracks the prover
+    #[serde(alias = "provenHeight")]
+    pub proven_height: Option<u32>,
+}
+
+impl ConfirmationStatus {
+    pub fn new(
+        txid: String,
+        block_height: u32,
+        chain_head: u32,
+        finality: &FinalityPolicy,
+        proven_height: Option<u32>,
+    ) -> Self {
+        let required = match finality {
+            FinalityPolicy::Confirmations { confirmations } => Some(*confirmations),
+            _ => None,
+        };
+        Self {
+            txid,
+            block_height,
+            chain_head,
+            confirmations: (chain_head + 1).saturating_sub(block_height),
+            required,
+            proof_available: proven_height.is_some_and(|proven| proven >= block_height),
+            proven_height,
+        }
+    }
+
+    /// Whether the block has the required confirmations, `None` if the policy is not a
+    /// confirmation depth
+    pub fn is_confirmed(&self) -> Option<bool> {
+        self.required.map(|required| self.confirmations >= required)
+    }
+}
+
 /// Legacy CompressedSpvProof kept for backwards compatibility
+#[cfg(feature = "stark")]
 #[derive(Serialize, Deserialize)]
 pub struct CompressedSpvProof {
     /// The current state of the chain
+    #[serde(alias = "chainState")]
     pub chain_state: ChainState,
     /// Recursive STARK proof of the chain state and block MMR root validity
+    #[serde(alias = "chainStateProof")]
     pub chain_state_proof: CairoProof<Blake2sMerkleHasher>,
     /// The header of the block containing the transaction
     #[serde(
         serialize_with = "serialize_header",
-        deserialize_with = "deserialize_header"
+        deserialize_with = "deserialize_header",
+        alias = "blockHeader"
     )]
     pub block_header: Header,
     /// MMR inclusion proof for the block header
+    #[serde(alias = "blockHeaderProof")]
     pub block_header_proof: Vec<u8>,
     /// The transaction to be proven
     #[serde(
@@ -138,10 +703,11 @@ pub struct CompressedSpvProof {
     )]
     pub transaction: Transaction,
     /// Encoded [MerkleProof] structure, contains Merkle branch for the transaction
+    #[serde(alias = "transactionProof")]
     pub transaction_proof: Vec<u8>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Target([u8; 32]);
 
 impl Target {
@@ -150,7 +716,10 @@ impl Target {
         if bytes.len() != 32 {
             return Err(anyhow::anyhow!("Invalid target length"));
         }
-        Ok(Self(bytes.try_into().unwrap()))
+        let bytes: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Invalid target length"))?;
+        Ok(Self(bytes))
     }
 
     pub fn to_hex(&self) -> String {
@@ -163,22 +732,30 @@ impl Target {
 }
 
 /// Snapshot of the consensus chain state used to validate block inclusion
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ChainState {
     /// The height of the best block in the chain
+    #[serde(alias = "blockHeight")]
     pub block_height: u32,
     /// The total accumulated work of the chain
+    #[serde(alias = "totalWork")]
     pub total_work: u128,
-    /// The hash of the best block in the chain
+    /// The hash of the best block in the chain, in internal byte order
+    #[schemars(with = "[u8; 32]")]
+    #[serde(alias = "bestBlockHash")]
     pub best_block_hash: Hash,
     /// The current target difficulty
+    #[serde(alias = "currentTarget")]
     pub current_target: Target,
     /// The start time (UNIX seconds) of the current difficulty epoch
+    #[serde(alias = "prevTimestamps")]
     pub prev_timestamps: Vec<u32>,
     /// Timestamp of the block that started the current difficulty epoch (legacy field kept for
     /// serialization compatibility)
+    #[serde(alias = "epochStartTime")]
     pub epoch_start_time: u32,
     /// Difficulty targets (u256) of the most recent blocks as decimal strings
+    #[serde(alias = "powTargetHistory")]
     pub pow_target_history: Vec<Target>,
 }
 
@@ -213,13 +790,13 @@ pub struct TaskResult {
 
 impl BootloaderOutput {
     /// Decode `BootloaderOutput` from the Cairo public output felts emitted by the bootloader.
+    ///
+    /// Returns an error (never panics) on truncated or malformed output.
     pub fn decode(mut output: Vec<FieldElement>) -> anyhow::Result<Self> {
-        let n_tasks = output
-            .remove(0)
+        let n_tasks = next_felt(&mut output, "number of tasks")?
             .try_into()
             .map_err(|_| anyhow::anyhow!("Expected number of tasks to be a u32"))?;
-        let task_output_size = output
-            .remove(0)
+        let task_output_size = next_felt(&mut output, "task output size")?
             .try_into()
             .map_err(|_| anyhow::anyhow!("Expected task output size to be a u32"))?;
         let task_program_hash = decode_truncated_hash(&mut output)?;
@@ -248,16 +825,28 @@ impl TaskResult {
     }
 }
 
+/// Pop the next felt from the front of the output, failing if the output is exhausted
+fn next_felt(output: &mut Vec<FieldElement>, name: &str) -> anyhow::Result<FieldElement> {
+    if output.is_empty() {
+        anyhow::bail!("Cairo output is truncated: missing {name}");
+    }
+    Ok(output.remove(0))
+}
+
 fn decode_hash(output: &mut Vec<FieldElement>) -> anyhow::Result<String> {
     // In Cairo serde u256 low goes first, high goes second
-    let lo: u128 = output.remove(0).try_into().unwrap();
-    let hi: u128 = output.remove(0).try_into().unwrap();
+    let lo: u128 = next_felt(output, "u256 low part")?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Expected u256 low part to be a u128"))?;
+    let hi: u128 = next_felt(output, "u256 high part")?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Expected u256 high part to be a u128"))?;
     let bytes = [hi.to_be_bytes(), lo.to_be_bytes()].concat();
     Ok(format!("0x{}", hex::encode(bytes)))
 }
 
 fn decode_truncated_hash(output: &mut Vec<FieldElement>) -> anyhow::Result<String> {
-    let bytes = output.remove(0).to_bytes_be();
+    let bytes = next_felt(output, "felt252 hash")?.to_bytes_be();
     Ok(format!("0x{}", hex::encode(bytes)))
 }
 
@@ -325,6 +914,7 @@ fn split_bytes_into_words(bytes: &[u8]) -> Vec<u32> {
 mod tests {
     use super::*;
     use hex::FromHex;
+    use zebra_chain::serialization::ZcashDeserialize;
 
     #[test]
     fn test_chain_state_hash() {
@@ -354,6 +944,65 @@ mod tests {
         assert_eq!(res, expected);
     }
 
+    #[cfg(feature = "flyclient")]
+    #[test]
+    fn test_node_data_check_header() {
+        let bytes = hex::decode(include_str!("../tests/data/genesis_header.hex").trim()).unwrap();
+        let header = Header::zcash_deserialize(bytes.as_slice()).unwrap();
+        let time = header.time.timestamp() as u32;
+        let bits = u32::from_be_bytes(header.difficulty_threshold.bytes_in_display_order());
+        let mut node = FlyClientNodeData {
+            block_height: 903_000,
+            epoch: "heartwood".to_string(),
+            leaf_index: 0,
+            encoded: String::new(),
+            consensus_branch_id: 0xf5b9_230b,
+            subtree_commitment: header.hash(),
+            start_time: time,
+            end_time: time,
+            start_target: bits,
+            end_target: bits,
+            start_sapling_root: [0x33; 32],
+            end_sapling_root: [0x33; 32],
+            subtree_total_work: "8192".to_string(),
+            start_height: 903_000,
+            end_height: 903_000,
+            sapling_tx: 0,
+        };
+        node.check_header(&header).unwrap();
+
+        node.end_time += 1;
+        let error = node.check_header(&header).unwrap_err();
+        assert!(error.to_string().contains("wrong time"));
+
+        // Canopy branch ID for a Heartwood block
+        node.end_time = time;
+        node.consensus_branch_id = 0xe9ff_75a6;
+        assert!(node.check_header(&header).is_err());
+    }
+
+    #[test]
+    fn test_coinbase_output() {
+        let output = |script: &str| transparent::Output {
+            value: zebra_chain::amount::Amount::try_from(312_500_000).unwrap(),
+            lock_script: transparent::Script::new(&hex::decode(script).unwrap()),
+        };
+        let p2pkh = format!("76a914{}88ac", "11".repeat(20));
+        let parsed = CoinbaseOutput::new(1, &output(&p2pkh));
+        assert_eq!(parsed.index, 1);
+        assert_eq!(parsed.value, 312_500_000);
+        assert_eq!(parsed.script_pubkey, p2pkh);
+        assert!(parsed.address.unwrap().starts_with("t1"));
+
+        let p2sh = format!("a914{}87", "22".repeat(20));
+        assert!(CoinbaseOutput::new(0, &output(&p2sh))
+            .address
+            .unwrap()
+            .starts_with("t3"));
+        // OP_RETURN
+        assert_eq!(CoinbaseOutput::new(0, &output("6a00")).address, None);
+    }
+
     #[test]
     fn test_decode_hash() {
         let mut output = vec![
@@ -364,4 +1013,88 @@ mod tests {
         let expected = "0x6002eaa4410bd0b15e778656f84fc895fd091827e27ce697ba4231076c70c43b";
         assert_eq!(res, expected);
     }
+
+    #[test]
+    fn test_decode_truncated_output_does_not_panic() {
+        assert!(BootloaderOutput::decode(vec![]).is_err());
+        assert!(BootloaderOutput::decode(vec![FieldElement::ONE; 3]).is_err());
+        // Felt that does not fit into a u128
+        let mut output = vec![FieldElement::MAX, FieldElement::ONE];
+        assert!(decode_hash(&mut output).is_err());
+    }
+
+    #[test]
+    fn test_block_inclusion_proof_chain_height() {
+        let proof = BlockInclusionProof {
+            block_height: 903_010,
+            peaks_hashes: vec![],
+            siblings_hashes: vec![],
+            leaf_index: 10,
+            leaf_count: 25,
+            epoch: None,
+            epoch_start_height: None,
+            mmr_id: None,
+        };
+        // Epoch starts at 903_000, last leaf is the 25th block of the epoch
+        assert_eq!(proof.chain_height(), 903_024);
+    }
+
+    #[test]
+    fn test_confirmation_status() {
+        let policy = FinalityPolicy::default();
+        let status = ConfirmationStatus::new("ab".repeat(32), 100, 104, &policy, Some(99));
+        assert_eq!(status.confirmations, 5);
+        assert_eq!(status.required, Some(6));
+        assert_eq!(status.is_confirmed(), Some(false));
+        assert!(!status.proof_available);
+
+        let status = ConfirmationStatus::new("ab".repeat(32), 100, 105, &policy, Some(100));
+        assert_eq!(status.is_confirmed(), Some(true));
+        assert!(status.proof_available);
+
+        // Block past the chain head of a lagging bridge
+        let status = ConfirmationStatus::new(
+            "ab".repeat(32),
+            100,
+            98,
+            &FinalityPolicy::ProofCovered,
+            None,
+        );
+        assert_eq!(status.confirmations, 0);
+        assert_eq!(status.is_confirmed(), None);
+        assert!(!status.proof_available);
+    }
+    #[test]
+    fn test_camel_case_aliases() {
+        let json = r#"{
+            "blockHeight": 903010,
+            "peaksHashes": [],
+            "siblingsHashes": [],
+            "leafIndex": 10,
+            "leafCount": 25,
+            "epochStartHeight": 903000,
+            "mmrId": "flyclient_nu6"
+        }"#;
+        let proof: BlockInclusionProof = serde_json::from_str(json).unwrap();
+        assert_eq!(proof.chain_height(), 903_024);
+        assert_eq!(proof.mmr_id.as_deref(), Some("flyclient_nu6"));
+
+        // Serialized with the canonical snake_case names
+        let chain_state = serde_json::to_value(ChainState::genesis()).unwrap();
+        assert!(chain_state.get("block_height").is_some());
+        let camel_case = serde_json::json!({
+            "blockHeight": chain_state["block_height"],
+            "totalWork": chain_state["total_work"],
+            "bestBlockHash": chain_state["best_block_hash"],
+            "currentTarget": chain_state["current_target"],
+            "prevTimestamps": chain_state["prev_timestamps"],
+            "epochStartTime": chain_state["epoch_start_time"],
+            "powTargetHistory": chain_state["pow_target_history"],
+        });
+        let decoded: ChainState = serde_json::from_value(camel_case).unwrap();
+        assert_eq!(
+            decoded.blake2s_digest().unwrap(),
+            ChainState::genesis().blake2s_digest().unwrap()
+        );
+    }
 }
@@ -0,0 +1,192 @@
+//! Error reports of the CLIs, with remediation hints for the most common failures.
+//!
+//! Errors stay `anyhow` errors: a hint is attached with [`WithHint::hint`] where the failure is
+//! detected, and [`report`] prints the error chain followed by its hints. Transport failures are
+//! hinted from the error type, and the hints are translated by the [`crate::i18n`] catalog.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::i18n::Locale;
+
+pub(crate) const BRIDGE_TLS_HINT: &str =
+    "The bridge certificate was rejected: check --bridge-pin and --bridge-ca-cert";
+pub(crate) const BRIDGE_UNREACHABLE_HINT: &str =
+    "Check the bridge URL (--bridge-url) and that the bridge node is running";
+pub(crate) const BRIDGE_TIMEOUT_HINT: &str =
+    "The bridge node did not answer in time: retry, or use another bridge";
+pub(crate) const BRIDGE_MALFORMED_HINT: &str =
+    "The bridge response is malformed: check that its version matches (GET /version)";
+pub(crate) const PRE_HEARTWOOD_HINT: &str =
+    "Blocks before Heartwood have no FlyClient proof: use verify-tx without --verify-block-proof, or pick a later block";
+pub(crate) const NOT_FOUND_HINT: &str =
+    "The bridge may not have indexed it yet: check the identifier and its byte order (--id-type), or retry later";
+pub(crate) const BRIDGE_DOWN_HINT: &str =
+    "The bridge's Zcash node is unavailable: retry later, or use another bridge";
+pub(crate) const BRIDGE_AUTH_HINT: &str =
+    "The bridge requires an API key or its quota is exhausted: use another bridge";
+
+/// Error with a remediation hint
+#[derive(Debug)]
+struct Hinted {
+    error: anyhow::Error,
+    hint: &'static str,
+}
+
+impl fmt::Display for Hinted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl Error for Hinted {
+    // Skips the wrapped error, whose message is the one of `Hinted`
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.error.source()
+    }
+}
+
+/// Attach a remediation hint to an error
+pub trait WithHint<T> {
+    fn hint(self, hint: &'static str) -> anyhow::Result<T>;
+}
+
+impl<T, E: Into<anyhow::Error>> WithHint<T> for Result<T, E> {
+    fn hint(self, hint: &'static str) -> anyhow::Result<T> {
+        self.map_err(|error| {
+            anyhow::Error::new(Hinted {
+                error: error.into(),
+                hint,
+            })
+        })
+    }
+}
+
+/// Hints of an error, outermost first
+pub fn hints(error: &anyhow::Error) -> Vec<&'static str> {
+    let mut hints: Vec<&'static str> = Vec::new();
+    let hinted = error
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<Hinted>())
+        .map(|hinted| hinted.hint);
+    for hint in hinted.chain(transport_hint(error)) {
+        if !hints.contains(&hint) {
+            hints.push(hint);
+        }
+    }
+    hints
+}
+
+/// Hint of a failure to reach the bridge node
+#[cfg(feature = "client")]
+fn transport_hint(error: &anyhow::Error) -> Option<&'static str> {
+    let transport = error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<reqwest::Error>())?;
+    if transport.is_connect() {
+        // TLS failures surface as connection errors; the rustls error is only in the messages
+        let tls = error.chain().any(|cause| {
+            cause.downcast_ref::<rustls::Error>().is_some()
+                || cause.to_string().contains("certificate")
+        });
+        Some(if tls {
+            BRIDGE_TLS_HINT
+        } else {
+            BRIDGE_UNREACHABLE_HINT
+        })
+    } else if transport.is_timeout() {
+        Some(BRIDGE_TIMEOUT_HINT)
+    } else if transport.is_decode() {
+        Some(BRIDGE_MALFORMED_HINT)
+    } else {
+        None
+    }
+}
+
+/// Without the HTTP client, no error comes from the bridge transport
+#[cfg(not(feature = "client"))]
+fn transport_hint(_error: &anyhow::Error) -> Option<&'static str> {
+    None
+}
+
+/// Hint for an error status of the bridge node
+#[cfg(feature = "client")]
+pub fn status_hint(status: reqwest::StatusCode, body: &str) -> Option<&'static str> {
+    match status {
+        reqwest::StatusCode::BAD_REQUEST if body.contains("Heartwood") => Some(PRE_HEARTWOOD_HINT),
+        reqwest::StatusCode::NOT_FOUND => Some(NOT_FOUND_HINT),
+        reqwest::StatusCode::SERVICE_UNAVAILABLE => Some(BRIDGE_DOWN_HINT),
+        reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::TOO_MANY_REQUESTS => {
+            Some(BRIDGE_AUTH_HINT)
+        }
+        _ => None,
+    }
+}
+
+/// Report of an error: its message, its causes and its hints, the latter translated to `locale`
+pub fn report(error: &anyhow::Error, locale: Locale) -> String {
+    let mut report = format!("{}: {error}", locale.tr("Error"));
+    let mut causes = error.chain().skip(1).peekable();
+    if causes.peek().is_some() {
+        report.push_str(&format!("\n\n{}:", locale.tr("Caused by")));
+        for cause in causes {
+            report.push_str(&format!("\n    {cause}"));
+        }
+    }
+    for hint in hints(error) {
+        report.push_str(&format!("\n\n{}: {}", locale.tr("hint"), locale.tr(hint)));
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_hint_keeps_message_and_causes() {
+        let error = Err::<(), _>(std::io::Error::other("disk on fire"))
+            .context("Failed to read the proof")
+            .hint("Check the proof path")
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Failed to read the proof");
+        assert_eq!(error.chain().count(), 2);
+        assert_eq!(hints(&error), vec!["Check the proof path"]);
+        assert_eq!(
+            report(&error, Locale::English),
+            "Error: Failed to read the proof\n\nCaused by:\n    disk on fire\n\nhint: Check the proof path"
+        );
+    }
+
+    #[test]
+    fn test_context_over_hint() {
+        let error = Err::<(), _>(anyhow::anyhow!("MMR root mismatch"))
+            .hint("Retry against another bridge")
+            .context("Block inclusion proof is invalid")
+            .unwrap_err();
+        assert_eq!(hints(&error), vec!["Retry against another bridge"]);
+        assert_eq!(
+            report(&anyhow::anyhow!("Plain"), Locale::English),
+            "Error: Plain"
+        );
+    }
+
+    #[test]
+    fn test_translated_report() {
+        let error = Err::<(), _>(anyhow::anyhow!("Failed to get proof: 404 Not Found"))
+            .hint(NOT_FOUND_HINT)
+            .unwrap_err();
+        let report = report(&error, Locale::French);
+        assert!(report.starts_with("Erreur: Failed to get proof: 404 Not Found\n\nconseil: "));
+        assert!(!report.contains(NOT_FOUND_HINT));
+        #[cfg(feature = "client")]
+        assert_eq!(
+            status_hint(
+                reqwest::StatusCode::BAD_REQUEST,
+                "Block is before Heartwood"
+            ),
+            Some(PRE_HEARTWOOD_HINT)
+        );
+    }
+}
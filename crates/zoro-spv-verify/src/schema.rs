@@ -0,0 +1,124 @@
+//! JSON Schemas of the proof, bridge response and config formats.
+//!
+//! The schemas are derived from the Rust definitions, so they follow the types as they change.
+//! [`write`] stores them as one `<name>.schema.json` file each, for implementations in other
+//! languages. Consensus-encoded headers and transactions are hex strings; block hashes and Merkle
+//! roots are arrays of 32 bytes in internal byte order; STARK proofs are left unconstrained.
+
+use std::path::Path;
+
+use schemars::schema::RootSchema;
+use schemars::schema_for;
+
+use crate::block_mmr::{RangeProof, SparseRootsRecord};
+use crate::finality::FinalityPolicy;
+use crate::manifest::{ReleaseManifest, SignedManifest};
+use crate::proof::{
+    BlockHeaderRecord, BlockInclusionProof, ChainState, CoinbaseProofResponse, ConfirmationStatus,
+    FlyClientNodeData, FullProofResponse, MmrRootRecord, ProverHeartbeat, ProvingStatus,
+    TransactionAbsenceProof, TransactionBucketResponse, TransactionInclusionProof,
+    TransactionProofResponse,
+};
+#[cfg(feature = "stark")]
+use crate::proof::{ChainStateProof, FullInclusionProof};
+use crate::verify::VerifierConfig;
+
+/// Schemas of every published format, by file name stem. The STARK proof formats are only
+/// included with the `stark` feature.
+pub fn schemas() -> Vec<(&'static str, RootSchema)> {
+    #[allow(unused_mut)]
+    let mut schemas = vec![
+        ("chain_state", schema_for!(ChainState)),
+        ("block_inclusion_proof", schema_for!(BlockInclusionProof)),
+        ("transaction_proof", schema_for!(TransactionInclusionProof)),
+        (
+            "transaction_absence_proof",
+            schema_for!(TransactionAbsenceProof),
+        ),
+        ("range_proof", schema_for!(RangeProof)),
+        (
+            "transaction_proof_response",
+            schema_for!(TransactionProofResponse),
+        ),
+        (
+            "transaction_bucket_response",
+            schema_for!(TransactionBucketResponse),
+        ),
+        ("full_proof_response", schema_for!(FullProofResponse)),
+        (
+            "coinbase_proof_response",
+            schema_for!(CoinbaseProofResponse),
+        ),
+        ("block_header_record", schema_for!(BlockHeaderRecord)),
+        ("mmr_root_record", schema_for!(MmrRootRecord)),
+        ("flyclient_node_data", schema_for!(FlyClientNodeData)),
+        ("sparse_roots_record", schema_for!(SparseRootsRecord)),
+        ("confirmation_status", schema_for!(ConfirmationStatus)),
+        ("prover_heartbeat", schema_for!(ProverHeartbeat)),
+        ("proving_status", schema_for!(ProvingStatus)),
+        ("verifier_config", schema_for!(VerifierConfig)),
+        ("finality_policy", schema_for!(FinalityPolicy)),
+        ("release_manifest", schema_for!(ReleaseManifest)),
+        ("signed_manifest", schema_for!(SignedManifest)),
+    ];
+    #[cfg(feature = "stark")]
+    schemas.extend([
+        ("chain_state_proof", schema_for!(ChainStateProof)),
+        ("full_inclusion_proof", schema_for!(FullInclusionProof)),
+    ]);
+    schemas
+}
+
+/// Write the schemas to `dir`, returning their number
+pub fn write(dir: &Path) -> anyhow::Result<usize> {
+    std::fs::create_dir_all(dir)?;
+    let schemas = schemas();
+    for (name, schema) in &schemas {
+        let path = dir.join(format!("{name}.schema.json"));
+        std::fs::write(&path, serde_json::to_vec_pretty(schema)?)
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {e}", path.display()))?;
+    }
+    Ok(schemas.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Properties of the top-level object of a schema
+    fn properties(schema: &RootSchema) -> Vec<String> {
+        let mut properties: Vec<String> = schema
+            .schema
+            .object
+            .as_ref()
+            .map(|object| object.properties.keys().cloned().collect())
+            .unwrap_or_default();
+        properties.sort();
+        properties
+    }
+
+    #[test]
+    fn test_schema_matches_serialized_chain_state() {
+        let value = serde_json::to_value(ChainState::genesis()).unwrap();
+        let mut keys: Vec<String> = value.as_object().unwrap().keys().cloned().collect();
+        keys.sort();
+        assert_eq!(properties(&schema_for!(ChainState)), keys);
+    }
+
+    #[test]
+    fn test_schema_matches_serialized_verifier_config() {
+        let value = serde_json::to_value(VerifierConfig::default()).unwrap();
+        let mut keys: Vec<String> = value.as_object().unwrap().keys().cloned().collect();
+        keys.sort();
+        assert_eq!(properties(&schema_for!(VerifierConfig)), keys);
+    }
+
+    #[test]
+    fn test_schema_names_are_unique() {
+        let schemas = schemas();
+        let mut names: Vec<_> = schemas.iter().map(|(name, _)| *name).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), schemas.len());
+    }
+}
@@ -0,0 +1,74 @@
+//! One-sentence summary of a verification result, for end users.
+//!
+//! The summary depends only on the [`VerificationResult`] and the [`Locale`], without box
+//! drawing, padding or colors, so that embedders showing it to their users (notifications,
+//! wallet screens) get the same text as `spv-cli`.
+
+use crate::i18n::Locale;
+use crate::verify::VerificationResult;
+
+/// Summary of a verification result in English, e.g. "Transaction 5a2f… confirmed in block
+/// 0000… (height 2500000) with 6 confirmations; chain validity STARK-verified."
+pub fn render_human(result: &VerificationResult) -> String {
+    render_human_in(result, Locale::English)
+}
+
+/// Summary of a verification result in the given language
+pub fn render_human_in(result: &VerificationResult, locale: Locale) -> String {
+    let confirmed = if result.confirmations == 1 {
+        locale.tr("Transaction {transaction} confirmed in block {block} (height {height}) with 1 confirmation")
+    } else {
+        locale.tr("Transaction {transaction} confirmed in block {block} (height {height}) with {confirmations} confirmations")
+    };
+    let chain = if result.chain_state_verified {
+        locale.tr("chain validity STARK-verified")
+    } else {
+        locale.tr("chain validity not verified")
+    };
+    let confirmed = confirmed
+        .replace("{transaction}", &result.transaction_hash.to_string())
+        .replace("{block}", &result.block_hash.to_string())
+        .replace("{height}", &result.block_height.to_string())
+        .replace("{confirmations}", &result.confirmations.to_string());
+    format!("{confirmed}; {chain}.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zebra_chain::{block, transaction};
+
+    fn result(confirmations: u32, chain_state_verified: bool) -> VerificationResult {
+        VerificationResult {
+            transaction_hash: transaction::Hash([0x11; 32]),
+            block_hash: block::Hash([0x22; 32]),
+            block_height: 2_500_000,
+            chain_height: 2_500_000 + confirmations - 1,
+            confirmations,
+            expiry_height: None,
+            chain_state_verified,
+        }
+    }
+
+    #[test]
+    fn test_render_human() {
+        assert_eq!(
+            render_human(&result(6, true)),
+            format!(
+                "Transaction {} confirmed in block {} (height 2500000) with 6 confirmations; \
+                 chain validity STARK-verified.",
+                "11".repeat(32),
+                "22".repeat(32)
+            )
+        );
+        assert!(render_human(&result(1, false))
+            .ends_with("with 1 confirmation; chain validity not verified."));
+    }
+
+    #[test]
+    fn test_render_human_in() {
+        let summary = render_human_in(&result(6, true), Locale::Spanish);
+        assert!(summary.starts_with("Transacción "));
+        assert!(!summary.contains('{'));
+    }
+}
@@ -0,0 +1,291 @@
+//! Transports to the bridge node.
+//!
+//! A [`BridgeClient`] sends the GET requests of the verifier to a bridge URL. Besides HTTP(S),
+//! the `local-transport` feature reaches a bridge co-located with the client without a TCP
+//! server:
+//! - `unix:<socket path>` URLs go through a Unix domain socket (on Unix platforms)
+//! - [`BridgeClient::in_process`] calls a [`BridgeService`] of the same process, e.g. a bridge
+//!   router in tests
+//!
+//! Request URLs are built by appending the endpoint path to the base URL (`{base}/head`), so the
+//! same URL building works for every transport.
+//!
+//! Responses are untrusted: every request is given the [`ProofLimits`] of the verifier, and a body
+//! larger than `max_proof_bytes` is rejected while it is read, before it is buffered in full.
+
+#[cfg(all(unix, feature = "local-transport"))]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "local-transport")]
+use std::sync::Arc;
+
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+
+use crate::untrusted::{parse_json_limited, ProofLimits};
+
+/// Scheme of the bridge URLs served on a Unix domain socket
+pub const UNIX_SCHEME: &str = "unix:";
+
+/// Base URL of the in-process bridges
+pub const IN_PROCESS_URL: &str = "in-process:";
+
+/// Response of the bridge node, read in full
+#[derive(Debug, Clone)]
+pub struct BridgeResponse {
+    pub status: StatusCode,
+    pub body: Vec<u8>,
+}
+
+impl BridgeResponse {
+    /// Whether the status is a 2xx success
+    pub fn is_success(&self) -> bool {
+        self.status.is_success()
+    }
+
+    /// Body as text, invalid UTF-8 replaced
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    /// Body parsed as the JSON document `what`, within `limits`
+    pub fn json<T: DeserializeOwned>(&self, what: &str, limits: &ProofLimits) -> anyhow::Result<T> {
+        parse_json_limited(&self.body, what, limits)
+    }
+}
+
+/// Bridge node running in the same process, answering the requests of an in-process
+/// [`BridgeClient`]
+#[cfg(feature = "local-transport")]
+#[async_trait::async_trait]
+pub trait BridgeService: Send + Sync {
+    /// Answer a GET request for `path`, which includes the query string
+    async fn get(&self, path: &str) -> anyhow::Result<BridgeResponse>;
+}
+
+#[derive(Clone)]
+enum Transport {
+    Http(reqwest::Client),
+    #[cfg(all(unix, feature = "local-transport"))]
+    Unix(PathBuf),
+    #[cfg(feature = "local-transport")]
+    InProcess(Arc<dyn BridgeService>),
+}
+
+/// Client of a bridge node
+#[derive(Clone)]
+pub struct BridgeClient {
+    base_url: String,
+    transport: Transport,
+}
+
+impl BridgeClient {
+    /// Bridge served over HTTP(S) at `base_url`
+    pub fn http(client: reqwest::Client, base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            transport: Transport::Http(client),
+        }
+    }
+
+    /// Bridge served on the Unix domain socket at `socket`, with the base URL `unix:<socket>`
+    #[cfg(all(unix, feature = "local-transport"))]
+    pub fn unix_socket(socket: impl Into<PathBuf>) -> Self {
+        let socket = socket.into();
+        Self {
+            base_url: format!("{UNIX_SCHEME}{}", socket.display()),
+            transport: Transport::Unix(socket),
+        }
+    }
+
+    /// Bridge running in this process, with the base URL [`IN_PROCESS_URL`]
+    #[cfg(feature = "local-transport")]
+    pub fn in_process(service: Arc<dyn BridgeService>) -> Self {
+        Self {
+            base_url: IN_PROCESS_URL.to_string(),
+            transport: Transport::InProcess(service),
+        }
+    }
+
+    /// Client for `base_url`: a Unix socket for `unix:` URLs, else HTTP(S) with `client`
+    pub fn for_url(client: reqwest::Client, base_url: &str) -> anyhow::Result<Self> {
+        match base_url.strip_prefix(UNIX_SCHEME) {
+            #[cfg(all(unix, feature = "local-transport"))]
+            Some(socket) => Ok(Self::unix_socket(socket)),
+            #[cfg(not(all(unix, feature = "local-transport")))]
+            Some(_) => anyhow::bail!(
+                "Bridge URL {base_url} requires the local-transport feature on a Unix platform"
+            ),
+            None => Ok(Self::http(client, base_url)),
+        }
+    }
+
+    /// Base URL the request URLs are built from
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Send a GET request for `url`, an endpoint path appended to the base URL, and read the
+    /// response, failing as soon as its body exceeds `limits.max_proof_bytes`
+    pub async fn get(&self, url: &str, limits: &ProofLimits) -> anyhow::Result<BridgeResponse> {
+        match &self.transport {
+            Transport::Http(client) => {
+                let response = client.get(url).send().await?;
+                let status = response.status();
+                let body = read_body(response, limits).await?;
+                Ok(BridgeResponse { status, body })
+            }
+            #[cfg(all(unix, feature = "local-transport"))]
+            Transport::Unix(socket) => get_unix(socket, self.path(url)?, limits).await,
+            #[cfg(feature = "local-transport")]
+            Transport::InProcess(service) => {
+                let response = service.get(self.path(url)?).await?;
+                limits.check_document_size(response.body.len())?;
+                Ok(response)
+            }
+        }
+    }
+
+    /// Path and query of a request URL
+    #[cfg(feature = "local-transport")]
+    fn path<'a>(&self, url: &'a str) -> anyhow::Result<&'a str> {
+        let path = url.strip_prefix(&self.base_url).unwrap_or(url);
+        if !path.starts_with('/') {
+            anyhow::bail!(
+                "URL {url} is not an endpoint of the bridge at {}",
+                self.base_url
+            );
+        }
+        Ok(path)
+    }
+}
+
+/// Read the body of an HTTP response, rejecting it on its `Content-Length` or as soon as the
+/// chunks read exceed `limits.max_proof_bytes`
+pub(crate) async fn read_body(
+    mut response: reqwest::Response,
+    limits: &ProofLimits,
+) -> anyhow::Result<Vec<u8>> {
+    if let Some(len) = response.content_length() {
+        limits.check_document_size(usize::try_from(len).unwrap_or(usize::MAX))?;
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        limits.check_document_size(body.len() + chunk.len())?;
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+/// Send a GET request over HTTP/1.1 on a new connection to a Unix domain socket
+#[cfg(all(unix, feature = "local-transport"))]
+async fn get_unix(
+    socket: &Path,
+    path: &str,
+    limits: &ProofLimits,
+) -> anyhow::Result<BridgeResponse> {
+    use hyper::body::HttpBody;
+
+    let stream = tokio::net::UnixStream::connect(socket)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to connect to {}: {e}", socket.display()))?;
+    let (mut sender, connection) = hyper::client::conn::handshake(stream).await?;
+    tokio::spawn(connection);
+    let request = hyper::Request::get(path)
+        .header(hyper::header::HOST, "localhost")
+        .body(hyper::Body::empty())?;
+    let response = sender.send_request(request).await?;
+    let status = response.status();
+    let mut stream = response.into_body();
+    // Upper bound of the size, known from the `Content-Length` header
+    if let Some(len) = stream.size_hint().upper() {
+        limits.check_document_size(usize::try_from(len).unwrap_or(usize::MAX))?;
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = stream.data().await {
+        let chunk = chunk?;
+        limits.check_document_size(body.len() + chunk.len())?;
+        body.extend_from_slice(&chunk);
+    }
+    Ok(BridgeResponse { status, body })
+}
+
+#[cfg(all(test, feature = "local-transport"))]
+mod tests {
+    use super::*;
+
+    struct Head;
+
+    #[async_trait::async_trait]
+    impl BridgeService for Head {
+        async fn get(&self, path: &str) -> anyhow::Result<BridgeResponse> {
+            let (status, body) = match path {
+                "/head" => (StatusCode::OK, b"3000000".to_vec()),
+                _ => (StatusCode::NOT_FOUND, b"Not found".to_vec()),
+            };
+            Ok(BridgeResponse { status, body })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_in_process() {
+        let client = BridgeClient::in_process(Arc::new(Head));
+        let limits = ProofLimits::default();
+        let url = format!("{}/head", client.base_url());
+        let response = client.get(&url, &limits).await.unwrap();
+        let head: u32 = response.json("chain head", &limits).unwrap();
+        assert_eq!(head, 3_000_000);
+
+        let response = client.get("/chain-state/1", &limits).await.unwrap();
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        assert!(client
+            .get("http://127.0.0.1:5000/head", &limits)
+            .await
+            .is_err());
+
+        // Oversized bodies are rejected
+        let limits = ProofLimits {
+            max_proof_bytes: 4,
+            ..Default::default()
+        };
+        let err = client.get(&url, &limits).await.unwrap_err();
+        assert!(err.to_string().contains("proof document size"), "{err}");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_unix_socket() {
+        use std::io::{Read, Write};
+
+        let socket = std::env::temp_dir().join(format!("zoro-bridge-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket);
+        let listener = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            while !request.ends_with(b"\r\n\r\n") {
+                let mut buf = [0; 1024];
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 7\r\n\r\n3000000")
+                .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let base_url = format!("unix:{}", socket.display());
+        let client = BridgeClient::for_url(reqwest::Client::new(), &base_url).unwrap();
+        let limits = ProofLimits::default();
+        let response = client
+            .get(&format!("{base_url}/head"), &limits)
+            .await
+            .unwrap();
+        assert!(response.is_success());
+        assert_eq!(
+            response.json::<u32>("chain head", &limits).unwrap(),
+            3_000_000
+        );
+        assert!(server.join().unwrap().starts_with("GET /head HTTP/1.1\r\n"));
+        std::fs::remove_file(&socket).unwrap();
+    }
+}
@@ -0,0 +1,531 @@
+//! Boundary for data received from untrusted sources (bridge node responses, proof files).
+//!
+//! A malicious bridge must not be able to crash a wallet, so everything here turns malformed
+//! input into an error: `unwrap`, `expect`, explicit panics and unchecked indexing are denied.
+//! Third-party verification code that may still panic on adversarial input runs under [`guard`].
+
+#![deny(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    clippy::indexing_slicing
+)]
+
+use std::cell::Cell;
+use std::fmt;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+#[cfg(feature = "flyclient")]
+use accumulators::hasher::flyclient::decode_node_data;
+use schemars::JsonSchema;
+use serde::de::{self, DeserializeOwned, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use zebra_chain::serialization::ZcashSerialize;
+use zebra_chain::transaction::Transaction;
+use zoro_zcash_client::MerkleProof;
+
+use crate::mmr_math::leaf_count_to_peaks_count;
+#[cfg(feature = "stark")]
+use crate::proof::FullInclusionProof;
+use crate::proof::{BlockInclusionProof, TransactionAbsenceProof, TransactionInclusionProof};
+use crate::report::WithHint;
+
+/// Resource limits applied to proofs received from untrusted sources.
+///
+/// The document size is checked before a response or file is read in full, and again before
+/// parsing. The remaining limits are enforced while the document is scanned, before the typed
+/// values are allocated, and checked again right after parsing, before any verification work.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ProofLimits {
+    /// Maximum size of a serialized proof document in bytes
+    pub max_proof_bytes: usize,
+    /// Maximum number of MMR sibling hashes in a block inclusion proof
+    pub max_siblings: usize,
+    /// Maximum number of MMR peaks in a block inclusion proof
+    pub max_peaks: usize,
+    /// Maximum length of a transaction Merkle path
+    pub max_merkle_path: usize,
+    /// Maximum number of felts in the Cairo public output
+    pub max_felt_output: usize,
+    /// Maximum serialized transaction size in bytes
+    pub max_transaction_bytes: usize,
+    /// Maximum number of txids in a transaction absence proof
+    pub max_block_transactions: usize,
+}
+
+impl Default for ProofLimits {
+    fn default() -> Self {
+        Self {
+            max_proof_bytes: 64 * 1024 * 1024, // Cairo proofs are a few MB in JSON
+            max_siblings: 64,                  // MMR height is bounded by log2 of the leaf count
+            max_peaks: 64,
+            max_merkle_path: 32, // 2^32 transactions per block is far beyond consensus limits
+            max_felt_output: 64,
+            max_transaction_bytes: 2_000_000, // Zcash block size limit
+            max_block_transactions: 40_000,   // 2 MB blocks of ~50-byte transactions
+        }
+    }
+}
+
+impl ProofLimits {
+    /// Check the size of a serialized proof document
+    pub fn check_document_size(&self, len: usize) -> anyhow::Result<()> {
+        check_limit("proof document size", len, self.max_proof_bytes)
+    }
+
+    /// Check a FlyClient block inclusion proof
+    pub fn check_block_inclusion(&self, proof: &BlockInclusionProof) -> anyhow::Result<()> {
+        check_limit("MMR peaks", proof.peaks_hashes.len(), self.max_peaks)?;
+        check_limit(
+            "MMR siblings",
+            proof.siblings_hashes.len(),
+            self.max_siblings,
+        )
+    }
+
+    /// Check a transaction Merkle proof
+    pub fn check_merkle_proof(&self, proof: &MerkleProof) -> anyhow::Result<()> {
+        check_limit("Merkle path length", proof.path.len(), self.max_merkle_path)?;
+        check_limit(
+            "Merkle sibling positions",
+            proof.positions.len(),
+            self.max_merkle_path,
+        )
+    }
+
+    /// Check the size of a transaction
+    pub fn check_transaction(&self, transaction: &Transaction) -> anyhow::Result<()> {
+        check_limit(
+            "transaction size",
+            transaction.zcash_serialized_size(),
+            self.max_transaction_bytes,
+        )
+    }
+
+    /// Check the length of the Cairo public output
+    pub fn check_felt_output(&self, len: usize) -> anyhow::Result<()> {
+        check_limit("Cairo output length", len, self.max_felt_output)
+    }
+
+    /// Check a transaction inclusion proof
+    pub fn check_transaction_inclusion(
+        &self,
+        proof: &TransactionInclusionProof,
+    ) -> anyhow::Result<()> {
+        self.check_merkle_proof(&proof.transaction_proof)?;
+        self.check_transaction(&proof.transaction)
+    }
+
+    /// Check a transaction absence proof
+    pub fn check_transaction_absence(&self, proof: &TransactionAbsenceProof) -> anyhow::Result<()> {
+        check_limit(
+            "block transactions",
+            proof.txids.len(),
+            self.max_block_transactions,
+        )?;
+        self.check_transaction(&proof.coinbase)
+    }
+
+    /// Check every layer of a full inclusion proof
+    #[cfg(feature = "stark")]
+    pub fn check_full_proof(&self, proof: &FullInclusionProof) -> anyhow::Result<()> {
+        self.check_block_inclusion(&proof.block_inclusion_proof)?;
+        self.check_merkle_proof(&proof.transaction_proof)?;
+        self.check_transaction(&proof.transaction)?;
+        self.check_felt_output(
+            proof
+                .chain_state_proof
+                .claim
+                .public_data
+                .public_memory
+                .output
+                .len(),
+        )
+    }
+}
+
+/// Limit on the value of a known proof field
+#[derive(Debug, Clone, Copy)]
+enum FieldLimit {
+    /// Number of array items
+    Items(&'static str, usize),
+    /// Size in bytes of a hex-encoded transaction
+    HexBytes(&'static str, usize),
+}
+
+/// Field name without case and underscores, so that the camelCase aliases match too
+fn normalize_key(key: &str) -> String {
+    key.chars()
+        .filter(|c| *c != '_')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+impl ProofLimits {
+    /// Limit on the value of the field `key` of the object at the field `parent`
+    fn field_limit(&self, parent: Option<&str>, key: &str) -> Option<FieldLimit> {
+        let parent = parent.map(normalize_key);
+        let limit = match (parent.as_deref(), normalize_key(key).as_str()) {
+            (_, "peakshashes") => FieldLimit::Items("MMR peaks", self.max_peaks),
+            (_, "siblingshashes") => FieldLimit::Items("MMR siblings", self.max_siblings),
+            (Some("transactionproof"), "path") => {
+                FieldLimit::Items("Merkle path length", self.max_merkle_path)
+            }
+            (Some("transactionproof"), "positions") => {
+                FieldLimit::Items("Merkle sibling positions", self.max_merkle_path)
+            }
+            (Some("publicmemory"), "output") => {
+                FieldLimit::Items("Cairo output length", self.max_felt_output)
+            }
+            (_, "txids") => FieldLimit::Items("block transactions", self.max_block_transactions),
+            (_, "transaction" | "coinbase") => {
+                FieldLimit::HexBytes("transaction size", self.max_transaction_bytes)
+            }
+            _ => return None,
+        };
+        Some(limit)
+    }
+}
+
+/// Visitor walking a JSON document without keeping it, failing as soon as a known proof field
+/// exceeds its limit. The error of the exceeded limit is kept in `exceeded`, since serde errors
+/// only carry a message.
+#[derive(Clone, Copy)]
+struct Scan<'a> {
+    limits: &'a ProofLimits,
+    exceeded: &'a Cell<Option<anyhow::Error>>,
+    /// Key of the scanned value in its parent object
+    key: Option<&'a str>,
+    limit: Option<FieldLimit>,
+}
+
+impl Scan<'_> {
+    fn check<E: de::Error>(&self, what: &str, value: usize, max: usize) -> Result<(), E> {
+        check_limit(what, value, max).map_err(|e| {
+            let error = E::custom(&e);
+            self.exceeded.set(Some(e));
+            error
+        })
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for Scan<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Scan<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_bool<E: de::Error>(self, _: bool) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_i64<E: de::Error>(self, _: i64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_u64<E: de::Error>(self, _: u64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_f64<E: de::Error>(self, _: f64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<(), E> {
+        match self.limit {
+            Some(FieldLimit::HexBytes(what, max)) => self.check(what, value.len() / 2, max),
+            _ => Ok(()),
+        }
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let item = Scan {
+            key: None,
+            limit: None,
+            ..self
+        };
+        let mut len = 0;
+        while seq.next_element_seed(item)?.is_some() {
+            len += 1;
+            if let Some(FieldLimit::Items(what, max)) = self.limit {
+                self.check(what, len, max)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            map.next_value_seed(Scan {
+                limits: self.limits,
+                exceeded: self.exceeded,
+                key: Some(&key),
+                limit: self.limits.field_limit(self.key, &key),
+            })?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) const LIMIT_HINT: &str =
+    "If the proof is legitimate, raise the limit in the `limits` section of the verifier config";
+
+fn check_limit(what: &str, value: usize, max: usize) -> anyhow::Result<()> {
+    if value > max {
+        return Err(anyhow::anyhow!(
+            "Proof exceeds limit: {what} is {value}, maximum is {max}"
+        ))
+        .hint(LIMIT_HINT);
+    }
+    Ok(())
+}
+
+/// Parse a JSON document received from an untrusted source
+pub fn parse_json<T: DeserializeOwned>(bytes: &[u8], what: &str) -> anyhow::Result<T> {
+    serde_json::from_slice(bytes).map_err(|e| anyhow::anyhow!("Malformed {what}: {e}"))
+}
+
+/// Parse a JSON document received from an untrusted source, rejecting oversized input
+/// before it is parsed.
+///
+/// The document is first scanned without being kept: arrays and transactions of the known proof
+/// fields (MMR peaks and siblings, Merkle paths, Cairo output, block txids) are rejected as soon
+/// as they exceed their limit, before the typed values are allocated.
+pub fn parse_json_limited<T: DeserializeOwned>(
+    bytes: &[u8],
+    what: &str,
+    limits: &ProofLimits,
+) -> anyhow::Result<T> {
+    limits.check_document_size(bytes.len())?;
+    let exceeded = Cell::new(None);
+    let scan = Scan {
+        limits,
+        exceeded: &exceeded,
+        key: None,
+        limit: None,
+    };
+    let mut deserializer = serde_json::Deserializer::from_slice(bytes);
+    if let Err(e) = scan.deserialize(&mut deserializer) {
+        return Err(exceeded
+            .take()
+            .unwrap_or_else(|| anyhow::anyhow!("Malformed {what}: {e}")));
+    }
+    parse_json(bytes, what)
+}
+
+/// Decode a 32-byte hash from a hex string, with or without the `0x` prefix
+pub fn decode_hash32(hex_str: &str) -> anyhow::Result<[u8; 32]> {
+    let body = hex_str.strip_prefix("0x").unwrap_or(hex_str);
+    let bytes = hex::decode(body).map_err(|e| anyhow::anyhow!("Invalid hash hex: {e}"))?;
+    let len = bytes.len();
+    bytes
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Invalid hash length: expected 32 bytes, got {len}"))
+}
+
+/// Run third-party verification code, converting a panic into an error.
+///
+/// This only helps when the binary is built with `panic = "unwind"`; with `panic = "abort"`
+/// input must be rejected by the shape checks before reaching such code.
+pub fn guard<T>(what: &str, f: impl FnOnce() -> anyhow::Result<T>) -> anyhow::Result<T> {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(res) => res,
+        Err(_) => anyhow::bail!("{what} panicked on malformed input"),
+    }
+}
+
+/// Check that a block inclusion proof is well-formed before running MMR math on it
+pub fn check_block_inclusion_shape(proof: &BlockInclusionProof) -> anyhow::Result<()> {
+    let BlockInclusionProof {
+        block_height,
+        peaks_hashes,
+        leaf_index,
+        leaf_count,
+        epoch_start_height,
+        ..
+    } = proof;
+
+    if peaks_hashes.is_empty() {
+        anyhow::bail!("Block inclusion proof has no peaks");
+    }
+    if *leaf_count == 0 || leaf_index >= leaf_count {
+        anyhow::bail!(
+            "Block inclusion proof leaf index {} is out of range for {} leaves",
+            leaf_index,
+            leaf_count
+        );
+    }
+    // Keep the MMR size computation from overflowing
+    if *leaf_count > u32::MAX as usize {
+        anyhow::bail!(
+            "Block inclusion proof leaf count {} is too large",
+            leaf_count
+        );
+    }
+
+    let expected_peaks = leaf_count_to_peaks_count(*leaf_count as u64) as usize;
+    if peaks_hashes.len() != expected_peaks {
+        anyhow::bail!(
+            "Block inclusion proof has {} peaks, expected {} for {} leaves",
+            peaks_hashes.len(),
+            expected_peaks,
+            leaf_count
+        );
+    }
+    // Leaf `i` of an epoch MMR holds block `epoch_start + i`
+    if let Some(epoch_start_height) = epoch_start_height {
+        if (*block_height as usize).checked_sub(*leaf_index) != Some(*epoch_start_height as usize) {
+            anyhow::bail!(
+                "Block inclusion proof leaf index {} does not place block {} in the epoch \
+                 starting at {}",
+                leaf_index,
+                block_height,
+                epoch_start_height
+            );
+        }
+    }
+    // Peaks are only decoded by the FlyClient MMR verification
+    #[cfg(feature = "flyclient")]
+    for (i, peak) in peaks_hashes.iter().enumerate() {
+        if decode_node_data(peak).is_err() {
+            anyhow::bail!("Block inclusion proof peak #{i} is not valid node data");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_hash32_rejects_wrong_length() {
+        assert!(decode_hash32("0xabcd").is_err());
+        assert!(decode_hash32("zz").is_err());
+        assert_eq!(decode_hash32(&"00".repeat(32)).ok(), Some([0u8; 32]));
+    }
+
+    #[test]
+    #[allow(clippy::panic)]
+    fn test_guard_catches_panics() {
+        let res: anyhow::Result<()> = guard("test", || panic!("boom"));
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_proof_limits() {
+        let limits = ProofLimits {
+            max_proof_bytes: 4,
+            max_peaks: 1,
+            ..Default::default()
+        };
+        assert!(parse_json_limited::<u32>(b"12345", "number", &limits).is_err());
+        assert_eq!(
+            parse_json_limited::<u32>(b"1234", "number", &limits).ok(),
+            Some(1234)
+        );
+
+        let proof = BlockInclusionProof {
+            block_height: 903_002,
+            peaks_hashes: vec!["00".to_string(); 2],
+            siblings_hashes: vec![],
+            leaf_index: 2,
+            leaf_count: 3,
+            epoch: None,
+            epoch_start_height: None,
+            mmr_id: None,
+        };
+        assert!(limits.check_block_inclusion(&proof).is_err());
+        assert!(ProofLimits::default().check_block_inclusion(&proof).is_ok());
+    }
+
+    #[test]
+    fn test_limits_enforced_while_parsing() {
+        let limits = ProofLimits {
+            max_peaks: 1,
+            max_merkle_path: 1,
+            max_transaction_bytes: 2,
+            ..Default::default()
+        };
+        let parse = |json: &str| {
+            parse_json_limited::<serde_json::Value>(json.as_bytes(), "proof", &limits)
+                .map_err(|e| e.to_string())
+        };
+        let err = parse(r#"{"block_inclusion_proof": {"peaksHashes": ["00", "01"]}}"#);
+        assert!(err.is_err_and(|e| e.contains("MMR peaks is 2")));
+        let err = parse(r#"{"transaction_proof": {"path": [[0], [1]]}}"#);
+        assert!(err.is_err_and(|e| e.contains("Merkle path length is 2")));
+        let err = parse(r#"{"transaction": "00112233"}"#);
+        assert!(err.is_err_and(|e| e.contains("transaction size is 4")));
+
+        // Fields of the same name elsewhere are not limited
+        assert!(parse(r#"{"path": [0, 1], "peaks": ["00", "01"], "txid": "00112233"}"#).is_ok());
+        assert!(parse(r#"{"peaks_hashes": ["00"], "transaction": "0011"}"#).is_ok());
+        assert!(parse(r#"{"peaks_hashes": ["00"]"#).is_err_and(|e| e.contains("Malformed")));
+    }
+
+    #[test]
+    fn test_block_inclusion_shape_rejects_bad_counts() {
+        let mut proof = BlockInclusionProof {
+            block_height: 903_000,
+            peaks_hashes: vec!["00".to_string()],
+            siblings_hashes: vec![],
+            leaf_index: 0,
+            leaf_count: 0,
+            epoch: None,
+            epoch_start_height: None,
+            mmr_id: None,
+        };
+        assert!(check_block_inclusion_shape(&proof).is_err());
+        proof.leaf_count = 1;
+        proof.leaf_index = 1;
+        assert!(check_block_inclusion_shape(&proof).is_err());
+        proof.leaf_count = 3;
+        proof.leaf_index = 0;
+        // Three leaves need two peaks
+        assert!(check_block_inclusion_shape(&proof).is_err());
+    }
+
+    #[test]
+    fn test_block_inclusion_shape_checks_epoch_start() {
+        let proof = BlockInclusionProof {
+            block_height: 903_002,
+            peaks_hashes: vec!["00".to_string(); 2],
+            siblings_hashes: vec![],
+            leaf_index: 2,
+            leaf_count: 3,
+            epoch: Some("heartwood".to_string()),
+            epoch_start_height: Some(903_001),
+            mmr_id: Some("flyclient_heartwood".to_string()),
+        };
+        let err = check_block_inclusion_shape(&proof).unwrap_err();
+        assert!(
+            err.to_string().contains("epoch starting at 903001"),
+            "{err}"
+        );
+        // Consistent epoch start, rejected later for its peaks
+        #[cfg(feature = "flyclient")]
+        {
+            let proof = BlockInclusionProof {
+                epoch_start_height: Some(903_000),
+                ..proof
+            };
+            let err = check_block_inclusion_shape(&proof).unwrap_err();
+            assert!(err.to_string().contains("node data"), "{err}");
+        }
+    }
+}
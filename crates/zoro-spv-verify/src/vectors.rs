@@ -0,0 +1,250 @@
+//! Conformance test vectors for the Zoro proof formats.
+//!
+//! [`generate`] derives the vectors deterministically from the Zcash genesis block header: block
+//! headers chained on top of it (with invalid proof of work, they only exercise the encodings),
+//! transaction Merkle proofs over synthetic txids, block header MMR range proofs and chain
+//! states, each with its expected digests. [`write`] stores them as one JSON file per category,
+//! and [`check`] verifies such a directory against this implementation, as other
+//! implementations should.
+
+use std::path::Path;
+
+use hex::FromHex;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zebra_chain::block::{Hash, Header};
+use zebra_chain::serialization::ZcashDeserialize;
+use zebra_chain::transaction;
+use zoro_zcash_client::serialize::{deserialize_header, serialize_header};
+use zoro_zcash_client::{MerkleProof, MerkleTree};
+
+use crate::block_mmr::{header_leaf, verify_range_proof, BlockMmr, RangeProof, SparseRoots};
+use crate::canonical::CanonicalSerialize;
+use crate::proof::{ChainState, Target};
+use crate::untrusted::decode_hash32;
+
+const GENESIS_HEADER: &str = include_str!("../tests/data/genesis_header.hex");
+
+/// Number of headers of the header and MMR vectors
+const HEADER_COUNT: usize = 7;
+/// Transaction counts of the Merkle vectors, covering odd levels
+const TX_COUNTS: [usize; 4] = [1, 2, 3, 7];
+/// Ranges of the MMR vectors, as `(start_height, end_height)`
+const MMR_RANGES: [(u32, u32); 4] = [(0, 0), (2, 4), (6, 6), (0, 6)];
+
+/// A block header with its identifiers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeaderVector {
+    /// Consensus encoding of the header, in hex
+    #[serde(
+        serialize_with = "serialize_header",
+        deserialize_with = "deserialize_header"
+    )]
+    pub header: Header,
+    /// Block hash, in display order
+    pub block_hash: String,
+    /// Leaf of the header in the block header MMR (Cairo `u256` word order)
+    pub mmr_leaf: String,
+}
+
+/// A transaction Merkle proof
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MerkleProofVector {
+    /// Txids of the block, in display order
+    pub txids: Vec<String>,
+    /// Index of the proven transaction
+    pub index: usize,
+    /// Proof of the transaction at `index`
+    pub proof: MerkleProof,
+    /// Canonical digest of `proof`
+    pub canonical_digest: String,
+}
+
+/// A block header MMR range proof
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RangeProofVector {
+    /// Roots of the MMR
+    pub roots: SparseRoots,
+    /// MMR leaves of the proven range
+    pub leaves: Vec<String>,
+    /// Proof of `leaves` against `roots`
+    pub proof: RangeProof,
+}
+
+/// A chain state with its digests
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainStateVector {
+    pub chain_state: ChainState,
+    /// Digest committed to by the Cairo program
+    pub blake2s_digest: String,
+    /// Canonical digest of `chain_state`
+    pub canonical_digest: String,
+}
+
+/// The full set of vectors
+#[derive(Debug, Clone)]
+pub struct Vectors {
+    pub headers: Vec<HeaderVector>,
+    pub merkle_proofs: Vec<MerkleProofVector>,
+    pub mmr_proofs: Vec<RangeProofVector>,
+    pub chain_states: Vec<ChainStateVector>,
+}
+
+/// Generate the vectors
+pub fn generate() -> anyhow::Result<Vectors> {
+    let mut headers = Vec::new();
+    let genesis = Header::zcash_deserialize(hex::decode(GENESIS_HEADER.trim())?.as_slice())?;
+    let mut header = genesis;
+    for _ in 0..HEADER_COUNT {
+        headers.push(HeaderVector {
+            header,
+            block_hash: header.hash().to_string(),
+            mmr_leaf: format!("0x{}", hex::encode(header_leaf(&header)?)),
+        });
+        header = Header {
+            previous_block_hash: header.hash(),
+            ..genesis
+        };
+    }
+
+    let mut merkle_proofs = Vec::new();
+    for tx_count in TX_COUNTS {
+        let tx_hashes: Vec<transaction::Hash> = (0..tx_count as u32)
+            .map(|i| transaction::Hash(Sha256::digest(i.to_le_bytes()).into()))
+            .collect();
+        let root = MerkleTree::compute_root(&tx_hashes).map_err(anyhow::Error::msg)?;
+        let tree = MerkleTree::from_tx_hashes(tx_hashes, root).map_err(anyhow::Error::msg)?;
+        for index in 0..tx_count {
+            let proof = tree.generate_proof(index).map_err(anyhow::Error::msg)?;
+            merkle_proofs.push(MerkleProofVector {
+                txids: tree.tx_hashes().iter().map(ToString::to_string).collect(),
+                index,
+                canonical_digest: proof.canonical_digest()?,
+                proof,
+            });
+        }
+    }
+
+    let mut mmr = BlockMmr::default();
+    for vector in &headers {
+        mmr.add_block_header(&vector.header)?;
+    }
+    let mut mmr_proofs = Vec::new();
+    for (start_height, end_height) in MMR_RANGES {
+        mmr_proofs.push(RangeProofVector {
+            roots: mmr.roots().clone(),
+            leaves: headers[start_height as usize..=end_height as usize]
+                .iter()
+                .map(|vector| vector.mmr_leaf.clone())
+                .collect(),
+            proof: mmr.generate_range_proof(start_height, end_height)?,
+        });
+    }
+
+    let genesis_target =
+        Target::from_hex("0007ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff")?;
+    let mut chain_states = Vec::new();
+    for (block_height, vector) in headers.iter().enumerate().take(2) {
+        let chain_state = ChainState {
+            block_height: block_height as u32,
+            total_work: 0x2000 * (block_height as u128 + 1),
+            best_block_hash: Hash::from_hex(&vector.block_hash)?,
+            current_target: genesis_target.clone(),
+            prev_timestamps: vec![1477641360; block_height + 1],
+            epoch_start_time: 1477641360,
+            pow_target_history: vec![genesis_target.clone(); 17],
+        };
+        chain_states.push(ChainStateVector {
+            blake2s_digest: chain_state.blake2s_digest()?,
+            canonical_digest: chain_state.canonical_digest()?,
+            chain_state,
+        });
+    }
+
+    Ok(Vectors {
+        headers,
+        merkle_proofs,
+        mmr_proofs,
+        chain_states,
+    })
+}
+
+/// Write the vectors to `dir`, one JSON file per category
+pub fn write(vectors: &Vectors, dir: &Path) -> anyhow::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    write_file(dir, "headers.json", &vectors.headers)?;
+    write_file(dir, "merkle_proofs.json", &vectors.merkle_proofs)?;
+    write_file(dir, "mmr_proofs.json", &vectors.mmr_proofs)?;
+    write_file(dir, "chain_states.json", &vectors.chain_states)
+}
+
+fn write_file<T: Serialize>(dir: &Path, name: &str, cases: &[T]) -> anyhow::Result<()> {
+    let path = dir.join(name);
+    std::fs::write(&path, serde_json::to_vec_pretty(cases)?)
+        .map_err(|e| anyhow::anyhow!("Failed to write {}: {e}", path.display()))
+}
+
+fn read_file<T: DeserializeOwned>(dir: &Path, name: &str) -> anyhow::Result<Vec<T>> {
+    let path = dir.join(name);
+    let data = std::fs::read(&path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {e}", path.display()))?;
+    serde_json::from_slice(&data)
+        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {e}", path.display()))
+}
+
+/// Check the vectors of `dir` against this implementation, returning the number of cases
+pub fn check(dir: &Path) -> anyhow::Result<usize> {
+    let headers: Vec<HeaderVector> = read_file(dir, "headers.json")?;
+    for (i, vector) in headers.iter().enumerate() {
+        let block_hash = vector.header.hash().to_string();
+        let mmr_leaf = format!("0x{}", hex::encode(header_leaf(&vector.header)?));
+        if block_hash != vector.block_hash || mmr_leaf != vector.mmr_leaf {
+            anyhow::bail!("Header vector {i}: expected {block_hash} and {mmr_leaf}");
+        }
+    }
+
+    let merkle_proofs: Vec<MerkleProofVector> = read_file(dir, "merkle_proofs.json")?;
+    for (i, vector) in merkle_proofs.iter().enumerate() {
+        let tx_hashes = vector
+            .txids
+            .iter()
+            .map(|txid| txid.parse())
+            .collect::<Result<Vec<transaction::Hash>, _>>()?;
+        let root = MerkleTree::compute_root(&tx_hashes).map_err(anyhow::Error::msg)?;
+        let tx_hash = tx_hashes
+            .get(vector.index)
+            .ok_or_else(|| anyhow::anyhow!("Merkle vector {i}: index out of bounds"))?;
+        if vector.proof.root != root || vector.proof.index != vector.index {
+            anyhow::bail!("Merkle vector {i}: proof is not for the transactions");
+        }
+        if !vector.proof.verify(tx_hash.0) {
+            anyhow::bail!("Merkle vector {i}: proof does not verify");
+        }
+        if vector.proof.canonical_digest()? != vector.canonical_digest {
+            anyhow::bail!("Merkle vector {i}: canonical digest mismatch");
+        }
+    }
+
+    let mmr_proofs: Vec<RangeProofVector> = read_file(dir, "mmr_proofs.json")?;
+    for (i, vector) in mmr_proofs.iter().enumerate() {
+        let leaves = vector
+            .leaves
+            .iter()
+            .map(|leaf| decode_hash32(leaf))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        verify_range_proof(&vector.roots, &leaves, &vector.proof)
+            .map_err(|e| anyhow::anyhow!("MMR vector {i}: {e}"))?;
+    }
+
+    let chain_states: Vec<ChainStateVector> = read_file(dir, "chain_states.json")?;
+    for (i, vector) in chain_states.iter().enumerate() {
+        if vector.chain_state.blake2s_digest()? != vector.blake2s_digest
+            || vector.chain_state.canonical_digest()? != vector.canonical_digest
+        {
+            anyhow::bail!("Chain state vector {i}: digest mismatch");
+        }
+    }
+
+    Ok(headers.len() + merkle_proofs.len() + mmr_proofs.len() + chain_states.len())
+}
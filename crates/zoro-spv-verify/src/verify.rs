@@ -1,25 +1,64 @@
 //! Verification routines for compressed SPV proofs, including transaction, block MMR,
 //! Cairo recursive proof, and subchain work checks.
 
+#[cfg(feature = "flyclient")]
 use std::sync::Arc;
 
-use accumulators::hasher::flyclient::ZcashFlyclientHasher;
-use accumulators::mmr::{leaf_count_to_mmr_size, MMR};
-use accumulators::store::memory::InMemoryStore;
+#[cfg(feature = "flyclient")]
+use accumulators::{
+    hasher::flyclient::ZcashFlyclientHasher, mmr::MMR, store::memory::InMemoryStore,
+};
+#[cfg(feature = "stark")]
 use cairo_air::utils::{get_verification_output, VerificationOutput};
+#[cfg(feature = "stark")]
 use cairo_air::{CairoProof, PreProcessedTraceVariant};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+#[cfg(any(feature = "stark", feature = "flyclient"))]
 use tracing::info;
-use zebra_chain::block::Header;
-use zebra_chain::transaction::Transaction;
-use zoro_zcash_client::MerkleProof;
-
+use tracing::warn;
+use zebra_chain::block::{merkle, Header};
+use zebra_chain::transaction::{self, Transaction};
+use zebra_chain::transparent;
+use zoro_chain_params::Network;
+use zoro_zcash_client::{MerkleProof, MerkleTree};
+
+use crate::cancel::CancellationToken;
+use crate::checkpoint::{check_checkpoints, Checkpoint};
+use crate::finality::{FinalityPolicy, DEFAULT_CONFIRMATIONS};
+#[cfg(feature = "flyclient")]
+use crate::mmr_math::leaf_count_to_mmr_size;
+#[cfg(all(feature = "stark", feature = "flyclient"))]
+use crate::proof::FullInclusionProof;
 use crate::proof::{
-    BlockInclusionProof, BootloaderOutput, ChainState, FullInclusionProof, TaskResult,
+    BlockInclusionProof, ChainState, CoinbaseOutput, CoinbaseProofResponse, MmrRootRecord,
+    TransactionAbsenceProof,
 };
+#[cfg(feature = "stark")]
+use crate::proof::{BootloaderOutput, TaskResult};
+use crate::report::WithHint;
+#[cfg(feature = "flyclient")]
+use crate::untrusted::check_block_inclusion_shape;
+#[cfg(feature = "stark")]
+use crate::untrusted::guard;
+use crate::untrusted::ProofLimits;
+
+/// Hint for proofs with fewer confirmations than required
+pub const INSUFFICIENT_CONFIRMATIONS_HINT: &str =
+    "Wait for more blocks to be mined, or verify against a later chain height";
+pub(crate) const MMR_MISMATCH_HINT: &str =
+    "The bridge may follow another chain or serve stale data: do not trust this proof and retry against another bridge";
+pub(crate) const PROGRAM_VERSION_HINT: &str =
+    "The STARK proof comes from another program version: check bootloader_hash and task_program_hash in the verifier config";
+pub(crate) const CHAIN_STATE_HEIGHT_HINT: &str =
+    "Verify against a chain state at or after the block height";
+pub(crate) const CHAIN_STATE_HASH_HINT: &str =
+    "Use the chain state at the height the STARK proof was generated for";
+pub(crate) const PROOF_LAG_HINT: &str =
+    "The prover is behind the bridge: retry once a more recent chain state proof is published, or raise --max-proof-lag";
 
 /// Configuration parameters controlling verification policies
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct VerifierConfig {
     /// Minimum cumulative work required on top of the target block (decimal string)
     pub min_work: String,
@@ -29,8 +68,36 @@ pub struct VerifierConfig {
     pub task_program_hash: String,
     /// Expected size of the payload program output in felts
     pub task_output_size: u32,
-    /// Minimum number of block confirmations required
+    /// Minimum number of block confirmations required, unless `finality` is set
     pub min_confirmations: u32,
+    /// When the block counts as final (optional, `min_confirmations` confirmations if unset)
+    #[serde(default)]
+    pub finality: Option<FinalityPolicy>,
+    /// Resource limits for untrusted proofs
+    #[serde(default)]
+    pub limits: ProofLimits,
+    /// What to do when the containing block is past the transaction's expiry height
+    #[serde(default)]
+    pub expiry_policy: ExpiryPolicy,
+    /// Checkpoints every verified chain state, and the blocks proven in its chain, must match
+    #[serde(default)]
+    pub checkpoints: Vec<Checkpoint>,
+}
+
+/// Handling of transactions mined after their expiry height.
+///
+/// Consensus rejects such transactions, so a proof claiming one indicates a malformed or
+/// fabricated proof.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExpiryPolicy {
+    /// Do not check the expiry height
+    Ignore,
+    /// Log a warning and accept the proof
+    #[default]
+    Warn,
+    /// Reject the proof
+    Error,
 }
 
 impl Default for VerifierConfig {
@@ -42,11 +109,26 @@ impl Default for VerifierConfig {
             task_program_hash: "0x009a4925039ebb547c27335f40168be7b9d3e8e897db0729a38b8160da53724a"
                 .to_string(),
             task_output_size: 6, // 1 felt for program hash, 4 for Result (u256 + 2 felt252), 1 for size
-            min_confirmations: 6,
+            min_confirmations: DEFAULT_CONFIRMATIONS,
+            finality: None,
+            limits: ProofLimits::default(),
+            expiry_policy: ExpiryPolicy::default(),
+            checkpoints: Vec::new(),
         }
     }
 }
 
+impl VerifierConfig {
+    /// Finality policy of the verifier
+    pub fn finality(&self) -> FinalityPolicy {
+        self.finality
+            .clone()
+            .unwrap_or(FinalityPolicy::Confirmations {
+                confirmations: self.min_confirmations,
+            })
+    }
+}
+
 /// Result of a successful full inclusion proof verification
 #[derive(Debug, Clone)]
 pub struct VerificationResult {
@@ -60,6 +142,11 @@ pub struct VerificationResult {
     pub chain_height: u32,
     /// Number of confirmations
     pub confirmations: u32,
+    /// Expiry height of the transaction, if it has one
+    pub expiry_height: Option<u32>,
+    /// Whether the chain state was verified against its STARK proof. Unset for
+    /// [`quick_verify`] and when the chain state proof is skipped.
+    pub chain_state_verified: bool,
 }
 
 /// Options for controlling which parts of the proof to verify
@@ -69,8 +156,19 @@ pub struct VerifyOptions {
     pub skip_chain_proof: bool,
     /// Skip block inclusion (FlyClient MMR) proof verification
     pub skip_block_proof: bool,
+    /// Check the block timestamp against the chain state's recent timestamps
+    pub check_timestamps: bool,
+    /// Token to abort the verification between its stages
+    pub cancel: CancellationToken,
 }
 
+/// Maximum time (seconds) a block timestamp may be ahead of the chain tip timestamps,
+/// mirroring the consensus "future block time" rule.
+pub const MAX_FUTURE_BLOCK_TIME: u32 = 2 * 60 * 60;
+
+/// Timestamp of the Zcash mainnet genesis block
+pub const GENESIS_TIMESTAMP: u32 = Network::Mainnet.genesis_timestamp();
+
 /// Verify a full inclusion proof end-to-end.
 ///
 /// This performs three layers of verification:
@@ -79,6 +177,9 @@ pub struct VerifyOptions {
 /// 3. Transaction Inclusion Proof: Verifies the Merkle proof that the tx is in the block
 ///
 /// Also checks that proofs are properly interlinked and that sufficient confirmations exist.
+/// The cheap checks and layers 2 and 3 run first, so that an inconsistent proof is rejected
+/// before the STARK verification.
+#[cfg(all(feature = "stark", feature = "flyclient"))]
 pub async fn verify_full_inclusion_proof(
     proof: FullInclusionProof,
     config: &VerifierConfig,
@@ -86,42 +187,103 @@ pub async fn verify_full_inclusion_proof(
     verify_full_inclusion_proof_with_options(proof, config, VerifyOptions::default()).await
 }
 
-/// Verify a full inclusion proof with options to skip certain verifications (for testing)
+/// Verify a full inclusion proof with options to skip certain verifications (for testing) or to
+/// cancel it. A cancelled verification fails with a [`Cancelled`](crate::cancel::Cancelled) error.
+#[cfg(all(feature = "stark", feature = "flyclient"))]
 pub async fn verify_full_inclusion_proof_with_options(
     proof: FullInclusionProof,
     config: &VerifierConfig,
     options: VerifyOptions,
+) -> Result<VerificationResult, anyhow::Error> {
+    config.limits.check_full_proof(&proof)?;
+    let mut result = verify_cheap_layers(&proof, config, &options).await?;
+
+    // === Layer 1: Verify Chain State Proof ===
+    options.cancel.check("chain state proof verification")?;
+    if options.skip_chain_proof {
+        info!("SKIPPING chain state proof verification (--skip-chain-proof)");
+    } else {
+        info!("Verifying chain state proof (STARK)...");
+        let verified_chain_state_hash =
+            verify_chain_state(&proof.chain_state, proof.chain_state_proof, config)?;
+        info!("Chain state verified: {}", verified_chain_state_hash);
+        result.chain_state_verified = true;
+    }
+
+    info!(
+        "✓ Full verification successful! {} confirmations",
+        result.confirmations
+    );
+    Ok(result)
+}
+
+/// Verify a full inclusion proof without its STARK proof, for flows that cannot wait for it.
+///
+/// Runs every check of [`verify_full_inclusion_proof`] but the chain state proof verification:
+/// the block and transaction are proven to be in the chain described by the chain state, not that
+/// this chain is valid. The result has `chain_state_verified` unset, and finality policies
+/// requiring a proven chain state fail.
+#[cfg(all(feature = "stark", feature = "flyclient"))]
+pub async fn quick_verify(
+    proof: &FullInclusionProof,
+    config: &VerifierConfig,
+) -> Result<VerificationResult, anyhow::Error> {
+    config.limits.check_full_proof(proof)?;
+    let options = VerifyOptions {
+        skip_chain_proof: true,
+        ..Default::default()
+    };
+    let result = verify_cheap_layers(proof, config, &options).await?;
+    warn!(
+        "Quick verification: block {} and transaction {} verified against an UNVERIFIED chain state",
+        result.block_hash, result.transaction_hash
+    );
+    Ok(result)
+}
+
+/// Checks of a full inclusion proof that do not involve the STARK proof: consistency of its
+/// layers, finality, timestamps, block inclusion, transaction inclusion and expiry
+#[cfg(all(feature = "stark", feature = "flyclient"))]
+async fn verify_cheap_layers(
+    proof: &FullInclusionProof,
+    config: &VerifierConfig,
+    options: &VerifyOptions,
 ) -> Result<VerificationResult, anyhow::Error> {
     let FullInclusionProof {
         chain_state,
-        chain_state_proof,
         block_header,
         block_height,
         block_inclusion_proof,
         transaction,
         transaction_proof,
+        ..
     } = proof;
+    let block_height = *block_height;
 
     // === Sanity Checks ===
 
     // Block must be at or before the chain state height
     if block_height > chain_state.block_height {
-        anyhow::bail!(
+        return Err(anyhow::anyhow!(
             "Block height {} is after chain state height {}",
             block_height,
             chain_state.block_height
-        );
+        ))
+        .hint(CHAIN_STATE_HEIGHT_HINT);
     }
 
-    // Check minimum confirmations
+    let block_hash = block_header.hash();
+    check_checkpoints(
+        &config.checkpoints,
+        chain_state,
+        Some((block_height, &block_hash)),
+    )?;
+
+    // Check finality, the STARK proof being verified last
     let confirmations = chain_state.block_height.saturating_sub(block_height) + 1;
-    if confirmations < config.min_confirmations {
-        anyhow::bail!(
-            "Insufficient confirmations: {} < {} required",
-            confirmations,
-            config.min_confirmations
-        );
-    }
+    config
+        .finality()
+        .check(block_height, chain_state, !options.skip_chain_proof)?;
 
     // Block inclusion proof height must match the claimed block height (skip if mocked)
     if !options.skip_block_proof && block_inclusion_proof.block_height != block_height {
@@ -132,47 +294,148 @@ pub async fn verify_full_inclusion_proof_with_options(
         );
     }
 
-    // === Layer 1: Verify Chain State Proof ===
-    if options.skip_chain_proof {
-        info!("SKIPPING chain state proof verification (--skip-chain-proof)");
-    } else {
-        info!("Verifying chain state proof (STARK)...");
-        let verified_chain_state_hash =
-            verify_chain_state(&chain_state, chain_state_proof, config)?;
-        info!("Chain state verified: {}", verified_chain_state_hash);
+    if options.check_timestamps {
+        verify_block_timestamp(
+            block_header.time.timestamp() as u32,
+            block_height,
+            chain_state,
+        )?;
     }
 
+    // === Layer 3: Verify Transaction Inclusion Proof ===
+    options
+        .cancel
+        .check("transaction inclusion proof verification")?;
+    info!("Verifying transaction inclusion proof (Merkle)...");
+    verify_transaction(transaction, block_header, transaction_proof.clone())?;
+    let tx_hash = transaction.hash();
+    info!("Transaction {} included in block {}", tx_hash, block_hash);
+
+    let expiry_height = transaction.expiry_height().map(|height| height.0);
+    check_transaction_expiry(expiry_height, block_height, config.expiry_policy)?;
+
     // === Layer 2: Verify Block Inclusion Proof ===
-    let block_hash = block_header.hash();
+    options.cancel.check("block inclusion proof verification")?;
     if options.skip_block_proof {
         info!("SKIPPING block inclusion proof verification (--skip-block-proof)");
     } else {
         info!("Verifying block inclusion proof (FlyClient MMR)...");
-        verify_block_inclusion(&block_header, &block_inclusion_proof).await?;
+        verify_block_inclusion(block_header, block_inclusion_proof).await?;
         info!("Block {} included at height {}", block_hash, block_height);
     }
 
-    // === Layer 3: Verify Transaction Inclusion Proof ===
-    info!("Verifying transaction inclusion proof (Merkle)...");
-    verify_transaction(&transaction, &block_header, transaction_proof)?;
-    let tx_hash = transaction.hash();
-    info!("Transaction {} included in block {}", tx_hash, block_hash);
-
-    info!(
-        "✓ Full verification successful! {} confirmations",
-        confirmations
-    );
-
     Ok(VerificationResult {
         transaction_hash: tx_hash,
         block_hash,
         block_height,
         chain_height: chain_state.block_height,
         confirmations,
+        expiry_height,
+        chain_state_verified: false,
     })
 }
 
+/// Check that a transaction was not mined after its expiry height.
+///
+/// `expiry_height` is `None` for transactions without expiry (pre-Overwinter, or an expiry
+/// height of zero).
+pub fn check_transaction_expiry(
+    expiry_height: Option<u32>,
+    block_height: u32,
+    policy: ExpiryPolicy,
+) -> anyhow::Result<()> {
+    let Some(expiry_height) = expiry_height else {
+        return Ok(());
+    };
+    if block_height <= expiry_height {
+        return Ok(());
+    }
+    let message = format!(
+        "Transaction expired at height {} but is included in block {}",
+        expiry_height, block_height
+    );
+    match policy {
+        ExpiryPolicy::Ignore => Ok(()),
+        ExpiryPolicy::Warn => {
+            warn!("{message}");
+            Ok(())
+        }
+        ExpiryPolicy::Error => anyhow::bail!(message),
+    }
+}
+
+/// Number of blocks the bridge head is past the latest proven chain state.
+///
+/// Fails if the lag exceeds `max_lag`, as the chain state a STARK proof can vouch for is then
+/// too old for the caller.
+pub fn check_proof_lag(proven_height: u32, head: u32, max_lag: Option<u32>) -> anyhow::Result<u32> {
+    let lag = head.saturating_sub(proven_height);
+    match max_lag {
+        Some(max_lag) if lag > max_lag => Err(anyhow::anyhow!(
+            "Latest proven chain state at height {} is {} blocks behind the bridge head {} (at most {} allowed)",
+            proven_height,
+            lag,
+            head,
+            max_lag
+        ))
+        .hint(PROOF_LAG_HINT),
+        _ => Ok(lag),
+    }
+}
+
+/// Check that a block timestamp is plausible relative to the chain state.
+///
+/// `prev_timestamps` holds the timestamps of the most recent blocks ending at the chain tip, so
+/// a block within that window must match its recorded timestamp exactly. Older blocks must be
+/// after genesis and no later than the tip timestamps plus [`MAX_FUTURE_BLOCK_TIME`].
+pub fn verify_block_timestamp(
+    block_time: u32,
+    block_height: u32,
+    chain_state: &ChainState,
+) -> anyhow::Result<()> {
+    let timestamps = &chain_state.prev_timestamps;
+    let newest = timestamps
+        .iter()
+        .copied()
+        .max()
+        .ok_or_else(|| anyhow::anyhow!("Chain state has no timestamps"))?;
+
+    let depth = chain_state.block_height.saturating_sub(block_height) as usize;
+    if let Some(&expected) = timestamps
+        .len()
+        .checked_sub(depth + 1)
+        .and_then(|idx| timestamps.get(idx))
+    {
+        if block_time != expected {
+            anyhow::bail!(
+                "Block timestamp {} doesn't match chain state timestamp {} at height {}",
+                block_time,
+                expected,
+                block_height
+            );
+        }
+        return Ok(());
+    }
+
+    if block_time < GENESIS_TIMESTAMP {
+        anyhow::bail!(
+            "Block timestamp {} is before the genesis block timestamp {}",
+            block_time,
+            GENESIS_TIMESTAMP
+        );
+    }
+    if block_time > newest.saturating_add(MAX_FUTURE_BLOCK_TIME) {
+        anyhow::bail!(
+            "Block timestamp {} is too far ahead of the chain state tip timestamp {}",
+            block_time,
+            newest
+        );
+    }
+    Ok(())
+}
+
 /// Legacy verify_proof function for backwards compatibility
+#[cfg(feature = "stark")]
 pub async fn verify_proof(
     _proof: crate::proof::CompressedSpvProof,
     _config: &VerifierConfig,
@@ -187,6 +450,7 @@ pub fn verify_transaction(
     block_header: &Header,
     transaction_proof: MerkleProof,
 ) -> anyhow::Result<()> {
+    check_transaction_index(transaction.is_coinbase(), transaction_proof.index)?;
     let valid = transaction_proof.verify(transaction.hash().into());
     if !valid {
         anyhow::bail!("Transaction proof verification failed");
@@ -199,29 +463,116 @@ pub fn verify_transaction(
     Ok(())
 }
 
+/// Check that a transaction is at index 0 of its block if and only if it is a coinbase
+/// transaction
+fn check_transaction_index(is_coinbase: bool, index: usize) -> anyhow::Result<()> {
+    match (is_coinbase, index) {
+        (true, 0) | (false, 1..) => Ok(()),
+        (true, _) => anyhow::bail!("Coinbase transaction proven at index {index}, not 0"),
+        (false, _) => anyhow::bail!("Non-coinbase transaction proven at index 0"),
+    }
+}
+
+/// Verify a coinbase proof: the transaction is the coinbase transaction of the block, it
+/// commits to the block height, and the outputs listed by the bridge are its outputs.
+///
+/// Only the block header is checked: its inclusion in the chain is proven separately, as for
+/// transaction inclusion proofs.
+pub fn verify_coinbase_proof(response: &CoinbaseProofResponse) -> anyhow::Result<()> {
+    let proof = &response.proof;
+    if !proof.transaction.is_coinbase() {
+        anyhow::bail!("Coinbase proof is for a non-coinbase transaction");
+    }
+    verify_transaction(
+        &proof.transaction,
+        &proof.block_header,
+        proof.transaction_proof.clone(),
+    )?;
+
+    // BIP 34 height, part of the coinbase input script
+    let height = proof
+        .transaction
+        .inputs()
+        .first()
+        .and_then(|input| match input {
+            transparent::Input::Coinbase { height, .. } => Some(height.0),
+            _ => None,
+        });
+    if height != Some(proof.block_height) {
+        anyhow::bail!(
+            "Coinbase transaction commits to height {:?}, proof is for block {}",
+            height,
+            proof.block_height
+        );
+    }
+
+    if response.outputs != CoinbaseOutput::from_transaction(&proof.transaction) {
+        anyhow::bail!("Coinbase outputs do not match the coinbase transaction");
+    }
+    let total_value: u64 = response.outputs.iter().map(|output| output.value).sum();
+    if response.total_value != total_value {
+        anyhow::bail!(
+            "Coinbase total value {} does not match its outputs ({})",
+            response.total_value,
+            total_value
+        );
+    }
+    Ok(())
+}
+
+/// Verify that `txid` is not included in the block of an absence proof.
+///
+/// Only the block header is checked: its inclusion in the chain is proven separately, as for
+/// transaction inclusion proofs.
+pub fn verify_transaction_absence(
+    txid: &transaction::Hash,
+    proof: &TransactionAbsenceProof,
+) -> anyhow::Result<()> {
+    let tx_hashes = proof.tx_hashes()?;
+    if !proof.coinbase.is_coinbase() || tx_hashes.first() != Some(&proof.coinbase.hash()) {
+        anyhow::bail!("Absence proof does not start with the coinbase transaction");
+    }
+    check_block_txids(txid, &tx_hashes, proof.block_header.merkle_root)
+}
+
+/// Check that `tx_hashes` hash to `merkle_root` and do not include `txid`
+fn check_block_txids(
+    txid: &transaction::Hash,
+    tx_hashes: &[transaction::Hash],
+    merkle_root: merkle::Root,
+) -> anyhow::Result<()> {
+    let root = MerkleTree::compute_root(tx_hashes).map_err(anyhow::Error::msg)?;
+    if root != merkle_root {
+        anyhow::bail!("Transaction list does not match the block Merkle root");
+    }
+    if tx_hashes.contains(txid) {
+        anyhow::bail!("Transaction {txid} is included in the block");
+    }
+    Ok(())
+}
+
 /// Verify that a block header is included in the FlyClient MMR using the supplied inclusion proof.
 ///
 /// This reconstructs the MMR from peaks and verifies the inclusion proof.
+#[cfg(feature = "flyclient")]
 pub async fn verify_block_inclusion(
     block_header: &Header,
     proof: &BlockInclusionProof,
 ) -> anyhow::Result<String> {
     let BlockInclusionProof {
-        block_height: _,
         peaks_hashes,
         siblings_hashes,
         leaf_index,
         leaf_count,
+        ..
     } = proof;
 
-    if peaks_hashes.is_empty() {
-        anyhow::bail!("Block inclusion proof has no peaks");
-    }
+    check_block_inclusion_shape(proof)?;
 
     // Create an in-memory MMR from the peaks
     let store = Arc::new(InMemoryStore::new(Some("verify")));
     let hasher = Arc::new(ZcashFlyclientHasher);
-    let elements_count = leaf_count_to_mmr_size(*leaf_count);
+    let elements_count = leaf_count_to_mmr_size(*leaf_count as u64) as usize;
 
     let mmr = MMR::create_from_peaks(
         store,
@@ -261,6 +612,56 @@ pub async fn verify_block_inclusion(
     Ok(root)
 }
 
+/// Check that the MMR root reconstructed from a block inclusion proof is the root the bridge
+/// node recorded at the proof's chain height.
+///
+/// Without this check the proof only shows that the block is part of *some* MMR state; the
+/// recorded root pins that state to a concrete chain height.
+pub fn verify_mmr_root_history(
+    proof: &BlockInclusionProof,
+    root: &str,
+    record: &MmrRootRecord,
+) -> anyhow::Result<()> {
+    let chain_height = proof.chain_height();
+    if let Some(epoch) = &proof.epoch {
+        if *epoch != record.epoch {
+            return Err(anyhow::anyhow!(
+                "MMR epoch mismatch at height {}: proof is for {}, bridge recorded {}",
+                chain_height,
+                epoch,
+                record.epoch
+            ))
+            .hint(MMR_MISMATCH_HINT);
+        }
+    }
+    if record.height != chain_height {
+        anyhow::bail!(
+            "Recorded MMR root is for height {}, but the proof was generated at height {}",
+            record.height,
+            chain_height
+        );
+    }
+    if record.leaf_count != proof.leaf_count {
+        return Err(anyhow::anyhow!(
+            "MMR leaf count mismatch at height {}: proof has {}, bridge recorded {}",
+            chain_height,
+            proof.leaf_count,
+            record.leaf_count
+        ))
+        .hint(MMR_MISMATCH_HINT);
+    }
+    if record.root != root {
+        return Err(anyhow::anyhow!(
+            "MMR root mismatch at height {}: proof reconstructs {}, bridge recorded {}",
+            chain_height,
+            root,
+            record.root
+        ))
+        .hint(MMR_MISMATCH_HINT);
+    }
+    Ok(())
+}
+
 /// Legacy verify_block_header kept for backwards compatibility  
 pub async fn verify_block_header(
     _block_header: &Header,
@@ -269,14 +670,18 @@ pub async fn verify_block_header(
     anyhow::bail!("Legacy verify_block_header is deprecated. Use verify_block_inclusion instead.")
 }
 
-/// Verify the Cairo recursive proof and consistency of the bootloader output with `chain_state`.
+/// Verify the Cairo recursive proof and consistency of the bootloader output with `chain_state`,
+/// after checking `chain_state` against the checkpoints of `config`.
 ///
 /// Returns the block MMR root extracted from the proof on success.
+#[cfg(feature = "stark")]
 pub fn verify_chain_state(
     chain_state: &ChainState,
     chain_state_proof: CairoProof<stwo::core::vcs::blake2_merkle::Blake2sMerkleHasher>,
     config: &VerifierConfig,
 ) -> anyhow::Result<String> {
+    check_checkpoints(&config.checkpoints, chain_state, None)?;
+
     info!("Extracting verification output...");
 
     // Extract verification output from the public memory
@@ -286,11 +691,16 @@ pub fn verify_chain_state(
         public_memory.program.len(),
         public_memory.output.len()
     );
+    config
+        .limits
+        .check_felt_output(public_memory.output.len())?;
 
     let VerificationOutput {
         program_hash: bootloader_hash,
         output,
-    } = get_verification_output(public_memory);
+    } = guard("Cairo output extraction", || {
+        Ok(get_verification_output(public_memory))
+    })?;
 
     info!("Output has {} elements", output.len());
 
@@ -325,18 +735,20 @@ pub fn verify_chain_state(
     // Check that chain state hashes match
     let expected_chain_state_hash = chain_state.blake2s_digest()?;
     if chain_state_hash != expected_chain_state_hash {
-        anyhow::bail!(
+        return Err(anyhow::anyhow!(
             "Chain state hash doesn't match the expected hash: {chain_state_hash} != {expected_chain_state_hash}"
-        );
+        ))
+        .hint(CHAIN_STATE_HASH_HINT);
     }
 
     // Check that the program hash is the same as in the bootloader output and as expected
     if task_program_hash != config.task_program_hash {
-        anyhow::bail!(
+        return Err(anyhow::anyhow!(
             "Bootloader output: task program hash doesn't match the expected hash: {} != {}",
             task_program_hash,
             config.task_program_hash
-        );
+        ))
+        .hint(PROGRAM_VERSION_HINT);
     }
     // For genesis state, prev_program_hash is 0; only check if non-zero
     let zero_hash = "0x0000000000000000000000000000000000000000000000000000000000000000";
@@ -348,11 +760,12 @@ pub fn verify_chain_state(
 
     // Check that the previous bootloader hash is the same as in the Cairo claim and as expected
     if bootloader_hash != config.bootloader_hash {
-        anyhow::bail!(
+        return Err(anyhow::anyhow!(
             "Bootloader hash doesn't match the expected hash: {} != {}",
             bootloader_hash,
             config.bootloader_hash
-        );
+        ))
+        .hint(PROGRAM_VERSION_HINT);
     }
     // For genesis state, prev_bootloader_hash is 0; only check if non-zero
     if prev_bootloader_hash != zero_hash && bootloader_hash != prev_bootloader_hash {
@@ -362,10 +775,96 @@ pub fn verify_chain_state(
     }
 
     info!("Verifying Cairo proof...");
-    cairo_air::verifier::verify_cairo::<stwo::core::vcs::blake2_merkle::Blake2sMerkleChannel>(
-        chain_state_proof,
-        PreProcessedTraceVariant::Canonical,
-    )?;
+    guard("Cairo proof verification", || {
+        cairo_air::verifier::verify_cairo::<stwo::core::vcs::blake2_merkle::Blake2sMerkleChannel>(
+            chain_state_proof,
+            PreProcessedTraceVariant::Canonical,
+        )
+        .map_err(Into::into)
+    })?;
 
     Ok(chain_state_hash)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof::Target;
+    use zebra_chain::block::Hash;
+
+    fn chain_state(block_height: u32, prev_timestamps: Vec<u32>) -> ChainState {
+        ChainState {
+            block_height,
+            total_work: 0,
+            best_block_hash: Hash([0; 32]),
+            current_target: Target::from_hex(&"00".repeat(32)).unwrap(),
+            prev_timestamps,
+            epoch_start_time: 0,
+            pow_target_history: vec![],
+        }
+    }
+
+    #[test]
+    fn test_check_transaction_expiry() {
+        assert!(check_transaction_expiry(None, 100, ExpiryPolicy::Error).is_ok());
+        assert!(check_transaction_expiry(Some(100), 100, ExpiryPolicy::Error).is_ok());
+        assert!(check_transaction_expiry(Some(99), 100, ExpiryPolicy::Error).is_err());
+        assert!(check_transaction_expiry(Some(99), 100, ExpiryPolicy::Warn).is_ok());
+        assert!(check_transaction_expiry(Some(99), 100, ExpiryPolicy::Ignore).is_ok());
+    }
+
+    #[test]
+    fn test_check_proof_lag() {
+        assert_eq!(check_proof_lag(100, 130, None).unwrap(), 30);
+        assert_eq!(check_proof_lag(100, 130, Some(30)).unwrap(), 30);
+        assert!(check_proof_lag(100, 131, Some(30)).is_err());
+        // A proof ahead of a lagging bridge head has no lag
+        assert_eq!(check_proof_lag(140, 130, Some(0)).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_verify_block_timestamp() {
+        let base = GENESIS_TIMESTAMP + 1_000_000;
+        let state = chain_state(100, vec![base, base + 75, base + 150]);
+
+        // Within the window the timestamp must match exactly
+        assert!(verify_block_timestamp(base + 150, 100, &state).is_ok());
+        assert!(verify_block_timestamp(base, 98, &state).is_ok());
+        assert!(verify_block_timestamp(base + 1, 98, &state).is_err());
+
+        // Older blocks only need to be plausible
+        assert!(verify_block_timestamp(base - 500, 50, &state).is_ok());
+        assert!(verify_block_timestamp(GENESIS_TIMESTAMP - 1, 50, &state).is_err());
+        assert!(
+            verify_block_timestamp(base + 150 + MAX_FUTURE_BLOCK_TIME + 1, 50, &state).is_err()
+        );
+    }
+
+    #[test]
+    fn test_check_transaction_index() {
+        assert!(check_transaction_index(true, 0).is_ok());
+        assert!(check_transaction_index(false, 3).is_ok());
+        assert!(check_transaction_index(true, 3).is_err());
+        assert!(check_transaction_index(false, 0).is_err());
+    }
+
+    #[test]
+    fn test_check_block_txids() {
+        use sha2::{Digest, Sha256};
+
+        let tx_hashes: Vec<transaction::Hash> = (0..5u32)
+            .map(|i| transaction::Hash(Sha256::digest(i.to_le_bytes()).into()))
+            .collect();
+        let root = MerkleTree::compute_root(&tx_hashes[..4]).unwrap();
+        assert!(check_block_txids(&tx_hashes[4], &tx_hashes[..4], root).is_ok());
+        assert!(check_block_txids(&tx_hashes[2], &tx_hashes[..4], root).is_err());
+        assert!(check_block_txids(&tx_hashes[4], &tx_hashes[..3], root).is_err());
+
+        // A level of inner nodes hashes to the same root: the coinbase check rules it out
+        let nodes: Vec<transaction::Hash> = tx_hashes[..4]
+            .chunks(2)
+            .map(|pair| transaction::Hash(MerkleTree::compute_root(pair).unwrap().0))
+            .collect();
+        assert!(check_block_txids(&tx_hashes[2], &nodes, root).is_ok());
+    }
+}
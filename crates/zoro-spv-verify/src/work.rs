@@ -15,8 +15,17 @@ pub fn verify_subchain_work(
     Ok(())
 }
 
+/// Estimate the work of the last `blocks` blocks of the chain from its current target.
+///
+/// Zcash adjusts the target every block, with bounded steps, so the estimate is close for the
+/// few blocks of a confirmation depth.
+pub fn subchain_work(blocks: u32, chain_state: &ChainState) -> BigUint {
+    let target = BigUint::from_bytes_be(chain_state.current_target.as_bytes());
+    compute_work_from_target(&target) * blocks
+}
+
 /// Compute the expected work for a single block given the target difficulty.
-fn _compute_work_from_target(target: &BigUint) -> BigUint {
+fn compute_work_from_target(target: &BigUint) -> BigUint {
     // 2^256
     let max_work = BigUint::from_str(
         "115792089237316195423570985008687907853269984665640564039457584007913129639936",
@@ -0,0 +1,55 @@
+//! Conformance fixtures: generated, written out and run back as the `conformance` binary does
+
+use zoro_spv_verify::conformance::{self, Verdict};
+
+#[test]
+fn conformance_run() {
+    let fixtures = conformance::generate().unwrap();
+    let dir = std::env::temp_dir().join(format!("zoro-conformance-{}", std::process::id()));
+    conformance::write(&fixtures, &dir).unwrap();
+    // Fixtures that do not decode are malformed, whatever their kind
+    std::fs::write(
+        dir.join("unknown_kind.json"),
+        r#"{"name": "unknown_kind", "kind": "coinbase", "input": {}, "expected": "malformed"}"#,
+    )
+    .unwrap();
+    let mut truncated: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(dir.join("mmr_range_proof_0.json")).unwrap())
+            .unwrap();
+    truncated["name"] = "truncated_leaf".into();
+    truncated["input"]["leaves"][0] = "0x00".into();
+    truncated["expected"] = "malformed".into();
+    std::fs::write(
+        dir.join("truncated_leaf.json"),
+        serde_json::to_vec(&truncated).unwrap(),
+    )
+    .unwrap();
+
+    let outcomes = conformance::run(&dir).unwrap();
+    assert_eq!(outcomes.len(), fixtures.len() + 2);
+    for outcome in &outcomes {
+        assert!(outcome.passed(), "{outcome:?}");
+    }
+    assert!(outcomes
+        .iter()
+        .any(|outcome| outcome.actual == Verdict::Malformed));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn conformance_mismatch_fails() {
+    let mut fixtures = conformance::generate().unwrap();
+    fixtures[0].expected = Verdict::MerkleProofInvalid;
+    let dir =
+        std::env::temp_dir().join(format!("zoro-conformance-mismatch-{}", std::process::id()));
+    conformance::write(&fixtures, &dir).unwrap();
+    let outcomes = conformance::run(&dir).unwrap();
+    let failed: Vec<_> = outcomes
+        .iter()
+        .filter(|outcome| !outcome.passed())
+        .collect();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].fixture, fixtures[0].name);
+    assert_eq!(failed[0].actual, Verdict::Valid);
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,59 @@
+//! Conformance vectors: generated, written out and checked back as another implementation would
+
+use std::path::PathBuf;
+
+use zoro_spv_verify::vectors::{self, Vectors};
+
+fn write_vectors(name: &str, vectors: &Vectors) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("zoro-vectors-{name}-{}", std::process::id()));
+    vectors::write(vectors, &dir).unwrap();
+    dir
+}
+
+#[test]
+fn vectors_check() {
+    let vectors = vectors::generate().unwrap();
+    let dir = write_vectors("check", &vectors);
+    let cases = vectors::check(&dir).unwrap();
+    assert_eq!(
+        cases,
+        vectors.headers.len()
+            + vectors.merkle_proofs.len()
+            + vectors.mmr_proofs.len()
+            + vectors.chain_states.len()
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn vectors_are_pinned() {
+    let vectors = vectors::generate().unwrap();
+    // Genesis values also checked by the unit tests and the Cairo packages
+    assert_eq!(
+        vectors.headers[0].block_hash,
+        "00040fe8ec8471911baa1db1266ea15dd06b4a8a5c453883c000b031973dce08"
+    );
+    assert_eq!(
+        vectors.headers[0].mmr_leaf,
+        "0x0d6195eb80a1a9dcdf5fb7aaf820639b76b96fe9448b2fce9e117b6385c69c37"
+    );
+    assert_eq!(
+        vectors.chain_states[0].blake2s_digest,
+        "0x5f075316d513cf571854e8f4df77f22ce7bfae4c7a1b271d57d9dfb61a54e2ec"
+    );
+    // Generation is deterministic
+    let again = vectors::generate().unwrap();
+    assert_eq!(
+        again.merkle_proofs.last().unwrap().canonical_digest,
+        vectors.merkle_proofs.last().unwrap().canonical_digest
+    );
+}
+
+#[test]
+fn tampered_vectors_fail() {
+    let mut vectors = vectors::generate().unwrap();
+    vectors.merkle_proofs[3].proof.path[0][0] ^= 1;
+    let dir = write_vectors("tampered", &vectors);
+    assert!(vectors::check(&dir).is_err());
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,580 @@
+//! Zcash RPC client for fetching block headers, transactions and chain information with retry logic.
+
+use base64::{engine::general_purpose, Engine as _};
+use jsonrpsee::core::client::{ClientT, Error as RpcError};
+use jsonrpsee::core::params::ArrayParams;
+use jsonrpsee::http_client::{HeaderMap, HeaderValue, HttpClient};
+use jsonrpsee::rpc_params;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use std::time::Duration;
+use thiserror::Error;
+use tracing::{debug, info, warn};
+use zebra_chain::block::{Block, Hash as BlockHash, Header};
+use zebra_chain::serialization::ZcashDeserialize;
+use zebra_chain::transaction::{Hash as TxHash, Transaction};
+
+use crate::{DisplayOrderHash, MerkleTree};
+
+/// Error types for Zcash RPC client operations
+#[derive(Error, Debug)]
+pub enum ZcashClientError {
+    /// RPC client errors
+    #[error("RPC client error: {0}")]
+    RpcClient(#[from] jsonrpsee::core::client::Error),
+    /// Invalid HTTP header value
+    #[error("Invalid HTTP header value")]
+    InvalidHeader,
+    /// Failed to read the RPC cookie file
+    #[error("Failed to read RPC cookie file: {0}")]
+    CookieFile(std::io::Error),
+    /// Failed to decode hex response
+    #[error("Failed to decode hex response: {0}")]
+    HexDecode(#[from] hex::FromHexError),
+    /// Failed to deserialize Zcash block header
+    #[error("Failed to deserialize Zcash block header: {0}")]
+    ZcashBlockHeaderDeserialize(#[from] zebra_chain::serialization::SerializationError),
+    /// Failed to read Zcash block header
+    #[error("Failed to read Zcash block header: {0}")]
+    ZcashBlockHeaderRead(#[from] std::io::Error),
+    /// Failed to read Zcash transaction
+    #[error("Failed to read Zcash transaction: {0}")]
+    ZcashTransactionRead(std::io::Error),
+    /// Unsupported or unknown network reported by node
+    #[error("Unsupported Zcash network: {0}")]
+    UnsupportedNetwork(String),
+    /// Failed to convert block hash
+    #[error("Failed to convert block hash: {0}")]
+    InvalidBlockHash(String),
+    /// Failed to deserialize Zcash transaction
+    #[error("Failed to deserialize Zcash transaction: {0}")]
+    ZcashTransactionDeserialize(zebra_chain::serialization::SerializationError),
+    /// Failed to deserialize Zcash block
+    #[error("Failed to deserialize Zcash block: {0}")]
+    ZcashBlockDeserialize(zebra_chain::serialization::SerializationError),
+    /// Merkle root mismatch
+    #[error("Merkle root mismatch: expected {expected:?}, calculated {calculated:?}")]
+    MerkleRootMismatch {
+        expected: String,
+        calculated: String,
+    },
+}
+
+/// Default HTTP request timeout
+pub const HTTP_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Default timeout of `getblock`, whose verbose output for large blocks takes longer to build
+pub const GETBLOCK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Default chain height update interval in seconds
+pub const CHAIN_HEIGHT_UPDATE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Credentials used to authenticate to the node RPC
+#[derive(Clone, Default)]
+pub enum RpcAuth {
+    /// No authentication
+    #[default]
+    None,
+    /// Static `user:password`
+    UserPassword(String),
+    /// Cookie file written by the node on startup (`.cookie` in its data directory), holding
+    /// `__cookie__:<password>`. It is read again when the node rotates it on restart.
+    CookieFile(PathBuf),
+}
+
+impl std::fmt::Debug for RpcAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RpcAuth::None => f.write_str("None"),
+            RpcAuth::UserPassword(_) => f.write_str("UserPassword(<redacted>)"),
+            RpcAuth::CookieFile(path) => f.debug_tuple("CookieFile").field(path).finish(),
+        }
+    }
+}
+
+impl RpcAuth {
+    /// Value of the `Authorization` header, if any
+    fn authorization(&self) -> Result<Option<HeaderValue>, ZcashClientError> {
+        let userpwd = match self {
+            RpcAuth::None => return Ok(None),
+            RpcAuth::UserPassword(userpwd) => userpwd.clone(),
+            RpcAuth::CookieFile(path) => std::fs::read_to_string(path)
+                .map_err(ZcashClientError::CookieFile)?
+                .trim()
+                .to_string(),
+        };
+        let creds = general_purpose::STANDARD.encode(userpwd);
+        let mut value = HeaderValue::from_str(&format!("Basic {creds}"))
+            .map_err(|_| ZcashClientError::InvalidHeader)?;
+        value.set_sensitive(true);
+        Ok(Some(value))
+    }
+}
+
+/// Request timeouts, per RPC method
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpcTimeouts {
+    /// Timeout of the methods without an override
+    pub default: Duration,
+    /// Timeout overrides by method name
+    pub methods: HashMap<String, Duration>,
+}
+
+impl Default for RpcTimeouts {
+    fn default() -> Self {
+        Self {
+            default: HTTP_REQUEST_TIMEOUT,
+            methods: HashMap::from([("getblock".to_string(), GETBLOCK_TIMEOUT)]),
+        }
+    }
+}
+
+impl RpcTimeouts {
+    /// Timeout of a request to `method`
+    pub fn get(&self, method: &str) -> Duration {
+        self.methods.get(method).copied().unwrap_or(self.default)
+    }
+
+    /// Longest timeout of any method
+    fn max(&self) -> Duration {
+        self.methods
+            .values()
+            .copied()
+            .fold(self.default, Duration::max)
+    }
+}
+
+/// HTTP client along with the credentials it was built with
+#[derive(Debug, Clone)]
+struct Connection {
+    client: HttpClient,
+    authorization: Option<HeaderValue>,
+}
+
+impl Connection {
+    fn new(
+        url: &str,
+        authorization: Option<HeaderValue>,
+        timeouts: &RpcTimeouts,
+    ) -> Result<Self, ZcashClientError> {
+        let mut headers = HeaderMap::new();
+        if let Some(value) = &authorization {
+            headers.insert("Authorization", value.clone());
+        }
+        let client = HttpClient::builder()
+            .set_headers(headers)
+            // Requests are timed out per method, the HTTP timeout only bounds the longest one
+            .request_timeout(timeouts.max())
+            .build(url)?;
+        Ok(Self {
+            client,
+            authorization,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct ZcashClient {
+    /// Connection to the node, rebuilt when it is lost or the credentials change
+    connection: RwLock<Connection>,
+    url: String,
+    auth: RpcAuth,
+    timeouts: RpcTimeouts,
+    chain_height: u32,
+    backoff: backoff::ExponentialBackoff,
+}
+
+impl ZcashClient {
+    /// Create a new Zcash RPC client with default retry settings (exponential backoff)
+    pub async fn new(url: String, userpwd: Option<String>) -> Result<Self, ZcashClientError> {
+        Self::with_auth(url, userpwd.map_or(RpcAuth::None, RpcAuth::UserPassword)).await
+    }
+
+    /// Create a new Zcash RPC client authenticating with `auth`, with default retry settings
+    pub async fn with_auth(url: String, auth: RpcAuth) -> Result<Self, ZcashClientError> {
+        let timeouts = RpcTimeouts::default();
+        let connection = Connection::new(&url, auth.authorization()?, &timeouts)?;
+
+        let backoff = backoff::ExponentialBackoff::default();
+
+        Ok(Self {
+            connection: RwLock::new(connection),
+            url,
+            auth,
+            timeouts,
+            backoff: backoff.clone(),
+            chain_height: 0,
+        })
+    }
+
+    /// Replace the request timeouts
+    pub fn with_timeouts(mut self, timeouts: RpcTimeouts) -> Result<Self, ZcashClientError> {
+        let authorization = self.connection.read().unwrap().authorization.clone();
+        self.connection = RwLock::new(Connection::new(&self.url, authorization, &timeouts)?);
+        self.timeouts = timeouts;
+        Ok(self)
+    }
+
+    async fn request<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: ArrayParams,
+    ) -> Result<T, ZcashClientError> {
+        let timeout = self.timeouts.get(method);
+        request_with_retry(self.backoff.clone(), || async {
+            let client = self.connection.read().unwrap().client.clone();
+            let res = tokio::time::timeout(timeout, client.request(method, params.clone()))
+                .await
+                .unwrap_or(Err(RpcError::RequestTimeout));
+            match &res {
+                // The client is unusable from now on: replace it before the next retry
+                Err(RpcError::RestartNeeded(_) | RpcError::ServiceDisconnect) => {
+                    self.reconnect()?;
+                }
+                // Rejected requests may be due to a cookie rotated by a node restart
+                Err(RpcError::Transport(_)) if matches!(self.auth, RpcAuth::CookieFile(_)) => {
+                    self.refresh_credentials()?;
+                }
+                _ => {}
+            }
+            res.map_err(Into::into)
+        })
+        .await
+    }
+
+    /// Replace the HTTP client with a new one, dropping its lost connection
+    fn reconnect(&self) -> Result<(), ZcashClientError> {
+        let authorization = self.connection.read().unwrap().authorization.clone();
+        let connection = Connection::new(&self.url, authorization, &self.timeouts)?;
+        *self.connection.write().unwrap() = connection;
+        warn!("Zcash RPC connection lost, reconnecting to {}", self.url);
+        Ok(())
+    }
+
+    /// Read the credentials again, replacing the HTTP client if they changed. Read failures
+    /// are ignored, as the node removes its cookie file while restarting.
+    fn refresh_credentials(&self) -> Result<(), ZcashClientError> {
+        let authorization = match self.auth.authorization() {
+            Ok(authorization) => authorization,
+            Err(e) => {
+                debug!("Could not refresh Zcash RPC credentials: {e}");
+                return Ok(());
+            }
+        };
+        if authorization == self.connection.read().unwrap().authorization {
+            return Ok(());
+        }
+        let connection = Connection::new(&self.url, authorization, &self.timeouts)?;
+        *self.connection.write().unwrap() = connection;
+        info!(
+            "Zcash RPC credentials changed, reconnecting to {}",
+            self.url
+        );
+        Ok(())
+    }
+
+    /// Get block hash by height
+    pub async fn get_block_hash(&self, height: u32) -> Result<BlockHash, ZcashClientError> {
+        self.request::<String>("getblockhash", rpc_params![height])
+            .await
+            .and_then(|s| {
+                let hash: DisplayOrderHash = s.parse()?;
+                Ok(hash.to_internal().into())
+            })
+    }
+
+    /// Get block header by hash
+    pub async fn get_block_header(&self, hash: &BlockHash) -> Result<Header, ZcashClientError> {
+        self.request::<String>("getblockheader", rpc_params![hash.to_string(), false])
+            .await
+            .and_then(|header_hex| {
+                let header_bytes = hex::decode(header_hex)?;
+                let mut reader = header_bytes.as_slice();
+                Header::zcash_deserialize(&mut reader).map_err(Into::into)
+            })
+    }
+
+    /// Get block height by hash
+    pub async fn get_block_height(&self, hash: &BlockHash) -> Result<u32, ZcashClientError> {
+        let header_info: serde_json::Value = self
+            .request("getblockheader", rpc_params![hash.to_string(), true])
+            .await?;
+        let block_height = decode_block_height(&header_info)?;
+        Ok(block_height)
+    }
+
+    /// Get block height by hash string
+    pub async fn get_block_height_by_hash_str(&self, hash: &str) -> Result<u32, ZcashClientError> {
+        let header_info: serde_json::Value = self
+            .request("getblockheader", rpc_params![hash, true])
+            .await?;
+        let block_height = decode_block_height(&header_info)?;
+        Ok(block_height)
+    }
+
+    /// Get block header by height
+    pub async fn get_block_header_by_height(
+        &self,
+        height: u32,
+    ) -> Result<(Header, BlockHash), ZcashClientError> {
+        let hash = self.get_block_hash(height).await?;
+        let header = self.get_block_header(&hash).await?;
+        Ok((header, hash))
+    }
+
+    pub async fn get_transaction_block_height(
+        &self,
+        txid: &TxHash,
+    ) -> Result<u32, ZcashClientError> {
+        let tx: Value = self
+            .request("getrawtransaction", rpc_params![txid.to_string(), 1])
+            .await?;
+
+        let block_height = tx
+            .get("height")
+            .and_then(|h| h.as_u64())
+            .map(|h| h as u32)
+            .ok_or_else(|| {
+                ZcashClientError::ZcashBlockHeaderRead(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "missing or invalid block height in getrawtransaction response",
+                ))
+            })?;
+
+        Ok(block_height)
+    }
+
+    /// Get transaction by txid and hash of the block containing the transaction
+    pub async fn get_transaction(&self, txid: &TxHash) -> Result<Transaction, ZcashClientError> {
+        // get raw tx from rpc in json mode
+        let tx: String = self
+            .request("getrawtransaction", rpc_params![txid.to_string()])
+            .await?;
+
+        let tx_bytes = hex::decode(tx)?;
+        let transaction = Transaction::zcash_deserialize(&mut tx_bytes.as_slice())
+            .map_err(ZcashClientError::ZcashTransactionDeserialize)?;
+
+        Ok(transaction)
+    }
+
+    /// Get transaction inclusion proof
+    pub async fn get_transaction_inclusion_proof(
+        &self,
+        _txid: &[u8],
+    ) -> Result<(), ZcashClientError> {
+        unimplemented!();
+        // self.request("gettxoutproof", rpc_params![[txid.to_string()]])
+        //     .await
+    }
+
+    /// Get block by hash
+    pub async fn get_block(&self, hash: &BlockHash) -> Result<Block, ZcashClientError> {
+        let block_hex: String = self
+            .request("getblock", rpc_params![hash.to_string(), 0])
+            .await?;
+        let block_bytes = hex::decode(block_hex)?;
+        let block = Block::zcash_deserialize(&mut block_bytes.as_slice())
+            .map_err(ZcashClientError::ZcashBlockDeserialize)?;
+        Ok(block)
+    }
+
+    /// Build the tx merkle tree of a given block number. This is required for generating the tx inclusion proof.
+    pub async fn build_block_merkle_tree(
+        &self,
+        block_height: u32,
+    ) -> Result<MerkleTree, ZcashClientError> {
+        let hash = self.get_block_hash(block_height).await?;
+        let block = self.get_block(&hash).await?;
+
+        // Only the transaction hashes are kept: the block is dropped once the tree is built
+        MerkleTree::new(&block.transactions, block.header.merkle_root).map_err(|e| {
+            ZcashClientError::MerkleRootMismatch {
+                expected: format!("{:?}", block.header.merkle_root),
+                calculated: e, // Ideally parse e properly, but string error from module is fine for now
+            }
+        })
+    }
+
+    /// Get current chain height
+    pub async fn get_chain_height(&self) -> Result<u32, ZcashClientError> {
+        let result: u64 = self.request("getblockcount", rpc_params![]).await?;
+        Ok(result as u32)
+    }
+
+    /// Get the version of the node software, e.g. `/Zebra:2.3.0/` or `/MagicBean:6.2.0/`, from
+    /// `getnetworkinfo` or, for nodes without it, `getinfo`
+    pub async fn get_node_version(&self) -> Result<String, ZcashClientError> {
+        let info: Value = match self.request("getnetworkinfo", rpc_params![]).await {
+            Ok(info) => info,
+            Err(_) => self.request("getinfo", rpc_params![]).await?,
+        };
+        info.get("subversion")
+            .or_else(|| info.get("build"))
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| {
+                ZcashClientError::ZcashBlockHeaderRead(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "missing subversion in getinfo response",
+                ))
+            })
+    }
+
+    /// Wait for a block header at the given height.
+    /// If the specified lag is non-zero, the function will wait till `lag` blocks are built on top of the expected block.
+    pub async fn wait_block_header(
+        &mut self,
+        height: u32,
+        lag: u32,
+    ) -> Result<(Header, BlockHash), ZcashClientError> {
+        while height > self.chain_height {
+            self.chain_height = self.get_chain_height().await?.saturating_sub(lag);
+            if height <= self.chain_height {
+                debug!("New chain height: {}", self.chain_height);
+                break;
+            } else {
+                tokio::time::sleep(CHAIN_HEIGHT_UPDATE_INTERVAL).await;
+            }
+        }
+        self.get_block_header_by_height(height).await
+    }
+
+    /// Get block data needed for FlyClient MMR (sapling root and sapling tx count)
+    pub async fn get_block_flyclient_data(
+        &self,
+        height: u32,
+    ) -> Result<([u8; 32], u64), ZcashClientError> {
+        let hash = self.get_block_hash(height).await?;
+        let blk: Value = self
+            .request("getblock", rpc_params![hash.to_string(), 2])
+            .await?;
+
+        // Extract finalsaplingroot, shown in display order by the RPC
+        let sapling_root_hex = blk["finalsaplingroot"].as_str().ok_or_else(|| {
+            ZcashClientError::ZcashBlockHeaderRead(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "missing finalsaplingroot in getblock response",
+            ))
+        })?;
+        let sapling_root = sapling_root_hex
+            .parse::<DisplayOrderHash>()
+            .map_err(|_| {
+                ZcashClientError::ZcashBlockHeaderRead(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "finalsaplingroot in getblock response is not 32 bytes of hex",
+                ))
+            })?
+            .to_internal()
+            .0;
+
+        // Count sapling transactions (those with shielded spends or outputs)
+        let sapling_tx = blk["tx"]
+            .as_array()
+            .map(|txs| {
+                txs.iter()
+                    .filter(|tx| {
+                        tx.get("vShieldedSpend")
+                            .and_then(|v| v.as_array())
+                            .map_or(false, |a| !a.is_empty())
+                            || tx
+                                .get("vShieldedOutput")
+                                .and_then(|v| v.as_array())
+                                .map_or(false, |a| !a.is_empty())
+                    })
+                    .count() as u64
+            })
+            .unwrap_or(0);
+
+        Ok((sapling_root, sapling_tx))
+    }
+
+    /// Get block commitment (FlyClient root) for a given height
+    pub async fn get_block_commitment(&self, height: u32) -> Result<String, ZcashClientError> {
+        let hash = self.get_block_hash(height).await?;
+        let blk: Value = self
+            .request("getblock", rpc_params![hash.to_string(), 1])
+            .await?;
+
+        let commitment = blk["blockcommitments"]
+            .as_str()
+            .ok_or_else(|| {
+                ZcashClientError::ZcashBlockHeaderRead(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "missing blockcommitments in getblock response",
+                ))
+            })?
+            .to_string();
+
+        Ok(commitment)
+    }
+}
+
+fn decode_block_height(header_info: &serde_json::Value) -> Result<u32, ZcashClientError> {
+    header_info
+        .get("height")
+        .and_then(|h| h.as_u64())
+        .map(|h| h as u32)
+        .ok_or_else(|| {
+            ZcashClientError::ZcashBlockHeaderRead(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "missing or invalid block height in getblockheader response",
+            ))
+        })
+}
+
+/// Execute a request with retry logic using exponential backoff
+/// Only retries on unexpected HTTP errors (not 200 OK or 400 Bad Request)
+async fn request_with_retry<F, Fut, T>(
+    backoff: backoff::ExponentialBackoff,
+    operation: F,
+) -> Result<T, ZcashClientError>
+where
+    F: Fn() -> Fut,
+    Fut: std::future::Future<Output = Result<T, ZcashClientError>>,
+{
+    use backoff::{future::retry_notify, Error};
+
+    retry_notify(
+        backoff,
+        || async {
+            match operation().await {
+                Ok(result) => Ok(result),
+                Err(err) => {
+                    // Check if this is a retryable HTTP error
+                    if is_retryable_error(&err) {
+                        Err(Error::transient(err))
+                    } else {
+                        Err(Error::permanent(err))
+                    }
+                }
+            }
+        },
+        |err, duration| {
+            info!("Request failed, retrying in {:?}: {}", duration, err);
+        },
+    )
+    .await
+}
+
+/// Determines if an error should be retried - only retry HTTP errors (except bad request)
+fn is_retryable_error(err: &ZcashClientError) -> bool {
+    match err {
+        // Only retry RPC client errors that are HTTP-related (transport, timeouts, server errors)
+        ZcashClientError::RpcClient(rpc_err) => {
+            match rpc_err {
+                // Only retry transport errors and timeouts (HTTP-level issues)
+                RpcError::Transport(_) => true,
+                RpcError::RequestTimeout => true,
+                RpcError::RestartNeeded(_) => true,
+                RpcError::ServiceDisconnect => true,
+                // Don't retry any other RPC errors (JSON-RPC level issues, bad requests, etc.)
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
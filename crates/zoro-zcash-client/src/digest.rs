@@ -0,0 +1,97 @@
+//! 32-byte digests tagged with their byte order.
+//!
+//! Zcash hashes (block hashes, txids, Merkle and Sapling roots) are stored and hashed in internal
+//! byte order, but RPC responses, block explorers and users show them reversed ("display order").
+//! [`InternalOrderHash`] and [`DisplayOrderHash`] keep the order in the type, so that converting
+//! between the two is an explicit [`to_display`](InternalOrderHash::to_display) /
+//! [`to_internal`](DisplayOrderHash::to_internal) instead of a `reverse()` at the call site.
+
+use std::fmt;
+use std::str::FromStr;
+
+use hex::FromHex;
+use zebra_chain::{block, transaction};
+
+/// Digest in internal byte order, as hashed and serialized in blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InternalOrderHash(pub [u8; 32]);
+
+/// Digest in display byte order, as shown by RPC responses and block explorers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DisplayOrderHash(pub [u8; 32]);
+
+impl InternalOrderHash {
+    /// Same digest in display order
+    pub fn to_display(self) -> DisplayOrderHash {
+        let mut bytes = self.0;
+        bytes.reverse();
+        DisplayOrderHash(bytes)
+    }
+}
+
+impl DisplayOrderHash {
+    /// Same digest in internal order
+    pub fn to_internal(self) -> InternalOrderHash {
+        let mut bytes = self.0;
+        bytes.reverse();
+        InternalOrderHash(bytes)
+    }
+}
+
+impl From<InternalOrderHash> for DisplayOrderHash {
+    fn from(hash: InternalOrderHash) -> Self {
+        hash.to_display()
+    }
+}
+
+impl From<DisplayOrderHash> for InternalOrderHash {
+    fn from(hash: DisplayOrderHash) -> Self {
+        hash.to_internal()
+    }
+}
+
+/// Hex encoding of the bytes as stored, in either order
+macro_rules! impl_hex {
+    ($name:ident) => {
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&hex::encode(self.0))
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = hex::FromHexError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                <[u8; 32]>::from_hex(s).map(Self)
+            }
+        }
+    };
+}
+
+impl_hex!(InternalOrderHash);
+impl_hex!(DisplayOrderHash);
+
+impl From<block::Hash> for InternalOrderHash {
+    fn from(hash: block::Hash) -> Self {
+        Self(hash.0)
+    }
+}
+
+impl From<InternalOrderHash> for block::Hash {
+    fn from(hash: InternalOrderHash) -> Self {
+        block::Hash(hash.0)
+    }
+}
+
+impl From<transaction::Hash> for InternalOrderHash {
+    fn from(hash: transaction::Hash) -> Self {
+        Self(hash.0)
+    }
+}
+
+impl From<InternalOrderHash> for transaction::Hash {
+    fn from(hash: InternalOrderHash) -> Self {
+        transaction::Hash(hash.0)
+    }
+}
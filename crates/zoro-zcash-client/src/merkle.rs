@@ -1,32 +1,92 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use zebra_chain::block::merkle::Root;
 use zebra_chain::transaction::Hash;
 use zebra_chain::transaction::Transaction;
 
-/// Represents a block's transaction Merkle tree
+/// Represents a block's transaction Merkle tree.
+///
+/// Only the transaction hashes are kept. The root is checked with a single level alive at a time,
+/// and each inner level is hashed once, by the first proof that needs it, then shared by every
+/// proof.
 #[derive(Debug)]
 pub struct MerkleTree {
     pub root: Root,
-    pub transactions: Vec<Arc<Transaction>>,
+    tx_hashes: Vec<Hash>,
+    /// Levels between the leaves and the root, from the parents of the leaves up
+    levels: Vec<OnceLock<Vec<[u8; 32]>>>,
 }
 
-/// A Merkle inclusion proof for a transaction
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A Merkle inclusion proof for a transaction.
+///
+/// Hashes are in internal byte order, as hashed by SHA256d: the reverse of the display order of
+/// txids and block hashes. Each parent is `SHA256d(left || right)`, and the last node of a level
+/// with an odd number of nodes is paired with itself.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct MerkleProof {
     /// The root of the Merkle tree
+    #[schemars(with = "[u8; 32]")]
     pub root: Root,
-    /// The authentication path (sibling hashes)
+    /// The authentication path (sibling hashes), from the leaves up
     pub path: Vec<[u8; 32]>,
     /// The index of the transaction in the block
     pub index: usize,
+    /// Position of each sibling of `path`, which must match the bits of `index` from the least
+    /// significant one. Absent in proofs from older bridges, where it is derived from `index`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub positions: Vec<SiblingPosition>,
+}
+
+/// Position of a sibling relative to the node it is hashed with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(rename_all = "lowercase")]
+pub enum SiblingPosition {
+    /// The sibling is the left input of the parent hash
+    Left,
+    /// The sibling is the right input of the parent hash
+    Right,
+}
+
+impl SiblingPosition {
+    /// Position of the sibling of the node at `index` within its level
+    pub fn of_sibling(index: usize) -> Self {
+        if index % 2 == 0 {
+            SiblingPosition::Right
+        } else {
+            SiblingPosition::Left
+        }
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for MerkleProof {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        // `Root` is a foreign type, so build it from raw bytes
+        Ok(Self {
+            root: Root(u.arbitrary()?),
+            path: u.arbitrary()?,
+            index: u.arbitrary()?,
+            positions: u.arbitrary()?,
+        })
+    }
 }
 
 impl MerkleTree {
     /// Creates a new MerkleTree from a list of transactions and verifies the root
-    pub fn new(transactions: Vec<Arc<Transaction>>, expected_root: Root) -> Result<Self, String> {
-        let calculated_root: Root = transactions.iter().collect();
+    pub fn new(transactions: &[Arc<Transaction>], expected_root: Root) -> Result<Self, String> {
+        Self::from_tx_hashes(
+            transactions.iter().map(|tx| tx.hash()).collect(),
+            expected_root,
+        )
+    }
+
+    /// Creates a new MerkleTree from the transaction hashes of a block, in block order, and
+    /// verifies the root
+    pub fn from_tx_hashes(tx_hashes: Vec<Hash>, expected_root: Root) -> Result<Self, String> {
+        let calculated_root = Self::compute_root(&tx_hashes)?;
 
         if calculated_root != expected_root {
             return Err(format!(
@@ -34,62 +94,66 @@ impl MerkleTree {
             ));
         }
 
+        // Every level but the leaves and the root
+        let mut num_levels = 0;
+        let mut len = tx_hashes.len();
+        while len > 2 {
+            len = len.div_ceil(2);
+            num_levels += 1;
+        }
         Ok(Self {
             root: calculated_root,
-            transactions,
+            tx_hashes,
+            levels: (0..num_levels).map(|_| OnceLock::new()).collect(),
         })
     }
 
-    pub fn get_transaction_index(&self, tx_hash: Hash) -> Result<usize, String> {
-        for (i, tx) in self.transactions.iter().enumerate() {
-            if tx.hash() == tx_hash {
-                return Ok(i);
-            }
+    /// Computes the Merkle root of the transaction hashes of a block, in block order
+    pub fn compute_root(tx_hashes: &[Hash]) -> Result<Root, String> {
+        if tx_hashes.is_empty() {
+            return Err("Block has no transactions".to_string());
+        }
+
+        // Only one level is alive at a time
+        let mut level: Vec<[u8; 32]> = tx_hashes.iter().map(|hash| hash.0).collect();
+        while level.len() > 1 {
+            level = parent_level(&level);
         }
-        Err("Transaction not found".to_string())
+        Ok(Root(level[0]))
+    }
+
+    /// Hashes of the transactions, in block order
+    pub fn tx_hashes(&self) -> &[Hash] {
+        &self.tx_hashes
+    }
+
+    pub fn get_transaction_index(&self, tx_hash: Hash) -> Result<usize, String> {
+        self.tx_hashes
+            .iter()
+            .position(|hash| *hash == tx_hash)
+            .ok_or_else(|| "Transaction not found".to_string())
     }
 
     /// Generates a Merkle inclusion proof for the transaction at the given index
     pub fn generate_proof(&self, tx_index: usize) -> Result<MerkleProof, String> {
-        if tx_index >= self.transactions.len() {
+        if tx_index >= self.tx_hashes.len() {
             return Err("Transaction index out of bounds".to_string());
         }
 
-        let mut current_layer: Vec<[u8; 32]> = self
-            .transactions
-            .iter()
-            .map(|tx| tx.hash().into())
-            .collect();
-
         let mut path = Vec::new();
+        let mut positions = Vec::new();
         let mut current_index = tx_index;
 
-        // Continue until we reach the root (layer size 1)
-        while current_layer.len() > 1 {
-            let is_right_child = current_index % 2 != 0;
-
-            let sibling_index = if is_right_child {
-                current_index - 1
-            } else {
-                // If current is left child, sibling is right.
-                // If right doesn't exist (odd layer length), duplicate left (self).
-                (current_index + 1).min(current_layer.len() - 1)
-            };
-
-            path.push(current_layer[sibling_index]);
-
-            // Compute parent layer
-            current_layer = current_layer
-                .chunks(2)
-                .map(|chunk| {
-                    match chunk {
-                        [left, right] => double_sha256(left, right),
-                        [left] => double_sha256(left, left), // Handle odd last element
-                        _ => unreachable!("Chunk size is at most 2"),
-                    }
-                })
-                .collect();
-
+        // Every level but the root one: the leaves, unless a single one is the root, then the
+        // inner levels
+        if self.tx_hashes.len() > 1 {
+            path.push(sibling(&self.tx_hashes, current_index).0);
+            positions.push(SiblingPosition::of_sibling(current_index));
+            current_index /= 2;
+        }
+        for depth in 0..self.levels.len() {
+            path.push(sibling(self.level(depth), current_index));
+            positions.push(SiblingPosition::of_sibling(current_index));
             current_index /= 2;
         }
 
@@ -97,25 +161,71 @@ impl MerkleTree {
             root: self.root,
             path,
             index: tx_index,
+            positions,
         })
     }
+
+    /// Inner level at `depth` (0 for the parents of the leaves), hashed from the level below it
+    /// on first use
+    fn level(&self, depth: usize) -> &[[u8; 32]] {
+        self.levels[depth].get_or_init(|| match depth {
+            0 => parent_level(&self.tx_hashes.iter().map(|hash| hash.0).collect::<Vec<_>>()),
+            _ => parent_level(self.level(depth - 1)),
+        })
+    }
+}
+
+/// Sibling of the node at `index` within its level: the last node of an odd level is its own
+/// sibling
+fn sibling<T: Copy>(level: &[T], index: usize) -> T {
+    level[(index ^ 1).min(level.len() - 1)]
+}
+
+/// Hash the pairs of a level, duplicating the last node of odd levels
+fn parent_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|chunk| {
+            match chunk {
+                [left, right] => double_sha256(left, right),
+                [left] => double_sha256(left, left), // Handle odd last element
+                _ => unreachable!("Chunk size is at most 2"),
+            }
+        })
+        .collect()
 }
 
 impl MerkleProof {
+    /// Positions of the siblings of `path`: the explicit ones, else the ones derived from
+    /// `index`. `None` if the explicit positions do not match `path` and `index`.
+    pub fn sibling_positions(&self) -> Option<Vec<SiblingPosition>> {
+        let derived: Vec<SiblingPosition> = (0..self.path.len())
+            .map(|level| {
+                let index = self.index.checked_shr(level as u32).unwrap_or(0);
+                SiblingPosition::of_sibling(index)
+            })
+            .collect();
+        if self.positions.is_empty() || self.positions == derived {
+            Some(derived)
+        } else {
+            None
+        }
+    }
+
     /// Verifies the proof against a transaction hash
     pub fn verify(&self, tx_hash: [u8; 32]) -> bool {
+        let Some(positions) = self.sibling_positions() else {
+            return false;
+        };
         let mut current = tx_hash;
-        let mut index = self.index;
 
-        for sibling in &self.path {
-            let (left, right) = if index % 2 == 0 {
-                (current, *sibling)
-            } else {
-                (*sibling, current)
+        for (sibling, position) in self.path.iter().zip(positions) {
+            let (left, right) = match position {
+                SiblingPosition::Right => (current, *sibling),
+                SiblingPosition::Left => (*sibling, current),
             };
 
             current = double_sha256(&left, &right);
-            index /= 2;
         }
 
         Root(current) == self.root
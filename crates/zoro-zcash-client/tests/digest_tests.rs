@@ -0,0 +1,21 @@
+use zebra_chain::block;
+use zoro_zcash_client::{DisplayOrderHash, InternalOrderHash};
+
+#[test]
+fn display_order_matches_zebra() {
+    // Genesis block hash, as shown by explorers
+    let display: DisplayOrderHash =
+        "00040fe8ec8471911baa1db1266ea15dd06b4a8a5c453883c000b031973dce08"
+            .parse()
+            .unwrap();
+    let hash = block::Hash::from(display.to_internal());
+    assert_eq!(hash.to_string(), display.to_string());
+    assert_eq!(InternalOrderHash::from(hash).to_display(), display);
+    assert_eq!(display.to_internal().to_display(), display);
+}
+
+#[test]
+fn digest_hex_must_be_32_bytes() {
+    assert!("00".parse::<DisplayOrderHash>().is_err());
+    assert!("zz".repeat(32).parse::<InternalOrderHash>().is_err());
+}
@@ -0,0 +1,83 @@
+use sha2::{Digest, Sha256};
+use zebra_chain::block::merkle::Root;
+use zebra_chain::transaction::Hash;
+use zoro_zcash_client::{MerkleProof, MerkleTree, SiblingPosition};
+
+fn sha256d(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let first = Sha256::new()
+        .chain_update(left)
+        .chain_update(right)
+        .finalize();
+    Sha256::digest(first).into()
+}
+
+/// Tree of three transactions: the last one is paired with itself
+fn tree() -> (MerkleTree, [[u8; 32]; 3]) {
+    let leaves = [[1u8; 32], [2u8; 32], [3u8; 32]];
+    let root = sha256d(
+        &sha256d(&leaves[0], &leaves[1]),
+        &sha256d(&leaves[2], &leaves[2]),
+    );
+    let tree = MerkleTree::from_tx_hashes(leaves.iter().copied().map(Hash).collect(), Root(root))
+        .expect("root must match");
+    (tree, leaves)
+}
+
+#[test]
+fn merkle_proof_positions() {
+    let (tree, leaves) = tree();
+    let proof = tree.generate_proof(2).unwrap();
+    assert_eq!(
+        proof.positions,
+        vec![SiblingPosition::Right, SiblingPosition::Left]
+    );
+    assert_eq!(proof.path[0], leaves[2]);
+    assert!(proof.verify(leaves[2]));
+    assert!(!proof.verify(leaves[1]));
+
+    // Positions contradicting the index are rejected
+    let mut flipped = proof.clone();
+    flipped.positions[1] = SiblingPosition::Right;
+    assert!(!flipped.verify(leaves[2]));
+    assert!(flipped.sibling_positions().is_none());
+
+    // Proofs without positions derive them from the index
+    let mut legacy = serde_json::to_value(&proof).unwrap();
+    legacy.as_object_mut().unwrap().remove("positions");
+    let legacy: MerkleProof = serde_json::from_value(legacy).unwrap();
+    assert!(legacy.positions.is_empty());
+    assert!(legacy.verify(leaves[2]));
+}
+
+#[test]
+fn merkle_tree_rejects_wrong_root() {
+    let (tree, leaves) = tree();
+    assert_eq!(tree.get_transaction_index(Hash(leaves[1])), Ok(1));
+    assert!(MerkleTree::from_tx_hashes(vec![Hash(leaves[0])], tree.root).is_err());
+    assert!(MerkleTree::from_tx_hashes(vec![], tree.root).is_err());
+}
+
+#[test]
+fn merkle_proof_single_transaction() {
+    // The transaction hash is the root
+    let leaf = [1u8; 32];
+    let tree = MerkleTree::from_tx_hashes(vec![Hash(leaf)], Root(leaf)).unwrap();
+    let proof = tree.generate_proof(0).unwrap();
+    assert!(proof.path.is_empty());
+    assert!(proof.verify(leaf));
+}
+
+#[test]
+fn merkle_proofs_of_every_tree_size() {
+    for num_leaves in 1..=9u8 {
+        let hashes: Vec<Hash> = (0..num_leaves).map(|i| Hash([i; 32])).collect();
+        let root = MerkleTree::compute_root(&hashes).unwrap();
+        let tree = MerkleTree::from_tx_hashes(hashes.clone(), root).unwrap();
+        // Proofs from the last leaf first, so that the levels are hashed out of leaf order
+        for (index, hash) in hashes.iter().enumerate().rev() {
+            let proof = tree.generate_proof(index).unwrap();
+            assert!(proof.verify(hash.0), "leaf {index} of {num_leaves}");
+        }
+        assert!(tree.generate_proof(hashes.len()).is_err());
+    }
+}
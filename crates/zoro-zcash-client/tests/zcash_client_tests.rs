@@ -1,6 +1,9 @@
+use std::time::Duration;
+
 use hex::FromHex;
 use zebra_chain::transaction::Hash;
-use zoro_zcash_client::ZcashClient;
+use zoro_zcash_client::{RpcTimeouts, ZcashClient, GETBLOCK_TIMEOUT, HTTP_REQUEST_TIMEOUT};
+
 #[tokio::test]
 async fn zcash_client_main_flow_like_example() {
     // Same parameters as `crates/zoro-zcash-client/src/main.rs`
@@ -94,3 +97,16 @@ async fn zcash_client_get_transaction_test() {
         );
     }
 }
+
+#[test]
+fn rpc_timeouts_per_method() {
+    let mut timeouts = RpcTimeouts::default();
+    assert_eq!(timeouts.get("getblockcount"), HTTP_REQUEST_TIMEOUT);
+    assert_eq!(timeouts.get("getblock"), GETBLOCK_TIMEOUT);
+
+    timeouts
+        .methods
+        .insert("getrawtransaction".to_string(), Duration::from_secs(20));
+    assert_eq!(timeouts.get("getrawtransaction"), Duration::from_secs(20));
+    assert_eq!(timeouts.get("getblockhash"), HTTP_REQUEST_TIMEOUT);
+}